@@ -1,15 +1,17 @@
 use std::time::Duration;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::Rng;
-use reveler::{fft, utils, RevelerCommit};
+use reveler::config::CommitConfig;
+use reveler::key::{CommitmentKey, Opening};
+use reveler::{commit_point, fft, utils, RevelerCommit};
 
 /// 基准测试 - 承诺生成
 fn commit_benchmark(c: &mut Criterion) {
     // 生成随机参数
     let (a_1, b_1) = utils::generate_params();
     let mut rng = rand::thread_rng();
-    let m: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
-    let r: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+    let m: Vec<u64> = (0..fft::MESSAGE_LEN).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+    let r = utils::generate_randomness(&mut rng);
 
     c.bench_function("commitment generation", |b| {
         b.iter(|| {
@@ -29,8 +31,8 @@ fn verify_benchmark(c: &mut Criterion) {
     // 生成随机参数并生成承诺
     let (a_1, b_1) = utils::generate_params();
     let mut rng = rand::thread_rng();
-    let m: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
-    let r: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+    let m: Vec<u64> = (0..fft::MESSAGE_LEN).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+    let r = utils::generate_randomness(&mut rng);
 
     let commit = RevelerCommit::new(a_1, b_1, m, r);
     let result = commit.commit().unwrap();
@@ -42,12 +44,61 @@ fn verify_benchmark(c: &mut Criterion) {
     });
 }
 
+/// 基准测试 - 线程与内联计算的分界，校准 DEFAULT_INLINE_WORK_THRESHOLD
+fn inline_threshold_benchmark(c: &mut Criterion) {
+    let (a_1, b_1) = utils::generate_params();
+    let mut rng = rand::thread_rng();
+    let m: Vec<u64> = (0..fft::MESSAGE_LEN).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+    let r = utils::generate_randomness(&mut rng);
+    let blocks = CommitConfig::new().with_small_message_limit(0);
+
+    for support in [64, 128, 256, 576] {
+        let (m, r) = (&m[..support.min(fft::MESSAGE_LEN)], &r[..support - support.min(fft::MESSAGE_LEN)]);
+        for (name, config) in [("inline", blocks.with_inline_work_threshold(usize::MAX)), ("threads", blocks.with_inline_work_threshold(0))] {
+            c.bench_function(&format!("inline threshold: {name}, support {support}"), |b| {
+                b.iter(|| commit_point((&a_1, &b_1), black_box(m), black_box(r), &config).unwrap());
+            });
+        }
+    }
+}
+
+/// 基准测试 - 批量验证与逐个验证
+fn verify_batch_benchmark(c: &mut Criterion) {
+    // 批量大小远大于 VERIFY_BATCH_ROUNDS，组合验证才有优势
+    let mut rng = rand::thread_rng();
+    let key = CommitmentKey::setup(&mut rng);
+    let claims: Vec<_> = (0..128)
+        .map(|_| {
+            let m: Vec<u64> = (0..fft::MESSAGE_LEN).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+            let r = utils::generate_randomness(&mut rng);
+            (key.commit(&m, &r).unwrap(), Opening::new(m, r))
+        })
+        .collect();
+
+    c.bench_function("batch verification of 128 openings", |b| {
+        b.iter(|| {
+            assert!(key.verify_batch(black_box(&claims)).unwrap().is_empty());
+        });
+    });
+    c.bench_function("separate verification of 128 openings", |b| {
+        b.iter(|| {
+            assert!(claims.iter().all(|(commitment, opening)| key.verify(black_box(commitment), opening).unwrap()));
+        });
+    });
+}
 
 criterion_group! {
     name = benches;
     config = Criterion::default()
         .measurement_time(Duration::from_secs(10))
         .sample_size(500);
-    targets = commit_benchmark, verify_benchmark
+    targets = commit_benchmark, verify_benchmark, inline_threshold_benchmark
+}
+criterion_group! {
+    name = batch_benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(30))
+        .sample_size(10);
+    targets = verify_batch_benchmark
 }
-criterion_main!(benches);
+criterion_main!(benches, batch_benches);
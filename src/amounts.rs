@@ -0,0 +1,366 @@
+//! This module packages confidential amounts: commitments to `u64` values with range and balance
+//! proofs, as used by confidential ledgers.
+//!
+//! An amount `a` is committed as the message of its `AMOUNT_BITS` bits, least significant first,
+//! so a `binary` proof over the commitment is a range proof for `a < 2^64`. Because the
+//! commitment is linear, the difference `D` of the summed input and output commitments commits
+//! to the coordinate-wise difference `d` of the bits. The inputs balance the outputs and the fee
+//! exactly when there are carries `c_0, …, c_62` with
+//!
+//! `d_j = 2·c_j - c_{j-1}` for every bit `j`, where `c_{-1} = c_63 = 0`.
+//!
+//! The balance proof is a `binary` proof that `D` commits to such a `d`, with every carry offset
+//! by `CARRY_OFFSET` and written in `CARRY_BITS` hidden bits. The carries stay small because a
+//! transaction spends and creates at most `MAX_TRANSFERS` amounts each, so the relation holds
+//! over the integers and not only modulo `q`.
+//!
+//! The randomness of `D` is the signed sum of the randomness of its `k` commitments, so the proof
+//! shows that it lies within `k` per coordinate, split into ternary blocks with the weights of
+//! `binary::weights`. A prover that opens `D` to other carries with such randomness holds a second
+//! opening of `D` with short randomness, which the binding of the commitment rules out, see
+//! `policy`: the output amounts cannot exceed the inputs less the fee.
+
+use serde::{Serialize, Deserialize};
+use crate::binary::{self, BinaryProof, Block, Relation};
+#[cfg(feature = "prover")]
+use crate::binary::Witness;
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::fft::{LOCAL_N, RANDOMNESS_LEN};
+use crate::{compute_point, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const BALANCE_LABEL: &[u8] = b"amounts/balance";
+
+/// Number of bits of a committed amount.
+pub const AMOUNT_BITS: usize = 64;
+
+/// Largest number of inputs, and of outputs, of a transaction.
+pub const MAX_TRANSFERS: usize = 16;
+
+/// Number of hidden bits encoding one carry of the balance proof.
+pub const CARRY_BITS: usize = 6;
+
+/// Offset making the carries of the balance proof non-negative.
+pub const CARRY_OFFSET: u64 = 1 << (CARRY_BITS - 1);
+
+// Number of hidden carry bits of the balance proof.
+const CARRY_WIDTH: usize = (AMOUNT_BITS - 1) * CARRY_BITS;
+
+/// Secret opening of a committed amount.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountOpening {
+    pub amount: u64,
+    pub local_r: Vec<u64>, // The randomness of the commitment
+}
+
+/// Commitment to an amount together with its range proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidentialAmount {
+    pub commitment: RevelerResult,
+    pub range_proof: BinaryProof, // Proof that the committed message is the bits of a u64
+}
+
+/// Transfer of committed amounts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub inputs: Vec<RevelerResult>,       // The commitments spent
+    pub outputs: Vec<ConfidentialAmount>, // The commitments created
+    pub fee: u64,                         // The public fee
+    pub balance_proof: BinaryProof,       // Proof that the inputs sum to the outputs plus the fee
+}
+
+impl AmountOpening {
+    /// Recomputes the commitment this opening opens.
+    pub fn commitment(&self, params: (&[Vec<u64>], &[Vec<u64>])) -> Result<RevelerResult, CommitError> {
+        let point = compute_point(params.0, params.1, &amount_bits(self.amount), &self.local_r, &CommitConfig::default())?;
+        Ok(result_of(point))
+    }
+}
+
+/// Commits to an amount and proves its range.
+///
+/// # Returns
+/// The `ConfidentialAmount` and the `AmountOpening` to keep secret.
+#[cfg(feature = "prover")]
+pub fn commit_amount(params: (&[Vec<u64>], &[Vec<u64>]), amount: u64) -> Result<(ConfidentialAmount, AmountOpening), CommitError> {
+    let mut rng = rand::thread_rng();
+    let opening = AmountOpening {
+        amount,
+        local_r: utils::generate_randomness(&mut rng),
+    };
+    let commitment = opening.commitment(params)?;
+    let range_proof = binary::prove_binary(params, &amount_bits(amount), &opening.local_r, &commitment)?;
+    Ok((ConfidentialAmount { commitment, range_proof }, opening))
+}
+
+/// Verifies the range proof of a committed amount.
+pub fn verify_amount(params: (&[Vec<u64>], &[Vec<u64>]), amount: &ConfidentialAmount) -> bool {
+    binary::verify_binary(params, &amount.commitment, AMOUNT_BITS, &amount.range_proof)
+}
+
+/// Sums commitments homomorphically.
+///
+/// # Returns
+/// A `RevelerResult` opened by the sum of the messages and the sum of the randomness.
+pub fn sum(commitments: &[RevelerResult]) -> RevelerResult {
+    let q = CommitConfig::default().modulus;
+    let points: Vec<&[u64]> = commitments.iter().map(|commitment| commitment.commitment_point.as_slice()).collect();
+    result_of(combine(&points, &[], q))
+}
+
+/// Proves that the inputs balance the outputs and the fee.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the amounts.
+/// - `inputs`: The openings of the spent commitments.
+/// - `outputs`: The openings of the created commitments.
+/// - `fee`: The public fee.
+///
+/// # Returns
+/// The balance `BinaryProof`, `CommitError::InvalidOpening` if the amounts do not balance, or
+/// `CommitError::ComputationError` if there are more than `MAX_TRANSFERS` inputs or outputs.
+#[cfg(feature = "prover")]
+pub fn prove_balance(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    inputs: &[AmountOpening],
+    outputs: &[AmountOpening],
+    fee: u64,
+) -> Result<BinaryProof, CommitError> {
+    if inputs.len() > MAX_TRANSFERS || outputs.len() > MAX_TRANSFERS {
+        return Err(CommitError::ComputationError);
+    }
+    let total_in: u128 = inputs.iter().map(|opening| opening.amount as u128).sum();
+    let total_out: u128 = outputs.iter().map(|opening| opening.amount as u128).sum::<u128>() + fee as u128;
+    if total_in != total_out {
+        return Err(CommitError::InvalidOpening);
+    }
+
+    // d_j = Σ 输入比特 - Σ 输出比特 - 手续费比特
+    let debits = outputs.iter().map(|opening| opening.amount).chain([fee]);
+    let differences: Vec<i128> = (0..AMOUNT_BITS)
+        .map(|j| {
+            let credit: i128 = inputs.iter().map(|opening| ((opening.amount >> j) & 1) as i128).sum();
+            let debit: i128 = debits.clone().map(|amount| ((amount >> j) & 1) as i128).sum();
+            credit - debit
+        })
+        .collect();
+
+    let mut carry_bits = Vec::with_capacity(CARRY_WIDTH);
+    let mut prefix = 0i128;
+    for (j, difference) in differences.iter().enumerate().take(AMOUNT_BITS - 1) {
+        prefix += difference << j;
+        let carry = (prefix >> (j + 1)) + CARRY_OFFSET as i128;
+        carry_bits.extend((0..CARRY_BITS).map(|t| ((carry >> t) & 1) as u64));
+    }
+
+    let q = CommitConfig::default().modulus;
+    let mut randomness = vec![0u64; RANDOMNESS_LEN];
+    for (opening, add) in inputs.iter().map(|opening| (opening, true)).chain(outputs.iter().map(|opening| (opening, false))) {
+        for (acc, &x) in randomness.iter_mut().zip(&opening.local_r) {
+            *acc = if add { (*acc + x % q) % q } else { (*acc + q - x % q) % q };
+        }
+    }
+
+    let input_commitments = inputs.iter().map(|opening| opening.commitment(params)).collect::<Result<Vec<_>, _>>()?;
+    let output_commitments = outputs.iter().map(|opening| opening.commitment(params)).collect::<Result<Vec<_>, _>>()?;
+    let target = balance_target(params, &input_commitments, &output_commitments, fee)?;
+    let summands = inputs.len() + outputs.len();
+    let relation = balance_relation(params, summands, target);
+    let mut blocks = vec![carry_bits];
+    blocks.extend(binary::decompose(&randomness, summands as u64, q));
+    let witness = Witness { free: Vec::new(), blocks };
+    binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads)
+}
+
+/// Verifies a proof that the inputs balance the outputs and the fee.
+///
+/// # Returns
+/// `true` if there are at most `MAX_TRANSFERS` inputs and outputs, every commitment hash is
+/// valid, and the proof checks out.
+pub fn verify_balance(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    inputs: &[RevelerResult],
+    outputs: &[RevelerResult],
+    fee: u64,
+    proof: &BinaryProof,
+) -> bool {
+    if inputs.len() > MAX_TRANSFERS
+        || outputs.len() > MAX_TRANSFERS
+        || !inputs.iter().chain(outputs).all(RevelerCommit::verify)
+    {
+        return false;
+    }
+    let Ok(target) = balance_target(params, inputs, outputs, fee) else {
+        return false;
+    };
+    let relation = balance_relation(params, inputs.len() + outputs.len(), target);
+    binary::verify_relation(&relation, &proof.rounds, binary::round_count())
+}
+
+/// Spends committed amounts into new commitments to `outputs` and a public fee.
+///
+/// # Returns
+/// The `Transaction` and the openings of its outputs, or the `CommitError` of `prove_balance`.
+#[cfg(feature = "prover")]
+pub fn transfer(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    inputs: &[AmountOpening],
+    outputs: &[u64],
+    fee: u64,
+) -> Result<(Transaction, Vec<AmountOpening>), CommitError> {
+    let input_commitments = inputs.iter().map(|opening| opening.commitment(params)).collect::<Result<Vec<_>, _>>()?;
+    let (confidential, openings): (Vec<ConfidentialAmount>, Vec<AmountOpening>) = outputs.iter()
+        .map(|&amount| commit_amount(params, amount))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip();
+    let balance_proof = prove_balance(params, inputs, &openings, fee)?;
+
+    let transaction = Transaction { inputs: input_commitments, outputs: confidential, fee, balance_proof };
+    Ok((transaction, openings))
+}
+
+/// Verifies the range proofs of the outputs and the balance proof of a transaction.
+///
+/// The inputs are not range-checked here: they are the outputs of earlier transactions, verified
+/// when those were accepted.
+pub fn verify_transaction(params: (&[Vec<u64>], &[Vec<u64>]), transaction: &Transaction) -> bool {
+    let outputs: Vec<RevelerResult> = transaction.outputs.iter().map(|output| output.commitment.clone()).collect();
+    transaction.outputs.iter().all(|output| verify_amount(params, output))
+        && verify_balance(params, &transaction.inputs, &outputs, transaction.fee, &transaction.balance_proof)
+}
+
+fn amount_bits(amount: u64) -> Vec<u64> {
+    (0..AMOUNT_BITS).map(|j| (amount >> j) & 1).collect()
+}
+
+// Compute Σ added - Σ subtracted coordinate-wise modulo q.
+fn combine(added: &[&[u64]], subtracted: &[&[u64]], q: u64) -> Vec<u64> {
+    let mut point = vec![0u64; LOCAL_N];
+    for (values, add) in added.iter().map(|values| (values, true)).chain(subtracted.iter().map(|values| (values, false))) {
+        for (acc, &x) in point.iter_mut().zip(values.iter()) {
+            *acc = if add { (*acc + x % q) % q } else { (*acc + q - x % q) % q };
+        }
+    }
+    point
+}
+
+fn result_of(point: Vec<u64>) -> RevelerResult {
+    let hash = utils::hash_to_commitment(&point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+    RevelerResult::new(point, hash)
+}
+
+// The balance statement: `target` opens to the embedded carries with the signed sum of the
+// randomness of `summands` commitments.
+fn balance_relation<'a>(params: (&'a [Vec<u64>], &'a [Vec<u64>]), summands: usize, target: Vec<u64>) -> Relation<'a> {
+    let weights = binary::weights(summands as u64);
+    let mut blocks = vec![Block::Bits(CARRY_WIDTH)];
+    blocks.extend(weights.iter().map(|_| Block::Ternary(RANDOMNESS_LEN)));
+    Relation {
+        label: BALANCE_LABEL,
+        statement: binary::params_digest(params),
+        free: 0,
+        blocks,
+        map: Box::new(move |_, heads| {
+            let (carries, pieces) = heads.split_first().ok_or(CommitError::ComputationError)?;
+            let randomness = binary::recompose(pieces, &weights, CommitConfig::default().modulus);
+            binary::point(params, &embed_carries(carries), &randomness)
+        }),
+        target,
+    }
+}
+
+// Map the carry bits to the linear part of d_j = 2·c_j - c_{j-1} modulo q.
+fn embed_carries(bits: &[u64]) -> Vec<u64> {
+    let q = CommitConfig::default().modulus;
+    let carries: Vec<u64> = bits.chunks(CARRY_BITS)
+        .map(|chunk| chunk.iter().enumerate().fold(0, |acc, (t, &bit)| (acc + ((bit % q) << t)) % q))
+        .collect();
+    (0..AMOUNT_BITS)
+        .map(|j| {
+            let current = carries.get(j).map_or(0, |&c| 2 * c % q);
+            let previous = j.checked_sub(1).and_then(|i| carries.get(i)).map_or(0, |&c| c % q);
+            (current + q - previous) % q
+        })
+        .collect()
+}
+
+// Compute the target D - A·offset of the balance relation, where D = Σ inputs - Σ outputs - A·fee
+// and offset is the constant part of 2·c_j - c_{j-1} contributed by CARRY_OFFSET.
+fn balance_target(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    inputs: &[RevelerResult],
+    outputs: &[RevelerResult],
+    fee: u64,
+) -> Result<Vec<u64>, CommitError> {
+    let config = CommitConfig::default();
+    let q = config.modulus;
+    let offset = CARRY_OFFSET % q;
+
+    let mut constant = amount_bits(fee);
+    for (j, value) in constant.iter_mut().enumerate() {
+        let carry_term = match j {
+            0 => q - 2 * offset % q,
+            j if j == AMOUNT_BITS - 1 => offset,
+            _ => q - offset,
+        };
+        *value = (*value + carry_term) % q;
+    }
+    let constant_point = compute_point(params.0, params.1, &constant, &[], &config)?;
+
+    let added: Vec<&[u64]> = inputs.iter().map(|commitment| commitment.commitment_point.as_slice()).collect();
+    let mut subtracted: Vec<&[u64]> = outputs.iter().map(|commitment| commitment.commitment_point.as_slice()).collect();
+    subtracted.push(&constant_point);
+    Ok(combine(&added, &subtracted, q))
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::key::CommitmentKey;
+    use super::*;
+
+    #[test]
+    fn honest_transfer_verifies() {
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(230));
+        let params = key.as_pair();
+        let (input, opening) = commit_amount(params, 100).unwrap();
+        assert!(verify_amount(params, &input));
+
+        let (transaction, _) = transfer(params, &[opening], &[60, 38], 2).unwrap();
+        assert!(verify_transaction(params, &transaction));
+        let outputs: Vec<RevelerResult> = transaction.outputs.iter().map(|output| output.commitment.clone()).collect();
+        assert!(!verify_balance(params, &transaction.inputs, &outputs, 3, &transaction.balance_proof));
+    }
+
+    #[test]
+    fn minted_outputs_are_rejected() {
+        // 输入 5、输出 8：伪造者声称进位全为偏移量，并把差值 A·d 塞进 [B | I] 的单位块
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(230));
+        let params = key.as_pair();
+        let q = CommitConfig::default().modulus;
+        let (input, input_opening) = commit_amount(params, 5).unwrap();
+        let (output, output_opening) = commit_amount(params, 8).unwrap();
+        assert!(prove_balance(params, std::slice::from_ref(&input_opening), std::slice::from_ref(&output_opening), 0).is_err());
+
+        let differences: Vec<u64> = (0..AMOUNT_BITS).map(|j| (((5u64 >> j) & 1) + q - ((8u64 >> j) & 1)) % q).collect();
+        let shift = compute_point(params.0, params.1, &differences, &[], &CommitConfig::default()).unwrap();
+        let mut randomness: Vec<u64> = input_opening.local_r.iter().zip(&output_opening.local_r).map(|(&a, &b)| (a + q - b) % q).collect();
+        for (slot, &x) in randomness.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(&shift) {
+            *slot = (*slot + x) % q;
+        }
+        let carry_bits: Vec<u64> = (0..AMOUNT_BITS - 1).flat_map(|_| (0..CARRY_BITS).map(|t| u64::from(t == CARRY_BITS - 1))).collect();
+        let blocks = vec![carry_bits, randomness, vec![0; RANDOMNESS_LEN]];
+
+        let target = balance_target(params, std::slice::from_ref(&input.commitment), std::slice::from_ref(&output.commitment), 0).unwrap();
+        let relation = balance_relation(params, 2, target.clone());
+        let heads: Vec<&[u64]> = blocks.iter().map(Vec::as_slice).collect();
+        assert_eq!((relation.map)(&[], &heads).unwrap(), target);
+
+        let witness = Witness { free: Vec::new(), blocks };
+        let proof = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap();
+        assert!(!verify_balance(params, &[input.commitment], &[output.commitment], 0, &proof));
+    }
+}
@@ -0,0 +1,542 @@
+//! This module provides self-contained archives of a commitment with its opening, and
+//! `verify_any`, which checks an archive of any registered scheme.
+//!
+//! An archive is the scheme header followed by the length-prefixed commitment point and hash and
+//! the scheme-specific opening. For the schemes of this crate the opening is the length-prefixed
+//! `A`, `B`, `m` and `r`. `verify_any` resolves the header and hands the rest to the verifier
+//! registered for that scheme, so archival tools check artifacts of every scheme through one call,
+//! and backends or format versions added later plug in with `register_verifier` instead of a new
+//! code path in every verifier. Three verifiers are built in:
+//! - the current scheme, `SchemeId::current()`, checking the opening of the commitment;
+//! - `legacy_scheme()`, the hash-only archives of format version 0: the commitment and the
+//!   matrices without an opening, checked as `RevelerCommit::verify` did, by the binding hash;
+//! - `pedersen_scheme()` with the `curve` feature: the generator `H`, the compressed commitment,
+//!   the message and the blinding.
+//!
+//! An archive carries the parameters it was produced under, and anyone can produce an archive of
+//! any message under parameters of their own. `verify_any` therefore takes the digest of the
+//! trusted parameters, `params::params_digest` or `PedersenKey::digest`, and refuses archives
+//! produced under other ones.
+//!
+//! An `ArchiveCollection` stores many commitments produced under the same parameters: the matrices
+//! once, then the commitment and opening of every entry. With `ArchiveCodec::Delta` every vector
+//! is written as the difference to the same vector of the previous entry, each coordinate as a
+//! zigzag varint, which shrinks collections of points modulo a 16-bit `q` and of similar messages
+//! about threefold. `ArchiveCollection::from_bytes` reads either codec, so readers need not know
+//! which one the writer chose.
+
+use std::sync::{OnceLock, RwLock};
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+#[cfg(feature = "curve")]
+use crate::pedersen::{PedersenCommitment, PedersenKey, PedersenOpening, PedersenScheme};
+use crate::policy::VerifyPolicy;
+use crate::scheme::{self, SchemeId};
+use crate::{compute_point, limits, params, revocation, utils, RevelerCommit, RevelerResult};
+
+/// Checks the body of an archive, i.e. everything after the scheme header.
+///
+/// # Returns
+/// The digest of the parameters the archive was checked under, or the `CommitError` of the check.
+pub type ArchiveVerifier = fn(&SchemeId, &[u8]) -> Result<Vec<u8>, CommitError>;
+
+fn verifiers() -> &'static RwLock<Vec<(SchemeId, ArchiveVerifier)>> {
+    static VERIFIERS: OnceLock<RwLock<Vec<(SchemeId, ArchiveVerifier)>>> = OnceLock::new();
+    VERIFIERS.get_or_init(|| {
+        #[allow(unused_mut)]
+        let mut builtin = vec![
+            (SchemeId::current(), verify_lattice as ArchiveVerifier),
+            (legacy_scheme(), verify_legacy as ArchiveVerifier),
+        ];
+        #[cfg(feature = "curve")]
+        builtin.push((pedersen_scheme(), verify_pedersen as ArchiveVerifier));
+        RwLock::new(builtin)
+    })
+}
+
+/// Registers the verifier of the archives of `scheme`, and `scheme` itself with
+/// `scheme::register`, replacing any verifier registered for it before.
+pub fn register_verifier(scheme: SchemeId, verifier: ArchiveVerifier) {
+    scheme::register(scheme.clone());
+    let mut registered = verifiers().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match registered.iter_mut().find(|(known, _)| *known == scheme) {
+        Some(entry) => entry.1 = verifier,
+        None => registered.push((scheme, verifier)),
+    }
+}
+
+/// Returns the scheme of the hash-only archives of format version 0.
+pub fn legacy_scheme() -> SchemeId {
+    SchemeId { version: 0, ..SchemeId::current() }
+}
+
+/// Returns the scheme of the archives of Pedersen commitments. The group order does not fit the
+/// modulus of a `SchemeId`, which is `0`, and the commitment is not hashed.
+#[cfg(feature = "curve")]
+pub fn pedersen_scheme() -> SchemeId {
+    SchemeId {
+        version: scheme::FORMAT_VERSION,
+        backend: "pedersen".to_string(),
+        modulus: 0,
+        dimension: 1,
+        hasher: "none".to_string(),
+    }
+}
+
+/// Encodes a commitment and its opening as an archive of the current scheme.
+///
+/// # Parameters
+/// - `commitment`: A reference to the `RevelerResult`.
+/// - `opening`: A reference to the `RevelerCommit` it was computed from.
+///
+/// # Returns
+/// The scheme header followed by the length-prefixed commitment point and hash and the
+/// length-prefixed `A`, `B`, `m` and `r`.
+pub fn to_archive_bytes(commitment: &RevelerResult, opening: &RevelerCommit) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    scheme::write_header(&mut bytes);
+    bytes.extend(utils::encode_vector(&commitment.commitment_point));
+    bytes.extend(utils::encode_bytes(&commitment.commitment_hash));
+    bytes.extend(utils::encode_matrix(&opening.local_a));
+    bytes.extend(utils::encode_matrix(&opening.local_b));
+    bytes.extend(utils::encode_vector(&opening.local_m));
+    bytes.extend(utils::encode_vector(&opening.local_r));
+    bytes
+}
+
+/// Encodes a commitment without its opening as an archive of `legacy_scheme()`.
+///
+/// # Returns
+/// The legacy header followed by the length-prefixed commitment point and hash and the
+/// length-prefixed `A` and `B`.
+pub fn to_legacy_archive_bytes(commitment: &RevelerResult, params: (&[Vec<u64>], &[Vec<u64>])) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    scheme::write_scheme_header(&legacy_scheme(), &mut bytes);
+    bytes.extend(utils::encode_vector(&commitment.commitment_point));
+    bytes.extend(utils::encode_bytes(&commitment.commitment_hash));
+    bytes.extend(utils::encode_matrix(params.0));
+    bytes.extend(utils::encode_matrix(params.1));
+    bytes
+}
+
+/// Encodes a Pedersen commitment and its opening as an archive of `pedersen_scheme()`.
+///
+/// # Returns
+/// The header followed by the length-prefixed generator `H`, commitment, message and blinding,
+/// each 32 bytes.
+#[cfg(feature = "curve")]
+pub fn to_pedersen_archive_bytes(key: &PedersenKey, commitment: &PedersenCommitment, opening: &PedersenOpening) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    scheme::write_scheme_header(&pedersen_scheme(), &mut bytes);
+    bytes.extend(utils::encode_bytes(&key.h()));
+    bytes.extend(utils::encode_bytes(&commitment.point));
+    bytes.extend(utils::encode_bytes(opening.value().as_bytes()));
+    bytes.extend(utils::encode_bytes(opening.blinding().as_bytes()));
+    bytes
+}
+
+/// Checks an archive of any scheme with a registered verifier, under trusted parameters.
+///
+/// # Parameters
+/// - `bytes`: The archive, starting with its scheme header.
+/// - `params_digest`: The digest of the trusted parameters, `params::params_digest` of the
+///   matrices or `PedersenKey::digest`.
+///
+/// # Returns
+/// The `SchemeId` of the archive if its opening opens its commitment under the trusted parameters,
+/// `CommitError::UnsupportedScheme` if the header names a scheme without a verifier,
+/// `CommitError::LimitExceeded` if the archive is larger than `max_record_size` of the installed
+/// `limits::DecodeLimits`, `CommitError::MalformedEncoding` if the archive is not a complete
+/// encoding, `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if `revocation::check`
+/// refuses its parameters, or `CommitError::InvalidOpening` if the opening does not reproduce the commitment or the
+/// archive was produced under other parameters.
+pub fn verify_any(bytes: &[u8], params_digest: &[u8]) -> Result<SchemeId, CommitError> {
+    limits::check(bytes.len(), limits::current().max_record_size)?;
+    let (scheme, body) = scheme::parse_header(bytes)?;
+    let verifier = verifiers()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find(|(known, _)| *known == scheme)
+        .map(|&(_, verifier)| verifier)
+        .ok_or(CommitError::UnsupportedScheme)?;
+    if verifier(&scheme, body)? != params_digest {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(scheme)
+}
+
+// Verifier of the lattice schemes: the hash of the point, then A·m + B·r under the modulus of
+// the scheme.
+fn verify_lattice(scheme: &SchemeId, body: &[u8]) -> Result<Vec<u8>, CommitError> {
+    let (commitment_point, rest) = utils::decode_vector(body)?;
+    let (commitment_hash, rest) = utils::decode_bytes(rest)?;
+    let (local_a, rest) = utils::decode_matrix(rest)?;
+    let (local_b, rest) = utils::decode_matrix(rest)?;
+    let (local_m, rest) = utils::decode_vector(rest)?;
+    let (local_r, rest) = utils::decode_vector(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    revocation::check((&local_a, &local_b))?;
+
+    let commitment = RevelerResult::new(commitment_point, commitment_hash);
+    if !RevelerCommit::verify(&commitment) {
+        return Err(CommitError::InvalidOpening);
+    }
+    let config = CommitConfig::default().with_modulus(scheme.modulus);
+    if VerifyPolicy::new().check_norms(&local_m, &local_r, config.modulus).is_err() {
+        return Err(CommitError::InvalidOpening);
+    }
+    let point = compute_point(&local_a, &local_b, &local_m, &local_r, &config).map_err(|_| CommitError::InvalidOpening)?;
+    if point != commitment.commitment_point {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(params::params_digest((&local_a, &local_b)))
+}
+
+// Verifier of the legacy archives: the hash of the point, which must have `N` coordinates below
+// `q`. There is no opening to check.
+fn verify_legacy(scheme: &SchemeId, body: &[u8]) -> Result<Vec<u8>, CommitError> {
+    let (commitment_point, rest) = utils::decode_vector(body)?;
+    let (commitment_hash, rest) = utils::decode_bytes(rest)?;
+    let (local_a, rest) = utils::decode_matrix(rest)?;
+    let (local_b, rest) = utils::decode_matrix(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    revocation::check((&local_a, &local_b))?;
+
+    let commitment = RevelerResult::new(commitment_point, commitment_hash);
+    if commitment.commitment_point.len() != scheme.dimension
+        || commitment.commitment_point.iter().any(|&x| x >= scheme.modulus)
+        || !RevelerCommit::verify(&commitment)
+    {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(params::params_digest((&local_a, &local_b)))
+}
+
+// Verifier of the Pedersen archives: m·G + r·H under the generator `H` of the archive.
+#[cfg(feature = "curve")]
+fn verify_pedersen(_: &SchemeId, body: &[u8]) -> Result<Vec<u8>, CommitError> {
+    use crate::backend::CommitmentScheme;
+    use curve25519_dalek::scalar::Scalar;
+
+    fn decode_32(bytes: &[u8]) -> Result<([u8; 32], &[u8]), CommitError> {
+        let (value, rest) = utils::decode_bytes(bytes)?;
+        Ok((value.try_into().map_err(|_| CommitError::MalformedEncoding)?, rest))
+    }
+    let scalar = |bytes: [u8; 32]| Option::<Scalar>::from(Scalar::from_canonical_bytes(bytes)).ok_or(CommitError::MalformedEncoding);
+
+    let (h, rest) = decode_32(body)?;
+    let (point, rest) = decode_32(rest)?;
+    let (value, rest) = decode_32(rest)?;
+    let (blinding, rest) = decode_32(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let key = PedersenKey::from_h(h)?;
+    let value = scalar(value)?;
+    let opening = PedersenOpening::new(value, scalar(blinding)?);
+    if !PedersenScheme::verify(&key, &PedersenCommitment { point }, &value, &opening)? {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(key.digest())
+}
+
+/// Encoding of the entries of an `ArchiveCollection`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveCodec {
+    /// Fixed-width big-endian integers, as in single archives.
+    #[default]
+    Plain,
+    /// Zigzag varints of the differences to the previous entry.
+    Delta,
+}
+
+/// Commitment and opening stored in an `ArchiveCollection`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub commitment: RevelerResult, // The commitment
+    pub local_m: Vec<u64>,         // The message it opens to
+    pub local_r: Vec<u64>,         // The randomness it opens to
+}
+
+/// Archive of many commitments under the same parameters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveCollection {
+    pub local_a: Vec<Vec<u64>>,     // The matrix A shared by every entry
+    pub local_b: Vec<Vec<u64>>,     // The matrix B shared by every entry
+    pub entries: Vec<ArchiveEntry>, // The entries, in insertion order
+}
+
+impl ArchiveCollection {
+    /// Creates an empty collection under the parameters `(A, B)`.
+    pub fn new(local_a: Vec<Vec<u64>>, local_b: Vec<Vec<u64>>) -> Self {
+        ArchiveCollection { local_a, local_b, entries: Vec::new() }
+    }
+
+    /// Appends a commitment with its opening.
+    pub fn with_entry(mut self, commitment: RevelerResult, local_m: Vec<u64>, local_r: Vec<u64>) -> Self {
+        self.entries.push(ArchiveEntry { commitment, local_m, local_r });
+        self
+    }
+
+    /// Encodes the collection.
+    ///
+    /// # Parameters
+    /// - `codec`: The `ArchiveCodec` of the matrices and entries.
+    ///
+    /// # Returns
+    /// The scheme header, a codec byte, then `A`, `B`, the number of entries and every entry as
+    /// its commitment point, hash, `m` and `r`, in the encoding of `codec`.
+    pub fn to_bytes(&self, codec: ArchiveCodec) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        match codec {
+            ArchiveCodec::Plain => {
+                bytes.push(0);
+                bytes.extend(utils::encode_matrix(&self.local_a));
+                bytes.extend(utils::encode_matrix(&self.local_b));
+                bytes.extend_from_slice(&(self.entries.len() as u64).to_be_bytes());
+                for entry in &self.entries {
+                    bytes.extend(utils::encode_vector(&entry.commitment.commitment_point));
+                    bytes.extend(utils::encode_bytes(&entry.commitment.commitment_hash));
+                    bytes.extend(utils::encode_vector(&entry.local_m));
+                    bytes.extend(utils::encode_vector(&entry.local_r));
+                }
+            }
+            ArchiveCodec::Delta => {
+                bytes.push(1);
+                encode_delta_matrix(&self.local_a, &mut bytes);
+                encode_delta_matrix(&self.local_b, &mut bytes);
+                utils::encode_varint(self.entries.len() as u64, &mut bytes);
+                let mut previous: Option<&ArchiveEntry> = None;
+                for entry in &self.entries {
+                    let (point, local_m, local_r) = match previous {
+                        Some(previous) => (&previous.commitment.commitment_point[..], &previous.local_m[..], &previous.local_r[..]),
+                        None => (&[][..], &[][..], &[][..]),
+                    };
+                    encode_delta(&entry.commitment.commitment_point, point, &mut bytes);
+                    utils::encode_varint(entry.commitment.commitment_hash.len() as u64, &mut bytes);
+                    bytes.extend_from_slice(&entry.commitment.commitment_hash);
+                    encode_delta(&entry.local_m, local_m, &mut bytes);
+                    encode_delta(&entry.local_r, local_r, &mut bytes);
+                    previous = Some(entry);
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a collection written by `to_bytes` with either codec.
+    ///
+    /// # Returns
+    /// The decoded `ArchiveCollection`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, `CommitError::LimitExceeded` if the matrices, a vector or an entry
+    /// exceed the installed `limits::DecodeLimits`, or `CommitError::MalformedEncoding` if the
+    /// input is not a complete encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (&codec, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let (collection, rest) = match codec {
+            0 => decode_plain(rest)?,
+            1 => decode_delta_collection(rest)?,
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(collection)
+    }
+
+    /// Checks every entry with `RevelerCommit::verify_opening`.
+    ///
+    /// # Returns
+    /// `Ok(())`, `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if
+    /// `revocation::check` refuses the parameters, or `CommitError::InvalidOpening` if an entry does
+    /// not open its commitment.
+    pub fn verify(&self) -> Result<(), CommitError> {
+        for entry in &self.entries {
+            let opened = RevelerCommit::verify_opening((&self.local_a, &self.local_b), &entry.commitment, &entry.local_m, &entry.local_r);
+            match opened {
+                Ok(true) => {}
+                Err(error @ (CommitError::ParamsRevoked | CommitError::ParamsExpired)) => return Err(error),
+                _ => return Err(CommitError::InvalidOpening),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_plain(bytes: &[u8]) -> Result<(ArchiveCollection, &[u8]), CommitError> {
+    let (local_a, rest) = utils::decode_matrix(bytes)?;
+    let (local_b, rest) = utils::decode_matrix(rest)?;
+    let (count, mut rest) = utils::decode_u64(rest)?;
+    // 每个条目至少占 32 字节，先检查数量避免恶意前缀导致大量内存分配
+    if count > (rest.len() / 32) as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let mut entries = Vec::with_capacity(count as usize);
+    let max_record_size = limits::current().max_record_size;
+    for _ in 0..count {
+        let (commitment_point, next) = utils::decode_vector(rest)?;
+        let (commitment_hash, next) = utils::decode_bytes(next)?;
+        let (local_m, next) = utils::decode_vector(next)?;
+        let (local_r, next) = utils::decode_vector(next)?;
+        limits::check(rest.len() - next.len(), max_record_size)?;
+        entries.push(ArchiveEntry { commitment: RevelerResult::new(commitment_point, commitment_hash), local_m, local_r });
+        rest = next;
+    }
+    Ok((ArchiveCollection { local_a, local_b, entries }, rest))
+}
+
+fn decode_delta_collection(bytes: &[u8]) -> Result<(ArchiveCollection, &[u8]), CommitError> {
+    let (local_a, rest) = decode_delta_matrix(bytes)?;
+    let (local_b, rest) = decode_delta_matrix(rest)?;
+    let (count, mut rest) = utils::decode_varint(rest)?;
+    // 每个条目至少占 4 字节
+    if count > (rest.len() / 4) as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let mut entries: Vec<ArchiveEntry> = Vec::with_capacity(count as usize);
+    let max_record_size = limits::current().max_record_size;
+    for _ in 0..count {
+        let (point, local_m, local_r) = match entries.last() {
+            Some(previous) => (&previous.commitment.commitment_point[..], &previous.local_m[..], &previous.local_r[..]),
+            None => (&[][..], &[][..], &[][..]),
+        };
+        let (commitment_point, next) = decode_delta(rest, point)?;
+        let (hash_len, next) = utils::decode_varint(next)?;
+        if hash_len > next.len() as u64 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let (commitment_hash, next) = next.split_at(hash_len as usize);
+        let (local_m, next) = decode_delta(next, local_m)?;
+        let (local_r, next) = decode_delta(next, local_r)?;
+        limits::check(rest.len() - next.len(), max_record_size)?;
+        entries.push(ArchiveEntry {
+            commitment: RevelerResult::new(commitment_point, commitment_hash.to_vec()),
+            local_m,
+            local_r,
+        });
+        rest = next;
+    }
+    Ok((ArchiveCollection { local_a, local_b, entries }, rest))
+}
+
+// Write the length of the vector, then the wrapping difference of every coordinate to the same
+// coordinate of `previous` (zero past its end) as a zigzag varint, so small changes in either
+// direction take one or two bytes.
+fn encode_delta(values: &[u64], previous: &[u64], bytes: &mut Vec<u8>) {
+    utils::encode_varint(values.len() as u64, bytes);
+    for (index, &value) in values.iter().enumerate() {
+        let difference = value.wrapping_sub(previous.get(index).copied().unwrap_or(0)) as i64;
+        utils::encode_varint(((difference << 1) ^ (difference >> 63)) as u64, bytes);
+    }
+}
+
+fn decode_delta<'a>(bytes: &'a [u8], previous: &[u64]) -> Result<(Vec<u64>, &'a [u8]), CommitError> {
+    decode_delta_within(bytes, previous, limits::current().max_point_len)
+}
+
+fn decode_delta_within<'a>(bytes: &'a [u8], previous: &[u64], max_len: usize) -> Result<(Vec<u64>, &'a [u8]), CommitError> {
+    let (len, mut rest) = utils::decode_varint(bytes)?;
+    // 每个坐标至少占 1 字节
+    if len > rest.len() as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    limits::check(usize::try_from(len).unwrap_or(usize::MAX), max_len)?;
+    let mut values = Vec::with_capacity(len as usize);
+    for index in 0..len as usize {
+        let (zigzag, next) = utils::decode_varint(rest)?;
+        let difference = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+        values.push(previous.get(index).copied().unwrap_or(0).wrapping_add(difference as u64));
+        rest = next;
+    }
+    Ok((values, rest))
+}
+
+// Matrices are uniform, so their rows are written against zero rather than the previous row.
+fn encode_delta_matrix(rows: &[Vec<u64>], bytes: &mut Vec<u8>) {
+    utils::encode_varint(rows.len() as u64, bytes);
+    for row in rows {
+        encode_delta(row, &[], bytes);
+    }
+}
+
+fn decode_delta_matrix(bytes: &[u8]) -> Result<(Vec<Vec<u64>>, &[u8]), CommitError> {
+    let (count, mut rest) = utils::decode_varint(bytes)?;
+    if count > rest.len() as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let max_dimension = limits::current().max_dimension;
+    limits::check(usize::try_from(count).unwrap_or(usize::MAX), max_dimension)?;
+    let mut rows = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (row, next) = decode_delta_within(rest, &[], max_dimension)?;
+        rows.push(row);
+        rest = next;
+    }
+    Ok((rows, rest))
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::key::CommitmentKey;
+    use super::*;
+
+    #[test]
+    fn archives_verify_only_under_the_trusted_params() {
+        let mut rng = StdRng::seed_from_u64(244);
+        let (key, other) = (CommitmentKey::setup(&mut rng), CommitmentKey::setup(&mut rng));
+        let r = utils::generate_randomness(&mut rng);
+        let opening = RevelerCommit::new(key.as_pair().0.to_vec(), key.as_pair().1.to_vec(), vec![1, 2, 3], r.clone());
+        let commitment = opening.commit().unwrap();
+        let bytes = to_archive_bytes(&commitment, &opening);
+        assert_eq!(verify_any(&bytes, &key.digest()), Ok(SchemeId::current()));
+        assert_eq!(verify_any(&bytes, &other.digest()), Err(CommitError::InvalidOpening));
+
+        // 攻击者用自己的参数构造的存档
+        let forged = RevelerCommit::new(other.as_pair().0.to_vec(), other.as_pair().1.to_vec(), vec![9], r);
+        let forged = to_archive_bytes(&forged.commit().unwrap(), &forged);
+        assert_eq!(verify_any(&forged, &key.digest()), Err(CommitError::InvalidOpening));
+    }
+
+    #[test]
+    fn legacy_archives_check_the_hash() {
+        let mut rng = StdRng::seed_from_u64(244);
+        let key = CommitmentKey::setup(&mut rng);
+        let commitment = key.commit(&[4, 5], &utils::generate_randomness(&mut rng)).unwrap();
+        let bytes = to_legacy_archive_bytes(&commitment, key.as_pair());
+        assert_eq!(verify_any(&bytes, &key.digest()), Ok(legacy_scheme()));
+
+        let (mut point, hash) = commitment.into_parts();
+        point[0] = (point[0] + 1) % scheme::SchemeId::current().modulus;
+        let tampered = to_legacy_archive_bytes(&RevelerResult::new(point, hash), key.as_pair());
+        assert_eq!(verify_any(&tampered, &key.digest()), Err(CommitError::InvalidOpening));
+    }
+
+    #[cfg(feature = "curve")]
+    #[test]
+    fn pedersen_archives_verify_under_the_key_digest() {
+        use crate::backend::CommitmentScheme;
+        use curve25519_dalek::scalar::Scalar;
+
+        let mut rng = StdRng::seed_from_u64(244);
+        let key = PedersenScheme::setup(&mut rng);
+        let (commitment, opening) = PedersenScheme::commit(&key, &Scalar::from(7u64), &mut rng).unwrap();
+        let bytes = to_pedersen_archive_bytes(&key, &commitment, &opening);
+        assert_eq!(verify_any(&bytes, &key.digest()), Ok(pedersen_scheme()));
+        assert_eq!(verify_any(&bytes, &PedersenKey::standard().digest()), Err(CommitError::InvalidOpening));
+
+        let wrong = PedersenOpening::new(Scalar::from(8u64), opening.blinding());
+        let bytes = to_pedersen_archive_bytes(&key, &commitment, &wrong);
+        assert_eq!(verify_any(&bytes, &key.digest()), Err(CommitError::InvalidOpening));
+    }
+}
@@ -0,0 +1,234 @@
+//! This module provides the modular arithmetic used by the commitment computation.
+//!
+//! Every addition and multiplication on field elements goes through an `ArithmeticPolicy`, which
+//! decides how an overflow of the intermediate `u64` values is handled:
+//! - `Checked`: overflow is reported as `CommitError::ArithmeticOverflow`. Default in debug builds.
+//! - `Wrapping`: branchless wrapping arithmetic. Default in release builds.
+//! - `Saturating`: overflowing values are clamped to `u64::MAX`, which makes them easy to spot
+//!   while diagnosing parameter sets.
+//!
+//! For every modulus up to `MAX_SUPPORTED_Q` the operations below cannot overflow, which is
+//! asserted at compile time at the end of this module.
+//!
+//! Moduli up to `NARROW_MAX_Q`, such as `LOCAL_Q`, form the narrow parameter sets: reduced field
+//! elements fit in a `u16` and their products in a `u32`. Each coordinate of the commitment point
+//! reduces the inputs once into `u32` values, sums their products and reduces the sum once instead
+//! of every term. On 32-bit targets such as armv7, where a `u64` remainder is a library call,
+//! this is what keeps verification fast; on 64-bit targets it saves the per-term remainders.
+//!
+//! Each coordinate of the commitment point sums the terms of one row in a tree of fixed arity
+//! `REDUCTION_ARITY`, see `ArithmeticPolicy::tree_sum_mod`. The sum is exact modulo `q`, so any
+//! order gives the same value today, but the order is part of the scheme spec: a later wide
+//! accumulator, vectorized or floating-point sum must reduce in the same tree, so the point never
+//! depends on the thread count, the chunking of the rows or the run.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::fft::LOCAL_Q;
+
+/// Largest modulus for which the accumulation `(acc + x + y) % q` and the product `(a * b) % q`
+/// of reduced field elements fit in a `u64`.
+pub const MAX_SUPPORTED_Q: u64 = u32::MAX as u64;
+
+/// Largest modulus of the narrow parameter sets, whose reduced field elements fit in a `u16`.
+pub const NARROW_MAX_Q: u64 = 1 << 16;
+
+/// Number of values summed by each node of the reduction tree of the commitment point.
+pub const REDUCTION_ARITY: usize = 4;
+
+/// Returns whether `q` belongs to the narrow parameter sets.
+pub const fn is_narrow(q: u64) -> bool {
+    q <= NARROW_MAX_Q
+}
+
+/// Overflow handling applied to the intermediate `u64` arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithmeticPolicy {
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+impl ArithmeticPolicy {
+    /// Returns `Checked` in debug builds and `Wrapping` in release builds.
+    pub const fn for_build() -> Self {
+        if cfg!(debug_assertions) {
+            ArithmeticPolicy::Checked
+        } else {
+            ArithmeticPolicy::Wrapping
+        }
+    }
+
+    /// Adds two values under the policy.
+    pub fn add(self, a: u64, b: u64) -> Result<u64, CommitError> {
+        match self {
+            ArithmeticPolicy::Checked => a.checked_add(b).ok_or(CommitError::ArithmeticOverflow),
+            ArithmeticPolicy::Wrapping => Ok(a.wrapping_add(b)),
+            ArithmeticPolicy::Saturating => Ok(a.saturating_add(b)),
+        }
+    }
+
+    /// Multiplies two values under the policy.
+    pub fn mul(self, a: u64, b: u64) -> Result<u64, CommitError> {
+        match self {
+            ArithmeticPolicy::Checked => a.checked_mul(b).ok_or(CommitError::ArithmeticOverflow),
+            ArithmeticPolicy::Wrapping => Ok(a.wrapping_mul(b)),
+            ArithmeticPolicy::Saturating => Ok(a.saturating_mul(b)),
+        }
+    }
+
+    /// Computes `(acc + x + y) % q`, the accumulation step of the commitment point.
+    pub fn accumulate_mod(self, acc: u64, x: u64, y: u64, q: u64) -> Result<u64, CommitError> {
+        Ok(self.add(self.add(acc, x)?, y)? % q)
+    }
+
+    /// Computes `(acc + a * b % q) % q`, the multiply-accumulate step of a term of the
+    /// commitment point.
+    pub fn mul_add_mod(self, acc: u64, a: u64, b: u64, q: u64) -> Result<u64, CommitError> {
+        Ok(self.add(acc, self.mul(a, b)? % q)? % q)
+    }
+
+    /// Reduces the signed value `value` to its representative in `[0, q)`.
+    ///
+    /// # Returns
+    /// The representative, or `CommitError::ArithmeticOverflow` under the `Checked` policy if `q`
+    /// is 0 or does not fit in an `i64`.
+    pub fn reduce_signed_mod(self, value: i64, q: u64) -> Result<u64, CommitError> {
+        let modulus = match self {
+            ArithmeticPolicy::Checked => i64::try_from(q).map_err(|_| CommitError::ArithmeticOverflow)?,
+            ArithmeticPolicy::Wrapping => q as i64,
+            ArithmeticPolicy::Saturating => i64::try_from(q).unwrap_or(i64::MAX),
+        };
+        match self {
+            ArithmeticPolicy::Checked => value.checked_rem_euclid(modulus).ok_or(CommitError::ArithmeticOverflow),
+            _ => Ok(value.wrapping_rem_euclid(modulus)),
+        }
+        .map(|reduced| reduced as u64)
+    }
+
+    /// Sums `values` modulo `q` in a tree of arity `REDUCTION_ARITY`.
+    ///
+    /// Each level replaces every run of `REDUCTION_ARITY` consecutive values, the last one possibly
+    /// shorter, by its sum modulo `q` taken left to right, until one value remains.
+    ///
+    /// # Returns
+    /// The sum, `0` for no values, or `CommitError::ArithmeticOverflow` under the `Checked` policy.
+    pub fn tree_sum_mod(self, values: &[u64], q: u64) -> Result<u64, CommitError> {
+        let mut level: Vec<u64> = values.iter().map(|&x| x % q).collect();
+        while level.len() > 1 {
+            level = level.chunks(REDUCTION_ARITY)
+                .map(|run| run.iter().try_fold(0u64, |acc, &x| Ok(self.add(acc, x)? % q)))
+                .collect::<Result<_, CommitError>>()?;
+        }
+        Ok(level.first().copied().unwrap_or(0))
+    }
+}
+
+/// Returns the smallest prime factor of `q`, or `q` itself when `q < 2`.
+///
+/// Challenges of the sigma protocols are drawn below this bound, so that the difference of any
+/// two distinct challenges is invertible modulo `q`.
+pub fn smallest_prime_factor(q: u64) -> u64 {
+    if q < 4 {
+        return q;
+    }
+    let mut divisor = 2u64;
+    while divisor <= q / divisor {
+        if q.is_multiple_of(divisor) {
+            return divisor;
+        }
+        divisor += 1;
+    }
+    q
+}
+
+impl Default for ArithmeticPolicy {
+    fn default() -> Self {
+        ArithmeticPolicy::for_build()
+    }
+}
+
+// With every operand already reduced below `q`, the largest intermediate values are
+// `3 * (q - 1)` for `accumulate_mod` and `(q - 1) + (q - 1)^2` for `mul_add_mod`.
+const _: () = assert!(LOCAL_Q <= MAX_SUPPORTED_Q);
+const _: () = assert!(is_narrow(LOCAL_Q));
+// The narrow point kernel sums `2 * LOCAL_N + 1` products below `NARROW_MAX_Q^2` in a `u64`.
+const _: () = assert!(((NARROW_MAX_Q - 1) * (NARROW_MAX_Q - 1)).checked_mul(2 * crate::fft::LOCAL_N as u64 + 1).is_some());
+const _: () = assert!((MAX_SUPPORTED_Q - 1).checked_mul(3).is_some());
+const _: () = assert!(match (MAX_SUPPORTED_Q - 1).checked_mul(MAX_SUPPORTED_Q - 1) {
+    Some(square) => square.checked_add(MAX_SUPPORTED_Q - 1).is_some(),
+    None => false,
+});
+
+#[cfg(test)]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use super::*;
+
+    const POLICIES: [ArithmeticPolicy; 3] = [ArithmeticPolicy::Checked, ArithmeticPolicy::Wrapping, ArithmeticPolicy::Saturating];
+    const BOUNDARY_Q: [u64; 5] = [2, LOCAL_Q, NARROW_MAX_Q, NARROW_MAX_Q + 1, MAX_SUPPORTED_Q];
+
+    #[test]
+    fn overflow_follows_the_policy() {
+        assert_eq!(ArithmeticPolicy::Checked.add(u64::MAX, 1), Err(CommitError::ArithmeticOverflow));
+        assert_eq!(ArithmeticPolicy::Checked.mul(u64::MAX, 2), Err(CommitError::ArithmeticOverflow));
+        assert_eq!(ArithmeticPolicy::Wrapping.add(u64::MAX, 1), Ok(0));
+        assert_eq!(ArithmeticPolicy::Wrapping.mul(u64::MAX, 2), Ok(u64::MAX - 1));
+        assert_eq!(ArithmeticPolicy::Saturating.add(u64::MAX, 1), Ok(u64::MAX));
+        assert_eq!(ArithmeticPolicy::Saturating.mul(u64::MAX, 2), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn accumulation_is_exact_up_to_max_supported_q() {
+        // 所有操作数取最大约化值 q - 1，与 u128 的精确结果比较
+        for q in BOUNDARY_Q {
+            let top = q - 1;
+            let sum = (3 * top as u128 % q as u128) as u64;
+            let product = ((top as u128 + top as u128 * top as u128 % q as u128) % q as u128) as u64;
+            for policy in POLICIES {
+                assert_eq!(policy.accumulate_mod(top, top, top, q), Ok(sum), "{policy:?} q = {q}");
+                assert_eq!(policy.mul_add_mod(top, top, top, q), Ok(product), "{policy:?} q = {q}");
+            }
+        }
+    }
+
+    #[test]
+    fn accumulation_overflows_past_max_supported_q() {
+        // (q - 1)^2 = 2^64 不再能放进 u64
+        let q = MAX_SUPPORTED_Q + 2;
+        let top = q - 1;
+        let exact = ((top as u128 + top as u128 * top as u128 % q as u128) % q as u128) as u64;
+        assert_eq!(ArithmeticPolicy::Checked.mul_add_mod(top, top, top, q), Err(CommitError::ArithmeticOverflow));
+        assert_ne!(ArithmeticPolicy::Wrapping.mul_add_mod(top, top, top, q), Ok(exact));
+        assert_ne!(ArithmeticPolicy::Saturating.mul_add_mod(top, top, top, q), Ok(exact));
+
+        let top = u64::MAX / 2;
+        assert_eq!(ArithmeticPolicy::Checked.accumulate_mod(top, top, top, u64::MAX), Err(CommitError::ArithmeticOverflow));
+    }
+
+    #[test]
+    fn tree_sum_is_exact_up_to_max_supported_q() {
+        for q in BOUNDARY_Q {
+            let values = vec![q - 1; 1000];
+            let exact = (1000 * (q - 1) as u128 % q as u128) as u64;
+            for policy in POLICIES {
+                assert_eq!(policy.tree_sum_mod(&values, q), Ok(exact), "{policy:?} q = {q}");
+                assert_eq!(policy.tree_sum_mod(&[], q), Ok(0));
+            }
+        }
+    }
+
+    #[test]
+    fn signed_values_reduce_to_their_representative() {
+        for q in BOUNDARY_Q {
+            for value in [-1, 0, 1, i64::MIN, i64::MAX, -(q as i64), q as i64] {
+                let exact = (value as i128).rem_euclid(q as i128) as u64;
+                for policy in POLICIES {
+                    assert_eq!(policy.reduce_signed_mod(value, q), Ok(exact), "{policy:?} q = {q} value = {value}");
+                }
+            }
+        }
+        assert_eq!(ArithmeticPolicy::Checked.reduce_signed_mod(-1, 0), Err(CommitError::ArithmeticOverflow));
+        assert_eq!(ArithmeticPolicy::Checked.reduce_signed_mod(-1, u64::MAX), Err(CommitError::ArithmeticOverflow));
+    }
+}
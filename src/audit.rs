@@ -0,0 +1,166 @@
+//! This module provides selective audit tokens.
+//!
+//! A committer holding an `AuditIssuer` master secret can grant one auditor the ability to check
+//! the opening of one commitment. Every commitment gets its own scoped key
+//! `H("reveler/audit/scope" ‖ master ‖ commitment id)`, the opening is encrypted under that key,
+//! and the scoped key is wrapped under a key shared with the auditor and bound to the auditor's
+//! identity. A token therefore reveals nothing about other commitments of the same committer,
+//! and cannot be used by anyone who does not hold the auditor key.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::policy::VerifyPolicy;
+use crate::{compute_point, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const AUDIT_ID_DOMAIN: &[u8] = b"reveler/audit/id";
+pub(crate) const AUDIT_SCOPE_DOMAIN: &[u8] = b"reveler/audit/scope";
+pub(crate) const AUDIT_WRAP_DOMAIN: &[u8] = b"reveler/audit/wrap";
+pub(crate) const AUDIT_TAG_DOMAIN: &[u8] = b"reveler/audit/tag";
+
+/// Token granting one auditor access to the opening of one commitment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditToken {
+    pub commitment_id: Vec<u8>, // The identifier of the audited commitment
+    pub auditor_id: Vec<u8>,    // The identity of the auditor the token is issued to
+    pub wrapped_key: Vec<u8>,   // The scoped key, encrypted under the auditor key
+    pub ciphertext: Vec<u8>,    // The opening, encrypted under the scoped key
+    pub tag: Vec<u8>,           // The integrity tag over the token contents
+}
+
+/// Committer-side issuer of audit tokens.
+pub struct AuditIssuer {
+    master_secret: Vec<u8>,
+}
+
+impl AuditIssuer {
+    /// Creates an issuer from the committer's master secret.
+    pub fn new(master_secret: Vec<u8>) -> Self {
+        AuditIssuer { master_secret }
+    }
+
+    /// Derives the key scoped to a single commitment.
+    pub fn scoped_key(&self, commitment: &RevelerResult) -> Vec<u8> {
+        let mut input = AUDIT_SCOPE_DOMAIN.to_vec();
+        input.extend(utils::encode_bytes(&self.master_secret));
+        input.extend(utils::encode_bytes(&commitment_id(commitment)));
+        utils::hash_to_commitment(&input)
+    }
+
+    /// Issues a token letting `auditor_id` check the opening of `commitment`.
+    ///
+    /// # Parameters
+    /// - `commitment`: A reference to the audited `RevelerResult`.
+    /// - `opening`: A reference to the `RevelerCommit` whose message and randomness open it.
+    /// - `auditor_id`: The identity of the auditor.
+    /// - `auditor_key`: The symmetric key shared with the auditor.
+    ///
+    /// # Returns
+    /// The `AuditToken` for this commitment and auditor.
+    pub fn issue(
+        &self,
+        commitment: &RevelerResult,
+        opening: &RevelerCommit,
+        auditor_id: &[u8],
+        auditor_key: &[u8],
+    ) -> AuditToken {
+        let commitment_id = commitment_id(commitment);
+        let scoped_key = self.scoped_key(commitment);
+
+        let mut plaintext = utils::encode_vector(&opening.local_m);
+        plaintext.extend(utils::encode_vector(&opening.local_r));
+        let ciphertext = utils::xor_keystream(&scoped_key, &plaintext);
+        let wrapped_key = utils::xor_keystream(&wrapping_key(auditor_key, &commitment_id, auditor_id), &scoped_key);
+
+        AuditToken {
+            tag: compute_tag(&scoped_key, &commitment_id, auditor_id, &ciphertext),
+            commitment_id,
+            auditor_id: auditor_id.to_vec(),
+            wrapped_key,
+            ciphertext,
+        }
+    }
+}
+
+/// Computes the identifier of a commitment that audit tokens are bound to.
+pub fn commitment_id(commitment: &RevelerResult) -> Vec<u8> {
+    let mut input = AUDIT_ID_DOMAIN.to_vec();
+    input.extend(commitment.to_bytes());
+    utils::hash_to_commitment(&input)
+}
+
+/// Decrypts the opening carried by a token without checking it against the parameters.
+///
+/// # Parameters
+/// - `token`: A reference to the `AuditToken`.
+/// - `commitment`: A reference to the `RevelerResult` the token claims to cover.
+/// - `auditor_key`: The symmetric key shared with the committer.
+///
+/// # Returns
+/// The `(m, r)` opening, `CommitError::InvalidOpening` if the token is for another commitment,
+/// or `CommitError::DecryptionFailed` if the token does not authenticate under `auditor_key`.
+pub fn open(token: &AuditToken, commitment: &RevelerResult, auditor_key: &[u8]) -> Result<(Vec<u64>, Vec<u64>), CommitError> {
+    if token.commitment_id != commitment_id(commitment) {
+        return Err(CommitError::InvalidOpening);
+    }
+
+    let unwrapping_key = wrapping_key(auditor_key, &token.commitment_id, &token.auditor_id);
+    let scoped_key = utils::xor_keystream(&unwrapping_key, &token.wrapped_key);
+    if compute_tag(&scoped_key, &token.commitment_id, &token.auditor_id, &token.ciphertext) != token.tag {
+        return Err(CommitError::DecryptionFailed);
+    }
+
+    let plaintext = utils::xor_keystream(&scoped_key, &token.ciphertext);
+    let (local_m, rest) = utils::decode_vector(&plaintext)?;
+    let (local_r, rest) = utils::decode_vector(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok((local_m, local_r))
+}
+
+/// Decrypts the opening carried by a token and checks it against the commitment.
+///
+/// # Parameters
+/// - `token`: A reference to the `AuditToken`.
+/// - `commitment`: A reference to the audited `RevelerResult`.
+/// - `params`: The `(A, B)` matrices the commitment was computed with.
+/// - `auditor_key`: The symmetric key shared with the committer.
+///
+/// # Returns
+/// The verified `(m, r)` opening, or `CommitError::InvalidOpening` if it does not reproduce the
+/// commitment within the norm bounds of `VerifyPolicy::new`.
+pub fn audit(
+    token: &AuditToken,
+    commitment: &RevelerResult,
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    auditor_key: &[u8],
+) -> Result<(Vec<u64>, Vec<u64>), CommitError> {
+    let (local_m, local_r) = open(token, commitment, auditor_key)?;
+    if VerifyPolicy::new().check_norms(&local_m, &local_r, CommitConfig::default().modulus).is_err() {
+        return Err(CommitError::InvalidOpening);
+    }
+
+    let point = compute_point(params.0, params.1, &local_m, &local_r, &CommitConfig::default())?;
+    if point != commitment.commitment_point || !RevelerCommit::verify(commitment) {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok((local_m, local_r))
+}
+
+fn wrapping_key(auditor_key: &[u8], commitment_id: &[u8], auditor_id: &[u8]) -> Vec<u8> {
+    let mut input = AUDIT_WRAP_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(auditor_key));
+    input.extend(utils::encode_bytes(commitment_id));
+    input.extend(utils::encode_bytes(auditor_id));
+    utils::hash_to_commitment(&input)
+}
+
+fn compute_tag(scoped_key: &[u8], commitment_id: &[u8], auditor_id: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut input = AUDIT_TAG_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(scoped_key));
+    input.extend(utils::encode_bytes(commitment_id));
+    input.extend(utils::encode_bytes(auditor_id));
+    input.extend(utils::encode_bytes(ciphertext));
+    utils::hash_to_commitment(&input)
+}
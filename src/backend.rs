@@ -0,0 +1,151 @@
+//! This module defines `CommitmentScheme`, the interface generic protocol code is written against.
+//!
+//! A scheme names its key, commitment, opening and message types and provides `setup`, `commit`,
+//! `open` and `verify` over them, so callers swap one backend for another, e.g. a hash-based or a
+//! Pedersen one, without being rewritten. `LatticeScheme` implements the trait with the
+//! `key::CommitmentKey` of this crate: messages are vectors of at most `MESSAGE_LEN` coordinates
+//! and the randomness is drawn uniformly from `{-1, 0, 1}`. With the `curve` feature,
+//! `pedersen::PedersenScheme` implements it with Pedersen commitments over Ristretto.
+//!
+//! `CommitmentScheme::capabilities` describes what the crate offers for a scheme at runtime: the
+//! homomorphic operations, the proofs, the hash functions, the largest message and the size of a
+//! commitment, so generic code feature-detects a backend instead of depending on cargo features.
+//! It is unrelated to `scheme::Capabilities`, the list of scheme identifiers peers negotiate.
+//!
+//! `setup` and `commit` draw randomness and are compiled with the `prover` feature; `open` and
+//! `verify` are always available.
+
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_N, MESSAGE_LEN};
+use crate::key::{CommitmentKey, Opening};
+use crate::scheme::{self, SchemeId};
+#[cfg(feature = "prover")]
+use crate::utils;
+use crate::RevelerResult;
+
+/// Zero-knowledge proof the crate provides for the commitments of a scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProofKind {
+    /// `binary::prove_binary`: the message is a bit vector.
+    Binary,
+    /// `linking::prove_same_message_across`: two commitments hide the same message.
+    Linking,
+    /// `CommitmentKey::open_positions`: the message holds the revealed values at some coordinates.
+    Disclosure,
+    /// `proofs::prove_knowledge`: the prover knows an opening.
+    Knowledge,
+    /// `range::prove_range`: a coordinate of the message lies in `[0, 2^k)`.
+    Range,
+    /// `proofs::prove_bit`: a coordinate of the message is 0 or 1.
+    Bit,
+    /// `proofs::prove_sum`: a coordinate of the messages of several commitments adds up to a total.
+    Sum,
+    /// `range::prove_leq`: a coordinate of the message is at most that of another.
+    Leq,
+    /// `proofs::prove_and`: the prover can open every commitment of a list.
+    And,
+    /// `proofs::prove_or`: the prover can open at least one commitment of a list.
+    Or,
+}
+
+/// What the crate offers for the commitments of a scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemeCapabilities {
+    pub scheme: String,         // The identifier of the scheme
+    pub homomorphic: bool,      // Commitments and openings are added and scaled by the crate
+    pub rerandomizable: bool,   // Commitments are re-randomized by this build of the crate
+    pub proofs: Vec<ProofKind>, // The zero-knowledge proofs available
+    pub hashers: Vec<String>,   // The hash functions binding commitments, empty if none
+    pub max_message_len: usize, // The largest message, in coordinates or scalars
+    pub commitment_size: usize, // The size of an encoded commitment in bytes
+}
+
+/// Commitment scheme with its key, commitment, opening and message types.
+pub trait CommitmentScheme {
+    /// The public parameters commitments are computed under.
+    type Key;
+    /// The commitment published before the reveal.
+    type Commitment;
+    /// The secret needed to open a commitment.
+    type Opening;
+    /// The committed value.
+    type Message;
+
+    /// Returns what the crate offers for the commitments of the scheme.
+    fn capabilities() -> SchemeCapabilities;
+
+    /// Samples a fresh key from `rng`.
+    #[cfg(feature = "prover")]
+    fn setup<R: Rng>(rng: &mut R) -> Self::Key;
+
+    /// Commits to `message` under `key` with randomness from `rng`.
+    ///
+    /// # Returns
+    /// The commitment and its opening, or the `CommitError` of the scheme if `message` cannot be
+    /// committed to.
+    #[cfg(feature = "prover")]
+    fn commit<R: Rng>(key: &Self::Key, message: &Self::Message, rng: &mut R) -> Result<(Self::Commitment, Self::Opening), CommitError>;
+
+    /// Checks `opening` against `commitment` and returns the committed message.
+    ///
+    /// # Returns
+    /// The message, or `CommitError::InvalidOpening` if `opening` does not open `commitment`.
+    fn open(key: &Self::Key, commitment: &Self::Commitment, opening: &Self::Opening) -> Result<Self::Message, CommitError>;
+
+    /// Checks that `opening` opens `commitment` to `message`.
+    fn verify(key: &Self::Key, commitment: &Self::Commitment, message: &Self::Message, opening: &Self::Opening) -> Result<bool, CommitError>;
+}
+
+/// The lattice commitment of this crate as a `CommitmentScheme`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatticeScheme;
+
+impl CommitmentScheme for LatticeScheme {
+    type Key = CommitmentKey;
+    type Commitment = RevelerResult;
+    type Opening = Opening;
+    type Message = Vec<u64>;
+
+    fn capabilities() -> SchemeCapabilities {
+        let scheme = SchemeId::current();
+        let mut header = Vec::new();
+        scheme::write_header(&mut header);
+        SchemeCapabilities {
+            scheme: scheme.to_string(),
+            homomorphic: true,
+            rerandomizable: cfg!(feature = "prover"),
+            proofs: vec![ProofKind::Binary, ProofKind::Linking, ProofKind::Disclosure, ProofKind::Knowledge, ProofKind::Range, ProofKind::Bit, ProofKind::Sum, ProofKind::Leq, ProofKind::And, ProofKind::Or],
+            hashers: vec![scheme.hasher],
+            max_message_len: MESSAGE_LEN,
+            // 头部、带长度前缀的承诺点与 256 位哈希
+            commitment_size: header.len() + 8 + 8 * LOCAL_N + 8 + 32,
+        }
+    }
+
+    #[cfg(feature = "prover")]
+    fn setup<R: Rng>(rng: &mut R) -> CommitmentKey {
+        CommitmentKey::setup(rng)
+    }
+
+    #[cfg(feature = "prover")]
+    fn commit<R: Rng>(key: &CommitmentKey, message: &Vec<u64>, rng: &mut R) -> Result<(RevelerResult, Opening), CommitError> {
+        let local_r = utils::generate_randomness(rng);
+        let opening = Opening::new(message.clone(), local_r);
+        let commitment = key.commit_opening(&opening)?;
+        Ok((commitment, opening))
+    }
+
+    fn open(key: &CommitmentKey, commitment: &RevelerResult, opening: &Opening) -> Result<Vec<u64>, CommitError> {
+        if !key.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        Ok(opening.local_m().to_vec())
+    }
+
+    fn verify(key: &CommitmentKey, commitment: &RevelerResult, message: &Vec<u64>, opening: &Opening) -> Result<bool, CommitError> {
+        Ok(opening.local_m() == message.as_slice() && key.verify(commitment, opening)?)
+    }
+}
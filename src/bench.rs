@@ -0,0 +1,289 @@
+//! This module runs representative workloads of the crate programmatically, so integrators measure
+//! them on their own hardware or in their own CI without copying the criterion harness of this
+//! repository.
+//!
+//! A `Scenario` names a workload:
+//! - `SingleCommit`: one commitment to a full-length random opening.
+//! - `Batch`: `CommitmentKey::commit_batch` over many random openings, `batch-1k` by default.
+//! - `Streaming`: a large input hashed in chunks of `STREAM_CHUNK_BYTES` with
+//!   `utils::CommitmentHasher`, then the digest committed with `CommitmentKey::commit_bytes`, as
+//!   when committing to a file; `streaming-1GB` by default.
+//! - `VerifyArchive`: decoding and verifying a delta-coded `ArchiveCollection`.
+//!
+//! `Scenario::prepare` builds the key and inputs of a scenario from `BenchConfig::seed`, outside of
+//! the measurement, and `Workload::run_once` runs the measured operation, so the workload also fits
+//! an external harness. `Scenario::run` times `BenchConfig::iterations` runs after
+//! `BenchConfig::warmup` untimed ones and returns a `ScenarioResult` with the sample durations and
+//! the amount of work per run, which serializes for dashboards and regression tracking.
+//!
+//! The module is compiled with the `bench` feature.
+
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Serialize, Deserialize};
+use crate::archive::{ArchiveCodec, ArchiveCollection};
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_Q, MESSAGE_LEN};
+use crate::key::{CommitmentKey, Opening};
+use crate::utils;
+
+/// Size of the chunks the `Streaming` scenario feeds to the hasher.
+pub const STREAM_CHUNK_BYTES: usize = 1 << 20;
+
+/// Workload to measure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scenario {
+    /// One commitment to a full-length random opening.
+    SingleCommit,
+    /// A batch commitment to `commitments` random openings.
+    Batch { commitments: usize },
+    /// Hashing `bytes` bytes of input and committing to the digest.
+    Streaming { bytes: u64 },
+    /// Decoding and verifying an archive of `entries` commitments with their openings.
+    VerifyArchive { entries: usize },
+}
+
+/// Settings of a benchmark run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchConfig {
+    pub seed: u64,         // The seed of the RNG generating the key and inputs
+    pub warmup: usize,     // The untimed runs before the measurement
+    pub iterations: usize, // The timed runs
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig { seed: 0, warmup: 1, iterations: 10 }
+    }
+}
+
+impl BenchConfig {
+    /// Sets the seed of the RNG generating the key and inputs.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the number of untimed runs before the measurement.
+    pub fn with_warmup(mut self, warmup: usize) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Sets the number of timed runs, at least one.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+}
+
+/// Measurement of one scenario.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub scenario: Scenario,     // The measured scenario
+    pub name: String,           // Its name, see `Scenario::name`
+    pub items: u64,             // The commitments computed or verified per run
+    pub bytes: u64,             // The input bytes processed per run
+    pub samples: Vec<Duration>, // The duration of every timed run, in order
+}
+
+impl ScenarioResult {
+    /// Returns the shortest run.
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+
+    /// Returns the mean duration of a run.
+    pub fn mean(&self) -> Duration {
+        let count = u32::try_from(self.samples.len()).unwrap_or(u32::MAX).max(1);
+        self.samples.iter().sum::<Duration>() / count
+    }
+
+    /// Returns the median duration of a run.
+    pub fn median(&self) -> Duration {
+        let mut samples = self.samples.clone();
+        samples.sort_unstable();
+        samples.get(samples.len() / 2).copied().unwrap_or_default()
+    }
+
+    /// Returns the commitments computed or verified per second, at the median duration.
+    pub fn items_per_second(&self) -> f64 {
+        per_second(self.items, self.median())
+    }
+
+    /// Returns the input bytes processed per second, at the median duration.
+    pub fn bytes_per_second(&self) -> f64 {
+        per_second(self.bytes, self.median())
+    }
+}
+
+/// Key and inputs of a scenario, ready to run.
+pub struct Workload {
+    key: CommitmentKey,
+    input: Input,
+}
+
+// 各场景的输入，在计时之外生成
+enum Input {
+    Single(Opening),
+    Batch(Vec<Opening>),
+    Streaming { chunk: Vec<u8>, bytes: u64 },
+    Archive { bytes: Vec<u8>, entries: usize },
+}
+
+impl Scenario {
+    /// Returns the default scenarios: `single-commit`, `batch-1k`, `streaming-1GB` and
+    /// `verify-archive-1k`.
+    pub fn standard() -> Vec<Scenario> {
+        vec![
+            Scenario::SingleCommit,
+            Scenario::Batch { commitments: 1000 },
+            Scenario::Streaming { bytes: 1 << 30 },
+            Scenario::VerifyArchive { entries: 1000 },
+        ]
+    }
+
+    /// Returns the name of the scenario, e.g. `batch-1k` or `streaming-1GB`.
+    pub fn name(&self) -> String {
+        match *self {
+            Scenario::SingleCommit => "single-commit".to_string(),
+            Scenario::Batch { commitments } => format!("batch-{}", count_label(commitments as u64)),
+            Scenario::Streaming { bytes } => format!("streaming-{}", byte_label(bytes)),
+            Scenario::VerifyArchive { entries } => format!("verify-archive-{}", count_label(entries as u64)),
+        }
+    }
+
+    /// Generates the key and inputs of the scenario from `config.seed`.
+    ///
+    /// # Returns
+    /// The `Workload`, or the `CommitError` of a failed commitment while building the archive of
+    /// `VerifyArchive`.
+    pub fn prepare(&self, config: &BenchConfig) -> Result<Workload, CommitError> {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let key = CommitmentKey::setup(&mut rng);
+        let input = match *self {
+            Scenario::SingleCommit => Input::Single(random_opening(&mut rng)),
+            Scenario::Batch { commitments } => Input::Batch((0..commitments).map(|_| random_opening(&mut rng)).collect()),
+            Scenario::Streaming { bytes } => {
+                let mut chunk = vec![0u8; STREAM_CHUNK_BYTES];
+                rng.fill(chunk.as_mut_slice());
+                Input::Streaming { chunk, bytes }
+            }
+            Scenario::VerifyArchive { entries } => {
+                let mut collection = ArchiveCollection::new(key.local_a().to_vec(), key.local_b().to_vec());
+                for _ in 0..entries {
+                    let opening = random_opening(&mut rng);
+                    let commitment = key.commit_opening(&opening)?;
+                    let (local_m, local_r) = opening.into_parts();
+                    collection = collection.with_entry(commitment, local_m, local_r);
+                }
+                Input::Archive { bytes: collection.to_bytes(ArchiveCodec::Delta), entries }
+            }
+        };
+        Ok(Workload { key, input })
+    }
+
+    /// Measures the scenario.
+    ///
+    /// # Parameters
+    /// - `config`: The seed of the inputs and the numbers of warm-up and timed runs.
+    ///
+    /// # Returns
+    /// The `ScenarioResult`, or the `CommitError` of a failed run.
+    pub fn run(&self, config: &BenchConfig) -> Result<ScenarioResult, CommitError> {
+        let workload = self.prepare(config)?;
+        for _ in 0..config.warmup {
+            workload.run_once()?;
+        }
+        let mut samples = Vec::with_capacity(config.iterations);
+        for _ in 0..config.iterations.max(1) {
+            let start = Instant::now();
+            workload.run_once()?;
+            samples.push(start.elapsed());
+        }
+        Ok(ScenarioResult { scenario: *self, name: self.name(), items: workload.items(), bytes: workload.bytes(), samples })
+    }
+}
+
+impl Workload {
+    /// Runs the measured operation once.
+    ///
+    /// # Returns
+    /// `Ok(())`, or the `CommitError` of the operation; `CommitError::InvalidOpening` if an entry
+    /// of the archive does not verify.
+    pub fn run_once(&self) -> Result<(), CommitError> {
+        match &self.input {
+            Input::Single(opening) => self.key.commit_opening(opening).map(|_| ()),
+            Input::Batch(openings) => self.key.commit_batch(openings).map(|_| ()),
+            Input::Streaming { chunk, bytes } => {
+                let mut hasher = utils::CommitmentHasher::new();
+                let mut remaining = *bytes;
+                while remaining > 0 {
+                    let len = usize::try_from(remaining).map_or(chunk.len(), |remaining| remaining.min(chunk.len()));
+                    hasher.update(chunk.get(..len).unwrap_or_default());
+                    remaining -= len as u64;
+                }
+                self.key.commit_bytes(&hasher.finalize()).map(|_| ())
+            }
+            Input::Archive { bytes, .. } => ArchiveCollection::from_bytes(bytes)?.verify(),
+        }
+    }
+
+    /// Returns the commitments computed or verified per run.
+    pub fn items(&self) -> u64 {
+        match &self.input {
+            Input::Single(_) | Input::Streaming { .. } => 1,
+            Input::Batch(openings) => openings.len() as u64,
+            Input::Archive { entries, .. } => *entries as u64,
+        }
+    }
+
+    /// Returns the input bytes processed per run: the streamed bytes, or the encoded archive.
+    pub fn bytes(&self) -> u64 {
+        match &self.input {
+            Input::Streaming { bytes, .. } => *bytes,
+            Input::Archive { bytes, .. } => bytes.len() as u64,
+            Input::Single(_) | Input::Batch(_) => 0,
+        }
+    }
+}
+
+/// Measures every scenario of `Scenario::standard`.
+///
+/// # Returns
+/// The `ScenarioResult` of every scenario in order, or the first `CommitError`.
+pub fn run_standard(config: &BenchConfig) -> Result<Vec<ScenarioResult>, CommitError> {
+    Scenario::standard().iter().map(|scenario| scenario.run(config)).collect()
+}
+
+fn random_opening<R: Rng>(rng: &mut R) -> Opening {
+    let local_m = (0..MESSAGE_LEN).map(|_| rng.gen_range(0..LOCAL_Q)).collect();
+    let local_r = utils::generate_randomness(rng);
+    Opening::new(local_m, local_r)
+}
+
+fn per_second(amount: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    amount as f64 / elapsed.as_secs_f64()
+}
+
+// `1000` as `1k`, `1000000` as `1M`; other counts as they are.
+fn count_label(count: u64) -> String {
+    match count {
+        0 => "0".to_string(),
+        _ if count.is_multiple_of(1_000_000) => format!("{}M", count / 1_000_000),
+        _ if count.is_multiple_of(1_000) => format!("{}k", count / 1_000),
+        _ => count.to_string(),
+    }
+}
+
+// Sizes in whole binary units as `1GB`, `64MB`, `4KB`; other sizes in bytes.
+fn byte_label(bytes: u64) -> String {
+    let units = [(1u64 << 30, "GB"), (1 << 20, "MB"), (1 << 10, "KB")];
+    units.iter()
+        .find(|&&(unit, _)| bytes != 0 && bytes.is_multiple_of(unit))
+        .map_or_else(|| format!("{bytes}B"), |&(unit, suffix)| format!("{}{suffix}", bytes / unit))
+}
@@ -0,0 +1,58 @@
+//! Command line tool of the reveler crate.
+//!
+//! Subcommands:
+//! - `conformance <output-dir> [--seed <hex>]`: writes the cross-language conformance suite.
+//!
+//! Byte strings on the command line follow the `hex` grammar of `reveler::grammar`.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use reveler::{conformance, grammar};
+
+const DEFAULT_SEED: &str = "726576656c6572"; // "reveler"
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("conformance") => run_conformance(&args[1..]),
+        _ => {
+            eprintln!("usage: reveler conformance <output-dir> [--seed <hex>]");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_conformance(args: &[String]) -> ExitCode {
+    let mut output = None;
+    let mut seed = DEFAULT_SEED.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.as_slice().first()) {
+            ("--seed", Some(value)) => {
+                seed = value.clone();
+                args.next();
+            }
+            (path, _) if output.is_none() && !path.starts_with("--") => output = Some(PathBuf::from(path)),
+            _ => {
+                eprintln!("unexpected argument: {arg}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let (Some(output), Ok(seed)) = (output, grammar::decode_hex(&seed)) else {
+        eprintln!("usage: reveler conformance <output-dir> [--seed <hex>]");
+        return ExitCode::FAILURE;
+    };
+    match conformance::write_suite(&output, &seed) {
+        Ok(count) => {
+            println!("wrote {count} cases to {}", output.display());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("failed to write the conformance suite: {error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
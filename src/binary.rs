@@ -0,0 +1,737 @@
+//! This module provides zero-knowledge proofs that a commitment hides a binary vector, and the
+//! proof system the other proofs of this crate are built on.
+//!
+//! `prove_binary` convinces a verifier that a commitment `c = A·x + [B | I]·r` hides a message
+//! whose first `k` coordinates are bits and whose other coordinates are zero, without revealing
+//! it. The proof is Stern's protocol with the extension technique of Ling, Nguyen, Stehlé and
+//! Wang, with both the bits and the randomness in the permuted witness: `x` is extended to
+//! `x' = (x, 1 - x)`, which has exactly `k` ones, and `r` to `r'` of `3·RANDOMNESS_LEN`
+//! coordinates holding exactly `RANDOMNESS_LEN` of each of `-1`, `0` and `1`, properties every
+//! permutation preserves. In each round the prover samples a permutation `π` of each part and a
+//! mask `y` of `w = (x', r')`, and publishes the salted hashes
+//! - `c1 = H(π, A·y_x + [B | I]·y_r)`, where `y_x` and `y_r` are the heads of the parts of `y`,
+//! - `c2 = H(π(y))`,
+//! - `c3 = H(π(y + w))`.
+//!
+//! Depending on the challenge it then reveals `(π(w), π(y))`, `(π, y + w)` or `(π, y)`. A round
+//! has soundness error 2/3, so the protocol is repeated until the error drops below
+//! `2^-SOUNDNESS_BITS`, with the challenges derived by Fiat–Shamir. The `_with_config` variants
+//! take the soundness target and the number of rounds from a `ProofConfig` instead.
+//!
+//! A prover that passes knows bits `x` and ternary `r` opening `c`. Bounding `r` is what makes the
+//! proof sound: the identity block of `[B | I]` opens any commitment to any message with some
+//! unbounded randomness, but two openings with ternary randomness contradict the binding of the
+//! commitment, see `policy`.
+//!
+//! The protocol proves any linear relation `L(f, w) = t` the same way, over free coordinates `f`,
+//! masked uniformly and revealed as `y_f + f`, and blocks of bits, of ternary coordinates or of a
+//! one-hot selector, each extended and permuted within itself. Randomness bounded by `β`, e.g.
+//! that of a sum of `β` commitments, is split into ternary blocks with the weights of `weights`.
+//! `amounts`, `range`, `linking` and `proofs` state their relations this way.
+
+use std::ops::Range;
+#[cfg(feature = "prover")]
+use rand::{seq::SliceRandom, Rng};
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+#[cfg(feature = "prover")]
+use crate::utils::Workers;
+use crate::config::{CommitConfig, ProofConfig};
+use crate::fft::{MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::{compute_point, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const BINARY_DOMAIN: &[u8] = b"reveler/binary";
+pub(crate) const BITS_LABEL: &[u8] = b"bits";
+#[cfg(feature = "prover")]
+pub(crate) const SALT_LEN: usize = 32;
+
+/// Soundness target of binary proofs in bits.
+pub const SOUNDNESS_BITS: u32 = 128;
+
+/// One round of a binary proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryRound {
+    pub commitments: Vec<Vec<u8>>, // The salted hashes c1, c2 and c3
+    pub response: BinaryResponse,
+}
+
+/// Response of one round, depending on its challenge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinaryResponse {
+    /// Challenge 0: opens `c2` and `c3`.
+    Permuted {
+        permuted_witness: Vec<u64>, // π(w)
+        permuted_mask: Vec<u64>,    // π(y)
+        mask_salt: Vec<u8>,
+        shifted_salt: Vec<u8>,
+    },
+    /// Challenge 1: opens `c1` and `c3`.
+    Shifted {
+        permutation: Vec<u32>,     // π
+        shifted_witness: Vec<u64>, // y + w
+        shifted_free: Vec<u64>,    // y_f + f, over the free coordinates
+        point_salt: Vec<u8>,
+        shifted_salt: Vec<u8>,
+    },
+    /// Challenge 2: opens `c1` and `c2`.
+    Masked {
+        permutation: Vec<u32>, // π
+        mask: Vec<u64>,        // y
+        mask_free: Vec<u64>,   // y_f, over the free coordinates
+        point_salt: Vec<u8>,
+        mask_salt: Vec<u8>,
+    },
+}
+
+/// Proof that a commitment hides a binary vector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+// Block of the permuted witness of a relation, by the property of its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Block {
+    // n bits x, extended to (x, 1 - x) with exactly n ones and n zeros.
+    Bits(usize),
+    // n coordinates in {-1, 0, 1}, extended to 3n with exactly n of each.
+    Ternary(usize),
+    // n coordinates with exactly one 1 and n - 1 zeros, a selector, kept as they are.
+    OneHot(usize),
+}
+
+// Linear map of a relation from the free coordinates and the head of every block.
+pub(crate) type LinearMap<'a> = Box<dyn Fn(&[u64], &[&[u64]]) -> Result<Vec<u64>, CommitError> + Sync + 'a>;
+
+// Statement `map(f, w) = target` over `free` coordinates `f` and the blocks `w`.
+pub(crate) struct Relation<'a> {
+    pub label: &'a [u8],
+    pub statement: Vec<u8>, // The public inputs besides the target, bound to the challenges
+    pub free: usize,
+    pub blocks: Vec<Block>,
+    pub map: LinearMap<'a>,
+    pub target: Vec<u64>,
+}
+
+// Witness of a relation: the free coordinates and the value of every block, before extension.
+#[cfg(feature = "prover")]
+pub(crate) struct Witness {
+    pub free: Vec<u64>,
+    pub blocks: Vec<Vec<u64>>,
+}
+
+// Secrets of one round, kept between its commitments and its response.
+#[cfg(feature = "prover")]
+struct RoundSecret {
+    permutation: Vec<u32>,
+    mask: Vec<u64>,
+    mask_free: Vec<u64>,
+    shifted: Vec<u64>,
+    salts: [Vec<u8>; 3],
+}
+
+// Rounds whose commitments are published and whose challenges are pending.
+#[cfg(feature = "prover")]
+pub(crate) struct Announcement {
+    extended: Vec<u64>,
+    free: Vec<u64>,
+    secrets: Vec<RoundSecret>,
+    pub commitments: Vec<Vec<Vec<u8>>>, // c1, c2 and c3 per round
+}
+
+/// Returns the number of rounds of a binary proof under the default `ProofConfig`.
+pub fn round_count() -> usize {
+    (SOUNDNESS_BITS as f64 / 1.5f64.log2()).ceil() as usize
+}
+
+/// Proves that `commitment` hides the binary message `local_m`.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the commitment.
+/// - `local_m`: The message, whose elements must all be 0 or 1.
+/// - `local_r`: The randomness of the commitment.
+/// - `commitment`: A reference to the `RevelerResult` to prove.
+///
+/// # Returns
+/// The `BinaryProof` for a message of `local_m.len()` bits, or `CommitError::InvalidOpening` if
+/// the message is not binary, longer than `MESSAGE_LEN`, or `(local_m, local_r)` does not open
+/// `commitment` with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_binary(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    local_r: &[u64],
+    commitment: &RevelerResult,
+) -> Result<BinaryProof, CommitError> {
+    prove_binary_with_config(params, local_m, local_r, commitment, &ProofConfig::default())
+}
+
+/// Proves that `commitment` hides the binary message `local_m` with the soundness of `config`.
+///
+/// # Returns
+/// The `BinaryProof`, `CommitError::InvalidProofConfig` if `config` is not valid for binary
+/// proofs, or the errors of `prove_binary`.
+#[cfg(feature = "prover")]
+pub fn prove_binary_with_config(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    local_r: &[u64],
+    commitment: &RevelerResult,
+    config: &ProofConfig,
+) -> Result<BinaryProof, CommitError> {
+    prove_binary_on(params, local_m, local_r, commitment, config, Workers::Threads)
+}
+
+/// Proves that `commitment` hides the binary message `local_m`, generating the rounds on `pool`.
+///
+/// # Returns
+/// The same proof distribution and errors as `prove_binary_with_config`.
+#[cfg(feature = "parallel")]
+pub fn prove_binary_with_pool(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    local_r: &[u64],
+    commitment: &RevelerResult,
+    config: &ProofConfig,
+    pool: &rayon::ThreadPool,
+) -> Result<BinaryProof, CommitError> {
+    prove_binary_on(params, local_m, local_r, commitment, config, Workers::Pool(pool))
+}
+
+#[cfg(feature = "prover")]
+fn prove_binary_on(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    local_r: &[u64],
+    commitment: &RevelerResult,
+    config: &ProofConfig,
+    workers: Workers,
+) -> Result<BinaryProof, CommitError> {
+    let rounds = config.binary_rounds()?;
+    if local_m.len() > MESSAGE_LEN
+        || local_m.iter().any(|&bit| bit > 1)
+        || !RevelerCommit::verify_opening(params, commitment, local_m, local_r)?
+    {
+        return Err(CommitError::InvalidOpening);
+    }
+    let relation = bits_relation(params, local_m.len(), commitment);
+    let witness = Witness { free: Vec::new(), blocks: vec![local_m.to_vec(), local_r.to_vec()] };
+    prove_relation(&relation, &witness, rounds, workers)
+}
+
+/// Verifies a proof that `commitment` hides a binary message of `width` bits.
+///
+/// # Returns
+/// `true` if the commitment hash is valid and every round of the proof checks out.
+pub fn verify_binary(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    commitment: &RevelerResult,
+    width: usize,
+    proof: &BinaryProof,
+) -> bool {
+    verify_binary_with_config(params, commitment, width, proof, &ProofConfig::default())
+}
+
+/// Verifies a binary proof made with the soundness of `config`.
+///
+/// # Returns
+/// `true` if `config` is valid for binary proofs, the commitment hash is valid, and the proof
+/// has the rounds of `config`, each of which checks out.
+pub fn verify_binary_with_config(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    commitment: &RevelerResult,
+    width: usize,
+    proof: &BinaryProof,
+    config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = config.binary_rounds() else {
+        return false;
+    };
+    width <= MESSAGE_LEN
+        && RevelerCommit::verify(commitment)
+        && verify_relation(&bits_relation(params, width, commitment), &proof.rounds, rounds)
+}
+
+// The statement of `prove_binary`: `commitment` opens to `width` bits with ternary randomness.
+pub(crate) fn bits_relation<'a>(params: (&'a [Vec<u64>], &'a [Vec<u64>]), width: usize, commitment: &RevelerResult) -> Relation<'a> {
+    Relation {
+        label: BITS_LABEL,
+        statement: params_digest(params),
+        free: 0,
+        blocks: vec![Block::Bits(width), Block::Ternary(RANDOMNESS_LEN)],
+        map: Box::new(move |_, heads| match heads {
+            [bits, r] => point(params, bits, r),
+            _ => Err(CommitError::ComputationError),
+        }),
+        target: commitment.commitment_point.clone(),
+    }
+}
+
+// The statement that coordinate `index` of the message of `commitment` is `Σ_j 2^j·b_j` for
+// `width` bits `b`, with the other coordinates free and randomness bounded by `bound`, split into
+// ternary blocks with the weights of `weights`.
+pub(crate) fn coordinate_relation<'a>(
+    params: (&'a [Vec<u64>], &'a [Vec<u64>]),
+    label: &'a [u8],
+    index: usize,
+    width: usize,
+    bound: u64,
+    commitment: &RevelerResult,
+) -> Relation<'a> {
+    let weights = weights(bound);
+    let mut statement = params_digest(params);
+    statement.extend((index as u64).to_be_bytes());
+    statement.extend(bound.to_be_bytes());
+    let mut blocks = vec![Block::Bits(width)];
+    blocks.extend(weights.iter().map(|_| Block::Ternary(RANDOMNESS_LEN)));
+    Relation {
+        label,
+        statement,
+        free: MESSAGE_LEN - 1,
+        blocks,
+        map: Box::new(move |free, heads| {
+            let (bits, pieces) = heads.split_first().ok_or(CommitError::ComputationError)?;
+            let r = recompose(pieces, &weights, CommitConfig::default().modulus);
+            point(params, &insert(free, index, bits), &r)
+        }),
+        target: commitment.commitment_point.clone(),
+    }
+}
+
+// Witness of `coordinate_relation` for the opening `(m, r)`: the bits of coordinate `index`, the
+// other coordinates and the pieces of `r`. Bits past `width` are dropped, so a larger coordinate
+// yields a proof that fails.
+#[cfg(feature = "prover")]
+pub(crate) fn coordinate_witness(m: &[u64], r: &[u64], index: usize, width: usize, bound: u64) -> Witness {
+    let q = CommitConfig::default().modulus;
+    let mut free: Vec<u64> = m.iter().map(|&x| x % q).collect();
+    free.resize(MESSAGE_LEN, 0);
+    let value = if index < free.len() { free.remove(index) } else { 0 };
+    let mut blocks = vec![(0..width).map(|j| (value >> j) & 1).collect()];
+    blocks.extend(decompose(r, bound, q));
+    Witness { free, blocks }
+}
+
+// The message with `Σ_j 2^j·b_j mod q` inserted at `index` among the free coordinates.
+fn insert(free: &[u64], index: usize, bits: &[u64]) -> Vec<u64> {
+    let q = CommitConfig::default().modulus;
+    let value = bits.iter().rev().fold(0, |acc, &bit| (2 * acc + bit) % q);
+    let mut m = free.to_vec();
+    m.insert(index.min(m.len()), value);
+    m
+}
+
+// Prove the relation for `witness` in `rounds` rounds, generating the rounds on `workers`. The
+// witness is not checked: a wrong witness yields a proof that fails.
+#[cfg(feature = "prover")]
+pub(crate) fn prove_relation(relation: &Relation, witness: &Witness, rounds: usize, workers: Workers) -> Result<BinaryProof, CommitError> {
+    let announcement = announce(relation, witness, rounds, workers)?;
+    let challenges = derive_challenges(relation, &announcement.commitments);
+    Ok(BinaryProof { rounds: announcement.respond(&challenges) })
+}
+
+// Draw the permutations and masks of `rounds` rounds for `witness` and commit to them, on
+// `workers`.
+#[cfg(feature = "prover")]
+pub(crate) fn announce(relation: &Relation, witness: &Witness, rounds: usize, workers: Workers) -> Result<Announcement, CommitError> {
+    let q = CommitConfig::default().modulus;
+    let extended: Vec<u64> = relation.blocks.iter().zip(&witness.blocks)
+        .flat_map(|(block, values)| block.extend(values, q))
+        .collect();
+    let mut free: Vec<u64> = witness.free.iter().map(|&x| x % q).collect();
+    free.resize(relation.free, 0);
+    let len = relation.len();
+
+    // 各轮相互独立，在工作线程间并行生成；置换只在每个块内部打乱
+    let (secrets, commitments): (Vec<_>, Vec<_>) = utils::run_indexed(workers, rounds, |_| {
+        let mut rng = rand::thread_rng();
+        let mut permutation = Vec::with_capacity(len);
+        for range in relation.ranges() {
+            let mut part: Vec<u32> = (range.start as u32..range.end as u32).collect();
+            part.shuffle(&mut rng);
+            permutation.extend(part);
+        }
+        let mask: Vec<u64> = (0..len).map(|_| rng.gen_range(0..q)).collect();
+        let mask_free: Vec<u64> = (0..relation.free).map(|_| rng.gen_range(0..q)).collect();
+        let salts: [Vec<u8>; 3] = std::array::from_fn(|_| (0..SALT_LEN).map(|_| rng.gen()).collect());
+
+        let point = relation.image(&mask_free, &mask)?;
+        let shifted = add_mod(&mask, &extended, q);
+        let commitments = vec![
+            salted_hash(&salts[0], &[&encode_permutation(&permutation), &utils::encode_vector(&point)]),
+            salted_hash(&salts[1], &[&utils::encode_vector(&permute(&permutation, &mask))]),
+            salted_hash(&salts[2], &[&utils::encode_vector(&permute(&permutation, &shifted))]),
+        ];
+        Ok((RoundSecret { permutation, mask, mask_free, shifted, salts }, commitments))
+    })?.into_iter().unzip();
+
+    Ok(Announcement { extended, free, secrets, commitments })
+}
+
+#[cfg(feature = "prover")]
+impl Announcement {
+    // Answer the challenge of every round, each in {0, 1, 2}.
+    pub(crate) fn respond(self, challenges: &[u64]) -> Vec<BinaryRound> {
+        let q = CommitConfig::default().modulus;
+        let (extended, free) = (self.extended, self.free);
+        self.secrets.into_iter().zip(self.commitments).zip(challenges)
+            .map(|((secret, commitments), &challenge)| {
+                let RoundSecret { permutation, mask, mask_free, shifted, salts: [point_salt, mask_salt, shifted_salt] } = secret;
+                let response = match challenge {
+                    0 => BinaryResponse::Permuted {
+                        permuted_witness: permute(&permutation, &extended),
+                        permuted_mask: permute(&permutation, &mask),
+                        mask_salt,
+                        shifted_salt,
+                    },
+                    1 => BinaryResponse::Shifted {
+                        shifted_free: add_mod(&mask_free, &free, q),
+                        permutation,
+                        shifted_witness: shifted,
+                        point_salt,
+                        shifted_salt,
+                    },
+                    _ => BinaryResponse::Masked { permutation, mask, mask_free, point_salt, mask_salt },
+                };
+                BinaryRound { commitments, response }
+            })
+            .collect()
+    }
+}
+
+// Verify a proof of the relation with `count` rounds.
+pub(crate) fn verify_relation(relation: &Relation, rounds: &[BinaryRound], count: usize) -> bool {
+    if rounds.len() != count || rounds.iter().any(|round| round.commitments.len() != 3) {
+        return false;
+    }
+    let commitments: Vec<Vec<Vec<u8>>> = rounds.iter().map(|round| round.commitments.clone()).collect();
+    let challenges = derive_challenges(relation, &commitments);
+
+    rounds.iter().zip(challenges)
+        .all(|(round, challenge)| verify_round(relation, &round.commitments, &round.response, challenge))
+}
+
+// Verify one round given its commitments c1, c2, c3 and its challenge.
+pub(crate) fn verify_round(relation: &Relation, commitments: &[Vec<u8>], response: &BinaryResponse, challenge: u64) -> bool {
+    let q = CommitConfig::default().modulus;
+    let len = relation.len();
+    let in_range = |values: &[u64], len: usize| values.len() == len && values.iter().all(|&x| x < q);
+    // 置换必须把每个块映射到自身，否则各块的扩展性质不再约束见证
+    let permutes = |permutation: &[u32]| is_permutation(permutation, len) && relation.preserves(permutation);
+
+    let [c1, c2, c3] = commitments else {
+        return false;
+    };
+    match (response, challenge) {
+        (BinaryResponse::Permuted { permuted_witness, permuted_mask, mask_salt, shifted_salt }, 0) => {
+            in_range(permuted_mask, len)
+                && relation.holds(permuted_witness, q)
+                && *c2 == salted_hash(mask_salt, &[&utils::encode_vector(permuted_mask)])
+                && *c3 == salted_hash(shifted_salt, &[&utils::encode_vector(&add_mod(permuted_mask, permuted_witness, q))])
+        }
+        (BinaryResponse::Shifted { permutation, shifted_witness, shifted_free, point_salt, shifted_salt }, 1) => {
+            if !permutes(permutation) || !in_range(shifted_witness, len) || !in_range(shifted_free, relation.free) {
+                return false;
+            }
+            let Ok(point) = relation.image(shifted_free, shifted_witness) else {
+                return false;
+            };
+            point.len() == relation.target.len()
+                && *c1 == salted_hash(point_salt, &[&encode_permutation(permutation), &utils::encode_vector(&sub_mod(&point, &relation.target, q))])
+                && *c3 == salted_hash(shifted_salt, &[&utils::encode_vector(&permute(permutation, shifted_witness))])
+        }
+        (BinaryResponse::Masked { permutation, mask, mask_free, point_salt, mask_salt }, 2) => {
+            if !permutes(permutation) || !in_range(mask, len) || !in_range(mask_free, relation.free) {
+                return false;
+            }
+            let Ok(point) = relation.image(mask_free, mask) else {
+                return false;
+            };
+            *c1 == salted_hash(point_salt, &[&encode_permutation(permutation), &utils::encode_vector(&point)])
+                && *c2 == salted_hash(mask_salt, &[&utils::encode_vector(&permute(permutation, mask))])
+        }
+        _ => false,
+    }
+}
+
+impl Block {
+    // Number of coordinates before the extension.
+    fn width(self) -> usize {
+        match self {
+            Block::Bits(n) | Block::Ternary(n) | Block::OneHot(n) => n,
+        }
+    }
+
+    // Number of coordinates after the extension.
+    fn len(self) -> usize {
+        match self {
+            Block::Bits(n) => 2 * n,
+            Block::Ternary(n) => 3 * n,
+            Block::OneHot(n) => n,
+        }
+    }
+
+    // Extend `values`, padded or cut to the width. Values without the property of the block are
+    // kept, so the extension then fails `holds`.
+    #[cfg(feature = "prover")]
+    fn extend(self, values: &[u64], q: u64) -> Vec<u64> {
+        let mut extended: Vec<u64> = values.iter().map(|&x| x % q).collect();
+        extended.resize(self.width(), 0);
+        match self {
+            Block::Bits(_) => {
+                let complement: Vec<u64> = extended.iter().map(|&x| (1 + q - x) % q).collect();
+                extended.extend(complement);
+            }
+            Block::Ternary(n) => {
+                for value in [q - 1, 0, 1] {
+                    let missing = n.saturating_sub(extended.iter().filter(|&&x| x == value).count());
+                    extended.resize(extended.len() + missing, value);
+                }
+                extended.truncate(3 * n);
+            }
+            Block::OneHot(_) => {}
+        }
+        extended
+    }
+
+    // Whether `extended` has the property of the extension of the block.
+    fn holds(self, extended: &[u64], q: u64) -> bool {
+        let count = |value: u64| extended.iter().filter(|&&x| x == value).count();
+        extended.len() == self.len()
+            && match self {
+                Block::Bits(n) => count(1) == n && count(0) == n,
+                Block::Ternary(n) => count(q - 1) == n && count(0) == n && count(1) == n,
+                Block::OneHot(n) => count(1) == 1 && count(0) + 1 == n,
+            }
+    }
+
+    fn tag(self) -> u64 {
+        match self {
+            Block::Bits(_) => 0,
+            Block::Ternary(_) => 1,
+            Block::OneHot(_) => 2,
+        }
+    }
+}
+
+impl Relation<'_> {
+    // Number of coordinates of the extended witness.
+    pub(crate) fn len(&self) -> usize {
+        self.blocks.iter().map(|block| block.len()).sum()
+    }
+
+    // Positions of the blocks in the extended witness.
+    fn ranges(&self) -> Vec<Range<usize>> {
+        let mut start = 0;
+        self.blocks.iter()
+            .map(|block| {
+                let range = start..start + block.len();
+                start = range.end;
+                range
+            })
+            .collect()
+    }
+
+    // Whether every block of `extended` has the property of its extension.
+    fn holds(&self, extended: &[u64], q: u64) -> bool {
+        extended.len() == self.len()
+            && self.blocks.iter().zip(self.ranges()).all(|(block, range)| extended.get(range).is_some_and(|part| block.holds(part, q)))
+    }
+
+    // Whether `permutation` maps every block onto itself.
+    fn preserves(&self, permutation: &[u32]) -> bool {
+        self.ranges().into_iter()
+            .all(|range| permutation.get(range.clone()).is_some_and(|part| part.iter().all(|&target| range.contains(&(target as usize)))))
+    }
+
+    // The map applied to the free coordinates and the heads of the blocks of `extended`.
+    fn image(&self, free: &[u64], extended: &[u64]) -> Result<Vec<u64>, CommitError> {
+        let heads = self.blocks.iter().zip(self.ranges())
+            .map(|(block, range)| extended.get(range.start..range.start + block.width()).ok_or(CommitError::ComputationError))
+            .collect::<Result<Vec<&[u64]>, _>>()?;
+        (self.map)(free, &heads)
+    }
+}
+
+// Weights β_j = ⌊(β + 2^j) / 2^(j+1)⌋ for j up to ⌊log2 β⌋: every integer of [-β, β] is Σ_j β_j·t_j
+// with every t_j in {-1, 0, 1}.
+pub(crate) fn weights(bound: u64) -> Vec<u64> {
+    let bound = bound.max(1);
+    (0..=bound.ilog2()).map(|j| (bound + (1 << j)) >> (j + 1)).collect()
+}
+
+// Split centered coordinates of magnitude at most `bound` into one ternary vector per weight,
+// greedily from the largest weight. Larger coordinates leave a remainder, so the pieces then do
+// not add up to them.
+#[cfg(feature = "prover")]
+pub(crate) fn decompose(values: &[u64], bound: u64, q: u64) -> Vec<Vec<u64>> {
+    let weights = weights(bound);
+    let mut pieces = vec![vec![0u64; values.len()]; weights.len()];
+    for (index, &x) in values.iter().enumerate() {
+        let x = x % q;
+        let (mut rest, sign) = if x > q / 2 { (q - x, q - 1) } else { (x, 1) };
+        for (piece, &weight) in pieces.iter_mut().zip(&weights) {
+            if rest >= weight {
+                rest -= weight;
+                if let Some(slot) = piece.get_mut(index) {
+                    *slot = sign;
+                }
+            }
+        }
+    }
+    pieces
+}
+
+// Σ_j β_j·t_j coordinate-wise modulo q.
+pub(crate) fn recompose(pieces: &[&[u64]], weights: &[u64], q: u64) -> Vec<u64> {
+    let mut sum = vec![0u64; pieces.first().map_or(0, |piece| piece.len())];
+    for (piece, &weight) in pieces.iter().zip(weights) {
+        for (acc, &x) in sum.iter_mut().zip(piece.iter()) {
+            *acc = (*acc + x % q * (weight % q)) % q;
+        }
+    }
+    sum
+}
+
+// A·m + [B | I]·r computed on the calling thread, as every round is.
+pub(crate) fn point(params: (&[Vec<u64>], &[Vec<u64>]), m: &[u64], r: &[u64]) -> Result<Vec<u64>, CommitError> {
+    compute_point(params.0, params.1, m, r, &CommitConfig::default().with_inline_work_threshold(usize::MAX))
+}
+
+// Digest of the matrices, binding them to the challenges.
+pub(crate) fn params_digest(params: (&[Vec<u64>], &[Vec<u64>])) -> Vec<u8> {
+    utils::hash_to_commitment(&[utils::encode_matrix(params.0), utils::encode_matrix(params.1)].concat())
+}
+
+// Move the element at position i to position permutation[i]; targets out of range are dropped,
+// callers check the permutation with `is_permutation` first.
+pub(crate) fn permute(permutation: &[u32], values: &[u64]) -> Vec<u64> {
+    let mut permuted = vec![0u64; values.len()];
+    for (&target, &value) in permutation.iter().zip(values) {
+        if let Some(slot) = permuted.get_mut(target as usize) {
+            *slot = value;
+        }
+    }
+    permuted
+}
+
+pub(crate) fn is_permutation(permutation: &[u32], len: usize) -> bool {
+    if permutation.len() != len {
+        return false;
+    }
+    let mut seen = vec![false; len];
+    permutation.iter().all(|&target| match seen.get_mut(target as usize) {
+        Some(slot) if !*slot => {
+            *slot = true;
+            true
+        }
+        _ => false,
+    })
+}
+
+pub(crate) fn encode_permutation(permutation: &[u32]) -> Vec<u8> {
+    utils::encode_vector(&permutation.iter().map(|&target| target as u64).collect::<Vec<u64>>())
+}
+
+fn add_mod(x: &[u64], y: &[u64], q: u64) -> Vec<u64> {
+    x.iter().zip(y).map(|(&x, &y)| (x % q + y % q) % q).collect()
+}
+
+fn sub_mod(x: &[u64], y: &[u64], q: u64) -> Vec<u64> {
+    x.iter().zip(y).map(|(&x, &y)| (x % q + q - y % q) % q).collect()
+}
+
+pub(crate) fn salted_hash(salt: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut input = BINARY_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(salt));
+    for part in parts {
+        input.extend_from_slice(part);
+    }
+    utils::hash_to_commitment(&input)
+}
+
+fn derive_challenges(relation: &Relation, commitments: &[Vec<Vec<u8>>]) -> Vec<u64> {
+    let mut transcript = transcript(relation);
+    for round in commitments {
+        for commitment in round {
+            transcript.update(&utils::encode_bytes(commitment));
+        }
+    }
+    challenges(&transcript.finalize(), commitments.len())
+}
+
+// Start the Fiat–Shamir transcript with the statement; the round commitments follow it.
+pub(crate) fn transcript(relation: &Relation) -> utils::CommitmentHasher {
+    let mut transcript = utils::CommitmentHasher::new();
+    transcript.update(BINARY_DOMAIN);
+    transcript.update(&utils::encode_bytes(relation.label));
+    transcript.update(&(relation.free as u64).to_be_bytes());
+    let shape: Vec<u64> = relation.blocks.iter().flat_map(|&block| [block.tag(), block.width() as u64]).collect();
+    transcript.update(&utils::encode_vector(&shape));
+    transcript.update(&utils::encode_bytes(&relation.statement));
+    transcript.update(&utils::encode_vector(&relation.target));
+    transcript
+}
+
+// Derive one challenge in {0, 1, 2} per round from the transcript seed.
+pub(crate) fn challenges(seed: &[u8], rounds: usize) -> Vec<u64> {
+    utils::hash_to_range(BINARY_DOMAIN, seed, rounds, 3)
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::key::CommitmentKey;
+    use super::*;
+
+    fn setup() -> (CommitmentKey, Vec<u64>, Vec<u64>, RevelerResult) {
+        let mut rng = StdRng::seed_from_u64(230);
+        let key = CommitmentKey::setup(&mut rng);
+        let (m, r) = (vec![1, 0, 1, 1], utils::generate_randomness(&mut rng));
+        let commitment = key.commit(&m, &r).unwrap();
+        (key, m, r, commitment)
+    }
+
+    #[test]
+    fn honest_binary_proof_verifies() {
+        let (key, m, r, commitment) = setup();
+        let proof = prove_binary(key.as_pair(), &m, &r, &commitment).unwrap();
+        assert!(verify_binary(key.as_pair(), &commitment, m.len(), &proof));
+        assert!(!verify_binary(key.as_pair(), &commitment, m.len() + 1, &proof));
+    }
+
+    #[test]
+    fn bits_opened_through_the_identity_block_are_rejected() {
+        // 承诺 (2, 0, 1, 1)，再用 [B | I] 的单位块把第 0 个坐标改写成比特 1
+        let (key, _, r, _) = setup();
+        let q = CommitConfig::default().modulus;
+        let committed = vec![2, 0, 1, 1];
+        let commitment = key.commit(&committed, &r).unwrap();
+        let bits = vec![1, 0, 1, 1];
+        let mut forged = r.clone();
+        for (row, slot) in key.local_a().iter().zip(forged.iter_mut().skip(RANDOMNESS_LEN - key.local_a().len())) {
+            *slot = (*slot + row[0]) % q;
+        }
+        assert_eq!(key.commit(&bits, &forged).unwrap().commitment_point(), commitment.commitment_point());
+        assert!(prove_binary(key.as_pair(), &bits, &forged, &commitment).is_err());
+
+        let relation = bits_relation(key.as_pair(), bits.len(), &commitment);
+        let witness = Witness { free: Vec::new(), blocks: vec![bits.clone(), forged] };
+        let proof = prove_relation(&relation, &witness, round_count(), Workers::Threads).unwrap();
+        assert!(!verify_binary(key.as_pair(), &commitment, bits.len(), &proof));
+    }
+
+    #[test]
+    fn bounded_values_decompose_into_ternary_pieces() {
+        let q = CommitConfig::default().modulus;
+        for bound in [1, 2, 5, 13, 32] {
+            let values: Vec<u64> = (0..=bound).flat_map(|x| [x, (q - x) % q]).collect();
+            let pieces = decompose(&values, bound, q);
+            assert!(pieces.iter().flatten().all(|&t| t <= 1 || t == q - 1));
+            let pieces: Vec<&[u64]> = pieces.iter().map(Vec::as_slice).collect();
+            assert_eq!(recompose(&pieces, &weights(bound), q), values);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! This module commits to Bloom filters, for approximate membership in sets too large to commit to
+//! exactly.
+//!
+//! A `BloomFilter` of `BloomParams` sets `hashes` of its `bits` for every item, at the positions
+//! `utils::hash_to_range` derives from the item. `BloomParams::for_capacity` picks the classic
+//! sizes `bits = ⌈-n·ln p / ln² 2⌉` and `hashes = round(bits / n · ln 2)` for `n` items at a false
+//! positive rate `p`.
+//!
+//! `CommitmentKey::commit_bloom` commits to the digest of the parameters and the bits with
+//! `CommitmentKey::commit_bytes`, and publishes the parameters next to the commitment, so the
+//! false positive rate is fixed before the filter is revealed. `CommitmentKey::open_bloom` checks a
+//! revealed filter against the commitment and also bounds its fill: a filter may set at most
+//! `BloomParams::max_set_bits`, the expected number of set bits after `capacity` insertions with a
+//! margin of six standard deviations. A committer who inserted notably more items than announced,
+//! raising the false positive rate above the committed one, therefore fails the reveal.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::key::{CommitmentKey, Opening};
+use crate::{utils, RevelerResult};
+
+pub(crate) const INDEX_DOMAIN: &[u8] = b"reveler/bloom/index";
+pub(crate) const DIGEST_DOMAIN: &[u8] = b"reveler/bloom/digest";
+
+/// Size, number of hash functions and capacity of a Bloom filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BloomParams {
+    pub bits: u64,     // The number of bits of the filter
+    pub hashes: u32,   // The number of bits set per item
+    pub capacity: u64, // The number of items the false positive rate is committed for
+}
+
+/// Bloom filter over byte-string items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BloomFilter {
+    params: BloomParams,
+    bits: Vec<u8>, // The bits, least significant bit of byte 0 first
+}
+
+/// Commitment to a Bloom filter, published with its parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BloomCommitment {
+    pub params: BloomParams,       // The parameters the filter is revealed with
+    pub commitment: RevelerResult, // The commitment to the digest of the parameters and the bits
+}
+
+impl BloomParams {
+    /// Creates the parameters of a filter.
+    ///
+    /// # Returns
+    /// The `BloomParams`, or `CommitError::ComputationError` if `bits`, `hashes` or `capacity` is 0
+    /// or the filter would not fit in memory.
+    pub fn new(bits: u64, hashes: u32, capacity: u64) -> Result<Self, CommitError> {
+        if bits == 0 || hashes == 0 || capacity == 0 || usize::try_from(bits.div_ceil(8)).is_err() {
+            return Err(CommitError::ComputationError);
+        }
+        Ok(BloomParams { bits, hashes, capacity })
+    }
+
+    /// Sizes a filter for `capacity` items at the false positive rate `rate`.
+    ///
+    /// # Returns
+    /// The `BloomParams`, or `CommitError::ComputationError` if `capacity` is 0 or `rate` is not
+    /// strictly between 0 and 1.
+    pub fn for_capacity(capacity: u64, rate: f64) -> Result<Self, CommitError> {
+        if capacity == 0 || !(rate > 0.0 && rate < 1.0) {
+            return Err(CommitError::ComputationError);
+        }
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(capacity as f64) * rate.ln() / (ln2 * ln2)).ceil().max(1.0);
+        let hashes = (bits / capacity as f64 * ln2).round().max(1.0);
+        BloomParams::new(bits as u64, hashes as u32, capacity)
+    }
+
+    /// Returns the expected false positive rate of the filter holding `capacity` items,
+    /// `(1 - e^(-hashes·capacity/bits))^hashes`.
+    pub fn false_positive_rate(&self) -> f64 {
+        let fill = 1.0 - (-(self.hashes as f64) * self.capacity as f64 / self.bits as f64).exp();
+        fill.powf(self.hashes as f64)
+    }
+
+    /// Returns the largest number of set bits a revealed filter may have.
+    ///
+    /// This is the expected count after `capacity` insertions,
+    /// `μ = bits·(1 - (1 - 1/bits)^(hashes·capacity))`, plus `6·√μ`, more than six standard
+    /// deviations, so a filter of `capacity` items stays below it except with negligible
+    /// probability. The bound is capped at `hashes·capacity` and `bits`.
+    pub fn max_set_bits(&self) -> u64 {
+        let inserted = self.hashes as f64 * self.capacity as f64;
+        let expected = self.bits as f64 * (1.0 - (1.0 - 1.0 / self.bits as f64).powf(inserted));
+        let bound = (expected + 6.0 * expected.sqrt()).ceil() as u64;
+        bound.min(u64::from(self.hashes).saturating_mul(self.capacity)).min(self.bits)
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = self.bits.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.hashes.to_be_bytes());
+        bytes.extend_from_slice(&self.capacity.to_be_bytes());
+        bytes
+    }
+}
+
+impl BloomFilter {
+    /// Creates an empty filter.
+    pub fn new(params: BloomParams) -> Self {
+        BloomFilter { params, bits: vec![0; params.bits.div_ceil(8) as usize] }
+    }
+
+    /// Returns the parameters of the filter.
+    pub fn params(&self) -> &BloomParams {
+        &self.params
+    }
+
+    /// Returns the bits of the filter, least significant bit of byte 0 first.
+    pub fn bits(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// Adds `item` to the filter.
+    pub fn insert(&mut self, item: &[u8]) {
+        for position in self.positions(item) {
+            if let Some(byte) = byte_index(position).and_then(|index| self.bits.get_mut(index)) {
+                *byte |= 1 << (position % 8);
+            }
+        }
+    }
+
+    /// Returns whether `item` may be in the set: `false` means it is not, `true` that it is, up to
+    /// the false positive rate.
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item)
+            .into_iter()
+            .all(|position| byte_index(position).and_then(|index| self.bits.get(index)).is_some_and(|byte| byte >> (position % 8) & 1 == 1))
+    }
+
+    /// Returns the number of set bits.
+    pub fn set_bits(&self) -> u64 {
+        self.bits.iter().map(|byte| u64::from(byte.count_ones())).sum()
+    }
+
+    // Whether the bit vector has the length of the parameters and no bit set past `bits`.
+    fn is_well_formed(&self) -> bool {
+        let padding = self.params.bits % 8;
+        self.bits.len() as u64 == self.params.bits.div_ceil(8)
+            && (padding == 0 || self.bits.last().is_some_and(|&last| last >> padding == 0))
+    }
+
+    fn positions(&self, item: &[u8]) -> Vec<u64> {
+        utils::hash_to_range(INDEX_DOMAIN, item, self.params.hashes as usize, self.params.bits)
+    }
+
+    fn digest(&self) -> Vec<u8> {
+        let mut input = DIGEST_DOMAIN.to_vec();
+        input.extend(self.params.to_bytes());
+        input.extend(utils::encode_bytes(&self.bits));
+        utils::hash_to_commitment(&input)
+    }
+}
+
+// Index of the byte holding bit `position`, or None if it does not fit a `usize`, which on 32-bit
+// targets would otherwise wrap onto a lower byte.
+fn byte_index(position: u64) -> Option<usize> {
+    usize::try_from(position / 8).ok()
+}
+
+impl CommitmentKey {
+    /// Commits to a Bloom filter under this key.
+    ///
+    /// # Returns
+    /// The `BloomCommitment` and the `Opening` to reveal with the filter.
+    #[cfg(feature = "prover")]
+    pub fn commit_bloom(&self, filter: &BloomFilter) -> Result<(BloomCommitment, Opening), CommitError> {
+        let (commitment, opening) = self.commit_bytes(&filter.digest())?;
+        Ok((BloomCommitment { params: filter.params, commitment }, opening))
+    }
+
+    /// Checks a revealed filter against its commitment.
+    ///
+    /// # Returns
+    /// `true` if the filter has the committed parameters, no more than `BloomParams::max_set_bits`
+    /// set bits, and `opening` opens the commitment to its digest, `false` otherwise, or
+    /// `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if `revocation::check` refuses
+    /// the key.
+    pub fn open_bloom(&self, commitment: &BloomCommitment, filter: &BloomFilter, opening: &Opening) -> Result<bool, CommitError> {
+        if filter.params != commitment.params || !filter.is_well_formed() || filter.set_bits() > filter.params.max_set_bits() {
+            return Ok(false);
+        }
+        match self.open_bytes(&commitment.commitment, opening) {
+            Ok(digest) => Ok(digest == filter.digest()),
+            Err(CommitError::InvalidOpening | CommitError::MalformedEncoding) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! This module provides content-addressed caches for commitment results and expanded parameters.
+//!
+//! Committing is deterministic: the same matrices, message and randomness always produce the same
+//! `RevelerResult`. A `CommitCache` commits under one `CommitmentKey`, whose `params_digest` it
+//! computes once, and keys every commitment by the hash of that digest with the message and the
+//! randomness, so pipelines that recommit duplicate records get the stored result back without
+//! recomputing it, and a lookup hashes the opening rather than the megabyte of the matrices.
+//!
+//! Expanding parameters from a seed is deterministic as well. `params_cache()` is the process-wide
+//! cache `PublicParams::from_seed` consults, so the matrices of a seed are expanded once however
+//! many places of an application ask for them. It is disabled until `set_capacity` opts in.
+//!
+//! Hits, misses and evictions are counted and exposed through `CacheMetrics`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+use crate::params::PublicParams;
+#[cfg(feature = "prover")]
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::{utils, RevelerResult};
+
+/// Default capacity of a `CommitCache`, see `CommitCache::with_default_capacity`.
+#[cfg(feature = "prover")]
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Snapshot of the cache counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+impl CacheMetrics {
+    /// Returns the fraction of lookups that were served from the cache, or `0.0` before any lookup.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<Vec<u8>, RevelerResult>,
+    order: VecDeque<Vec<u8>>,
+}
+
+/// Deduplicating committer under one key that stores results keyed by the message and randomness.
+///
+/// The oldest entry is evicted once `capacity` results are stored. The cache is safe to share
+/// between threads.
+#[cfg(feature = "prover")]
+pub struct CommitCache {
+    key: CommitmentKey,
+    params_digest: Vec<u8>,
+    capacity: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+#[cfg(feature = "prover")]
+impl CommitCache {
+    /// Creates an empty cache of the commitments under `key` holding at most `capacity` results.
+    ///
+    /// # Parameters
+    /// - `key`: The `CommitmentKey` every commitment of the cache is computed under. Its digest is
+    ///   computed here, once.
+    /// - `capacity`: The maximum number of stored results. A capacity of `0` disables storage.
+    ///
+    /// # Returns
+    /// A new instance of `CommitCache`.
+    pub fn new(key: CommitmentKey, capacity: usize) -> Self {
+        CommitCache {
+            params_digest: key.digest(),
+            key,
+            capacity,
+            state: Mutex::new(CacheState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates an empty cache of the commitments under `key` holding at most
+    /// `DEFAULT_CACHE_CAPACITY` results.
+    pub fn with_default_capacity(key: CommitmentKey) -> Self {
+        CommitCache::new(key, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Returns the key every commitment of the cache is computed under.
+    pub fn commitment_key(&self) -> &CommitmentKey {
+        &self.key
+    }
+
+    /// Computes the cache key of an opening: the hash of the digest of the parameters followed by
+    /// the length-prefixed message and randomness.
+    pub fn key(&self, local_m: &[u64], local_r: &[u64]) -> Vec<u8> {
+        let mut input = utils::encode_bytes(&self.params_digest);
+        input.extend(utils::encode_vector(local_m));
+        input.extend(utils::encode_vector(local_r));
+        utils::hash_to_commitment(&input)
+    }
+
+    /// Returns the cached result for the opening `(local_m, local_r)`, computing and storing it on
+    /// a miss.
+    ///
+    /// # Parameters
+    /// - `local_m`: The message vector.
+    /// - `local_r`: The randomness vector.
+    ///
+    /// # Returns
+    /// The `RevelerResult` for the inputs, as `CommitmentKey::commit`, or the `CommitError` raised
+    /// while computing it.
+    pub fn commit(&self, local_m: &[u64], local_r: &[u64]) -> Result<RevelerResult, CommitError> {
+        let key = self.key(local_m, local_r);
+
+        if let Some(result) = self.lock().entries.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(result.clone());
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let result = self.key.commit(local_m, local_r)?;
+        self.insert(key, result.clone());
+        Ok(result)
+    }
+
+    /// Returns the current counters and the number of stored results.
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.lock().entries.len(),
+        }
+    }
+
+    /// Removes every stored result. The counters are kept.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.order.clear();
+    }
+
+    fn insert(&self, key: Vec<u8>, result: RevelerResult) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        if state.entries.contains_key(&key) {
+            return;
+        }
+
+        while state.entries.len() >= self.capacity {
+            match state.order.pop_front() {
+                Some(oldest) => {
+                    state.entries.remove(&oldest);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+                None => break,
+            }
+        }
+
+        state.order.push_back(key.clone());
+        state.entries.insert(key, result);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Default)]
+struct ParamsState {
+    capacity: usize,
+    entries: HashMap<Vec<u8>, Arc<PublicParams>>,
+    order: VecDeque<Vec<u8>>,
+}
+
+/// Process-wide cache of parameters expanded from seeds.
+///
+/// The oldest entry is evicted once `capacity` parameter sets are stored. The cache is safe to
+/// share between threads.
+pub struct ParamsCache {
+    state: Mutex<ParamsState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// Returns the process-wide parameters cache.
+pub fn params_cache() -> &'static ParamsCache {
+    static CACHE: OnceLock<ParamsCache> = OnceLock::new();
+    CACHE.get_or_init(|| ParamsCache {
+        state: Mutex::new(ParamsState::default()),
+        hits: AtomicU64::new(0),
+        misses: AtomicU64::new(0),
+        evictions: AtomicU64::new(0),
+    })
+}
+
+impl ParamsCache {
+    /// Sets the maximum number of stored parameter sets, evicting the oldest ones beyond it.
+    ///
+    /// # Parameters
+    /// - `capacity`: The maximum number of stored parameter sets. The default of `0` disables the
+    ///   cache.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.lock();
+        state.capacity = capacity;
+        self.evict_beyond(&mut state, capacity);
+    }
+
+    /// Returns the current counters and the number of stored parameter sets.
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.lock().entries.len(),
+        }
+    }
+
+    /// Removes every stored parameter set. The counters and the capacity are kept.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.order.clear();
+    }
+
+    // Return the parameters stored under `key`, expanding and storing them on a miss.
+    pub(crate) fn get_or_expand(&self, key: Vec<u8>, expand: impl FnOnce() -> PublicParams) -> Arc<PublicParams> {
+        if let Some(params) = self.lock().entries.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return params.clone();
+        }
+
+        // 在锁外展开矩阵，避免阻塞其他线程的查询
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let params = Arc::new(expand());
+
+        let mut state = self.lock();
+        let capacity = state.capacity;
+        if capacity == 0 {
+            return params;
+        }
+        if let Some(existing) = state.entries.get(&key) {
+            return existing.clone();
+        }
+        self.evict_beyond(&mut state, capacity - 1);
+        state.order.push_back(key.clone());
+        state.entries.insert(key, params.clone());
+        params
+    }
+
+    fn evict_beyond(&self, state: &mut ParamsState, capacity: usize) {
+        while state.entries.len() > capacity {
+            match state.order.pop_front() {
+                Some(oldest) => {
+                    state.entries.remove(&oldest);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ParamsState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn lookups_key_on_the_opening_under_the_key_digest() {
+        let mut rng = StdRng::seed_from_u64(205);
+        let (key, other) = (CommitmentKey::setup(&mut rng), CommitmentKey::setup(&mut rng));
+        let r = utils::generate_randomness(&mut rng);
+        let cache = CommitCache::new(key.clone(), 2);
+        let first = cache.commit(&[1, 2], &r).unwrap();
+        assert_eq!(cache.commit(&[1, 2], &r), Ok(first.clone()));
+        assert_eq!(first, key.commit(&[1, 2], &r).unwrap());
+        let metrics = cache.metrics();
+        assert_eq!((metrics.hits, metrics.misses, metrics.entries), (1, 1, 1));
+
+        // 相同的开启在不同参数下键不同
+        assert_ne!(cache.key(&[1, 2], &r), CommitCache::new(other, 2).key(&[1, 2], &r));
+        assert_ne!(cache.key(&[1, 2], &r), cache.key(&[1], &r));
+    }
+}
@@ -0,0 +1,56 @@
+//! This module provides the time sources used for deadlines and expiry.
+//!
+//! Every API that compares against a deadline takes a `&dyn Clock` instead of reading the system
+//! time, so deterministic tests can drive a `VirtualClock` and deployments with a trusted time
+//! source (roughtime, blockchain timestamps) can supply their own implementation.
+//!
+//! Times are expressed in seconds since the Unix epoch.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time in seconds since the Unix epoch.
+    fn now(&self) -> u64;
+}
+
+/// Clock reading the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        // 系统时间早于 Unix 纪元时按 0 处理
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+    }
+}
+
+/// Clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    now: AtomicU64,
+}
+
+impl VirtualClock {
+    /// Creates a clock showing time `start`.
+    pub fn new(start: u64) -> Self {
+        VirtualClock { now: AtomicU64::new(start) }
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Moves the clock forward by `seconds`.
+    pub fn advance(&self, seconds: u64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -1,4 +1,22 @@
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CommitError {
     ComputationError,
+    ArithmeticOverflow,
+    UnsupportedModulus,
+    MalformedEncoding,
+    DifficultyTooHigh,
+    DecryptionFailed,
+    InvalidThreshold,
+    InvalidOpening,
+    InvalidTransition,
+    TransportError,
+    HandshakeFailed,
+    StorageError,
+    UnsupportedScheme,
+    InvalidProofConfig,
+    ParamsExpired,
+    ParamsRevoked,
+    TenantMismatch,
+    LimitExceeded,
+    PolicyViolation,
 }
@@ -0,0 +1,331 @@
+//! This module provides committee-based conditional reveals as a fallback to time-locks.
+//!
+//! An opening is encrypted under the public key of a k-of-n committee with threshold ElGamal in
+//! the 2048-bit MODP group of RFC 3526, where `g = 2` generates the subgroup of prime order
+//! `q = (p - 1) / 2`. Any `k` committee members can jointly recover the opening, fewer learn
+//! nothing about it.
+//!
+//! The flow is:
+//! - `deal`: a trusted dealer splits the committee secret into Shamir shares. No distributed key
+//!   generation is involved.
+//! - `conditional_reveal`: the committer encrypts an opening to the committee key.
+//! - `partial_decrypt`: each member publishes its decryption share with a Chaum–Pedersen proof
+//!   that it used the share matching its public verification key.
+//! - `verify_partial` / `combine`: anyone checks the partial decryptions and combines `k` valid
+//!   ones into the opening.
+//!
+//! The module is compiled with the `committee` feature.
+
+use num_bigint::{BigUint, RandBigInt};
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::utils;
+
+pub(crate) const COMMITTEE_KEY_DOMAIN: &[u8] = b"reveler/committee/key";
+pub(crate) const COMMITTEE_TAG_DOMAIN: &[u8] = b"reveler/committee/tag";
+pub(crate) const COMMITTEE_PROOF_DOMAIN: &[u8] = b"reveler/committee/dleq";
+
+// RFC 3526 2048-bit MODP group prime.
+const GROUP_PRIME_HEX: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A0879",
+    "8E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B",
+    "0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA4836",
+    "1C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804",
+    "F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6",
+    "955817183995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF",
+);
+
+/// Public key of a k-of-n committee.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitteeKey {
+    pub threshold: usize,
+    pub public_key: Vec<u8>,             // h = g^x, big-endian
+    pub verification_keys: Vec<Vec<u8>>, // h_i = g^{x_i} of member i + 1, big-endian
+}
+
+/// Secret key share of a committee member.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub index: u64,      // The member index, starting at 1
+    pub secret: Vec<u8>, // The share x_i = f(index), big-endian
+}
+
+/// An opening encrypted to a committee.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedOpening {
+    pub ephemeral: Vec<u8>,  // c = g^y, big-endian
+    pub ciphertext: Vec<u8>, // The encrypted opening
+    pub tag: Vec<u8>,        // The integrity tag over the key and ciphertext
+}
+
+/// Decryption share of one member, with a proof of correct decryption.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialDecryption {
+    pub index: u64,
+    pub share: Vec<u8>,     // d_i = c^{x_i}, big-endian
+    pub challenge: Vec<u8>, // Chaum–Pedersen challenge e
+    pub response: Vec<u8>,  // Chaum–Pedersen response z = w + e·x_i mod q
+}
+
+struct Group {
+    p: BigUint,
+    q: BigUint,
+    g: BigUint,
+}
+
+impl Group {
+    fn new() -> Self {
+        let p = BigUint::parse_bytes(GROUP_PRIME_HEX.as_bytes(), 16).unwrap_or_default();
+        let q = (&p - 1u32) >> 1;
+        Group { p, q, g: BigUint::from(2u32) }
+    }
+
+    // Check that `x` lies in the prime-order subgroup.
+    fn contains(&self, x: &BigUint) -> bool {
+        *x > BigUint::from(1u32) && *x < self.p && x.modpow(&self.q, &self.p) == BigUint::from(1u32)
+    }
+
+    fn element(&self, bytes: &[u8]) -> Result<BigUint, CommitError> {
+        let x = BigUint::from_bytes_be(bytes);
+        if self.contains(&x) {
+            Ok(x)
+        } else {
+            Err(CommitError::MalformedEncoding)
+        }
+    }
+
+    fn scalar(&self, bytes: &[u8]) -> Result<BigUint, CommitError> {
+        let x = BigUint::from_bytes_be(bytes);
+        if x < self.q {
+            Ok(x)
+        } else {
+            Err(CommitError::MalformedEncoding)
+        }
+    }
+
+    fn random_scalar(&self) -> BigUint {
+        rand::thread_rng().gen_biguint_below(&self.q)
+    }
+
+    fn inverse_mod_q(&self, x: &BigUint) -> BigUint {
+        x.modpow(&(&self.q - 2u32), &self.q)
+    }
+}
+
+/// Generates a committee key and its `members` secret shares with a trusted dealer.
+///
+/// # Parameters
+/// - `threshold`: The number `k` of members needed to decrypt.
+/// - `members`: The committee size `n`.
+///
+/// # Returns
+/// The `CommitteeKey` and the `KeyShare` of each member, or `CommitError::InvalidThreshold`
+/// unless `1 <= threshold <= members`.
+pub fn deal(threshold: usize, members: usize) -> Result<(CommitteeKey, Vec<KeyShare>), CommitError> {
+    if threshold == 0 || threshold > members {
+        return Err(CommitError::InvalidThreshold);
+    }
+
+    let group = Group::new();
+    let coefficients: Vec<BigUint> = (0..threshold).map(|_| group.random_scalar()).collect();
+
+    let shares: Vec<KeyShare> = (1..=members as u64)
+        .map(|index| {
+            // Horner 法计算 f(index) mod q
+            let x = BigUint::from(index);
+            let secret = coefficients.iter().rev()
+                .fold(BigUint::from(0u32), |acc, c| (acc * &x + c) % &group.q);
+            KeyShare { index, secret: secret.to_bytes_be() }
+        })
+        .collect();
+
+    let key = CommitteeKey {
+        threshold,
+        public_key: group.g.modpow(coefficients.first().ok_or(CommitError::InvalidThreshold)?, &group.p).to_bytes_be(),
+        verification_keys: shares.iter()
+            .map(|share| group.g.modpow(&BigUint::from_bytes_be(&share.secret), &group.p).to_bytes_be())
+            .collect(),
+    };
+
+    Ok((key, shares))
+}
+
+/// Encrypts an opening to the committee.
+///
+/// # Parameters
+/// - `key`: A reference to the `CommitteeKey` to encrypt to.
+/// - `local_m`: A reference to the message vector of the opening.
+/// - `local_r`: A reference to the randomness vector of the opening.
+///
+/// # Returns
+/// The `EncryptedOpening`, or `CommitError::MalformedEncoding` if the public key is invalid.
+pub fn conditional_reveal(key: &CommitteeKey, local_m: &[u64], local_r: &[u64]) -> Result<EncryptedOpening, CommitError> {
+    let group = Group::new();
+    let public_key = group.element(&key.public_key)?;
+
+    let y = group.random_scalar();
+    let ephemeral = group.g.modpow(&y, &group.p);
+    let symmetric_key = derive_key(&public_key.modpow(&y, &group.p));
+
+    let mut plaintext = utils::encode_vector(local_m);
+    plaintext.extend(utils::encode_vector(local_r));
+    let ciphertext = utils::xor_keystream(&symmetric_key, &plaintext);
+
+    Ok(EncryptedOpening {
+        ephemeral: ephemeral.to_bytes_be(),
+        tag: compute_tag(&symmetric_key, &ciphertext),
+        ciphertext,
+    })
+}
+
+/// Computes a member's decryption share together with its proof of correctness.
+///
+/// # Parameters
+/// - `share`: A reference to the member's `KeyShare`.
+/// - `encrypted`: A reference to the `EncryptedOpening` to decrypt.
+///
+/// # Returns
+/// The `PartialDecryption` of the member.
+pub fn partial_decrypt(share: &KeyShare, encrypted: &EncryptedOpening) -> Result<PartialDecryption, CommitError> {
+    let group = Group::new();
+    let ephemeral = group.element(&encrypted.ephemeral)?;
+    let secret = group.scalar(&share.secret)?;
+
+    let verification_key = group.g.modpow(&secret, &group.p);
+    let decryption_share = ephemeral.modpow(&secret, &group.p);
+
+    let w = group.random_scalar();
+    let a1 = group.g.modpow(&w, &group.p);
+    let a2 = ephemeral.modpow(&w, &group.p);
+    let challenge = proof_challenge(&group, &verification_key, &ephemeral, &decryption_share, &a1, &a2);
+    let response = (w + &challenge * &secret) % &group.q;
+
+    Ok(PartialDecryption {
+        index: share.index,
+        share: decryption_share.to_bytes_be(),
+        challenge: challenge.to_bytes_be(),
+        response: response.to_bytes_be(),
+    })
+}
+
+/// Checks the Chaum–Pedersen proof of a partial decryption against the committee key.
+///
+/// # Returns
+/// `true` if the member index is known and the share was computed with its key share.
+pub fn verify_partial(key: &CommitteeKey, encrypted: &EncryptedOpening, partial: &PartialDecryption) -> bool {
+    let group = Group::new();
+    verify_partial_in(&group, key, encrypted, partial).unwrap_or(false)
+}
+
+/// Combines `threshold` valid partial decryptions into the opening.
+///
+/// Invalid and duplicate partial decryptions are skipped.
+///
+/// # Returns
+/// The `(m, r)` opening, `CommitError::InvalidThreshold` if fewer than `threshold` valid shares
+/// were supplied, or `CommitError::DecryptionFailed` if the ciphertext does not authenticate.
+pub fn combine(
+    key: &CommitteeKey,
+    encrypted: &EncryptedOpening,
+    partials: &[PartialDecryption],
+) -> Result<(Vec<u64>, Vec<u64>), CommitError> {
+    let group = Group::new();
+
+    let mut selected: Vec<&PartialDecryption> = Vec::with_capacity(key.threshold);
+    for partial in partials {
+        if selected.len() == key.threshold {
+            break;
+        }
+        if selected.iter().all(|s| s.index != partial.index)
+            && verify_partial_in(&group, key, encrypted, partial).unwrap_or(false)
+        {
+            selected.push(partial);
+        }
+    }
+    if selected.len() < key.threshold {
+        return Err(CommitError::InvalidThreshold);
+    }
+
+    // 在指数上做拉格朗日插值，恢复 h^y
+    let mut shared = BigUint::from(1u32);
+    for partial in &selected {
+        let i = BigUint::from(partial.index);
+        let mut numerator = BigUint::from(1u32);
+        let mut denominator = BigUint::from(1u32);
+        for other in &selected {
+            if other.index == partial.index {
+                continue;
+            }
+            let j = BigUint::from(other.index);
+            numerator = numerator * &j % &group.q;
+            denominator = denominator * ((&j + &group.q - &i) % &group.q) % &group.q;
+        }
+        let lambda = numerator * group.inverse_mod_q(&denominator) % &group.q;
+        let share = group.element(&partial.share)?;
+        shared = shared * share.modpow(&lambda, &group.p) % &group.p;
+    }
+
+    let symmetric_key = derive_key(&shared);
+    if compute_tag(&symmetric_key, &encrypted.ciphertext) != encrypted.tag {
+        return Err(CommitError::DecryptionFailed);
+    }
+
+    let plaintext = utils::xor_keystream(&symmetric_key, &encrypted.ciphertext);
+    let (local_m, rest) = utils::decode_vector(&plaintext)?;
+    let (local_r, rest) = utils::decode_vector(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok((local_m, local_r))
+}
+
+fn verify_partial_in(
+    group: &Group,
+    key: &CommitteeKey,
+    encrypted: &EncryptedOpening,
+    partial: &PartialDecryption,
+) -> Result<bool, CommitError> {
+    let verification_key = match partial.index.checked_sub(1).and_then(|i| usize::try_from(i).ok()).and_then(|i| key.verification_keys.get(i)) {
+        Some(bytes) => group.element(bytes)?,
+        None => return Ok(false),
+    };
+    let ephemeral = group.element(&encrypted.ephemeral)?;
+    let decryption_share = group.element(&partial.share)?;
+    let challenge = group.scalar(&partial.challenge)?;
+    let response = group.scalar(&partial.response)?;
+
+    // 子群阶为 q，故 x^{q-e} = x^{-e}
+    let negated = (&group.q - &challenge) % &group.q;
+    let a1 = group.g.modpow(&response, &group.p) * verification_key.modpow(&negated, &group.p) % &group.p;
+    let a2 = ephemeral.modpow(&response, &group.p) * decryption_share.modpow(&negated, &group.p) % &group.p;
+
+    Ok(proof_challenge(group, &verification_key, &ephemeral, &decryption_share, &a1, &a2) == challenge)
+}
+
+fn proof_challenge(
+    group: &Group,
+    verification_key: &BigUint,
+    ephemeral: &BigUint,
+    decryption_share: &BigUint,
+    a1: &BigUint,
+    a2: &BigUint,
+) -> BigUint {
+    let mut input = COMMITTEE_PROOF_DOMAIN.to_vec();
+    for element in [verification_key, ephemeral, decryption_share, a1, a2] {
+        input.extend(utils::encode_bytes(&element.to_bytes_be()));
+    }
+    BigUint::from_bytes_be(&utils::hash_to_commitment(&input)) % &group.q
+}
+
+fn derive_key(shared: &BigUint) -> Vec<u8> {
+    let mut input = COMMITTEE_KEY_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(&shared.to_bytes_be()));
+    utils::hash_to_commitment(&input)
+}
+
+fn compute_tag(key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut input = COMMITTEE_TAG_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(key));
+    input.extend(utils::encode_bytes(ciphertext));
+    utils::hash_to_commitment(&input)
+}
@@ -0,0 +1,51 @@
+//! This module keeps the public-field layout of `RevelerCommit` and `RevelerResult` from 0.1.
+//!
+//! Code written against the old layout can switch its imports to `reveler::compat` and convert
+//! with `From`/`Into` wherever it calls into the rest of the crate. The serde representation is
+//! the same as the current types, so stored JSON keeps working in both directions.
+//!
+//! The module is compiled with the `compat` feature.
+
+use serde::{Serialize, Deserialize};
+
+/// `RevelerResult` with public fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevelerResult {
+    pub commitment_point: Vec<u64>, // The commitment point computed from input parameters
+    pub commitment_hash: Vec<u8>,   // The hash of the commitment point
+}
+
+/// `RevelerCommit` with public fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevelerCommit {
+    pub local_a: Vec<Vec<u64>>,
+    pub local_b: Vec<Vec<u64>>,
+    pub local_m: Vec<u64>,
+    pub local_r: Vec<u64>,
+}
+
+impl From<crate::RevelerResult> for RevelerResult {
+    fn from(result: crate::RevelerResult) -> Self {
+        let (commitment_point, commitment_hash) = result.into_parts();
+        RevelerResult { commitment_point, commitment_hash }
+    }
+}
+
+impl From<RevelerResult> for crate::RevelerResult {
+    fn from(result: RevelerResult) -> Self {
+        crate::RevelerResult::new(result.commitment_point, result.commitment_hash)
+    }
+}
+
+impl From<crate::RevelerCommit> for RevelerCommit {
+    fn from(commit: crate::RevelerCommit) -> Self {
+        let (local_a, local_b, local_m, local_r) = commit.into_parts();
+        RevelerCommit { local_a, local_b, local_m, local_r }
+    }
+}
+
+impl From<RevelerCommit> for crate::RevelerCommit {
+    fn from(commit: RevelerCommit) -> Self {
+        crate::RevelerCommit::new(commit.local_a, commit.local_b, commit.local_m, commit.local_r)
+    }
+}
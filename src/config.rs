@@ -0,0 +1,268 @@
+//! This module provides the configuration used during commitment generation.
+//!
+//! `CommitConfig` collects the tuning knobs of `RevelerCommit::commit_with_config`. The default
+//! configuration is what `RevelerCommit::commit` uses.
+//!
+//! `BatchConfig` sets how the rows of a batch commitment are split into tasks, see
+//! `key::CommitmentKey::commit_batch_with_config`.
+//!
+//! `HashPolicy` selects the hash binding a commitment point in `hash_commitment_point`.
+//!
+//! `ProofConfig` sets the soundness of the `binary` and `linking` proofs, trading proof size for
+//! security; the default configuration is what `prove_binary` and `prove_same_message_across` use.
+
+use serde::{Serialize, Deserialize};
+use crate::arith::ArithmeticPolicy;
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_N, LOCAL_Q};
+
+/// Default number of leading field elements a message may occupy to take the direct path.
+pub const DEFAULT_SMALL_MESSAGE_LIMIT: usize = 8;
+
+/// Default number of matrix elements below which the block path runs on the calling thread,
+/// reached by supports shorter than 256 coordinates in total.
+///
+/// Measured with the `inline threshold` benchmark: a matrix element costs about 4.5 ns and
+/// spawning and joining a scoped thread about 19 µs, so the 16 workers of the default blocks
+/// only pay off above some 300 µs of work, about `256 * LOCAL_N` elements. A full opening of
+/// `MESSAGE_LEN + RANDOMNESS_LEN` coordinates stays on the worker threads.
+pub const DEFAULT_INLINE_WORK_THRESHOLD: usize = 256 * LOCAL_N;
+
+/// Default number of matrix rows in each block of the block path.
+pub const DEFAULT_BLOCK_ROWS: usize = 16;
+
+/// Tuning options for commitment generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitConfig {
+    /// Messages whose non-zero entries all lie within the first `small_message_limit`
+    /// coordinates are committed row by row on the calling thread, without splitting the rows
+    /// into blocks, whatever the length of the randomness. Set to `0` to always use the block
+    /// path.
+    pub small_message_limit: usize,
+    /// Workloads reading fewer than `inline_work_threshold` matrix elements (rows × the
+    /// supports of the message and the randomness) are computed single-threaded on the calling
+    /// thread, since spawning workers costs more than the work itself. Set to `0` to always use
+    /// worker threads.
+    pub inline_work_threshold: usize,
+    /// Number of matrix rows per block. Block boundaries depend only on this value, never on
+    /// the number of worker threads, so the sequence of per-block partial results is identical
+    /// on every machine. Values below `1` are treated as `1`.
+    pub block_rows: usize,
+    /// Overflow handling of the modular arithmetic. Defaults to checked arithmetic in debug
+    /// builds and wrapping arithmetic in release builds.
+    pub arithmetic: ArithmeticPolicy,
+    /// Modulus `q` of the commitment arithmetic, between `2` and `MAX_SUPPORTED_Q`. The point
+    /// is computed with exact integer arithmetic, so every path returns the same point on every
+    /// platform.
+    pub modulus: u64,
+    /// Attach the BLAKE3 fast digest of the point to every commitment, in addition to the binding
+    /// hash. Ignored without the `fast-digest` feature.
+    #[serde(default)]
+    pub fast_digest: bool,
+}
+
+impl CommitConfig {
+    /// Creates a configuration holding the default values.
+    pub fn new() -> Self {
+        CommitConfig {
+            small_message_limit: DEFAULT_SMALL_MESSAGE_LIMIT,
+            inline_work_threshold: DEFAULT_INLINE_WORK_THRESHOLD,
+            block_rows: DEFAULT_BLOCK_ROWS,
+            arithmetic: ArithmeticPolicy::for_build(),
+            modulus: LOCAL_Q,
+            fast_digest: false,
+        }
+    }
+
+    /// Sets the number of leading coordinates below which the direct path is selected.
+    pub fn with_small_message_limit(mut self, small_message_limit: usize) -> Self {
+        self.small_message_limit = small_message_limit;
+        self
+    }
+
+    /// Sets the number of matrix elements below which commitments are computed inline.
+    pub fn with_inline_work_threshold(mut self, inline_work_threshold: usize) -> Self {
+        self.inline_work_threshold = inline_work_threshold;
+        self
+    }
+
+    /// Sets the number of matrix rows in each block.
+    pub fn with_block_rows(mut self, block_rows: usize) -> Self {
+        self.block_rows = block_rows;
+        self
+    }
+
+    /// Sets the overflow handling of the modular arithmetic.
+    pub fn with_arithmetic(mut self, arithmetic: ArithmeticPolicy) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    /// Sets the modulus `q` of the commitment arithmetic.
+    pub fn with_modulus(mut self, modulus: u64) -> Self {
+        self.modulus = modulus;
+        self
+    }
+
+    /// Sets whether commitments carry the fast digest of their point, see `RevelerResult::fast_digest`.
+    #[cfg(feature = "fast-digest")]
+    pub fn with_fast_digest(mut self, fast_digest: bool) -> Self {
+        self.fast_digest = fast_digest;
+        self
+    }
+}
+
+impl Default for CommitConfig {
+    fn default() -> Self {
+        CommitConfig::new()
+    }
+}
+
+/// Smallest number of rows per task `BatchConfig::auto` picks, so that a few dozen rows amortize
+/// taking the next task.
+pub const MIN_AUTO_CHUNK_ROWS: usize = 32;
+
+/// Number of tasks per worker thread `BatchConfig::auto` aims for.
+pub const AUTO_TASKS_PER_THREAD: usize = 8;
+
+/// Work splitting of batch commitments.
+///
+/// The rows of all points of a batch, `LOCAL_N` per opening, are cut into chunks of consecutive
+/// rows, and every worker thread takes the next chunk as soon as it is done with its last one.
+/// Smaller chunks balance the load better when rows differ in cost, e.g. with sparse or small
+/// messages, at the price of more scheduling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchConfig {
+    pub rows_per_chunk: Option<usize>, // The rows per task, or None to size them with `auto`
+    pub commit: CommitConfig,          // The configuration of the point computation
+}
+
+impl BatchConfig {
+    /// Creates a configuration sizing the chunks automatically: `AUTO_TASKS_PER_THREAD` chunks per
+    /// worker thread, of at least `MIN_AUTO_CHUNK_ROWS` rows.
+    pub fn auto() -> Self {
+        BatchConfig { rows_per_chunk: None, commit: CommitConfig::default() }
+    }
+
+    /// Creates a configuration with chunks of `rows` rows. Values below `1` are treated as `1`.
+    pub fn chunk_rows(rows: usize) -> Self {
+        BatchConfig { rows_per_chunk: Some(rows), commit: CommitConfig::default() }
+    }
+
+    /// Sets the configuration of the point computation.
+    pub fn with_commit_config(mut self, commit: CommitConfig) -> Self {
+        self.commit = commit;
+        self
+    }
+
+    /// Returns the number of rows per task for a batch of `total_rows` rows on `threads` workers.
+    pub fn rows_per_task(&self, total_rows: usize, threads: usize) -> usize {
+        match self.rows_per_chunk {
+            Some(rows) => rows.max(1),
+            None => total_rows.div_ceil(threads.max(1) * AUTO_TASKS_PER_THREAD).max(MIN_AUTO_CHUNK_ROWS),
+        }
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig::auto()
+    }
+}
+
+/// Hash function binding a commitment point, as used by `hash_commitment_point`.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum HashPolicy {
+    /// `utils::hash_to_commitment`, the hash of every commitment of the current scheme.
+    #[default]
+    BlueHash256,
+    /// A caller-provided hash, e.g. the hasher a deployment migrates its commitments to.
+    Custom(fn(&[u8]) -> Vec<u8>),
+}
+
+/// Default soundness target of proofs in bits.
+pub const DEFAULT_SOUNDNESS_BITS: u32 = 128;
+
+/// Smallest soundness target a `ProofConfig` accepts.
+pub const MIN_SOUNDNESS_BITS: u32 = 40;
+
+/// Largest soundness target a `ProofConfig` accepts.
+pub const MAX_SOUNDNESS_BITS: u32 = 256;
+
+// Challenge space of a round of Stern's protocol, which has soundness error 2/3.
+const BINARY_CHALLENGE_SPACE: u64 = 3;
+
+/// Soundness options of a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofConfig {
+    /// Soundness target in bits, between `MIN_SOUNDNESS_BITS` and `MAX_SOUNDNESS_BITS`. A
+    /// cheating prover succeeds with probability at most `2^-soundness_bits`.
+    pub soundness_bits: u32,
+    /// Number of repetitions of the protocol. `None` uses the fewest rounds reaching the
+    /// soundness target; an explicit count must reach it as well.
+    pub repetitions: Option<usize>,
+    /// Number of challenges of a round. `None` uses `3`, the only space of the `binary` proofs
+    /// every proof of the crate is built on.
+    pub challenge_space: Option<u64>,
+}
+
+impl ProofConfig {
+    /// Creates a configuration holding the default values.
+    pub fn new() -> Self {
+        ProofConfig {
+            soundness_bits: DEFAULT_SOUNDNESS_BITS,
+            repetitions: None,
+            challenge_space: None,
+        }
+    }
+
+    /// Sets the soundness target in bits.
+    pub fn with_soundness_bits(mut self, soundness_bits: u32) -> Self {
+        self.soundness_bits = soundness_bits;
+        self
+    }
+
+    /// Sets the number of repetitions.
+    pub fn with_repetitions(mut self, repetitions: usize) -> Self {
+        self.repetitions = Some(repetitions);
+        self
+    }
+
+    /// Sets the number of challenges of a round.
+    pub fn with_challenge_space(mut self, challenge_space: u64) -> Self {
+        self.challenge_space = Some(challenge_space);
+        self
+    }
+
+    /// Validates the configuration for binary proofs.
+    ///
+    /// # Returns
+    /// The number of rounds, or `CommitError::InvalidProofConfig` if the soundness target is out of
+    /// range, the challenge space is not `3`, or the repetitions fall short of the target.
+    pub fn binary_rounds(&self) -> Result<usize, CommitError> {
+        if self.challenge_space.is_some_and(|space| space != BINARY_CHALLENGE_SPACE) {
+            return Err(CommitError::InvalidProofConfig);
+        }
+        // 每轮作弊成功概率为 2/3
+        self.rounds((BINARY_CHALLENGE_SPACE as f64 / (BINARY_CHALLENGE_SPACE - 1) as f64).log2())
+    }
+
+    // Count the rounds reaching the soundness target at `bits_per_round` bits each.
+    fn rounds(&self, bits_per_round: f64) -> Result<usize, CommitError> {
+        if !(MIN_SOUNDNESS_BITS..=MAX_SOUNDNESS_BITS).contains(&self.soundness_bits) {
+            return Err(CommitError::InvalidProofConfig);
+        }
+        let required = (self.soundness_bits as f64 / bits_per_round).ceil() as usize;
+        match self.repetitions {
+            Some(repetitions) if repetitions < required => Err(CommitError::InvalidProofConfig),
+            Some(repetitions) => Ok(repetitions),
+            None => Ok(required),
+        }
+    }
+}
+
+impl Default for ProofConfig {
+    fn default() -> Self {
+        ProofConfig::new()
+    }
+}
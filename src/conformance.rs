@@ -0,0 +1,334 @@
+//! This module generates the cross-language conformance suite.
+//!
+//! `write_suite` emits a directory of JSON test cases that reimplementations in other languages
+//! can replay to prove byte-level compatibility with this crate. The directory holds an
+//! `index.json` listing the case files, and one `<name>.json` file per case.
+//!
+//! # Schema
+//! Every case file is an object with the fields:
+//! - `schema`: always `SCHEMA_VERSION`.
+//! - `name`: the unique name of the case, also its file name without extension.
+//! - `kind`: one of `commit`, `verify`, `encoding`, `linking`, `misbehavior` or `text`.
+//! - `description`: what the case exercises.
+//! - `param_seed`: hex seed the `(A, B)` matrices are expanded from, see below.
+//! - `params_digest`: hex `transport::params_digest`, i.e.
+//!   `hash_to_commitment("reveler/transport/params" ‖ enc(A) ‖ enc(B))`.
+//! - `inputs`: the kind-specific inputs (messages, randomness, commitments, proofs, bytes).
+//! - `expected`: the kind-specific expected outputs, including the verification outcome.
+//!
+//! A `text` case lists `texts` of one grammar of `grammar` in `inputs.grammar` and, in
+//! `expected.values`, the value of every text or `null` if the grammar rejects it: the decimal
+//! text of a `decimal`, the lowercase hex of the bytes of a `hex` or `base64url` string, the
+//! canonical identifier of a `scheme-id`, and the hex `RevelerResult::to_bytes` of a `receipt`.
+//!
+//! Vectors are JSON arrays of integers below `q`, byte strings are lowercase hex, and
+//! `enc(·)` is the length-prefixed big-endian encoding of `utils::encode_matrix`. Every expected
+//! byte string starts with the scheme header of `scheme::write_header`.
+//!
+//! # Parameter expansion
+//! The matrices are the keystream `utils::xor_keystream(k, 0^(16·N²))` under the key
+//! `k = hash_to_commitment("reveler/conformance/params" ‖ seed)`, read as big-endian `u64`
+//! words reduced modulo `q`: the first `N²` words fill `A` row by row, the next `N²` fill `B`.
+//!
+//! The module is compiled with the `conformance` feature.
+
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::{json, Value};
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_N, LOCAL_Q, MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::grammar::{self, encode_hex};
+use crate::linking;
+use crate::protocol::evidence::{detect_inconsistent_reveal, MisbehaviorProof};
+use crate::protocol::messages::{CommitMessage, ProtocolMessage, RevealMessage};
+use crate::protocol::round::RoundDescriptor;
+use crate::protocol::transport::params_digest;
+use crate::scheme::SchemeId;
+use crate::{utils, RevelerCommit, RevelerResult};
+
+const PARAMS_DOMAIN: &[u8] = b"reveler/conformance/params";
+const VECTOR_DOMAIN: &[u8] = b"reveler/conformance/vector";
+
+/// Identifier of the case file schema.
+pub const SCHEMA_VERSION: &str = "reveler-conformance/1";
+
+/// One test case of the suite.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConformanceCase {
+    pub schema: String,
+    pub name: String,
+    pub kind: String,
+    pub description: String,
+    pub param_seed: String,
+    pub params_digest: String,
+    pub inputs: Value,
+    pub expected: Value,
+}
+
+/// Expands the `(A, B)` matrices from a seed as documented in the module header.
+pub fn params_from_seed(seed: &[u8]) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    let mut key_input = PARAMS_DOMAIN.to_vec();
+    key_input.extend_from_slice(seed);
+    let key = utils::hash_to_commitment(&key_input);
+    let words = keystream_words(&key, 2 * LOCAL_N * LOCAL_N);
+
+    let mut rows = words.chunks(LOCAL_N).map(|row| row.iter().map(|&x| x % LOCAL_Q).collect::<Vec<u64>>());
+    let local_a = rows.by_ref().take(LOCAL_N).collect();
+    let local_b = rows.collect();
+    (local_a, local_b)
+}
+
+/// Generates the cases of the suite for the given seed.
+///
+/// # Returns
+/// The cases in a fixed order, or the `CommitError` of a failed commitment or proof.
+pub fn generate_cases(seed: &[u8]) -> Result<Vec<ConformanceCase>, CommitError> {
+    let (local_a, local_b) = params_from_seed(seed);
+    let params = (local_a.as_slice(), local_b.as_slice());
+    let digest = encode_hex(&params_digest(params));
+    let case = |name: &str, kind: &str, description: &str, inputs: Value, expected: Value| ConformanceCase {
+        schema: SCHEMA_VERSION.to_string(),
+        name: name.to_string(),
+        kind: kind.to_string(),
+        description: description.to_string(),
+        param_seed: encode_hex(seed),
+        params_digest: digest.clone(),
+        inputs,
+        expected,
+    };
+    let mut cases = Vec::new();
+
+    let inputs = [
+        ("commit-zero", "all-zero message and randomness", vec![0; MESSAGE_LEN], vec![0; RANDOMNESS_LEN]),
+        ("commit-short", "short vectors touching the first columns only", sample_vector(seed, b"short-m", 4), sample_randomness(seed, b"short-r", 4)),
+        ("commit-full", "full-length message and ternary randomness", sample_vector(seed, b"full-m", MESSAGE_LEN), sample_randomness(seed, b"full-r", RANDOMNESS_LEN)),
+    ];
+    let mut openings = Vec::new();
+    for (name, description, local_m, local_r) in inputs {
+        let opening = RevelerCommit::new(local_a.clone(), local_b.clone(), local_m, local_r);
+        let commitment = opening.commit()?;
+        cases.push(case(
+            name,
+            "commit",
+            description,
+            json!({ "message": opening.local_m, "randomness": opening.local_r }),
+            json!({ "commitment_point": commitment.commitment_point, "commitment_hash": encode_hex(&commitment.commitment_hash) }),
+        ));
+        openings.push((opening, commitment));
+    }
+
+    let (opening, commitment) = &openings[2];
+    cases.push(case(
+        "verify-valid",
+        "verify",
+        "hash check of an honest commitment",
+        result_json(commitment),
+        json!({ "valid": true }),
+    ));
+    let mut tampered = commitment.clone();
+    tampered.commitment_hash[0] ^= 1;
+    cases.push(case(
+        "verify-tampered-hash",
+        "verify",
+        "hash check of a commitment whose hash was altered",
+        result_json(&tampered),
+        json!({ "valid": false }),
+    ));
+
+    cases.push(case(
+        "encoding-result",
+        "encoding",
+        "RevelerResult::to_bytes",
+        result_json(commitment),
+        json!({ "bytes": encode_hex(&commitment.to_bytes()) }),
+    ));
+    let descriptor = RoundDescriptor::new(1, 1_700_000_000, 60, 60).with_grace(5, 5);
+    let commit_message = CommitMessage {
+        round: descriptor.round,
+        participant: b"alice".to_vec(),
+        rules_digest: descriptor.digest(),
+        commitment: commitment.clone(),
+        timestamp: descriptor.opens_at + 10,
+        signature: Vec::new(),
+    };
+    let reveal_message = RevealMessage {
+        round: descriptor.round,
+        participant: b"alice".to_vec(),
+        local_m: opening.local_m.clone(),
+        local_r: opening.local_r.clone(),
+        timestamp: descriptor.commit_cutoff() + 10,
+        signature: Vec::new(),
+    };
+    cases.push(case(
+        "encoding-commit-message",
+        "encoding",
+        "ProtocolMessage::Commit::to_bytes, with the descriptor its rules digest is computed from",
+        json!({ "descriptor_bytes": encode_hex(&descriptor.to_bytes()), "message": commit_json(&commit_message) }),
+        json!({ "rules_digest": encode_hex(&descriptor.digest()), "bytes": encode_hex(&ProtocolMessage::Commit(commit_message.clone()).to_bytes()) }),
+    ));
+    cases.push(case(
+        "encoding-reveal-message",
+        "encoding",
+        "ProtocolMessage::Reveal::to_bytes",
+        json!({ "message": reveal_json(&reveal_message) }),
+        json!({ "bytes": encode_hex(&ProtocolMessage::Reveal(reveal_message.clone()).to_bytes()) }),
+    ));
+
+    let (second_opening, second_commitment) = &openings[1];
+    let relinked = RevelerCommit::new(local_a.clone(), local_b.clone(), opening.local_m.clone(), second_opening.local_r.clone());
+    let relinked_commitment = relinked.commit()?;
+    let proof = linking::prove_same_message_across(opening, commitment, &relinked, &relinked_commitment)?;
+    cases.push(case(
+        "linking-valid",
+        "linking",
+        "linking proof between two commitments to the same message under the same parameters",
+        json!({ "c1": result_json(commitment), "c2": result_json(&relinked_commitment), "proof": proof }),
+        json!({ "valid": true }),
+    ));
+    cases.push(case(
+        "linking-wrong-commitment",
+        "linking",
+        "the same proof checked against a commitment to another message",
+        json!({ "c1": result_json(commitment), "c2": result_json(second_commitment), "proof": proof }),
+        json!({ "valid": false }),
+    ));
+
+    let mut forged = reveal_message.clone();
+    forged.local_m[0] = (forged.local_m[0] + 1) % LOCAL_Q;
+    if let Some(MisbehaviorProof::InconsistentReveal { commit, reveal }) = detect_inconsistent_reveal(&commit_message, &forged, params) {
+        cases.push(case(
+            "misbehavior-inconsistent-reveal",
+            "misbehavior",
+            "InconsistentReveal evidence of a reveal that does not open the commitment, not counting the signatures of the unsigned messages",
+            json!({ "descriptor_bytes": encode_hex(&descriptor.to_bytes()), "commit": commit_json(&commit), "reveal": reveal_json(&reveal) }),
+            json!({ "valid": true }),
+        ));
+    }
+
+    let scheme = SchemeId::current().to_string();
+    let receipt = commitment.to_uri()?;
+    let texts = [
+        ("decimal", "ASCII decimal integers: no sign, leading zero, separator or non-ASCII digit", vec![
+            "0", "7", "18446744073709551615", "18446744073709551616", "", "007", "+7", "-0", " 7", "7 ", "1_000",
+            "1,5", "1.5", "1e3", "0x10", "\u{0661}\u{0662}",
+        ].into_iter().map(str::to_string).collect()),
+        ("hex", "hex byte strings of either case", vec!["", "00ff", "00FF", "0", "+f", "0x00", "g0", "00 ff"]
+            .into_iter().map(str::to_string).collect()),
+        ("base64url", "unpadded base64url with zero unused bits", vec![
+            "", "AA", "AB", "AAA", "AAB", "A", "AA==", "-_8", "+/8", "AA AA", "AAAAAA",
+        ].into_iter().map(str::to_string).collect()),
+        ("scheme-id", "scheme identifiers with canonical decimals", vec![
+            scheme.clone(),
+            scheme.replacen("/v", "/v0", 1),
+            scheme.replacen("-q", "-q+", 1),
+            scheme.replacen("/v1/", "/v4294967297/", 1),
+            scheme.to_uppercase(),
+        ]),
+        ("receipt", "receipt URIs of the encoding-result commitment", vec![
+            receipt.clone(),
+            receipt.replacen(":v", ":v01", 1),
+            receipt.replacen("-n", "-n0", 1),
+            format!("{receipt}="),
+            receipt.replacen(";", "; ", 1),
+        ]),
+    ];
+    for (syntax, description, texts) in texts {
+        let values: Vec<Option<String>> = texts.iter().map(|text| text_value(syntax, text)).collect();
+        cases.push(case(
+            &format!("text-{syntax}"),
+            "text",
+            description,
+            json!({ "grammar": syntax, "texts": texts }),
+            json!({ "values": values }),
+        ));
+    }
+
+    Ok(cases)
+}
+
+/// Writes the suite for the given seed into `dir`, creating it if needed.
+///
+/// # Returns
+/// The number of cases written, or `CommitError::StorageError` if the directory or a file cannot
+/// be written.
+pub fn write_suite(dir: &Path, seed: &[u8]) -> Result<usize, CommitError> {
+    let cases = generate_cases(seed)?;
+    fs::create_dir_all(dir).map_err(|_| CommitError::StorageError)?;
+
+    for case in &cases {
+        let json = serde_json::to_vec_pretty(case).map_err(|_| CommitError::MalformedEncoding)?;
+        fs::write(dir.join(format!("{}.json", case.name)), json).map_err(|_| CommitError::StorageError)?;
+    }
+    let index = json!({
+        "schema": SCHEMA_VERSION,
+        "param_seed": encode_hex(seed),
+        "cases": cases.iter().map(|case| format!("{}.json", case.name)).collect::<Vec<_>>(),
+    });
+    let index = serde_json::to_vec_pretty(&index).map_err(|_| CommitError::MalformedEncoding)?;
+    fs::write(dir.join("index.json"), index).map_err(|_| CommitError::StorageError)?;
+    Ok(cases.len())
+}
+
+// Value of `text` under the grammar `syntax`, as listed in a `text` case.
+fn text_value(syntax: &str, text: &str) -> Option<String> {
+    match syntax {
+        "decimal" => grammar::parse_decimal(text).ok().map(grammar::format_decimal),
+        "hex" => grammar::decode_hex(text).ok().map(|bytes| encode_hex(&bytes)),
+        "base64url" => grammar::decode_base64url(text).ok().map(|bytes| encode_hex(&bytes)),
+        "scheme-id" => text.parse::<SchemeId>().ok().map(|scheme| scheme.to_string()),
+        "receipt" => RevelerResult::from_uri(text).ok().map(|result| encode_hex(&result.to_bytes())),
+        _ => None,
+    }
+}
+
+// Derive a reproducible vector of `len` field elements labelled `label` from the seed.
+fn sample_vector(seed: &[u8], label: &[u8], len: usize) -> Vec<u64> {
+    let mut key_input = VECTOR_DOMAIN.to_vec();
+    key_input.extend(utils::encode_bytes(seed));
+    key_input.extend(utils::encode_bytes(label));
+    keystream_words(&utils::hash_to_commitment(&key_input), len).into_iter().map(|x| x % LOCAL_Q).collect()
+}
+
+// Derive a reproducible vector of `len` coordinates in `{q - 1, 0, 1}` labelled `label` from the
+// seed, as the randomness of a fresh commitment.
+fn sample_randomness(seed: &[u8], label: &[u8], len: usize) -> Vec<u64> {
+    sample_vector(seed, label, len).into_iter().map(|x| (x % 3 + LOCAL_Q - 1) % LOCAL_Q).collect()
+}
+
+fn keystream_words(key: &[u8], count: usize) -> Vec<u64> {
+    utils::xor_keystream(key, &vec![0u8; 8 * count])
+        .chunks_exact(8)
+        .map(|word| {
+            let mut buffer = [0u8; 8];
+            buffer.copy_from_slice(word);
+            u64::from_be_bytes(buffer)
+        })
+        .collect()
+}
+
+fn result_json(result: &RevelerResult) -> Value {
+    json!({ "commitment_point": result.commitment_point, "commitment_hash": encode_hex(&result.commitment_hash) })
+}
+
+fn commit_json(message: &CommitMessage) -> Value {
+    json!({
+        "round": message.round,
+        "participant": encode_hex(&message.participant),
+        "rules_digest": encode_hex(&message.rules_digest),
+        "commitment": result_json(&message.commitment),
+        "timestamp": message.timestamp,
+        "signature": encode_hex(&message.signature),
+    })
+}
+
+fn reveal_json(message: &RevealMessage) -> Value {
+    json!({
+        "round": message.round,
+        "participant": encode_hex(&message.participant),
+        "message": message.local_m,
+        "randomness": message.local_r,
+        "timestamp": message.timestamp,
+        "signature": encode_hex(&message.signature),
+    })
+}
@@ -0,0 +1,218 @@
+//! This module provides committed counters, for rate-limit and quota systems that audit counters
+//! without exposing them.
+//!
+//! A `Counter` commits to its value at coordinate 0 of the message, `c = A·(v) + B·r`. `increment`
+//! commits to the increment `δ` with fresh randomness, `c_δ = A·(δ) + B·s`, and returns an
+//! `Increment` holding `c_δ`, a range proof that `δ` lies in `[0, 2^k)`, and the new commitment
+//! `c' = c + c_δ`, which by linearity commits to `v + δ` under the randomness `r + s`. The verifier
+//! recomputes `c + c_δ` and checks the range proof, so it learns that the counter grew by a
+//! non-negative amount below `2^k` and neither the value nor the increment. After `k` increments
+//! the opening of the counter is the opening of a sum of `k + 1` commitments and verifies under
+//! `policy::VerifyPolicy::for_sum(k + 1)`.
+//!
+//! Values are taken modulo `q`: the prover refuses an increment that would reach `q`, but the
+//! verifier only sees the increment, so an auditor that needs a ceiling on the value itself checks
+//! it with a range proof of the counter, see `range::prove_range`.
+
+#[cfg(feature = "prover")]
+use std::fmt;
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+#[cfg(feature = "prover")]
+use crate::config::CommitConfig;
+use crate::config::ProofConfig;
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+use crate::range::{self, RangeProof, MAX_RANGE_BITS};
+#[cfg(feature = "prover")]
+use crate::utils;
+use crate::{RevelerCommit, RevelerResult};
+
+/// Coordinate of the message holding the value of a counter.
+pub const COUNTER_INDEX: usize = 0;
+
+/// Width of the range of an increment under `Counter::increment`.
+pub const DEFAULT_INCREMENT_BITS: usize = MAX_RANGE_BITS;
+
+/// Step of a counter: the new commitment and the proof that it grew by a non-negative amount.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Increment {
+    pub commitment: RevelerResult,       // c' = c + c_δ, the commitment to the new value
+    pub delta_commitment: RevelerResult, // c_δ, the commitment to the increment
+    pub range: RangeProof,               // The proof that the increment lies in [0, 2^k)
+}
+
+/// Counter held by its owner: the value, its commitment and the opening.
+#[cfg(feature = "prover")]
+pub struct Counter {
+    value: u64,                 // The current value
+    commitment: RevelerResult,  // The commitment to the current value
+    opening: Opening,           // The opening of the commitment
+}
+
+#[cfg(feature = "prover")]
+impl Counter {
+    /// Commits to a counter starting at `value`.
+    ///
+    /// # Returns
+    /// The `Counter`, `CommitError::ComputationError` if `value` is not below `q`, or the
+    /// `CommitError` of the commitment.
+    pub fn new(key: &CommitmentKey, value: u64) -> Result<Self, CommitError> {
+        let q = CommitConfig::default().modulus;
+        if value >= q {
+            return Err(CommitError::ComputationError);
+        }
+        let opening = fresh_opening(value);
+        let commitment = key.commit_opening(&opening)?;
+        Ok(Counter { value, commitment, opening })
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns the commitment to the current value, which the owner publishes.
+    pub fn commitment(&self) -> &RevelerResult {
+        &self.commitment
+    }
+
+    /// Returns the opening of the current commitment.
+    pub fn opening(&self) -> &Opening {
+        &self.opening
+    }
+
+    /// Increments the counter by `delta`, below `2^DEFAULT_INCREMENT_BITS`.
+    ///
+    /// # Returns
+    /// The `Increment` to publish, or the errors of `increment_with_config`.
+    pub fn increment(&mut self, key: &CommitmentKey, delta: u64) -> Result<Increment, CommitError> {
+        self.increment_with_config(key, delta, DEFAULT_INCREMENT_BITS, &ProofConfig::default())
+    }
+
+    /// Increments the counter by `delta`, proven below `2^bits` with the soundness of
+    /// `proof_config`. The counter is unchanged if the increment fails.
+    ///
+    /// # Returns
+    /// The `Increment` to publish, `CommitError::ComputationError` if the new value would reach
+    /// `q` or `bits` is out of the bounds of range proofs, `CommitError::InvalidOpening` if
+    /// `delta` is not below `2^bits`, or the errors of `range::prove_range_with_config`.
+    pub fn increment_with_config(
+        &mut self,
+        key: &CommitmentKey,
+        delta: u64,
+        bits: usize,
+        proof_config: &ProofConfig,
+    ) -> Result<Increment, CommitError> {
+        let q = CommitConfig::default().modulus;
+        let value = self.value.checked_add(delta).filter(|&value| value < q).ok_or(CommitError::ComputationError)?;
+        let delta_opening = fresh_opening(delta);
+        let delta_commitment = key.commit_opening(&delta_opening)?;
+        let range = range::prove_range_with_config(key, &delta_commitment, &delta_opening, COUNTER_INDEX, bits, proof_config)?;
+
+        self.value = value;
+        self.commitment = self.commitment.add(&delta_commitment);
+        self.opening = self.opening.add(&delta_opening);
+        Ok(Increment { commitment: self.commitment.clone(), delta_commitment, range })
+    }
+}
+
+// 不在调试输出中泄露计数值与打开值
+#[cfg(feature = "prover")]
+impl fmt::Debug for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Counter").field("commitment", &self.commitment).finish_non_exhaustive()
+    }
+}
+
+/// Verifies that `increment` moves the counter committed in `old` forward by a non-negative amount
+/// below `2^DEFAULT_INCREMENT_BITS`.
+///
+/// # Returns
+/// `true` if the new commitment is `old` plus the increment commitment and the range proof checks
+/// out.
+pub fn verify_increment(key: &CommitmentKey, old: &RevelerResult, increment: &Increment) -> bool {
+    verify_increment_with_config(key, old, increment, DEFAULT_INCREMENT_BITS, &ProofConfig::default())
+}
+
+/// Verifies an increment proven below `2^bits` with the soundness of `proof_config`.
+///
+/// # Returns
+/// The outcome of `verify_increment` for the range `[0, 2^bits)`.
+pub fn verify_increment_with_config(
+    key: &CommitmentKey,
+    old: &RevelerResult,
+    increment: &Increment,
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> bool {
+    RevelerCommit::verify(old)
+        && increment.commitment == old.add(&increment.delta_commitment)
+        && range::verify_range_with_config(key, &increment.delta_commitment, COUNTER_INDEX, bits, &increment.range, proof_config)
+}
+
+// Opening of `value` at the counter coordinate with fresh randomness.
+#[cfg(feature = "prover")]
+fn fresh_opening(value: u64) -> Opening {
+    Opening::new(vec![value], utils::generate_randomness(&mut rand::thread_rng()))
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::binary;
+    use crate::fft::{LOCAL_N, RANDOMNESS_LEN};
+    use crate::policy::VerifyPolicy;
+    use super::*;
+
+    #[test]
+    fn increments_verify() {
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(276));
+        let mut counter = Counter::new(&key, 10).unwrap();
+        let mut old = counter.commitment().clone();
+        for delta in [5, 0, 200] {
+            let increment = counter.increment_with_config(&key, delta, 8, &ProofConfig::default()).unwrap();
+            assert!(verify_increment_with_config(&key, &old, &increment, 8, &ProofConfig::default()));
+            assert!(!verify_increment_with_config(&key, &increment.commitment, &increment, 8, &ProofConfig::default()));
+            old = increment.commitment;
+        }
+        assert_eq!(counter.value(), 215);
+        let opening = counter.opening();
+        assert!(VerifyPolicy::for_sum(4).verify_opening(key.as_pair(), &old, opening.local_m(), opening.local_r()).unwrap());
+
+        assert_eq!(counter.increment_with_config(&key, 256, 8, &ProofConfig::default()), Err(CommitError::InvalidOpening));
+        assert_eq!(counter.value(), 215);
+    }
+
+    #[test]
+    fn decrements_are_rejected() {
+        // 增量实为 -5，伪造者把差值塞进 [B | I] 的单位块，声称增量为 3
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(276));
+        let q = CommitConfig::default().modulus;
+        let counter = Counter::new(&key, 10).unwrap();
+        let delta_opening = fresh_opening(q - 5);
+        let delta_commitment = key.commit_opening(&delta_opening).unwrap();
+        let shift = key.commit(&[q - 8], &[]).unwrap();
+        let mut forged_r = delta_opening.local_r().to_vec();
+        for (slot, &x) in forged_r.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(shift.commitment_point()) {
+            *slot = (*slot + x) % q;
+        }
+        let forged = Opening::new(vec![3], forged_r);
+        assert!(key.reproduces(&delta_commitment, &forged).unwrap());
+        assert!(range::prove_range(&key, &delta_commitment, &forged, COUNTER_INDEX, 8).is_err());
+
+        let relation = binary::coordinate_relation(key.as_pair(), range::RANGE_DOMAIN, COUNTER_INDEX, 8, 1, &delta_commitment);
+        let witness = binary::coordinate_witness(forged.local_m(), forged.local_r(), COUNTER_INDEX, 8, 1);
+        let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap().rounds;
+        let increment = Increment {
+            commitment: counter.commitment().add(&delta_commitment),
+            delta_commitment,
+            range: RangeProof { rounds },
+        };
+        assert!(!verify_increment_with_config(&key, counter.commitment(), &increment, 8, &ProofConfig::default()));
+    }
+}
@@ -0,0 +1,207 @@
+//! This module provides snapshot and delta commitments for state synchronisation of large,
+//! slowly-changing vectors.
+//!
+//! A publisher commits to the full state at a snapshot epoch and, at every later epoch, only to
+//! the difference `d` between the new and the previous state. Because the commitment is linear,
+//! the snapshot commitment plus the delta commitments is a commitment to the current state, and
+//! every delta carries the hash of that sum, so a follower checks with `current_commitment` that
+//! the snapshot and the deltas add up to the commitment the publisher claims.
+//!
+//! A `DeltaOpening` lists only the changed coordinates and a 32-byte seed the delta randomness is
+//! expanded from, so a follower holding the opening of the previous state applies it with
+//! `apply_opening` instead of downloading the full state again. The randomness of a delta is
+//! ternary, as that of a fresh commitment, so the opening of the state after `k` deltas is the
+//! opening of a sum of `k + 1` commitments and verifies under `VerifyPolicy::for_sum(k + 1)`.
+
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::amounts;
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::fft::{MESSAGE_LEN, RANDOMNESS_LEN};
+#[cfg(feature = "prover")]
+use crate::fft::LOCAL_Q;
+use crate::{compute_point, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const SEED_DOMAIN: &[u8] = b"reveler/delta/randomness";
+
+/// Length of the seed the randomness of a delta is expanded from.
+pub const SEED_LEN: usize = 32;
+
+/// Full commitment to the state at a snapshot epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub epoch: u64,
+    pub commitment: RevelerResult,
+}
+
+/// Secret opening of the state at some epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateOpening {
+    pub state: Vec<u64>,   // The committed state, MESSAGE_LEN elements modulo q
+    pub local_r: Vec<u64>, // The randomness of the commitment
+}
+
+/// Commitment to the change of the state from the previous epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDelta {
+    pub epoch: u64,
+    pub commitment: RevelerResult, // The commitment to the difference of the states
+    pub current_hash: Vec<u8>,     // The commitment hash of the state after the delta
+}
+
+/// Secret opening of a delta.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaOpening {
+    pub changes: Vec<(usize, u64)>, // The (index, difference modulo q) of every changed coordinate
+    pub seed: Vec<u8>,              // The seed of the delta randomness
+}
+
+/// Commits to the full state.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the state.
+/// - `epoch`: The epoch of the snapshot.
+/// - `state`: The state, of at most `MESSAGE_LEN` elements below `q`.
+///
+/// # Returns
+/// The `StateSnapshot` and its `StateOpening`, or `CommitError::ComputationError` if the state is
+/// too long or holds an element outside `Z_q`.
+#[cfg(feature = "prover")]
+pub fn snapshot(params: (&[Vec<u64>], &[Vec<u64>]), epoch: u64, state: &[u64]) -> Result<(StateSnapshot, StateOpening), CommitError> {
+    if state.len() > MESSAGE_LEN || state.iter().any(|&x| x >= LOCAL_Q) {
+        return Err(CommitError::ComputationError);
+    }
+    let mut rng = rand::thread_rng();
+    let mut opening = StateOpening { state: state.to_vec(), local_r: utils::generate_randomness(&mut rng) };
+    opening.state.resize(MESSAGE_LEN, 0);
+
+    let point = compute_point(params.0, params.1, &opening.state, &opening.local_r, &CommitConfig::default())?;
+    Ok((StateSnapshot { epoch, commitment: result_of(point) }, opening))
+}
+
+/// Commits to a change of the state.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the state.
+/// - `current`: The commitment to the state before the change.
+/// - `epoch`: The epoch of the delta.
+/// - `changes`: The `(index, difference modulo q)` of every changed coordinate.
+///
+/// # Returns
+/// The `StateDelta` and its `DeltaOpening`, or `CommitError::ComputationError` if an index is not
+/// below `MESSAGE_LEN` or a difference is not below `q`.
+#[cfg(feature = "prover")]
+pub fn commit_delta(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    current: &RevelerResult,
+    epoch: u64,
+    changes: &[(usize, u64)],
+) -> Result<(StateDelta, DeltaOpening), CommitError> {
+    let mut rng = rand::thread_rng();
+    let opening = DeltaOpening { changes: changes.to_vec(), seed: (0..SEED_LEN).map(|_| rng.gen()).collect() };
+    let commitment = delta_commitment(params, &opening)?;
+    let current_hash = amounts::sum(&[current.clone(), commitment.clone()]).commitment_hash;
+    Ok((StateDelta { epoch, commitment, current_hash }, opening))
+}
+
+/// Folds a delta into the commitment to the previous state.
+///
+/// # Returns
+/// The commitment to the state after the delta, or `CommitError::InvalidOpening` if the delta
+/// commitment is malformed or the sum does not hash to `delta.current_hash`.
+pub fn apply(current: &RevelerResult, delta: &StateDelta) -> Result<RevelerResult, CommitError> {
+    if !RevelerCommit::verify(&delta.commitment) {
+        return Err(CommitError::InvalidOpening);
+    }
+    let next = amounts::sum(&[current.clone(), delta.commitment.clone()]);
+    if next.commitment_hash != delta.current_hash {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(next)
+}
+
+/// Checks that a snapshot and the following deltas add up to the current commitment.
+///
+/// # Parameters
+/// - `snapshot`: A reference to the `StateSnapshot`.
+/// - `deltas`: The deltas of the epochs following the snapshot, in order.
+///
+/// # Returns
+/// The commitment to the current state, `CommitError::InvalidTransition` if the epochs of the
+/// deltas do not follow the snapshot one by one, or `CommitError::InvalidOpening` if a commitment
+/// is malformed or a delta does not lead to its `current_hash`.
+pub fn current_commitment(snapshot: &StateSnapshot, deltas: &[StateDelta]) -> Result<RevelerResult, CommitError> {
+    if !RevelerCommit::verify(&snapshot.commitment) {
+        return Err(CommitError::InvalidOpening);
+    }
+    let mut current = snapshot.commitment.clone();
+    for (offset, delta) in deltas.iter().enumerate() {
+        if snapshot.epoch.checked_add(offset as u64 + 1) != Some(delta.epoch) {
+            return Err(CommitError::InvalidTransition);
+        }
+        current = apply(&current, delta)?;
+    }
+    Ok(current)
+}
+
+/// Checks a delta opening against its delta.
+pub fn verify_delta_opening(params: (&[Vec<u64>], &[Vec<u64>]), delta: &StateDelta, opening: &DeltaOpening) -> bool {
+    delta_commitment(params, opening)
+        .map(|commitment| commitment.commitment_point == delta.commitment.commitment_point)
+        .unwrap_or(false)
+}
+
+/// Applies a delta opening to the opening of the previous state.
+///
+/// # Returns
+/// `Ok(())` once `opening` opens the state after the delta, or `CommitError::ComputationError` if
+/// the delta opening is malformed, in which case `opening` is left unchanged.
+pub fn apply_opening(opening: &mut StateOpening, delta: &DeltaOpening) -> Result<(), CommitError> {
+    let q = CommitConfig::default().modulus;
+    let difference = difference_vector(delta)?;
+    let randomness = expand_seed(&delta.seed)?;
+    opening.state.resize(MESSAGE_LEN, 0);
+    for (x, d) in opening.state.iter_mut().zip(difference) {
+        *x = (*x % q + d) % q;
+    }
+    opening.local_r.resize(RANDOMNESS_LEN, 0);
+    for (x, s) in opening.local_r.iter_mut().zip(randomness) {
+        *x = (*x % q + s) % q;
+    }
+    Ok(())
+}
+
+fn delta_commitment(params: (&[Vec<u64>], &[Vec<u64>]), opening: &DeltaOpening) -> Result<RevelerResult, CommitError> {
+    let difference = difference_vector(opening)?;
+    let randomness = expand_seed(&opening.seed)?;
+    compute_point(params.0, params.1, &difference, &randomness, &CommitConfig::default()).map(result_of)
+}
+
+// Scatter the sparse changes into a vector of MESSAGE_LEN coordinates.
+fn difference_vector(opening: &DeltaOpening) -> Result<Vec<u64>, CommitError> {
+    let q = CommitConfig::default().modulus;
+    let mut difference = vec![0u64; MESSAGE_LEN];
+    for &(index, value) in &opening.changes {
+        let coordinate = difference.get_mut(index).ok_or(CommitError::ComputationError)?;
+        if value >= q {
+            return Err(CommitError::ComputationError);
+        }
+        *coordinate = (*coordinate + value) % q;
+    }
+    Ok(difference)
+}
+
+// Expand the seed into RANDOMNESS_LEN ternary randomness coordinates.
+fn expand_seed(seed: &[u8]) -> Result<Vec<u64>, CommitError> {
+    if seed.len() != SEED_LEN {
+        return Err(CommitError::ComputationError);
+    }
+    utils::hash_to_ternary(SEED_DOMAIN, seed, RANDOMNESS_LEN)
+}
+
+fn result_of(point: Vec<u64>) -> RevelerResult {
+    let hash = utils::hash_to_commitment(&point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+    RevelerResult::new(point, hash)
+}
@@ -0,0 +1,439 @@
+//! This module provides partial openings: a commitment is opened at chosen coordinates of the
+//! message, and a proof convinces the verifier that the commitment holds these values without
+//! revealing the other coordinates or the randomness.
+//!
+//! For revealed positions `P` with values `v`, let `m_P` be the message that holds `v` at `P` and
+//! zero elsewhere, and `m_H` the hidden rest, so `c = A·m_P + A·m_H + [B | I]·r`. The verifier
+//! computes the target `c' = c - A·m_P` itself, and the prover shows knowledge of `(m_H, r)` with
+//! `A·m_H + [B | I]·r = c'`, `m_H` zero at `P` and `r` ternary. Bounding `r` is what makes the
+//! proof sound: the identity block of `[B | I]` solves the equation for any claimed values with
+//! some `r`, but a prover that convinces the verifier of other values with ternary `r` knows a
+//! second opening of `c` with short randomness, which the binding of the commitment rules out.
+//!
+//! The proof is Stern's protocol as in `binary`, with `r` as the permuted witness and `m_H` masked
+//! uniformly: `r` is extended to `r'` of `3·RANDOMNESS_LEN` coordinates holding exactly
+//! `RANDOMNESS_LEN` of each of `-1`, `0` and `1`, a property every permutation preserves. In each
+//! round the prover samples a permutation `π` and masks `(y_H, y_r')`, and publishes the salted
+//! hashes
+//! - `c1 = H(π, A·y_H + [B | I]·y_r)`, where `y_r` is the head of `y_r'`,
+//! - `c2 = H(π(y_r'))`,
+//! - `c3 = H(π(y_r' + r'))`.
+//!
+//! Depending on the challenge it then reveals `(π(r'), π(y_r'))`, `(π, y_r' + r', y_H + m_H)` or
+//! `(π, y_r', y_H)`, none of which tells anything about `m_H` or `r`. A round has soundness error
+//! 2/3 and the number of rounds is that of binary proofs, see `ProofConfig::binary_rounds`.
+//!
+//! This turns the commitment into a selective-disclosure commitment, e.g. for credentials whose
+//! holder shows some attributes and keeps the others. The challenges are bound to the key, the
+//! commitment, the positions and the values, so a proof cannot be replayed for other values. Only
+//! fresh commitments, whose randomness is ternary, are opened partially: sums of commitments are
+//! not.
+
+#[cfg(feature = "prover")]
+use rand::{seq::SliceRandom, Rng};
+use serde::{Serialize, Deserialize};
+use crate::binary::{encode_permutation, is_permutation, permute, salted_hash};
+#[cfg(feature = "prover")]
+use crate::binary::SALT_LEN;
+use crate::commit_error::CommitError;
+use crate::config::{CommitConfig, ProofConfig};
+use crate::fft::{MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+#[cfg(feature = "prover")]
+use crate::linking::reduce;
+use crate::{add_mod, compute_point, revocation, scale_mod, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const DISCLOSURE_DOMAIN: &[u8] = b"reveler/disclosure";
+
+/// One round of a disclosure proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisclosureRound {
+    pub commitments: Vec<Vec<u8>>, // The salted hashes c1, c2 and c3
+    pub response: DisclosureResponse,
+}
+
+/// Response of one round, depending on its challenge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisclosureResponse {
+    /// Challenge 0: opens `c2` and `c3`.
+    Permuted {
+        permuted_witness: Vec<u64>, // π(r')
+        permuted_mask: Vec<u64>,    // π(y_r')
+        mask_salt: Vec<u8>,
+        shifted_salt: Vec<u8>,
+    },
+    /// Challenge 1: opens `c1` and `c3`.
+    Shifted {
+        permutation: Vec<u32>,     // π
+        shifted_witness: Vec<u64>, // y_r' + r'
+        shifted_message: Vec<u64>, // y_H + m_H, over the hidden coordinates
+        point_salt: Vec<u8>,
+        shifted_salt: Vec<u8>,
+    },
+    /// Challenge 2: opens `c1` and `c2`.
+    Masked {
+        permutation: Vec<u32>,  // π
+        mask: Vec<u64>,         // y_r'
+        mask_message: Vec<u64>, // y_H, over the hidden coordinates
+        point_salt: Vec<u8>,
+        mask_salt: Vec<u8>,
+    },
+}
+
+/// Commitment opened at some coordinates of the message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialOpening {
+    pub positions: Vec<usize>,        // The revealed coordinates, strictly increasing
+    pub values: Vec<u64>,             // The message at the revealed coordinates
+    pub rounds: Vec<DisclosureRound>, // The proof over the hidden coordinates
+}
+
+impl PartialOpening {
+    /// Returns the revealed value at coordinate `position`, or `None` if it is hidden.
+    pub fn value(&self, position: usize) -> Option<u64> {
+        let index = self.positions.binary_search(&position).ok()?;
+        self.values.get(index).copied()
+    }
+}
+
+impl CommitmentKey {
+    /// Opens `commitment` at the coordinates `positions` of the message only.
+    ///
+    /// # Parameters
+    /// - `commitment`: The commitment to open.
+    /// - `opening`: Its full opening, which stays with the prover.
+    /// - `positions`: The coordinates to reveal, in any order; duplicates are ignored.
+    ///
+    /// # Returns
+    /// The `PartialOpening`, `CommitError::InvalidOpening` if `opening` does not open
+    /// `commitment` with ternary randomness, or `CommitError::ComputationError` if a position is
+    /// not below `MESSAGE_LEN`.
+    #[cfg(feature = "prover")]
+    pub fn open_positions(&self, commitment: &RevelerResult, opening: &Opening, positions: &[usize]) -> Result<PartialOpening, CommitError> {
+        self.open_positions_with_config(commitment, opening, positions, &ProofConfig::default())
+    }
+
+    /// Opens `commitment` at the coordinates `positions` with the soundness of `proof_config`.
+    ///
+    /// # Returns
+    /// The `PartialOpening`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for
+    /// binary proofs, or the errors of `open_positions`.
+    #[cfg(feature = "prover")]
+    pub fn open_positions_with_config(
+        &self,
+        commitment: &RevelerResult,
+        opening: &Opening,
+        positions: &[usize],
+        proof_config: &ProofConfig,
+    ) -> Result<PartialOpening, CommitError> {
+        let q = CommitConfig::default().modulus;
+        let rounds = proof_config.binary_rounds()?;
+
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+        if positions.last().is_some_and(|&position| position >= MESSAGE_LEN) {
+            return Err(CommitError::ComputationError);
+        }
+        // 默认策略要求随机数为三元向量，否则无法构造扩展见证
+        if !self.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        let m = reduce(opening.local_m(), MESSAGE_LEN, q);
+        let r = reduce(opening.local_r(), RANDOMNESS_LEN, q);
+        let (values, hidden) = split(&m, &positions);
+        prove_hidden(self, commitment, positions, values, &hidden, &r, rounds)
+    }
+
+    /// Checks that `commitment` holds the revealed values of `partial` at their positions.
+    ///
+    /// # Returns
+    /// `true` if the positions are strictly increasing and below `MESSAGE_LEN`, the values reduced,
+    /// the commitment hash valid and every round of the proof checks out, `false` otherwise, or
+    /// `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if `revocation::check` refuses
+    /// the key.
+    pub fn verify_partial(&self, commitment: &RevelerResult, partial: &PartialOpening) -> Result<bool, CommitError> {
+        self.verify_partial_with_config(commitment, partial, &ProofConfig::default())
+    }
+
+    /// Checks a partial opening made with the soundness of `proof_config`.
+    ///
+    /// # Returns
+    /// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+    /// `verify_partial`.
+    pub fn verify_partial_with_config(
+        &self,
+        commitment: &RevelerResult,
+        partial: &PartialOpening,
+        proof_config: &ProofConfig,
+    ) -> Result<bool, CommitError> {
+        revocation::check(self.as_pair())?;
+        let config = CommitConfig::default();
+        let q = config.modulus;
+        let Ok(rounds) = proof_config.binary_rounds() else {
+            return Ok(false);
+        };
+
+        let positions = &partial.positions;
+        let well_formed = positions.windows(2).all(|pair| pair.first() < pair.last())
+            && positions.last().is_none_or(|&position| position < MESSAGE_LEN)
+            && partial.values.len() == positions.len()
+            && partial.values.iter().all(|&value| value < q);
+        if !well_formed
+            || partial.rounds.len() != rounds
+            || partial.rounds.iter().any(|round| round.commitments.len() != 3)
+            || !RevelerCommit::verify(commitment)
+        {
+            return Ok(false);
+        }
+
+        // c' = c - A·m_P
+        let (local_a, local_b) = self.as_pair();
+        let revealed = compute_point(local_a, local_b, &merge(positions, &partial.values, &[]), &[], &config)?;
+        let target = add_mod(commitment.commitment_point(), &scale_mod(&revealed, q - 1, q), q);
+
+        let commitments: Vec<Vec<Vec<u8>>> = partial.rounds.iter().map(|round| round.commitments.clone()).collect();
+        let challenges = derive_challenges(self, commitment, positions, &partial.values, &commitments);
+        Ok(partial.rounds.iter().zip(challenges).all(|(round, challenge)| verify_round(self, positions, &target, round, challenge)))
+    }
+}
+
+// Prove knowledge of the hidden coordinates `hidden` and the randomness `r`, both reduced, in
+// `rounds` rounds. The witness is not checked: a wrong witness yields a proof that fails.
+#[cfg(feature = "prover")]
+fn prove_hidden(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    positions: Vec<usize>,
+    values: Vec<u64>,
+    hidden: &[u64],
+    r: &[u64],
+    rounds: usize,
+) -> Result<PartialOpening, CommitError> {
+    let config = CommitConfig::default();
+    let q = config.modulus;
+    let extended = extend(r, q);
+    let len = extended.len();
+
+    // 各轮相互独立，在工作线程间并行生成；每轮的承诺在本线程内计算
+    let (local_a, local_b) = key.as_pair();
+    let round_config = config.with_inline_work_threshold(usize::MAX);
+    let (secrets, commitments): (Vec<_>, Vec<_>) = utils::run_indexed(utils::Workers::Threads, rounds, |_| {
+        let mut rng = rand::thread_rng();
+        let mut permutation: Vec<u32> = (0..len as u32).collect();
+        permutation.shuffle(&mut rng);
+        let mask: Vec<u64> = (0..len).map(|_| rng.gen_range(0..q)).collect();
+        let mask_message: Vec<u64> = (0..hidden.len()).map(|_| rng.gen_range(0..q)).collect();
+        let salts: [Vec<u8>; 3] = std::array::from_fn(|_| (0..SALT_LEN).map(|_| rng.gen()).collect());
+
+        let point = compute_point(local_a, local_b, &merge(&positions, &[], &mask_message), head(&mask), &round_config)?;
+        let shifted = add_mod(&mask, &extended, q);
+        let commitments = vec![
+            salted_hash(&salts[0], &[&encode_permutation(&permutation), &utils::encode_vector(&point)]),
+            salted_hash(&salts[1], &[&utils::encode_vector(&permute(&permutation, &mask))]),
+            salted_hash(&salts[2], &[&utils::encode_vector(&permute(&permutation, &shifted))]),
+        ];
+        Ok(((permutation, mask, mask_message, shifted, salts), commitments))
+    })?.into_iter().unzip();
+
+    let challenges = derive_challenges(key, commitment, &positions, &values, &commitments);
+    let rounds = secrets.into_iter().zip(commitments).zip(challenges)
+        .map(|(((permutation, mask, mask_message, shifted, [point_salt, mask_salt, shifted_salt]), commitments), challenge)| {
+            let response = match challenge {
+                0 => DisclosureResponse::Permuted {
+                    permuted_witness: permute(&permutation, &extended),
+                    permuted_mask: permute(&permutation, &mask),
+                    mask_salt,
+                    shifted_salt,
+                },
+                1 => DisclosureResponse::Shifted {
+                    shifted_message: add_mod(&mask_message, hidden, q),
+                    permutation,
+                    shifted_witness: shifted,
+                    point_salt,
+                    shifted_salt,
+                },
+                _ => DisclosureResponse::Masked { permutation, mask, mask_message, point_salt, mask_salt },
+            };
+            DisclosureRound { commitments, response }
+        })
+        .collect();
+
+    Ok(PartialOpening { positions, values, rounds })
+}
+
+// Verify one round against the target c' = c - A·m_P given its challenge.
+fn verify_round(key: &CommitmentKey, positions: &[usize], target: &[u64], round: &DisclosureRound, challenge: u64) -> bool {
+    let config = CommitConfig::default();
+    let q = config.modulus;
+    let len = 3 * RANDOMNESS_LEN;
+    let hidden_len = MESSAGE_LEN.saturating_sub(positions.len());
+    let in_range = |values: &[u64], len: usize| values.len() == len && values.iter().all(|&x| x < q);
+    let (local_a, local_b) = key.as_pair();
+    let point = |message: &[u64], randomness: &[u64]| compute_point(local_a, local_b, &merge(positions, &[], message), head(randomness), &config);
+
+    let [c1, c2, c3] = round.commitments.as_slice() else {
+        return false;
+    };
+    match (&round.response, challenge) {
+        (DisclosureResponse::Permuted { permuted_witness, permuted_mask, mask_salt, shifted_salt }, 0) => {
+            in_range(permuted_mask, len)
+                && is_extended(permuted_witness, q)
+                && *c2 == salted_hash(mask_salt, &[&utils::encode_vector(permuted_mask)])
+                && *c3 == salted_hash(shifted_salt, &[&utils::encode_vector(&add_mod(permuted_mask, permuted_witness, q))])
+        }
+        (DisclosureResponse::Shifted { permutation, shifted_witness, shifted_message, point_salt, shifted_salt }, 1) => {
+            if !is_permutation(permutation, len) || !in_range(shifted_witness, len) || !in_range(shifted_message, hidden_len) {
+                return false;
+            }
+            let Ok(point) = point(shifted_message, shifted_witness) else {
+                return false;
+            };
+            let point = add_mod(&point, &scale_mod(target, q - 1, q), q);
+            *c1 == salted_hash(point_salt, &[&encode_permutation(permutation), &utils::encode_vector(&point)])
+                && *c3 == salted_hash(shifted_salt, &[&utils::encode_vector(&permute(permutation, shifted_witness))])
+        }
+        (DisclosureResponse::Masked { permutation, mask, mask_message, point_salt, mask_salt }, 2) => {
+            if !is_permutation(permutation, len) || !in_range(mask, len) || !in_range(mask_message, hidden_len) {
+                return false;
+            }
+            let Ok(point) = point(mask_message, mask) else {
+                return false;
+            };
+            *c1 == salted_hash(point_salt, &[&encode_permutation(permutation), &utils::encode_vector(&point)])
+                && *c2 == salted_hash(mask_salt, &[&utils::encode_vector(&permute(permutation, mask))])
+        }
+        _ => false,
+    }
+}
+
+// Extend ternary randomness with padding to r' of 3·RANDOMNESS_LEN coordinates holding exactly
+// RANDOMNESS_LEN of each of q - 1, 0 and 1. Other coordinates are kept and the padding cut to
+// length, so r' then fails `is_extended`.
+#[cfg(feature = "prover")]
+fn extend(r: &[u64], q: u64) -> Vec<u64> {
+    let mut extended = r.to_vec();
+    for value in [q - 1, 0, 1] {
+        let missing = RANDOMNESS_LEN.saturating_sub(r.iter().filter(|&&x| x == value).count());
+        extended.resize(extended.len() + missing, value);
+    }
+    extended.truncate(3 * RANDOMNESS_LEN);
+    extended
+}
+
+// Whether `witness` holds exactly RANDOMNESS_LEN of each of q - 1, 0 and 1, and nothing else.
+fn is_extended(witness: &[u64], q: u64) -> bool {
+    witness.len() == 3 * RANDOMNESS_LEN
+        && [q - 1, 0, 1].iter().all(|&value| witness.iter().filter(|&&x| x == value).count() == RANDOMNESS_LEN)
+}
+
+// The randomness r inside the extended vector r'.
+fn head(extended: &[u64]) -> &[u64] {
+    extended.get(..RANDOMNESS_LEN).unwrap_or(extended)
+}
+
+// Split a message into its values at `positions` and at the other coordinates, in order.
+#[cfg(feature = "prover")]
+fn split(m: &[u64], positions: &[usize]) -> (Vec<u64>, Vec<u64>) {
+    let (mut revealed, mut hidden) = (Vec::new(), Vec::new());
+    for (index, &x) in m.iter().enumerate() {
+        if positions.binary_search(&index).is_ok() {
+            revealed.push(x);
+        } else {
+            hidden.push(x);
+        }
+    }
+    (revealed, hidden)
+}
+
+// Inverse of `split`: a vector of `MESSAGE_LEN` coordinates holding `revealed` at `positions` and
+// `hidden` at the other coordinates, zero where either runs out.
+fn merge(positions: &[usize], revealed: &[u64], hidden: &[u64]) -> Vec<u64> {
+    let (mut revealed, mut hidden) = (revealed.iter(), hidden.iter());
+    (0..MESSAGE_LEN)
+        .map(|index| {
+            let source = if positions.binary_search(&index).is_ok() { &mut revealed } else { &mut hidden };
+            source.next().copied().unwrap_or(0)
+        })
+        .collect()
+}
+
+fn derive_challenges(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    positions: &[usize],
+    values: &[u64],
+    commitments: &[Vec<Vec<u8>>],
+) -> Vec<u64> {
+    let mut transcript = utils::CommitmentHasher::new();
+    transcript.update(DISCLOSURE_DOMAIN);
+    transcript.update(&key.digest());
+    transcript.update(&commitment.to_bytes());
+    let positions: Vec<u64> = positions.iter().map(|&position| position as u64).collect();
+    transcript.update(&utils::encode_vector(&positions));
+    transcript.update(&utils::encode_vector(values));
+    for round in commitments {
+        for commitment in round {
+            transcript.update(&utils::encode_bytes(commitment));
+        }
+    }
+    utils::hash_to_range(DISCLOSURE_DOMAIN, &transcript.finalize(), commitments.len(), 3)
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    fn setup() -> (CommitmentKey, Opening, RevelerResult) {
+        let mut rng = StdRng::seed_from_u64(265);
+        let key = CommitmentKey::setup(&mut rng);
+        let opening = Opening::new(vec![10, 20, 30, 40], utils::generate_randomness(&mut rng));
+        let commitment = key.commit_opening(&opening).unwrap();
+        (key, opening, commitment)
+    }
+
+    #[test]
+    fn honest_partial_opening_verifies() {
+        let (key, opening, commitment) = setup();
+        let partial = key.open_positions(&commitment, &opening, &[2, 0]).unwrap();
+        assert_eq!((partial.value(0), partial.value(2), partial.value(1)), (Some(10), Some(30), None));
+        assert!(key.verify_partial(&commitment, &partial).unwrap());
+    }
+
+    #[test]
+    fn values_shifted_into_hidden_coordinates_are_rejected() {
+        // 把公开坐标 0 的 6 挪到隐藏坐标 1，消息坐标之和不变
+        let (key, opening, commitment) = setup();
+        let q = CommitConfig::default().modulus;
+        let forged = [4, 26, 30, 40];
+        let (values, hidden) = split(&reduce(&forged, MESSAGE_LEN, q), &[0]);
+        let r = reduce(opening.local_r(), RANDOMNESS_LEN, q);
+        let rounds = ProofConfig::default().binary_rounds().unwrap();
+        let partial = prove_hidden(&key, &commitment, vec![0], values, &hidden, &r, rounds).unwrap();
+        assert!(!key.verify_partial(&commitment, &partial).unwrap());
+    }
+
+    #[test]
+    fn randomness_solved_through_the_identity_block_is_rejected() {
+        // 伪造者用 [B | I] 的单位块解出任意声称值的随机数，它不再是三元向量
+        let (key, opening, commitment) = setup();
+        let q = CommitConfig::default().modulus;
+        let forged = reduce(&[4, 20, 30, 40], MESSAGE_LEN, q);
+        let mut r = reduce(opening.local_r(), RANDOMNESS_LEN, q);
+        for (row, slot) in key.local_a().iter().zip(r.iter_mut().skip(RANDOMNESS_LEN - key.local_a().len())) {
+            *slot = (*slot + row[0] * 6) % q;
+        }
+        assert_eq!(key.commit(&forged, &r).unwrap().commitment_point(), commitment.commitment_point());
+
+        let (values, hidden) = split(&forged, &[0]);
+        let rounds = ProofConfig::default().binary_rounds().unwrap();
+        let partial = prove_hidden(&key, &commitment, vec![0], values, &hidden, &r, rounds).unwrap();
+        assert_eq!(partial.value(0), Some(4));
+        assert!(!key.verify_partial(&commitment, &partial).unwrap());
+    }
+}
@@ -0,0 +1,235 @@
+//! This module provides a self-describing transfer format for openings.
+//!
+//! An `OpeningEnvelope` carries the opening `(m, r)` of one commitment together with the
+//! identifier of that commitment and the digest of the `(A, B)` matrices it was computed with, so
+//! a reveal sent over a side channel such as email or chat names what it opens. The encoding ends
+//! with a checksum over everything before it, which catches corruption in transit, and the
+//! envelope may carry a signature of the sender over its contents.
+//!
+//! Signatures go through the `EnvelopeSigner` and `SignatureVerifier` traits, so deployments plug
+//! in the scheme and key management they already use.
+//!
+//! An envelope may also bind the `policy::VerifyPolicy` its sender and receiver agreed on, covered
+//! by the signature and the checksum. `verify_with_policy` checks the opening under the receiver's
+//! policy and refuses an envelope bound to another one; an envelope without a policy stands for
+//! the default policy, so envelopes written before policies existed still verify.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::policy::VerifyPolicy;
+use crate::{audit, params, revocation, scheme, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const ENVELOPE_SIGNING_DOMAIN: &[u8] = b"reveler/envelope/signing";
+pub(crate) const ENVELOPE_CHECKSUM_DOMAIN: &[u8] = b"reveler/envelope/checksum";
+
+/// Producer of signatures over envelope contents.
+pub trait EnvelopeSigner {
+    /// Returns the identifier of the signing key, e.g. a public key or its fingerprint.
+    fn key_id(&self) -> Vec<u8>;
+
+    /// Signs `message`.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Checker of signatures over envelope contents.
+pub trait SignatureVerifier {
+    /// Returns `true` if `signature` is a valid signature of `message` under the key `key_id`.
+    fn verify(&self, key_id: &[u8], message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Signature of the sender over the contents of an envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    pub key_id: Vec<u8>,    // The identifier of the signing key
+    pub signature: Vec<u8>, // The signature over `OpeningEnvelope::signing_bytes`
+}
+
+/// Opening of one commitment, packaged for transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpeningEnvelope {
+    pub commitment_id: Vec<u8>,               // The `audit::commitment_id` of the opened commitment
+    pub params_digest: Vec<u8>,               // The `params::params_digest` of the (A, B) matrices
+    pub local_m: Vec<u64>,                    // The message of the opening
+    pub local_r: Vec<u64>,                    // The randomness of the opening
+    pub signature: Option<EnvelopeSignature>, // The optional signature of the sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<VerifyPolicy>,         // The verification policy bound by the sender, if any
+}
+
+impl OpeningEnvelope {
+    /// Packages the opening of `commitment`.
+    ///
+    /// # Parameters
+    /// - `commitment`: A reference to the opened `RevelerResult`.
+    /// - `opening`: A reference to the `RevelerCommit` it was computed from.
+    ///
+    /// # Returns
+    /// An unsigned `OpeningEnvelope`.
+    pub fn new(commitment: &RevelerResult, opening: &RevelerCommit) -> Self {
+        OpeningEnvelope {
+            commitment_id: audit::commitment_id(commitment),
+            params_digest: params::params_digest((&opening.local_a, &opening.local_b)),
+            local_m: opening.local_m.clone(),
+            local_r: opening.local_r.clone(),
+            signature: None,
+            policy: None,
+        }
+    }
+
+    /// Binds the verification policy agreed with the receiver. Sign the envelope afterwards, so
+    /// the signature covers the policy.
+    pub fn with_policy(mut self, policy: VerifyPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Signs the envelope with `signer`, replacing any previous signature.
+    pub fn with_signature(mut self, signer: &dyn EnvelopeSigner) -> Self {
+        let signature = signer.sign(&self.signing_bytes());
+        self.signature = Some(EnvelopeSignature { key_id: signer.key_id(), signature });
+        self
+    }
+
+    /// Returns the bytes a signature covers: a fixed domain, the scheme header, the commitment
+    /// identifier, the parameters digest, the opening and the policy, if bound.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = ENVELOPE_SIGNING_DOMAIN.to_vec();
+        bytes.extend(self.body());
+        if let Some(policy) = &self.policy {
+            bytes.extend(policy.to_bytes());
+        }
+        bytes
+    }
+
+    /// Checks that the envelope opens `commitment` under `params`.
+    ///
+    /// # Parameters
+    /// - `commitment`: A reference to the `RevelerResult` the envelope claims to open.
+    /// - `params`: The `(A, B)` matrices the commitment was computed with.
+    ///
+    /// # Returns
+    /// `Ok(())` if the envelope names `commitment` and `params` and its opening reproduces the
+    /// commitment, `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if
+    /// `revocation::check` refuses `params`,
+    /// `CommitError::PolicyViolation` if the envelope binds a policy other than the default one,
+    /// otherwise `CommitError::InvalidOpening`. The signature is not checked, see
+    /// `verify_signature`.
+    pub fn verify_against(&self, commitment: &RevelerResult, params: (&[Vec<u64>], &[Vec<u64>])) -> Result<(), CommitError> {
+        self.verify_with_policy(commitment, params, &VerifyPolicy::default())
+    }
+
+    /// Checks that the envelope opens `commitment` under `params` and the receiver's `policy`.
+    ///
+    /// # Returns
+    /// `Ok(())` if the envelope binds `policy`, or no policy and `policy` is the default one, and
+    /// its opening passes `VerifyPolicy::verify_opening`; `CommitError::PolicyViolation` if the
+    /// envelope binds another policy or the opening exceeds a norm bound of `policy`, otherwise the
+    /// errors of `verify_against`.
+    pub fn verify_with_policy(
+        &self,
+        commitment: &RevelerResult,
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        policy: &VerifyPolicy,
+    ) -> Result<(), CommitError> {
+        revocation::check(params)?;
+        if self.policy.unwrap_or_default() != *policy {
+            return Err(CommitError::PolicyViolation);
+        }
+        if self.commitment_id != audit::commitment_id(commitment)
+            || self.params_digest != params::params_digest(params)
+            || (policy.check_hash && !RevelerCommit::verify(commitment))
+        {
+            return Err(CommitError::InvalidOpening);
+        }
+        match policy.verify_opening(params, commitment, &self.local_m, &self.local_r) {
+            Ok(true) => Ok(()),
+            Err(error @ (CommitError::PolicyViolation | CommitError::ParamsRevoked | CommitError::ParamsExpired)) => Err(error),
+            _ => Err(CommitError::InvalidOpening),
+        }
+    }
+
+    /// Checks the signature of the envelope.
+    ///
+    /// # Returns
+    /// The identifier of the signing key, or `CommitError::InvalidOpening` if the envelope is
+    /// unsigned or its signature does not verify under `verifier`.
+    pub fn verify_signature(&self, verifier: &dyn SignatureVerifier) -> Result<&[u8], CommitError> {
+        match &self.signature {
+            Some(signed) if verifier.verify(&signed.key_id, &self.signing_bytes(), &signed.signature) => Ok(&signed.key_id),
+            _ => Err(CommitError::InvalidOpening),
+        }
+    }
+
+    /// Encodes the envelope as the scheme header, the length-prefixed commitment identifier,
+    /// parameters digest, message and randomness, a flag byte with bit 0 set for a signature and
+    /// bit 1 for a policy, followed by the length-prefixed key identifier and signature and by the
+    /// policy if set, and a length-prefixed checksum over all preceding bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.body();
+        bytes.push(u8::from(self.signature.is_some()) | u8::from(self.policy.is_some()) << 1);
+        if let Some(signed) = &self.signature {
+            bytes.extend(utils::encode_bytes(&signed.key_id));
+            bytes.extend(utils::encode_bytes(&signed.signature));
+        }
+        if let Some(policy) = &self.policy {
+            bytes.extend(policy.to_bytes());
+        }
+        let checksum = checksum(&bytes);
+        bytes.extend(utils::encode_bytes(&checksum));
+        bytes
+    }
+
+    /// Decodes an envelope written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `OpeningEnvelope`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding or its checksum does not match.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (commitment_id, rest) = utils::decode_bytes(rest)?;
+        let (params_digest, rest) = utils::decode_bytes(rest)?;
+        let (local_m, rest) = utils::decode_vector(rest)?;
+        let (local_r, rest) = utils::decode_vector(rest)?;
+        let (&flag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        if flag > 3 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let (signature, rest) = if flag & 1 == 1 {
+            let (key_id, rest) = utils::decode_bytes(rest)?;
+            let (signature, rest) = utils::decode_bytes(rest)?;
+            (Some(EnvelopeSignature { key_id, signature }), rest)
+        } else {
+            (None, rest)
+        };
+        let (policy, rest) = if flag & 2 == 2 {
+            let (policy, rest) = VerifyPolicy::decode(rest)?;
+            (Some(policy), rest)
+        } else {
+            (None, rest)
+        };
+        let covered = bytes.get(..bytes.len() - rest.len()).ok_or(CommitError::MalformedEncoding)?;
+        let (expected, rest) = utils::decode_bytes(rest)?;
+        if !rest.is_empty() || expected != checksum(covered) {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(OpeningEnvelope { commitment_id, params_digest, local_m, local_r, signature, policy })
+    }
+
+    // Encode the signed part: the scheme header, the identifiers and the opening.
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend(utils::encode_bytes(&self.commitment_id));
+        bytes.extend(utils::encode_bytes(&self.params_digest));
+        bytes.extend(utils::encode_vector(&self.local_m));
+        bytes.extend(utils::encode_vector(&self.local_r));
+        bytes
+    }
+}
+
+fn checksum(bytes: &[u8]) -> Vec<u8> {
+    let mut input = ENVELOPE_CHECKSUM_DOMAIN.to_vec();
+    input.extend_from_slice(bytes);
+    utils::hash_to_commitment(&input)
+}
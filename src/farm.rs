@@ -0,0 +1,233 @@
+//! This module distributes the computation of commitment points across worker machines.
+//!
+//! Building on the split-phase API, a `FarmCoordinator` wraps the share of a batch of openings
+//! sent to each worker into a `PointJob`. A `FarmWorker` holding the same `CommitmentKey` answers
+//! the job with a `PointResult` holding `commit_point` of every opening. Before binding the points
+//! with `bind_point`, the coordinator recomputes a random sample of them on the direct path, which
+//! runs on the calling thread unlike the block path the workers take for full openings, and
+//! rejects the whole result if any sampled point differs. A worker returning `k` wrong points out of `n` escapes a sample of `s` points
+//! with probability at most `(1 - k/n)^s`.
+//!
+//! Jobs name the parameters by their `params::params_digest`, so a worker refuses jobs for a key
+//! it does not hold. Jobs carry the openings: run workers only on machines trusted with them.
+//!
+//! With the `transport` feature, `FarmWorker::serve_one` and `FarmCoordinator::dispatch` exchange
+//! jobs and results as frames of a `protocol::transport::FrameTransport`.
+//!
+//! The module is compiled with the `prover` feature.
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::{CommitConfig, HashPolicy};
+use crate::fft::LOCAL_N;
+use crate::key::CommitmentKey;
+#[cfg(feature = "transport")]
+use crate::protocol::transport::FrameTransport;
+use crate::{bind_point, commit_point, scheme, utils, RevelerResult};
+
+/// Default number of points of every result the coordinator recomputes.
+pub const DEFAULT_SAMPLES: usize = 4;
+
+/// Batch of openings whose commitment points a worker computes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointJob {
+    pub job_id: u64,                         // The identifier echoed in the result
+    pub params_digest: Vec<u8>,              // The digest of the key the points are computed under
+    pub openings: Vec<(Vec<u64>, Vec<u64>)>, // The (m, r) openings
+}
+
+/// Commitment points returned by a worker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointResult {
+    pub job_id: u64,           // The identifier of the job
+    pub points: Vec<Vec<u64>>, // The points, one per opening and in the same order
+}
+
+impl PointJob {
+    /// Encodes the job as the scheme header, the job identifier, the length-prefixed digest, the
+    /// number of openings and every opening as its length-prefixed `m` and `r`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.job_id.to_be_bytes());
+        bytes.extend(utils::encode_bytes(&self.params_digest));
+        bytes.extend_from_slice(&(self.openings.len() as u64).to_be_bytes());
+        for (local_m, local_r) in &self.openings {
+            bytes.extend(utils::encode_vector(local_m));
+            bytes.extend(utils::encode_vector(local_r));
+        }
+        bytes
+    }
+
+    /// Decodes a job written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `PointJob`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (job_id, rest) = utils::decode_u64(rest)?;
+        let (params_digest, rest) = utils::decode_bytes(rest)?;
+        let (count, mut rest) = utils::decode_u64(rest)?;
+        // 每个条目至少占 16 字节，先检查数量避免恶意前缀导致大量内存分配
+        if count > (rest.len() / 16) as u64 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let mut openings = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (local_m, next) = utils::decode_vector(rest)?;
+            let (local_r, next) = utils::decode_vector(next)?;
+            openings.push((local_m, local_r));
+            rest = next;
+        }
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(PointJob { job_id, params_digest, openings })
+    }
+}
+
+impl PointResult {
+    /// Encodes the result as the scheme header, the job identifier, the number of points and
+    /// every length-prefixed point.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.job_id.to_be_bytes());
+        bytes.extend(utils::encode_matrix(&self.points));
+        bytes
+    }
+
+    /// Decodes a result written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `PointResult`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (job_id, rest) = utils::decode_u64(rest)?;
+        let (points, rest) = utils::decode_vectors(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(PointResult { job_id, points })
+    }
+}
+
+/// Worker computing the points of jobs under one key.
+#[derive(Debug, Clone)]
+pub struct FarmWorker {
+    key: CommitmentKey,     // The key of the jobs the worker accepts
+    params_digest: Vec<u8>, // The digest of the key
+    config: CommitConfig,   // The configuration of the point computation
+}
+
+impl FarmWorker {
+    /// Creates a worker for the jobs under `key`, computing with the default configuration.
+    pub fn new(key: CommitmentKey) -> Self {
+        let params_digest = key.digest();
+        FarmWorker { key, params_digest, config: CommitConfig::default() }
+    }
+
+    /// Sets the configuration of the point computation.
+    pub fn with_config(mut self, config: CommitConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Computes the points of a job.
+    ///
+    /// # Returns
+    /// The `PointResult`, `CommitError::HandshakeFailed` if the job is for another key, or the
+    /// `CommitError` of the first opening whose point cannot be computed.
+    pub fn handle(&self, job: &PointJob) -> Result<PointResult, CommitError> {
+        if job.params_digest != self.params_digest {
+            return Err(CommitError::HandshakeFailed);
+        }
+        let points = job
+            .openings
+            .iter()
+            .map(|(local_m, local_r)| commit_point(self.key.as_pair(), local_m, local_r, &self.config))
+            .collect::<Result<_, _>>()?;
+        Ok(PointResult { job_id: job.job_id, points })
+    }
+
+    /// Receives one job from `transport`, computes it and sends back the result.
+    #[cfg(feature = "transport")]
+    pub fn serve_one<T: FrameTransport>(&self, transport: &mut T) -> Result<(), CommitError> {
+        let job = PointJob::from_bytes(&transport.receive_frame()?)?;
+        transport.send_frame(&self.handle(&job)?.to_bytes())
+    }
+}
+
+/// Coordinator distributing jobs and checking the returned points.
+#[derive(Debug, Clone)]
+pub struct FarmCoordinator {
+    key: CommitmentKey,     // The key the points are computed under
+    params_digest: Vec<u8>, // The digest of the key
+    samples: usize,         // The number of points of every result recomputed
+}
+
+impl FarmCoordinator {
+    /// Creates a coordinator for `key`, recomputing `DEFAULT_SAMPLES` points of every result.
+    pub fn new(key: CommitmentKey) -> Self {
+        let params_digest = key.digest();
+        FarmCoordinator { key, params_digest, samples: DEFAULT_SAMPLES }
+    }
+
+    /// Sets the number of points of every result recomputed before binding.
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Creates the job of the given openings.
+    pub fn job(&self, job_id: u64, openings: Vec<(Vec<u64>, Vec<u64>)>) -> PointJob {
+        PointJob { job_id, params_digest: self.params_digest.clone(), openings }
+    }
+
+    /// Checks the result of a job on a random sample and binds its points.
+    ///
+    /// # Parameters
+    /// - `job`: The job sent to the worker.
+    /// - `result`: The result returned by the worker.
+    /// - `rng`: The random number generator choosing the sample.
+    ///
+    /// # Returns
+    /// The commitments of the openings of the job, or `CommitError::InvalidOpening` if the result
+    /// is for another job, has the wrong number of points, holds a point of the wrong shape, or a
+    /// sampled point differs from its recomputation.
+    pub fn accept<R: Rng>(&self, job: &PointJob, result: PointResult, rng: &mut R) -> Result<Vec<RevelerResult>, CommitError> {
+        let q = CommitConfig::default().modulus;
+        if result.job_id != job.job_id
+            || result.points.len() != job.openings.len()
+            || result.points.iter().any(|point| point.len() != LOCAL_N || point.iter().any(|&x| x >= q))
+        {
+            return Err(CommitError::InvalidOpening);
+        }
+
+        // 直接路径不经过工作线程，作为参考实现
+        let reference = CommitConfig::default().with_small_message_limit(usize::MAX);
+        let sampled = rand::seq::index::sample(rng, job.openings.len(), self.samples.min(job.openings.len()));
+        for index in sampled {
+            let (Some((local_m, local_r)), Some(point)) = (job.openings.get(index), result.points.get(index)) else {
+                return Err(CommitError::InvalidOpening);
+            };
+            if commit_point(self.key.as_pair(), local_m, local_r, &reference)? != *point {
+                return Err(CommitError::InvalidOpening);
+            }
+        }
+        Ok(result.points.into_iter().map(|point| bind_point(point, HashPolicy::default())).collect())
+    }
+
+    /// Sends `job` over `transport`, waits for the result and checks it with `accept`.
+    #[cfg(feature = "transport")]
+    pub fn dispatch<T: FrameTransport, R: Rng>(&self, transport: &mut T, job: &PointJob, rng: &mut R) -> Result<Vec<RevelerResult>, CommitError> {
+        transport.send_frame(&job.to_bytes())?;
+        let result = PointResult::from_bytes(&transport.receive_frame()?)?;
+        self.accept(job, result, rng)
+    }
+}
@@ -0,0 +1,86 @@
+//! This module provides fault-injection hooks for tests, so applications exercise their handling of
+//! failures of `commit` that cannot be triggered deterministically otherwise.
+//!
+//! `inject` arms a `Fault` on the calling thread until the returned `FaultGuard` is dropped:
+//! - `Fault::WorkerPanic`: a worker thread of the block path panics. The commitment takes the block
+//!   path on worker threads whatever the configuration, and `commit` returns
+//!   `CommitError::ComputationError` as for any failed worker.
+//! - `Fault::TruncatedHash`: the hash binding the point is cut to the given number of bytes, as
+//!   a faulty hasher would, so `RevelerCommit::verify` rejects the result.
+//! - `Fault::CorruptedChunk`: one coordinate of the point is altered after the computation, as by
+//!   a worker returning a corrupted chunk of rows. The hash binds the altered point, so the result
+//!   passes `RevelerCommit::verify` but fails `RevelerCommit::verify_opening` once disarmed.
+//!
+//! Faults are armed per thread and read by the thread calling `commit`, so tests running in
+//! parallel do not see each other's faults. They apply to every point computed and every result
+//! bound on the arming thread, e.g. by `RevelerCommit::commit`, `CommitmentKey::commit` or the
+//! provers, but not to the points of batches, which are computed on worker threads.
+//!
+//! The module is compiled with the `fault-injection` feature, which is meant for test builds only.
+
+use std::cell::RefCell;
+
+/// Failure to simulate inside `commit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// A worker thread of the block path panics.
+    WorkerPanic,
+    /// The commitment hash is truncated to the given number of bytes.
+    TruncatedHash(usize),
+    /// One coordinate of the commitment point is altered.
+    CorruptedChunk,
+}
+
+/// Keeps a fault armed on the calling thread until dropped.
+#[derive(Debug)]
+#[must_use = "the fault is disarmed when the guard is dropped"]
+pub struct FaultGuard {
+    fault: Fault,
+}
+
+thread_local! {
+    static ARMED: RefCell<Vec<Fault>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Arms `fault` on the calling thread.
+///
+/// # Returns
+/// The `FaultGuard` disarming the fault when dropped.
+pub fn inject(fault: Fault) -> FaultGuard {
+    ARMED.with(|armed| armed.borrow_mut().push(fault));
+    FaultGuard { fault }
+}
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        ARMED.with(|armed| {
+            let mut armed = armed.borrow_mut();
+            if let Some(position) = armed.iter().rposition(|&fault| fault == self.fault) {
+                armed.remove(position);
+            }
+        });
+    }
+}
+
+pub(crate) fn worker_panic() -> bool {
+    ARMED.with(|armed| armed.borrow().contains(&Fault::WorkerPanic))
+}
+
+pub(crate) fn truncated_hash() -> Option<usize> {
+    ARMED.with(|armed| {
+        armed.borrow().iter().find_map(|fault| match *fault {
+            Fault::TruncatedHash(len) => Some(len),
+            _ => None,
+        })
+    })
+}
+
+pub(crate) fn corrupted_chunk() -> bool {
+    ARMED.with(|armed| armed.borrow().contains(&Fault::CorruptedChunk))
+}
+
+// 在工作线程中调用，模拟线程崩溃
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::panic))]
+pub(crate) fn panic_worker() {
+    panic!("injected worker panic");
+}
@@ -0,0 +1,84 @@
+//! This module provides fuzzing entry points for downstream integrators.
+//!
+//! The functions accept arbitrary bytes, as handed out by `cargo fuzz` or any other fuzzing
+//! engine, and exercise the public decoding and verification paths. They panic only when an
+//! invariant of the crate is violated, so every panic they raise is a bug worth reporting.
+//!
+//! The module is compiled with the `fuzzing` feature.
+
+use crate::grammar;
+use crate::scheme::SchemeId;
+use crate::{RevelerCommit, RevelerResult};
+
+/// Decodes `bytes` as a `RevelerResult` and as `RevelerCommit` inputs, and checks that every
+/// successfully decoded value re-encodes to exactly the same bytes and decodes back to itself.
+///
+/// # Parameters
+/// - `bytes`: Arbitrary input from the fuzzing engine.
+///
+/// # Panics
+/// If the canonical encoding is not a bijection on the accepted inputs.
+pub fn roundtrip_serde(bytes: &[u8]) {
+    if let Ok(result) = RevelerResult::from_bytes(bytes) {
+        let encoded = result.to_bytes();
+        assert_eq!(encoded, bytes, "RevelerResult encoding is not canonical");
+
+        let decoded = RevelerResult::from_bytes(&encoded).expect("re-encoded RevelerResult must decode");
+        assert_eq!(decoded.commitment_point, result.commitment_point);
+        assert_eq!(decoded.commitment_hash, result.commitment_hash);
+    }
+
+    if let Ok(commitment) = RevelerCommit::from_canonical_bytes(bytes) {
+        let encoded = commitment.to_canonical_bytes();
+        assert_eq!(encoded, bytes, "RevelerCommit encoding is not canonical");
+
+        let decoded = RevelerCommit::from_canonical_bytes(&encoded).expect("re-encoded RevelerCommit must decode");
+        assert_eq!(decoded.to_canonical_bytes(), encoded);
+    }
+}
+
+/// Decodes `bytes` as a `RevelerResult` and runs the verification on it.
+///
+/// # Parameters
+/// - `bytes`: Arbitrary input from the fuzzing engine.
+///
+/// # Returns
+/// `true` if the input decodes to a result that verifies, otherwise `false`. Any panic raised
+/// while decoding or verifying is a bug.
+pub fn verify_never_panics(bytes: &[u8]) -> bool {
+    match RevelerResult::from_bytes(bytes) {
+        Ok(result) => RevelerCommit::verify(&result),
+        Err(_) => false,
+    }
+}
+
+/// Parses `bytes` as text in every grammar of `grammar` and checks that every accepted text
+/// formats back to itself, so no two texts share a value.
+///
+/// # Parameters
+/// - `bytes`: Arbitrary input from the fuzzing engine, skipped unless it is UTF-8.
+///
+/// # Panics
+/// If a grammar accepts a text that does not format back to itself, up to the case of `hex`.
+pub fn roundtrip_text(bytes: &[u8]) {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return;
+    };
+    if let Ok(value) = grammar::parse_decimal(text) {
+        assert_eq!(grammar::format_decimal(value), text, "decimal grammar is not canonical");
+    }
+    if let Ok(decoded) = grammar::decode_hex(text) {
+        assert_eq!(grammar::encode_hex(&decoded), text.to_ascii_lowercase(), "hex grammar is not canonical");
+    }
+    if let Ok(decoded) = grammar::decode_base64url(text) {
+        assert_eq!(grammar::encode_base64url(&decoded), text, "base64url grammar is not canonical");
+    }
+    if let Ok(scheme) = text.parse::<SchemeId>() {
+        assert_eq!(scheme.to_string(), text, "scheme identifier grammar is not canonical");
+    }
+    // 默认注册表只含当前方案，解码成功的收据都能重新编码
+    if let Ok(result) = RevelerResult::from_uri(text) {
+        let encoded = result.to_uri().expect("decoded receipt must re-encode");
+        assert_eq!(encoded, text, "receipt grammar is not canonical");
+    }
+}
@@ -0,0 +1,234 @@
+//! This module records and checks golden files: snapshots of the outputs of this crate on fixed,
+//! seeded inputs, so applications detect silent behavior changes when they upgrade the dependency.
+//!
+//! `record` writes the snapshot of the current build to a file, which the application commits to
+//! its repository; `check` recomputes the snapshot and compares it with the file. The inputs are
+//! derived from the fixed master seed `MasterSeed::from_bytes([0; 32])` under `SEED_LABEL`: the
+//! key from `seed.derive("params")`, every message and randomness from `seed.derive(name)`. The
+//! snapshot holds:
+//! - `scheme`, `key` and `params-digest`: the scheme identifier, the encoded key and its digest.
+//! - `commit-*`: the `RevelerResult::to_bytes` of commitments to zero, short, full-length and
+//!   binary messages, of their homomorphic sum and of a scaled commitment.
+//! - `opening-full`: the `Opening::to_bytes` of the full-length opening.
+//! - `message-encoding` and `map-root`: the `message::encode_message` of a fixed string and the
+//!   root of a fixed `map::commit_map`.
+//! - `proof-binary` and `proof-linking`: the canonical JSON of a binary proof on the binary
+//!   commitment and of a linking proof between the full-length commitment and a re-commitment.
+//!
+//! Proofs draw fresh randomness, so they differ between two recordings. `check` therefore compares
+//! every other entry byte for byte but decodes the recorded proofs and verifies them against the
+//! recomputed commitments: a proof recorded by an earlier version must still decode and verify.
+//!
+//! The file is plain text, one entry per line after the `FORMAT_VERSION` line, as the name, a space
+//! and the value: lowercase hex for byte strings, canonical JSON for proofs. Changes show up as
+//! readable line diffs in code review.
+//!
+//! The module is compiled with the `golden` feature.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::binary::{self, BinaryProof};
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_N, MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::grammar::encode_hex;
+use crate::json::CanonicalJson;
+use crate::key::{CommitmentKey, Opening};
+use crate::linking::{self, LinkingProof};
+use crate::map;
+use crate::message;
+use crate::scheme::SchemeId;
+use crate::seed::{MasterSeed, Seed, SEED_LEN};
+use crate::{utils, RevelerCommit, RevelerResult};
+
+/// Identifier of the golden file format, its first line.
+pub const FORMAT_VERSION: &str = "reveler-golden/2";
+
+/// Label the inputs of the snapshot are derived from.
+pub const SEED_LABEL: &[u8] = b"reveler/golden/seed";
+
+/// Number of bits of the binary message of `proof-binary`.
+pub const BINARY_WIDTH: usize = 16;
+
+/// Difference between a golden file and the snapshot of the current build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoldenMismatch {
+    /// The value of the entry changed.
+    Changed { name: String, expected: String, actual: String },
+    /// The file lacks an entry of the snapshot.
+    Missing { name: String },
+    /// The file holds an entry the snapshot no longer produces.
+    Unexpected { name: String },
+    /// The recorded proof no longer decodes or verifies.
+    ProofRejected { name: String },
+}
+
+/// Outcome of `check`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldenReport {
+    pub checked: usize,                  // The number of entries compared or verified
+    pub mismatches: Vec<GoldenMismatch>, // The differences found, in the order of the snapshot
+}
+
+impl GoldenReport {
+    /// Returns whether the build reproduces the golden file.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+// Seeded inputs and the commitments the snapshot is computed from.
+struct Fixture {
+    key: CommitmentKey,
+    binary: (Opening, RevelerResult),
+    full: (Opening, RevelerResult),
+    relinked: (Opening, RevelerResult),
+    commitments: Vec<(&'static str, RevelerResult)>,
+}
+
+/// Writes the snapshot of the current build to `path`, replacing the file.
+///
+/// # Returns
+/// `Ok(())`, the `CommitError` of a failed commitment or proof, or `CommitError::StorageError` if
+/// the file cannot be written.
+pub fn record(path: impl AsRef<Path>) -> Result<(), CommitError> {
+    let fixture = fixture()?;
+    let mut entries = deterministic_entries(&fixture)?;
+    entries.extend(proof_entries(&fixture)?);
+
+    let mut text = format!("{FORMAT_VERSION}\n");
+    for (name, value) in entries {
+        text.push_str(&format!("{name} {value}\n"));
+    }
+    fs::write(path, text).map_err(|_| CommitError::StorageError)
+}
+
+/// Compares the golden file at `path` with the snapshot of the current build.
+///
+/// # Returns
+/// The `GoldenReport`, `CommitError::StorageError` if the file cannot be read,
+/// `CommitError::MalformedEncoding` if it is not a golden file of `FORMAT_VERSION`, or the
+/// `CommitError` of a failed commitment.
+pub fn check(path: impl AsRef<Path>) -> Result<GoldenReport, CommitError> {
+    let text = fs::read_to_string(path).map_err(|_| CommitError::StorageError)?;
+    let mut recorded = parse(&text)?;
+    let fixture = fixture()?;
+    let mut report = GoldenReport { checked: 0, mismatches: Vec::new() };
+
+    for (name, actual) in deterministic_entries(&fixture)? {
+        match recorded.remove(&name) {
+            None => report.mismatches.push(GoldenMismatch::Missing { name }),
+            Some(expected) => {
+                report.checked += 1;
+                if expected != actual {
+                    report.mismatches.push(GoldenMismatch::Changed { name, expected, actual });
+                }
+            }
+        }
+    }
+    for name in ["proof-binary", "proof-linking"] {
+        let Some(proof) = recorded.remove(name) else {
+            report.mismatches.push(GoldenMismatch::Missing { name: name.to_string() });
+            continue;
+        };
+        report.checked += 1;
+        if !verify_proof(&fixture, name, &proof) {
+            report.mismatches.push(GoldenMismatch::ProofRejected { name: name.to_string() });
+        }
+    }
+    report.mismatches.extend(recorded.into_keys().map(|name| GoldenMismatch::Unexpected { name }));
+    Ok(report)
+}
+
+fn fixture() -> Result<Fixture, CommitError> {
+    let seed = MasterSeed::from_bytes([0; SEED_LEN]).derive(SEED_LABEL);
+    let key = CommitmentKey::from_seed(&seed.derive(b"params"));
+    let vector = |label: &str, len: usize| derive_vector(&seed, label, len);
+    let open = |local_m: Vec<u64>, local_r: Vec<u64>| -> Result<(Opening, RevelerResult), CommitError> {
+        let opening = Opening::new(local_m, local_r);
+        let commitment = key.commit_opening(&opening)?;
+        Ok((opening, commitment))
+    };
+
+    let (_, zero) = open(vec![0; LOCAL_N], vec![0; LOCAL_N])?;
+    let (_, short) = open(vector("short-m", 4)?, vector("short-r", 4)?)?;
+    let full = open(vector("full-m", MESSAGE_LEN)?, vector("full-r", RANDOMNESS_LEN)?)?;
+    let bits = vector("binary-m", BINARY_WIDTH)?.into_iter().map(|x| x % 2).collect();
+    let binary = open(bits, vector("binary-r", RANDOMNESS_LEN)?)?;
+    let relinked = open(full.0.local_m().to_vec(), vector("relinked-r", RANDOMNESS_LEN)?)?;
+
+    let commitments = vec![
+        ("commit-zero", zero),
+        ("commit-short", short.clone()),
+        ("commit-full", full.1.clone()),
+        ("commit-binary", binary.1.clone()),
+        ("commit-sum", short.add(&full.1)),
+        ("commit-scaled", full.1.scale(7)),
+    ];
+    Ok(Fixture { key, binary, full, relinked, commitments })
+}
+
+fn deterministic_entries(fixture: &Fixture) -> Result<Vec<(String, String)>, CommitError> {
+    let mut entries = vec![
+        ("scheme".to_string(), SchemeId::current().to_string()),
+        ("key".to_string(), encode_hex(&fixture.key.to_bytes())),
+        ("params-digest".to_string(), encode_hex(&fixture.key.digest())),
+    ];
+    for (name, commitment) in &fixture.commitments {
+        entries.push((name.to_string(), encode_hex(&commitment.to_bytes())));
+    }
+    entries.push(("opening-full".to_string(), encode_hex(&fixture.full.0.to_bytes())));
+
+    let encoded = message::encode_message(b"reveler golden file")?;
+    entries.push(("message-encoding".to_string(), encode_hex(&utils::encode_vector(&encoded))));
+    let entries_map: BTreeMap<Vec<u8>, Vec<u8>> =
+        [("alice", "3"), ("bob", "5"), ("carol", "8")].map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec())).into();
+    let committed = map::commit_map(entries_map)?;
+    entries.push(("map-root".to_string(), encode_hex(&committed.commitment().root)));
+    Ok(entries)
+}
+
+fn proof_entries(fixture: &Fixture) -> Result<Vec<(String, String)>, CommitError> {
+    let (opening, commitment) = &fixture.binary;
+    let binary = binary::prove_binary(fixture.key.as_pair(), opening.local_m(), opening.local_r(), commitment)?;
+
+    let recommit = |(opening, _): &(Opening, RevelerResult)| RevelerCommit::from_key_and_opening(fixture.key.clone(), opening.clone());
+    let linking = linking::prove_same_message_across(&recommit(&fixture.full), &fixture.full.1, &recommit(&fixture.relinked), &fixture.relinked.1)?;
+    Ok(vec![
+        ("proof-binary".to_string(), binary.to_canonical_json()),
+        ("proof-linking".to_string(), linking.to_canonical_json()),
+    ])
+}
+
+fn verify_proof(fixture: &Fixture, name: &str, text: &str) -> bool {
+    let params = fixture.key.as_pair();
+    match name {
+        "proof-binary" => BinaryProof::from_canonical_json(text)
+            .is_ok_and(|proof| binary::verify_binary(params, &fixture.binary.1, BINARY_WIDTH, &proof)),
+        "proof-linking" => LinkingProof::from_canonical_json(text)
+            .is_ok_and(|proof| linking::verify_same_message_across(params, &fixture.full.1, params, &fixture.relinked.1, &proof)),
+        _ => false,
+    }
+}
+
+// Parse the entries of a golden file, rejecting a wrong format line and duplicate names.
+fn parse(text: &str) -> Result<BTreeMap<String, String>, CommitError> {
+    let mut lines = text.lines();
+    if lines.next() != Some(FORMAT_VERSION) {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let mut entries = BTreeMap::new();
+    for line in lines.filter(|line| !line.is_empty()) {
+        let (name, value) = line.split_once(' ').ok_or(CommitError::MalformedEncoding)?;
+        if entries.insert(name.to_string(), value.to_string()).is_some() {
+            return Err(CommitError::MalformedEncoding);
+        }
+    }
+    Ok(entries)
+}
+
+// Reproducible vector of `len` coordinates in {-1, 0, 1} labelled `label`.
+fn derive_vector(seed: &Seed, label: &str, len: usize) -> Result<Vec<u64>, CommitError> {
+    seed.derive(label.as_bytes()).randomness(len)
+}
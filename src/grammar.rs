@@ -0,0 +1,169 @@
+//! This module defines the exact grammars of the textual encodings of the crate and the parsers
+//! and formatters of their terminals, so text written on one system parses to the same value on
+//! every other, whatever the locale, shell or language of the tools in between.
+//!
+//! The grammars, in ABNF (RFC 5234), where `DIGIT` and `HEXDIG` are ASCII only:
+//! ```text
+//! decimal   = "0" / %x31-39 *DIGIT        ; no sign, no leading zero, at most 2^64 - 1
+//! hex       = *(2HEXDIG)                  ; either case on input, lowercase on output
+//! base64url = *(4b64) [2b64 / 3b64]       ; RFC 4648 §5 alphabet, no padding
+//! b64       = ALPHA / DIGIT / "-" / "_"
+//! name      = 1*(%x61-7A / DIGIT)         ; lowercase ASCII letters and digits
+//! scheme-id = "reveler/v" decimal "/" name "-q" decimal "-n" decimal "/" name
+//! receipt   = "reveler:v" decimal ";params=" name "-q" decimal "-n" decimal ";hash=" name
+//!             ";c=" base64url
+//! ```
+//! A `base64url` string also has its unused trailing bits set to zero, so every byte string has
+//! exactly one encoding. Fields narrower than 64 bits, e.g. the format version of a `scheme-id`,
+//! reject a `decimal` that does not fit them.
+//!
+//! Every grammar is canonical: a value has exactly one text, formatting a parsed text returns it
+//! unchanged, and parsing never depends on the locale, since only ASCII digits are accepted and
+//! no separators, signs, whitespace or alternative radixes are. The only exception is the case of
+//! `hex` input, which is accepted in either case for text typed or pasted into a shell.
+//!
+//! The canonical JSON of `json` writes integers as `decimal` numbers and byte strings as
+//! `base64url` strings; the command line tool takes its byte strings as `hex`. `fuzz::roundtrip_text`
+//! checks that every accepted text formats back to itself, and the `text` cases of the
+//! conformance suite list accepted and rejected texts for other implementations.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use crate::commit_error::CommitError;
+
+/// Parses a `decimal`.
+///
+/// # Returns
+/// The value, or `CommitError::MalformedEncoding` if `text` is empty, holds anything but ASCII
+/// digits, has a leading zero or exceeds `u64::MAX`.
+pub fn parse_decimal(text: &str) -> Result<u64, CommitError> {
+    let digits = text.as_bytes();
+    let canonical = match digits {
+        [] => false,
+        [b'0'] => true,
+        [first, ..] => *first != b'0' && digits.iter().all(u8::is_ascii_digit),
+    };
+    if !canonical {
+        return Err(CommitError::MalformedEncoding);
+    }
+    digits.iter().try_fold(0u64, |acc, &digit| {
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add(u64::from(digit - b'0')))
+            .ok_or(CommitError::MalformedEncoding)
+    })
+}
+
+/// Formats a `decimal`.
+pub fn format_decimal(value: u64) -> String {
+    value.to_string()
+}
+
+/// Decodes a `hex` string, in either case.
+///
+/// # Returns
+/// The bytes, or `CommitError::MalformedEncoding` if `text` has an odd length or holds anything
+/// but ASCII hex digits.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, CommitError> {
+    let digits = text.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(CommitError::MalformedEncoding);
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| match pair {
+            [high, low] => Ok(hex_value(*high)? << 4 | hex_value(*low)?),
+            _ => Err(CommitError::MalformedEncoding),
+        })
+        .collect()
+}
+
+/// Encodes bytes as a lowercase `hex` string.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a `base64url` string.
+///
+/// # Returns
+/// The bytes, or `CommitError::MalformedEncoding` if `text` holds characters outside the
+/// alphabet, padding, a length of one modulo four, or non-zero unused trailing bits.
+pub fn decode_base64url(text: &str) -> Result<Vec<u8>, CommitError> {
+    URL_SAFE_NO_PAD.decode(text).map_err(|_| CommitError::MalformedEncoding)
+}
+
+/// Encodes bytes as a `base64url` string.
+pub fn encode_base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Checks that `text` is a `name`.
+pub fn is_name(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+}
+
+fn hex_value(digit: u8) -> Result<u8, CommitError> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_round_trips_up_to_u64_max() {
+        for value in [0, 1, 9, 10, 65535, 1 << 32, u64::MAX - 1, u64::MAX] {
+            let text = format_decimal(value);
+            assert_eq!(parse_decimal(&text), Ok(value));
+            assert_eq!(format_decimal(parse_decimal(&text).unwrap()), text);
+        }
+        assert_eq!(format_decimal(u64::MAX), "18446744073709551615");
+    }
+
+    #[test]
+    fn non_canonical_decimals_are_rejected() {
+        // u64::MAX + 1，以及各种区域设置下常见的写法
+        for text in ["18446744073709551616", "99999999999999999999", "", "00", "01", "-1", "+1", " 1", "1 ", "1_000", "1,000", "1.0", "0x10", "１", "٣"] {
+            assert_eq!(parse_decimal(text), Err(CommitError::MalformedEncoding), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn hex_decodes_either_case_and_encodes_lowercase() {
+        let bytes = vec![0x00, 0x0f, 0xab, 0xcd, 0xef, 0xff];
+        assert_eq!(encode_hex(&bytes), "000fabcdefff");
+        assert_eq!(decode_hex("000fabcdefff"), Ok(bytes.clone()));
+        assert_eq!(decode_hex("000FABCDEFFF"), Ok(bytes.clone()));
+        assert_eq!(decode_hex("000fAbCdEfFf"), Ok(bytes));
+        assert_eq!(decode_hex(""), Ok(Vec::new()));
+        for text in ["0", "abc", "0g", "0x00", " 00", "éé"] {
+            assert_eq!(decode_hex(text), Err(CommitError::MalformedEncoding), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn base64url_round_trips_every_tail_length() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..8 {
+            let text = encode_base64url(&bytes[250 - len..250]);
+            assert!(!text.contains('='));
+            assert_eq!(decode_base64url(&text), Ok(bytes[250 - len..250].to_vec()));
+        }
+        assert_eq!(encode_base64url(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn padded_or_non_canonical_base64url_is_rejected() {
+        // 填充、标准字母表的 + 与 /、长度模 4 余 1、以及非零的尾部比特
+        for text in ["AA==", "AAA=", "AA=", "+/8", "-/8", "A", "AAAAA", "AB", "AAB", "AA AA", "AA\n"] {
+            assert_eq!(decode_base64url(text), Err(CommitError::MalformedEncoding), "{text:?}");
+        }
+        assert_eq!(decode_base64url("AA"), Ok(vec![0]));
+        assert_eq!(decode_base64url("AAA"), Ok(vec![0, 0]));
+    }
+}
@@ -0,0 +1,258 @@
+//! This module provides hierarchical commitments: commitments to lists of commitments.
+//!
+//! An `AggregateCommitment` binds an ordered list of children in a hash tree. At level 0 the
+//! children are `RevelerResult`s, e.g. the commitments of one epoch; at level `k + 1` they are
+//! aggregates of level `k`, e.g. the epochs of one checkpoint. Every leaf is hashed together with
+//! the level of its children and its index, and the root together with the level and the number
+//! of children, so a digest of one level is never accepted in place of a digest of another and an
+//! inclusion proof only verifies at the index it was issued for.
+//!
+//! `AggregateTree::prove` returns the `InclusionProof` of one child: the sibling hashes on the
+//! path from its leaf to the root, logarithmic in the number of children. When a layer has an odd
+//! number of nodes the last one is carried up unchanged, so it has no sibling at that layer.
+//! `AggregateTree::prove_many` returns the `MultiInclusionProof` of several children at once: the
+//! siblings of their paths in one list, leaving out every node the verifier computes from the
+//! children themselves, so paths that meet near the leaves share their upper siblings.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::{utils, RevelerResult};
+
+pub(crate) const LEAF_DOMAIN: &[u8] = b"reveler/hierarchy/leaf";
+pub(crate) const NODE_DOMAIN: &[u8] = b"reveler/hierarchy/node";
+pub(crate) const ROOT_DOMAIN: &[u8] = b"reveler/hierarchy/root";
+
+/// Commitment to an ordered list of commitments or aggregates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateCommitment {
+    pub level: u32,    // 0 over commitments, k + 1 over aggregates of level k
+    pub count: u64,    // The number of children
+    pub root: Vec<u8>, // The root hash, bound to the level and the count
+}
+
+/// Proof that a child sits at a given index of an aggregate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub index: u64,             // The index of the child
+    pub siblings: Vec<Vec<u8>>, // The sibling hashes from the leaf layer up
+}
+
+/// Proof that several children sit at given indices of an aggregate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiInclusionProof {
+    pub indices: Vec<u64>,      // The indices of the children, strictly increasing
+    pub siblings: Vec<Vec<u8>>, // The sibling hashes not computed from the children, layer by layer
+}
+
+/// Hash tree of an aggregate, kept by its producer to issue inclusion proofs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateTree {
+    aggregate: AggregateCommitment,
+    layers: Vec<Vec<Vec<u8>>>, // The node hashes of every layer, leaves first
+}
+
+impl AggregateCommitment {
+    /// Encodes the aggregate as its level, its count and its length-prefixed root.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.level.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.count.to_be_bytes());
+        bytes.extend(utils::encode_bytes(&self.root));
+        bytes
+    }
+}
+
+impl AggregateTree {
+    /// Returns the aggregate commitment.
+    pub fn aggregate(&self) -> &AggregateCommitment {
+        &self.aggregate
+    }
+
+    /// Returns the inclusion proof of child `index`, or `None` if there is no such child.
+    pub fn prove(&self, index: u64) -> Option<InclusionProof> {
+        if index >= self.aggregate.count {
+            return None;
+        }
+        let mut position = usize::try_from(index).ok()?;
+        let mut siblings = Vec::new();
+        let below_root = self.layers.split_last().map_or(&[][..], |(_, below)| below);
+        for layer in below_root {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                siblings.push(sibling.clone());
+            }
+            position /= 2;
+        }
+        Some(InclusionProof { index, siblings })
+    }
+
+    /// Returns the proof of the children at `indices`, or `None` if the indices are empty, not
+    /// strictly increasing, or past the last child.
+    pub fn prove_many(&self, indices: &[u64]) -> Option<MultiInclusionProof> {
+        let last = *indices.last()?;
+        if last >= self.aggregate.count || !indices.windows(2).all(|pair| pair.first() < pair.last()) {
+            return None;
+        }
+        let mut positions = indices.iter().map(|&index| usize::try_from(index).ok()).collect::<Option<Vec<_>>>()?;
+        let mut siblings = Vec::new();
+        let below_root = self.layers.split_last().map_or(&[][..], |(_, below)| below);
+        for layer in below_root {
+            // 相邻的两个已知节点互为兄弟，无需在证明中给出
+            let mut known = positions.iter().peekable();
+            while let Some(&position) = known.next() {
+                if position.is_multiple_of(2) && known.peek() == Some(&&(position + 1)) {
+                    known.next();
+                } else if let Some(sibling) = layer.get(position ^ 1) {
+                    siblings.push(sibling.clone());
+                }
+            }
+            positions = positions.iter().map(|position| position / 2).collect();
+            positions.dedup();
+        }
+        Some(MultiInclusionProof { indices: indices.to_vec(), siblings })
+    }
+}
+
+/// Commits to a list of commitments.
+///
+/// # Returns
+/// The `AggregateTree` of level 0, or `CommitError::ComputationError` if the list is empty.
+pub fn commit_commitments(commitments: &[RevelerResult]) -> Result<AggregateTree, CommitError> {
+    let children: Vec<Vec<u8>> = commitments.iter().map(RevelerResult::to_bytes).collect();
+    build(0, &children)
+}
+
+/// Commits to a list of aggregates of one level.
+///
+/// # Returns
+/// The `AggregateTree` one level above the children, or `CommitError::ComputationError` if the
+/// list is empty, its aggregates are of different levels, or the level would overflow.
+pub fn commit_aggregates(children: &[AggregateCommitment]) -> Result<AggregateTree, CommitError> {
+    let level = children.first().map(|child| child.level).ok_or(CommitError::ComputationError)?;
+    if children.iter().any(|child| child.level != level) {
+        return Err(CommitError::ComputationError);
+    }
+    let encoded: Vec<Vec<u8>> = children.iter().map(AggregateCommitment::to_bytes).collect();
+    build(level.checked_add(1).ok_or(CommitError::ComputationError)?, &encoded)
+}
+
+/// Checks that `commitment` is included in a level-0 aggregate at the index of `proof`.
+pub fn verify_commitment_inclusion(aggregate: &AggregateCommitment, commitment: &RevelerResult, proof: &InclusionProof) -> bool {
+    aggregate.level == 0 && verify_path(aggregate, &commitment.to_bytes(), proof)
+}
+
+/// Checks that `child` is included in an aggregate one level above it at the index of `proof`.
+pub fn verify_aggregate_inclusion(aggregate: &AggregateCommitment, child: &AggregateCommitment, proof: &InclusionProof) -> bool {
+    child.level.checked_add(1) == Some(aggregate.level) && verify_path(aggregate, &child.to_bytes(), proof)
+}
+
+pub(crate) fn build(level: u32, children: &[Vec<u8>]) -> Result<AggregateTree, CommitError> {
+    if children.is_empty() {
+        return Err(CommitError::ComputationError);
+    }
+    let leaves: Vec<Vec<u8>> = children.iter().enumerate().map(|(index, child)| leaf_hash(level, index as u64, child)).collect();
+    let mut layers = vec![leaves];
+    while let Some(layer) = layers.last().filter(|layer| layer.len() > 1) {
+        // 奇数个节点时最后一个原样上移
+        let parents = layer
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                single => single.concat(),
+            })
+            .collect();
+        layers.push(parents);
+    }
+    let count = children.len() as u64;
+    let top = layers.last().and_then(|layer| layer.first()).ok_or(CommitError::ComputationError)?;
+    let root = root_hash(level, count, top);
+    Ok(AggregateTree { aggregate: AggregateCommitment { level, count, root }, layers })
+}
+
+// Recompute the root from the encoded child and the siblings, following the layer sizes implied
+// by the count of the aggregate.
+pub(crate) fn verify_path(aggregate: &AggregateCommitment, child: &[u8], proof: &InclusionProof) -> bool {
+    if proof.index >= aggregate.count {
+        return false;
+    }
+    let mut hash = leaf_hash(aggregate.level, proof.index, child);
+    let mut position = proof.index;
+    let mut width = aggregate.count;
+    let mut siblings = proof.siblings.iter();
+    while width > 1 {
+        let sibling_position = position ^ 1;
+        if sibling_position < width {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            hash = if position.is_multiple_of(2) { node_hash(&hash, sibling) } else { node_hash(sibling, &hash) };
+        }
+        position /= 2;
+        width = width.div_ceil(2);
+    }
+    siblings.next().is_none() && root_hash(aggregate.level, aggregate.count, &hash) == aggregate.root
+}
+
+// Recompute the root from the encoded children, one per index of the proof, merging the paths
+// layer by layer in the order `AggregateTree::prove_many` emits the siblings.
+pub(crate) fn verify_paths(aggregate: &AggregateCommitment, children: &[Vec<u8>], proof: &MultiInclusionProof) -> bool {
+    let well_formed = !children.is_empty()
+        && children.len() == proof.indices.len()
+        && proof.indices.windows(2).all(|pair| pair.first() < pair.last())
+        && proof.indices.last().is_some_and(|&last| last < aggregate.count);
+    if !well_formed {
+        return false;
+    }
+    let mut known: Vec<(u64, Vec<u8>)> = proof.indices.iter().zip(children)
+        .map(|(&index, child)| (index, leaf_hash(aggregate.level, index, child)))
+        .collect();
+    let mut width = aggregate.count;
+    let mut siblings = proof.siblings.iter();
+    while width > 1 {
+        let mut parents = Vec::with_capacity(known.len());
+        let mut nodes = known.into_iter().peekable();
+        while let Some((position, hash)) = nodes.next() {
+            let hash = if position.is_multiple_of(2) && nodes.peek().is_some_and(|(next, _)| *next == position + 1) {
+                let Some((_, right)) = nodes.next() else {
+                    return false;
+                };
+                node_hash(&hash, &right)
+            } else if (position ^ 1) < width {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                if position.is_multiple_of(2) { node_hash(&hash, sibling) } else { node_hash(sibling, &hash) }
+            } else {
+                hash
+            };
+            parents.push((position / 2, hash));
+        }
+        known = parents;
+        width = width.div_ceil(2);
+    }
+    match known.as_slice() {
+        [(0, top)] => siblings.next().is_none() && root_hash(aggregate.level, aggregate.count, top) == aggregate.root,
+        _ => false,
+    }
+}
+
+fn leaf_hash(level: u32, index: u64, child: &[u8]) -> Vec<u8> {
+    let mut input = LEAF_DOMAIN.to_vec();
+    input.extend_from_slice(&level.to_be_bytes());
+    input.extend_from_slice(&index.to_be_bytes());
+    input.extend(utils::encode_bytes(child));
+    utils::hash_to_commitment(&input)
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut input = NODE_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(left));
+    input.extend(utils::encode_bytes(right));
+    utils::hash_to_commitment(&input)
+}
+
+fn root_hash(level: u32, count: u64, top: &[u8]) -> Vec<u8> {
+    let mut input = ROOT_DOMAIN.to_vec();
+    input.extend_from_slice(&level.to_be_bytes());
+    input.extend_from_slice(&count.to_be_bytes());
+    input.extend(utils::encode_bytes(top));
+    utils::hash_to_commitment(&input)
+}
@@ -0,0 +1,350 @@
+//! This module provides the canonical JSON form of commitments, proofs and opening envelopes for
+//! web APIs.
+//!
+//! Every artifact is a JSON object with a `type` and a `scheme` member next to its fields:
+//! - `commitment`: `hash`, `point`.
+//! - `binary-proof`: `rounds`, each with `commitments` and a `response` holding a `kind` of
+//!   `permuted`, `shifted` or `masked` and the fields of that response.
+//! - `linking-proof`: `rounds`, as for `binary-proof`.
+//! - `opening-envelope`: `commitment_id`, `local_m`, `local_r`, `params_digest`, `signature`,
+//!   the latter `null` or an object with `key_id` and `signature`, and `policy`, the bytes of
+//!   `VerifyPolicy::to_bytes`, if the envelope binds a policy.
+//!
+//! Field names are those of the Rust types. Byte strings are base64url without padding, vectors
+//! of integers are arrays of JSON numbers, and `scheme` is the identifier of `scheme::SchemeId`,
+//! following the `base64url`, `decimal` and `scheme-id` grammars of `grammar`: numbers with a
+//! fraction, an exponent or a sign, or above `u64::MAX`, are rejected.
+//! The canonical text has the members of every object sorted by name in byte order and no
+//! whitespace, so equal artifacts always produce identical text. Decoding is strict: unknown or
+//! missing members, unregistered schemes and text that is not in canonical form are rejected.
+//!
+//! The module is compiled with the `json` feature.
+
+use serde_json::{Map, Value};
+use crate::binary::{BinaryProof, BinaryResponse, BinaryRound};
+use crate::commit_error::CommitError;
+use crate::envelope::{EnvelopeSignature, OpeningEnvelope};
+use crate::grammar;
+use crate::limits;
+use crate::linking::LinkingProof;
+use crate::policy::VerifyPolicy;
+use crate::scheme::{self, SchemeId};
+use crate::RevelerResult;
+
+/// Artifact with a canonical JSON form.
+pub trait CanonicalJson: Sized {
+    /// The value of the `type` member.
+    const TYPE: &'static str;
+
+    /// Returns the members of the artifact, without `type` and `scheme`.
+    fn to_members(&self) -> Map<String, Value>;
+
+    /// Rebuilds the artifact from its members, without `type` and `scheme`.
+    fn from_members(members: &Map<String, Value>) -> Result<Self, CommitError>;
+
+    /// Encodes the artifact as canonical JSON text.
+    fn to_canonical_json(&self) -> String {
+        let mut members = self.to_members();
+        members.insert("scheme".to_string(), Value::String(SchemeId::current().to_string()));
+        members.insert("type".to_string(), Value::String(Self::TYPE.to_string()));
+        let mut text = String::new();
+        write_canonical(&Value::Object(members), &mut text);
+        text
+    }
+
+    /// Decodes canonical JSON text written by `to_canonical_json`.
+    ///
+    /// # Returns
+    /// The decoded artifact, `CommitError::UnsupportedScheme` if it names an unregistered scheme,
+    /// `CommitError::LimitExceeded` if the text is longer than `max_proof_size` of the installed
+    /// `limits::DecodeLimits`, or `CommitError::MalformedEncoding` if the text is not the
+    /// canonical JSON of an artifact of this type.
+    fn from_canonical_json(text: &str) -> Result<Self, CommitError> {
+        limits::check(text.len(), limits::current().max_proof_size)?;
+        let value: Value = serde_json::from_str(text).map_err(|_| CommitError::MalformedEncoding)?;
+        let Value::Object(mut members) = value else {
+            return Err(CommitError::MalformedEncoding);
+        };
+        match members.remove("type") {
+            Some(Value::String(kind)) if kind == Self::TYPE => {}
+            _ => return Err(CommitError::MalformedEncoding),
+        }
+        match members.remove("scheme") {
+            Some(Value::String(id)) => scheme::resolve(&id)?,
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        let artifact = Self::from_members(&members)?;
+
+        // 重新编码后逐字节比较，拒绝非规范形式
+        if artifact.to_canonical_json() != text {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(artifact)
+    }
+}
+
+impl CanonicalJson for RevelerResult {
+    const TYPE: &'static str = "commitment";
+
+    fn to_members(&self) -> Map<String, Value> {
+        let mut members = Map::new();
+        members.insert("hash".to_string(), bytes(&self.commitment_hash));
+        members.insert("point".to_string(), numbers(&self.commitment_point));
+        members
+    }
+
+    fn from_members(members: &Map<String, Value>) -> Result<Self, CommitError> {
+        expect_members(members, &["hash", "point"])?;
+        Ok(RevelerResult::new(get_numbers(members, "point")?, get_bytes(members, "hash")?))
+    }
+}
+
+impl CanonicalJson for BinaryProof {
+    const TYPE: &'static str = "binary-proof";
+
+    fn to_members(&self) -> Map<String, Value> {
+        binary_rounds(&self.rounds)
+    }
+
+    fn from_members(members: &Map<String, Value>) -> Result<Self, CommitError> {
+        Ok(BinaryProof { rounds: parse_binary_rounds(members)? })
+    }
+}
+
+impl CanonicalJson for LinkingProof {
+    const TYPE: &'static str = "linking-proof";
+
+    fn to_members(&self) -> Map<String, Value> {
+        binary_rounds(&self.rounds)
+    }
+
+    fn from_members(members: &Map<String, Value>) -> Result<Self, CommitError> {
+        Ok(LinkingProof { rounds: parse_binary_rounds(members)? })
+    }
+}
+
+impl CanonicalJson for OpeningEnvelope {
+    const TYPE: &'static str = "opening-envelope";
+
+    fn to_members(&self) -> Map<String, Value> {
+        let mut members = Map::new();
+        members.insert("commitment_id".to_string(), bytes(&self.commitment_id));
+        members.insert("local_m".to_string(), numbers(&self.local_m));
+        members.insert("local_r".to_string(), numbers(&self.local_r));
+        members.insert("params_digest".to_string(), bytes(&self.params_digest));
+        let signature = self.signature.as_ref().map_or(Value::Null, |signed| {
+            let mut signature = Map::new();
+            signature.insert("key_id".to_string(), bytes(&signed.key_id));
+            signature.insert("signature".to_string(), bytes(&signed.signature));
+            Value::Object(signature)
+        });
+        members.insert("signature".to_string(), signature);
+        if let Some(policy) = &self.policy {
+            members.insert("policy".to_string(), bytes(&policy.to_bytes()));
+        }
+        members
+    }
+
+    fn from_members(members: &Map<String, Value>) -> Result<Self, CommitError> {
+        let policy = match members.get("policy") {
+            Some(value) => match VerifyPolicy::decode(&to_bytes(value)?)? {
+                (policy, []) => Some(policy),
+                _ => return Err(CommitError::MalformedEncoding),
+            },
+            None => None,
+        };
+        let names = ["commitment_id", "local_m", "local_r", "params_digest", "policy", "signature"];
+        let names: Vec<&str> = names.into_iter().filter(|&name| name != "policy" || policy.is_some()).collect();
+        expect_members(members, &names)?;
+        let signature = match members.get("signature") {
+            Some(Value::Null) => None,
+            Some(Value::Object(signed)) => {
+                expect_members(signed, &["key_id", "signature"])?;
+                Some(EnvelopeSignature { key_id: get_bytes(signed, "key_id")?, signature: get_bytes(signed, "signature")? })
+            }
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        Ok(OpeningEnvelope {
+            commitment_id: get_bytes(members, "commitment_id")?,
+            params_digest: get_bytes(members, "params_digest")?,
+            local_m: get_numbers(members, "local_m")?,
+            local_r: get_numbers(members, "local_r")?,
+            signature,
+            policy,
+        })
+    }
+}
+
+// The `rounds` member of a proof on the binary engine.
+fn binary_rounds(rounds: &[BinaryRound]) -> Map<String, Value> {
+    let rounds = rounds.iter().map(|round| {
+        let mut members = Map::new();
+        members.insert("commitments".to_string(), Value::Array(round.commitments.iter().map(|c| bytes(c)).collect()));
+        members.insert("response".to_string(), Value::Object(binary_response(&round.response)));
+        Value::Object(members)
+    });
+    let mut members = Map::new();
+    members.insert("rounds".to_string(), Value::Array(rounds.collect()));
+    members
+}
+
+fn parse_binary_rounds(members: &Map<String, Value>) -> Result<Vec<BinaryRound>, CommitError> {
+    expect_members(members, &["rounds"])?;
+    get_objects(members, "rounds")?
+        .into_iter()
+        .map(|round| {
+            expect_members(round, &["commitments", "response"])?;
+            let commitments = match round.get("commitments") {
+                Some(Value::Array(items)) => items.iter().map(to_bytes).collect::<Result<_, _>>()?,
+                _ => return Err(CommitError::MalformedEncoding),
+            };
+            let response = match round.get("response") {
+                Some(Value::Object(response)) => parse_binary_response(response)?,
+                _ => return Err(CommitError::MalformedEncoding),
+            };
+            Ok(BinaryRound { commitments, response })
+        })
+        .collect()
+}
+
+fn binary_response(response: &BinaryResponse) -> Map<String, Value> {
+    let mut members = Map::new();
+    let kind = match response {
+        BinaryResponse::Permuted { permuted_witness, permuted_mask, mask_salt, shifted_salt } => {
+            members.insert("permuted_witness".to_string(), numbers(permuted_witness));
+            members.insert("permuted_mask".to_string(), numbers(permuted_mask));
+            members.insert("mask_salt".to_string(), bytes(mask_salt));
+            members.insert("shifted_salt".to_string(), bytes(shifted_salt));
+            "permuted"
+        }
+        BinaryResponse::Shifted { permutation, shifted_witness, shifted_free, point_salt, shifted_salt } => {
+            members.insert("permutation".to_string(), numbers(permutation));
+            members.insert("shifted_witness".to_string(), numbers(shifted_witness));
+            members.insert("shifted_free".to_string(), numbers(shifted_free));
+            members.insert("point_salt".to_string(), bytes(point_salt));
+            members.insert("shifted_salt".to_string(), bytes(shifted_salt));
+            "shifted"
+        }
+        BinaryResponse::Masked { permutation, mask, mask_free, point_salt, mask_salt } => {
+            members.insert("permutation".to_string(), numbers(permutation));
+            members.insert("mask".to_string(), numbers(mask));
+            members.insert("mask_free".to_string(), numbers(mask_free));
+            members.insert("point_salt".to_string(), bytes(point_salt));
+            members.insert("mask_salt".to_string(), bytes(mask_salt));
+            "masked"
+        }
+    };
+    members.insert("kind".to_string(), Value::String(kind.to_string()));
+    members
+}
+
+fn parse_binary_response(members: &Map<String, Value>) -> Result<BinaryResponse, CommitError> {
+    match members.get("kind").and_then(Value::as_str) {
+        Some("permuted") => {
+            expect_members(members, &["kind", "mask_salt", "permuted_mask", "permuted_witness", "shifted_salt"])?;
+            Ok(BinaryResponse::Permuted {
+                permuted_witness: get_numbers(members, "permuted_witness")?,
+                permuted_mask: get_numbers(members, "permuted_mask")?,
+                mask_salt: get_bytes(members, "mask_salt")?,
+                shifted_salt: get_bytes(members, "shifted_salt")?,
+            })
+        }
+        Some("shifted") => {
+            expect_members(members, &["kind", "permutation", "point_salt", "shifted_free", "shifted_salt", "shifted_witness"])?;
+            Ok(BinaryResponse::Shifted {
+                permutation: get_numbers(members, "permutation")?,
+                shifted_witness: get_numbers(members, "shifted_witness")?,
+                shifted_free: get_numbers(members, "shifted_free")?,
+                point_salt: get_bytes(members, "point_salt")?,
+                shifted_salt: get_bytes(members, "shifted_salt")?,
+            })
+        }
+        Some("masked") => {
+            expect_members(members, &["kind", "mask", "mask_free", "mask_salt", "permutation", "point_salt"])?;
+            Ok(BinaryResponse::Masked {
+                permutation: get_numbers(members, "permutation")?,
+                mask: get_numbers(members, "mask")?,
+                mask_free: get_numbers(members, "mask_free")?,
+                point_salt: get_bytes(members, "point_salt")?,
+                mask_salt: get_bytes(members, "mask_salt")?,
+            })
+        }
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
+
+// Write the value with the members of every object in byte order of their names and no
+// whitespace, independently of the key order `serde_json` is compiled with.
+fn write_canonical(value: &Value, text: &mut String) {
+    match value {
+        Value::Array(items) => {
+            text.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                write_canonical(item, text);
+            }
+            text.push(']');
+        }
+        Value::Object(members) => {
+            let mut sorted: Vec<(&String, &Value)> = members.iter().collect();
+            sorted.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            text.push('{');
+            for (index, (name, member)) in sorted.into_iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                text.push_str(&Value::String(name.clone()).to_string());
+                text.push(':');
+                write_canonical(member, text);
+            }
+            text.push('}');
+        }
+        scalar => text.push_str(&scalar.to_string()),
+    }
+}
+
+fn bytes(values: &[u8]) -> Value {
+    Value::String(grammar::encode_base64url(values))
+}
+
+fn numbers<T: Copy + Into<u64>>(values: &[T]) -> Value {
+    Value::Array(values.iter().map(|&x| Value::from(x.into())).collect())
+}
+
+// Check that the object has exactly the given members.
+fn expect_members(members: &Map<String, Value>, names: &[&str]) -> Result<(), CommitError> {
+    if members.len() != names.len() || names.iter().any(|name| !members.contains_key(*name)) {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok(())
+}
+
+fn to_bytes(value: &Value) -> Result<Vec<u8>, CommitError> {
+    value
+        .as_str()
+        .and_then(|text| grammar::decode_base64url(text).ok())
+        .ok_or(CommitError::MalformedEncoding)
+}
+
+fn get_bytes(members: &Map<String, Value>, name: &str) -> Result<Vec<u8>, CommitError> {
+    to_bytes(members.get(name).ok_or(CommitError::MalformedEncoding)?)
+}
+
+fn get_numbers<T: TryFrom<u64>>(members: &Map<String, Value>, name: &str) -> Result<Vec<T>, CommitError> {
+    match members.get(name) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_u64().and_then(|x| T::try_from(x).ok()).ok_or(CommitError::MalformedEncoding))
+            .collect(),
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
+
+fn get_objects<'a>(members: &'a Map<String, Value>, name: &str) -> Result<Vec<&'a Map<String, Value>>, CommitError> {
+    match members.get(name) {
+        Some(Value::Array(items)) => items.iter().map(|item| item.as_object().ok_or(CommitError::MalformedEncoding)).collect(),
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
@@ -0,0 +1,413 @@
+//! This module splits the inputs of a commitment into its public and its secret part.
+//!
+//! A `CommitmentKey` holds the `(A, B)` matrices, which are published with the commitments and
+//! shared by everyone verifying them. An `Opening` holds the message and randomness, which the
+//! prover keeps until the reveal. A `RevelerCommit` converts into and from the pair with
+//! `RevelerCommit::split` and `RevelerCommit::from_key_and_opening`.
+//!
+//! `CommitmentKey::setup` samples the matrices once, and the key then commits to any number of
+//! openings with `CommitmentKey::commit` and checks them with `CommitmentKey::verify`, borrowing
+//! the matrices instead of moving a copy into a `RevelerCommit` for every commitment.
+//! `CommitmentKey::commit_batch` commits to a whole batch, splitting the rows of all points across
+//! worker threads. Two commitments are under the same parameters exactly when their keys are
+//! equal, or their `CommitmentKey::digest`s.
+//!
+//! A key created with `CommitmentKey::from_seed` is stored and shipped as its 32-byte seed instead
+//! of the two `LOCAL_N × LOCAL_N` matrices, about 1 MB: `to_bytes` and `Serialize` write only the
+//! seed, and decoding expands the matrices again with `seed::Seed::params`, a BlueHash XOF under
+//! fixed domains, as Kyber and Dilithium expand their matrix A. Everyone holding the seed then
+//! commits and verifies under the same matrices.
+//!
+//! The commitment is additively homomorphic: `RevelerResult::add` sums two commitments under the
+//! same key and `Opening::add` sums their openings, so aggregates such as tallies are opened
+//! without revealing the individual messages. The randomness of a sum is the sum of the randomness,
+//! so `CommitmentKey::verify_with_policy` checks the opening of a sum of `k` commitments with
+//! `policy::VerifyPolicy::for_sum(k)`. `RevelerResult::scale` and `Opening::scale` multiply
+//! them by a public scalar, for linear-combination checks and sigma protocols.
+//! `CommitmentKey::rerandomize` adds a fresh commitment to zero, so a commitment can be passed on
+//! unlinkably, e.g. through a mix-net, and still opens to the same message.
+//! `CommitmentKey::update_coordinate` changes one coordinate of the message by adding its column
+//! of A to the point, in O(`LOCAL_N`) instead of recomputing the commitment.
+//!
+//! The key implements `Serialize` like the other public types of the crate. The opening does not
+//! and its `Debug` output is redacted, so it is never written out by a `#[derive(Serialize)]` or
+//! a log line by accident: encode it explicitly with `Opening::to_bytes` when it is revealed.
+
+use std::fmt;
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::HashPolicy;
+#[cfg(feature = "prover")]
+use crate::config::{BatchConfig, CommitConfig};
+#[cfg(feature = "prover")]
+use crate::{bind_with_config, compute_point, compute_points_batch};
+use crate::policy::VerifyPolicy;
+use crate::seed::{Seed, SEED_LEN};
+use crate::fft::{LOCAL_N, LOCAL_Q, MESSAGE_LEN};
+use crate::{add_mod, bind_point, limits, params, scale_mod, scheme, utils, RevelerCommit, RevelerResult};
+
+/// Public matrices commitments are computed under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "KeyRepr", from = "KeyRepr")]
+pub struct CommitmentKey {
+    local_a: Vec<Vec<u64>>,       // The matrix A applied to the message
+    local_b: Vec<Vec<u64>>,       // The matrix B applied to the randomness
+    seed: Option<[u8; SEED_LEN]>, // The seed the matrices are expanded from, if any
+}
+
+// Serialized form of a key: the seed when there is one, the matrices otherwise.
+#[derive(Serialize, Deserialize)]
+enum KeyRepr {
+    Matrices {
+        #[serde(deserialize_with = "limits::deserialize_matrix")]
+        local_a: Vec<Vec<u64>>,
+        #[serde(deserialize_with = "limits::deserialize_matrix")]
+        local_b: Vec<Vec<u64>>,
+    },
+    Seed([u8; SEED_LEN]),
+}
+
+/// Secret message and randomness of a commitment.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Opening {
+    local_m: Vec<u64>, // The message vector
+    local_r: Vec<u64>, // The randomness vector
+}
+
+impl CommitmentKey {
+    /// Creates a key from its matrices.
+    pub fn new(local_a: Vec<Vec<u64>>, local_b: Vec<Vec<u64>>) -> Self {
+        CommitmentKey { local_a, local_b, seed: None }
+    }
+
+    /// Creates a key whose matrices are expanded from `seed`.
+    ///
+    /// The key is encoded as the seed alone, and the matrices are those of `Seed::params`.
+    pub fn from_seed(seed: &Seed) -> Self {
+        let (local_a, local_b) = seed.params();
+        CommitmentKey { local_a, local_b, seed: Some(*seed.as_bytes()) }
+    }
+
+    /// Returns the seed of the key, if it was created with `from_seed`.
+    pub fn seed(&self) -> Option<Seed> {
+        self.seed.map(Seed::from_bytes)
+    }
+
+    /// Samples a fresh key from `rng`.
+    ///
+    /// # Parameters
+    /// - `rng`: The random number generator, e.g. `rand::thread_rng()` or a seeded one for replay.
+    ///
+    /// # Returns
+    /// The `CommitmentKey` with uniform `LOCAL_N × LOCAL_N` matrices modulo `LOCAL_Q`.
+    #[cfg(feature = "prover")]
+    pub fn setup<R: Rng>(rng: &mut R) -> Self {
+        let (local_a, local_b) = utils::generate_params_with_rng(rng);
+        CommitmentKey::new(local_a, local_b)
+    }
+
+    /// Commits to the message `local_m` with the randomness `local_r` under this key.
+    ///
+    /// # Returns
+    /// The `RevelerResult`, as `RevelerCommit::commit` with the same inputs.
+    #[cfg(feature = "prover")]
+    pub fn commit(&self, local_m: &[u64], local_r: &[u64]) -> Result<RevelerResult, CommitError> {
+        self.commit_with_config(local_m, local_r, &CommitConfig::default())
+    }
+
+    /// Commits to the message `local_m` with the randomness `local_r` using the given
+    /// configuration, as `RevelerCommit::commit_with_config`.
+    #[cfg(feature = "prover")]
+    pub fn commit_with_config(&self, local_m: &[u64], local_r: &[u64], config: &CommitConfig) -> Result<RevelerResult, CommitError> {
+        let commitment_point = compute_point(&self.local_a, &self.local_b, local_m, local_r, config)?;
+        Ok(bind_with_config(commitment_point, config))
+    }
+
+    /// Commits to an `Opening` under this key.
+    #[cfg(feature = "prover")]
+    pub fn commit_opening(&self, opening: &Opening) -> Result<RevelerResult, CommitError> {
+        self.commit(&opening.local_m, &opening.local_r)
+    }
+
+    /// Re-randomizes a commitment, so it opens to the same message but is unlinkable to the original.
+    ///
+    /// Adds the commitment to `(0, r')` for fresh ternary randomness `r'` with
+    /// `RevelerResult::add`, and `r'` to the randomness of the opening. The new commitment is a
+    /// fresh LWE sample away from the old one, so it cannot be linked to it without breaking the
+    /// hiding of the commitment. The randomness of the opening grows by up to
+    /// `policy::RANDOMNESS_BOUND` per coordinate: the opening of a commitment re-randomized `k`
+    /// times is checked with `policy::VerifyPolicy::for_sum(k + 1)`. The inputs are not checked: a
+    /// wrong opening stays wrong.
+    ///
+    /// # Returns
+    /// The new commitment and its opening, or the `CommitError` of computing the commitment to
+    /// `(0, r')`.
+    #[cfg(feature = "prover")]
+    pub fn rerandomize<R: Rng>(&self, commitment: &RevelerResult, opening: &Opening, rng: &mut R) -> Result<(RevelerResult, Opening), CommitError> {
+        let delta = Opening::new(Vec::new(), utils::generate_randomness(rng));
+        let shift = self.commit_opening(&delta)?;
+        Ok((commitment.add(&shift), opening.add(&delta)))
+    }
+
+    /// Updates a single coordinate of the committed message without recomputing the commitment.
+    ///
+    /// Changing `m[index]` by `d` moves the point by `d` times the column `A[:, index]` of the map
+    /// from the message to the point, so every update costs O(`LOCAL_N`) instead of a full
+    /// commitment. The inputs are not checked: a wrong opening stays wrong.
+    ///
+    /// # Parameters
+    /// - `commitment`: The commitment to update.
+    /// - `opening`: Its opening, which may hold fewer than `MESSAGE_LEN` coordinates.
+    /// - `index`: The coordinate of the message to change, below `MESSAGE_LEN`.
+    /// - `new_value`: The new value of the coordinate, reduced modulo `LOCAL_Q`.
+    ///
+    /// # Returns
+    /// The updated commitment and opening, or `CommitError::ComputationError` if `index` is out of
+    /// range or the point does not have `LOCAL_N` coordinates.
+    pub fn update_coordinate(&self, commitment: &RevelerResult, opening: &Opening, index: usize, new_value: u64) -> Result<(RevelerResult, Opening), CommitError> {
+        let point = commitment.commitment_point();
+        if index >= MESSAGE_LEN || point.len() != LOCAL_N {
+            return Err(CommitError::ComputationError);
+        }
+        let mut local_m = opening.local_m.clone();
+        if local_m.len() <= index {
+            local_m.resize(index + 1, 0);
+        }
+        let coordinate = local_m.get_mut(index).ok_or(CommitError::ComputationError)?;
+        let (old_value, new_value) = (*coordinate % LOCAL_Q, new_value % LOCAL_Q);
+        *coordinate = new_value;
+
+        let delta = (new_value + LOCAL_Q - old_value) % LOCAL_Q;
+        let column = self.local_a.iter().take(LOCAL_N).map(|row| row.get(index).map_or(0, |&a| a % LOCAL_Q));
+        let point: Vec<u64> = point.iter().zip(column).map(|(&x, c)| (x + c * delta % LOCAL_Q) % LOCAL_Q).collect();
+        Ok((bind_point(point, HashPolicy::default()), Opening::new(local_m, opening.local_r.clone())))
+    }
+
+    /// Commits to many openings under this key.
+    ///
+    /// The rows of the points are split into chunks taken by worker threads, sized by
+    /// `BatchConfig::auto`.
+    ///
+    /// # Returns
+    /// The commitments, one per opening and in the same order, equal to those of `commit_opening`.
+    #[cfg(feature = "prover")]
+    pub fn commit_batch(&self, openings: &[Opening]) -> Result<Vec<RevelerResult>, CommitError> {
+        self.commit_batch_with_config(openings, &BatchConfig::default())
+    }
+
+    /// Commits to many openings under this key, splitting the work as set by `batch`.
+    #[cfg(feature = "prover")]
+    pub fn commit_batch_with_config(&self, openings: &[Opening], batch: &BatchConfig) -> Result<Vec<RevelerResult>, CommitError> {
+        let pairs: Vec<(&[u64], &[u64])> = openings.iter().map(|opening| (opening.local_m.as_slice(), opening.local_r.as_slice())).collect();
+        let points = compute_points_batch(&self.local_a, &self.local_b, &pairs, batch)?;
+        Ok(points.into_iter().map(|point| bind_with_config(point, &batch.commit)).collect())
+    }
+
+    /// Checks that `opening` opens `commitment` under this key, as `RevelerCommit::verify_opening`.
+    pub fn verify(&self, commitment: &RevelerResult, opening: &Opening) -> Result<bool, CommitError> {
+        RevelerCommit::verify_opening(self.as_pair(), commitment, &opening.local_m, &opening.local_r)
+    }
+
+    /// Checks that `opening` opens `commitment` under this key and `policy`, as
+    /// `VerifyPolicy::verify_opening`.
+    pub fn verify_with_policy(&self, commitment: &RevelerResult, opening: &Opening, policy: &VerifyPolicy) -> Result<bool, CommitError> {
+        policy.verify_opening(self.as_pair(), commitment, &opening.local_m, &opening.local_r)
+    }
+
+    /// Checks that `opening` reproduces `commitment` under this key, whatever the norm of its
+    /// randomness, for tests building openings through the identity block of `[B | I]`.
+    #[cfg(all(test, feature = "prover"))]
+    pub(crate) fn reproduces(&self, commitment: &RevelerResult, opening: &Opening) -> Result<bool, CommitError> {
+        let policy = VerifyPolicy { randomness_bound: None, ..VerifyPolicy::new() };
+        self.verify_with_policy(commitment, opening, &policy)
+    }
+
+    /// Returns the matrix A.
+    pub fn local_a(&self) -> &[Vec<u64>] {
+        &self.local_a
+    }
+
+    /// Returns the matrix B.
+    pub fn local_b(&self) -> &[Vec<u64>] {
+        &self.local_b
+    }
+
+    /// Returns the matrices as the `(A, B)` pair taken by the functions of the crate.
+    pub fn as_pair(&self) -> (&[Vec<u64>], &[Vec<u64>]) {
+        (&self.local_a, &self.local_b)
+    }
+
+    /// Returns the `params::params_digest` of the matrices.
+    pub fn digest(&self) -> Vec<u8> {
+        params::params_digest(self.as_pair())
+    }
+
+    /// Encodes the key as the scheme header followed by either a `0` byte and the encoded
+    /// matrices A and B, or a `1` byte and the seed for keys created with `from_seed`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        match &self.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(seed);
+            }
+            None => {
+                bytes.push(0);
+                bytes.extend(utils::encode_matrix(&self.local_a));
+                bytes.extend(utils::encode_matrix(&self.local_b));
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a key written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `CommitmentKey`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        match rest.split_first() {
+            Some((0, rest)) => {
+                let (local_a, rest) = utils::decode_matrix(rest)?;
+                let (local_b, rest) = utils::decode_matrix(rest)?;
+                if !rest.is_empty() {
+                    return Err(CommitError::MalformedEncoding);
+                }
+                Ok(CommitmentKey::new(local_a, local_b))
+            }
+            Some((1, rest)) => {
+                let seed: [u8; SEED_LEN] = rest.try_into().map_err(|_| CommitError::MalformedEncoding)?;
+                Ok(CommitmentKey::from_seed(&Seed::from_bytes(seed)))
+            }
+            _ => Err(CommitError::MalformedEncoding),
+        }
+    }
+}
+
+// 两个密钥的矩阵相同即视为相同，与是否由种子生成无关
+impl PartialEq for CommitmentKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.local_a == other.local_a && self.local_b == other.local_b
+    }
+}
+
+impl Eq for CommitmentKey {}
+
+impl From<CommitmentKey> for KeyRepr {
+    fn from(key: CommitmentKey) -> Self {
+        match key.seed {
+            Some(seed) => KeyRepr::Seed(seed),
+            None => KeyRepr::Matrices { local_a: key.local_a, local_b: key.local_b },
+        }
+    }
+}
+
+impl From<KeyRepr> for CommitmentKey {
+    fn from(repr: KeyRepr) -> Self {
+        match repr {
+            KeyRepr::Matrices { local_a, local_b } => CommitmentKey::new(local_a, local_b),
+            KeyRepr::Seed(seed) => CommitmentKey::from_seed(&Seed::from_bytes(seed)),
+        }
+    }
+}
+
+impl Opening {
+    /// Creates an opening from its message and randomness.
+    pub fn new(local_m: Vec<u64>, local_r: Vec<u64>) -> Self {
+        Opening { local_m, local_r }
+    }
+
+    /// Returns the message vector.
+    pub fn local_m(&self) -> &[u64] {
+        &self.local_m
+    }
+
+    /// Returns the randomness vector.
+    pub fn local_r(&self) -> &[u64] {
+        &self.local_r
+    }
+
+    /// Adds two openings coordinate-wise modulo `LOCAL_Q`, the opening of the sum of their
+    /// commitments with `RevelerResult::add`.
+    pub fn add(&self, other: &Opening) -> Opening {
+        Opening {
+            local_m: add_mod(&self.local_m, &other.local_m, LOCAL_Q),
+            local_r: add_mod(&self.local_r, &other.local_r, LOCAL_Q),
+        }
+    }
+
+    /// Multiplies the opening by the scalar `k` modulo `LOCAL_Q`, the opening of the commitment
+    /// scaled with `RevelerResult::scale`.
+    pub fn scale(&self, k: u64) -> Opening {
+        Opening { local_m: scale_mod(&self.local_m, k, LOCAL_Q), local_r: scale_mod(&self.local_r, k, LOCAL_Q) }
+    }
+
+    /// Consumes the opening, returning the message and the randomness.
+    pub fn into_parts(self) -> (Vec<u64>, Vec<u64>) {
+        (self.local_m, self.local_r)
+    }
+
+    /// Encodes the opening as the scheme header and the length-prefixed message and randomness.
+    ///
+    /// The output reveals the committed message: only call this when the opening is revealed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend(utils::encode_vector(&self.local_m));
+        bytes.extend(utils::encode_vector(&self.local_r));
+        bytes
+    }
+
+    /// Decodes an opening written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `Opening`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (local_m, rest) = utils::decode_vector(rest)?;
+        let (local_r, rest) = utils::decode_vector(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(Opening { local_m, local_r })
+    }
+}
+
+// 不在调试输出中泄露消息和随机数
+impl fmt::Debug for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Opening(..)")
+    }
+}
+
+impl RevelerCommit {
+    /// Creates a `RevelerCommit` from a key and an opening.
+    pub fn from_key_and_opening(key: CommitmentKey, opening: Opening) -> Self {
+        RevelerCommit::new(key.local_a, key.local_b, opening.local_m, opening.local_r)
+    }
+
+    /// Consumes the `RevelerCommit`, returning its public key and its secret opening.
+    pub fn split(self) -> (CommitmentKey, Opening) {
+        let (local_a, local_b, local_m, local_r) = self.into_parts();
+        (CommitmentKey::new(local_a, local_b), Opening { local_m, local_r })
+    }
+}
+
+impl From<(CommitmentKey, Opening)> for RevelerCommit {
+    fn from((key, opening): (CommitmentKey, Opening)) -> Self {
+        RevelerCommit::from_key_and_opening(key, opening)
+    }
+}
+
+impl From<RevelerCommit> for (CommitmentKey, Opening) {
+    fn from(commit: RevelerCommit) -> Self {
+        commit.split()
+    }
+}
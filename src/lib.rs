@@ -1,26 +1,187 @@
-//! This is a library for cryptographic commitment schemes and verification using optimized FFT and hashing functions.
+//! This is a library for cryptographic commitment schemes and verification using lattice arithmetic and hashing functions.
 //!
 //! This module provides the functions for commitment generation and verification.
 //! It provides the following functions:
 //! - `commit`: to generate a cryptographic commitment.
 //! - `verify`: to verify a commitment.
+//! - `verify_opening`: to verify a commitment against its message and randomness.
+//! - `hash_commitment_point`: to recompute the hash of an existing commitment point.
+//! - `commit_point` and `bind_point`: the arithmetic and the binding step of `commit`, separately.
+//!
 //! Function enhances the verification process by including a random challenge.
 //!
+//! A commitment to the message `m` with the randomness `r` is the point `A·m + [B | I]·r mod q`
+//! and its hash, see `commit_point`. The randomness of a fresh commitment is ternary, so the point
+//! hides the message as long as `[B | I]·r` is indistinguishable from uniform, an LWE instance with
+//! ternary secret and error. The message holds at most `fft::MESSAGE_LEN` coordinates, few enough
+//! that two openings of the same point with randomness within `policy::RANDOMNESS_BOUND` exist
+//! only with probability below `2^-1800` over the matrices: `verify_opening` rejects randomness
+//! past the bound, which makes the commitment binding. Sums of commitments open with larger
+//! randomness, which `policy::VerifyPolicy::for_sum` admits; binding then weakens with the number
+//! of summands, see there.
+//!
+//! Commitment generation is compiled with the default `prover` feature. Building with
+//! `default-features = false, features = ["verify-only"]` keeps only the verification and encoding
+//! paths, without the RNG, parameter generation, or worker threads: `verify-only` fails the
+//! build if any crate in the dependency graph turns `prover` back on.
+//!
+//! The fields of `RevelerCommit` and `RevelerResult` are private since 0.2: use the accessors,
+//! `into_parts` or the `From` conversions, or enable the `compat` feature for the old layout.
+//! `RevelerCommit::split` separates the public `key::CommitmentKey` from the secret
+//! `key::Opening`, which is not serializable, so the matrices are published without the opening.
+//!
+//! With the `strict-no-panic` feature, clippy rejects `unwrap`, `expect`, `panic!`,
+//! `unreachable!` and panicking indexing or slicing in the crate, so the commit, verify and decoding
+//! paths report every failure as a `CommitError` instead of aborting the process. Run
+//! `cargo clippy --features strict-no-panic` to audit a build. The `fuzz`, `conformance` and
+//! `protocol::simulate` harnesses are exempt. The `#[no_panic]` tests of this module check at link
+//! time that the commit, verify and decode entry points contain no panic path once optimized; they
+//! run with `cargo test --release --features strict-no-panic`. Spawning a worker thread panics if
+//! the system cannot create it, so commit and verify with a configuration that computes the point
+//! on the calling thread, `CommitConfig::new().with_small_message_limit(usize::MAX)`, through
+//! `key::CommitmentKey::commit_with_config` and `policy::VerifyPolicy::verify_opening_with_config`.
+//!
+//! With the `fast-digest` feature a `RevelerResult` can also carry a BLAKE3 digest of its point,
+//! set with `CommitConfig::with_fast_digest` or `RevelerResult::with_fast_digest`, for databases
+//! that index or deduplicate commitments cheaply. It comes in addition to the binding hash, which
+//! is unchanged: the digest never replaces it in verification, and results are equal whenever
+//! their points and binding hashes are, with or without a digest. A decoded digest that does not
+//! match the point is dropped.
+//!
+//! The test-only `fault-injection` feature adds the `faults` module, whose hooks make `commit`
+//! fail as a panicking worker, a truncating hasher or a corrupted chunk would, so applications can
+//! test their handling of these failures.
+//!
+//! The crate contains no `unsafe` code unless the opt-in `fast-unsafe` feature is enabled, which
+//! elides the bounds checks of the terms of every coordinate of the commitment point after
+//! validating the dimensions of the rows up front. It only applies to moduli above
+//! `arith::NARROW_MAX_Q`, whose `u32` kernel has no bounds checks to elide; the point is the same
+//! with or without it.
+//!
 //! You can check repository from github: https://github.com/blueokanna/BlueHash for more details.
 
+#![cfg_attr(not(feature = "fast-unsafe"), forbid(unsafe_code))]
+#![cfg_attr(
+    feature = "strict-no-panic",
+    deny(clippy::unwrap_used, clippy::expect_used, clippy::panic, clippy::unreachable, clippy::indexing_slicing)
+)]
+
+#[cfg(all(feature = "verify-only", feature = "prover"))]
+compile_error!("`verify-only` excludes commitment generation: disable the `prover` feature and every feature enabling it");
+
+pub mod amounts;
+pub mod archive;
+pub mod batch;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod arith;
+pub mod audit;
+pub mod backend;
+pub mod binary;
+pub mod bloom;
+pub mod clock;
 pub mod fft;
+pub mod hierarchy;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod key;
+pub mod limits;
 pub mod utils;
 pub mod commit_error;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "conformance")]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::indexing_slicing))]
+pub mod conformance;
+pub mod cache;
+#[cfg(feature = "committee")]
+pub mod committee;
+pub mod config;
+pub mod counter;
+pub mod delta;
+pub mod disclosure;
+pub mod envelope;
+#[cfg(feature = "prover")]
+pub mod farm;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod grammar;
+#[cfg(feature = "fuzzing")]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::expect_used))]
+pub mod fuzz;
+pub mod linking;
+pub mod map;
+pub mod message;
+pub mod params;
+#[cfg(feature = "curve")]
+pub mod pedersen;
+pub mod policy;
+pub mod pow;
+pub mod proofs;
+pub mod sampling;
+pub mod protocol;
+pub mod provenance;
+pub mod range;
+pub mod receipt;
+pub mod revocation;
+pub mod scheme;
+pub mod seed;
+pub mod sharing;
+pub mod stream;
+pub mod tenant;
+pub mod timestamp;
+pub mod transcript;
+pub mod transition;
+pub mod vector;
+pub mod voting;
+#[cfg(feature = "timelock")]
+pub mod timelock;
 
+#[cfg(feature = "prover")]
 use std::{cmp, thread};
+#[cfg(feature = "prover")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use serde::{Serialize, Deserialize};
+#[cfg(feature = "prover")]
 use rand::Rng;
 use crate::commit_error::CommitError;
+use crate::config::{BatchConfig, CommitConfig, HashPolicy};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg(feature = "fast-digest")]
+pub(crate) const FAST_DIGEST_DOMAIN: &[u8] = b"reveler/fast-digest";
+
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
+#[serde(from = "RevelerResultFields")]
 pub struct RevelerResult {
-    pub commitment_point: Vec<u64>, // The commitment point computed from input parameters
-    pub commitment_hash: Vec<u8>,   // The hash of the commitment point
+    commitment_point: Vec<u64>, // The commitment point computed from input parameters
+    commitment_hash: Vec<u8>,   // The hash of the commitment point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fast_digest: Option<Vec<u8>>, // The BLAKE3 digest of the point for indexing, if computed
+}
+
+// 反序列化得到的快速摘要不可信，只有与点重新计算的摘要一致时才保留
+#[derive(Deserialize)]
+struct RevelerResultFields {
+    #[serde(deserialize_with = "limits::deserialize_vector")]
+    commitment_point: Vec<u64>,
+    commitment_hash: Vec<u8>,
+    #[serde(default)]
+    fast_digest: Option<Vec<u8>>,
+}
+
+impl From<RevelerResultFields> for RevelerResult {
+    fn from(fields: RevelerResultFields) -> Self {
+        RevelerResult::new(fields.commitment_point, fields.commitment_hash).with_checked_fast_digest(fields.fast_digest)
+    }
+}
+
+// 快速摘要只是索引用的附加信息，不参与相等性比较
+impl PartialEq for RevelerResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.commitment_point == other.commitment_point && self.commitment_hash == other.commitment_hash
+    }
 }
 
 impl RevelerResult {
@@ -28,17 +189,145 @@ impl RevelerResult {
         RevelerResult {
             commitment_point,
             commitment_hash,
+            fast_digest: None,
+        }
+    }
+
+    /// Returns the commitment point.
+    pub fn commitment_point(&self) -> &[u64] {
+        &self.commitment_point
+    }
+
+    /// Returns the hash of the commitment point.
+    pub fn commitment_hash(&self) -> &[u8] {
+        &self.commitment_hash
+    }
+
+    /// Returns the fast digest of the commitment point, if it was computed.
+    ///
+    /// The digest is meant for indexing and deduplication only: it is not the binding hash, and a
+    /// store keyed by it still checks `commitment_hash` before trusting a match. A digest decoded
+    /// by `from_bytes` or serde is kept only if it is the digest of the point, so it always is;
+    /// without the `fast-digest` feature it cannot be checked and is dropped.
+    pub fn fast_digest(&self) -> Option<&[u8]> {
+        self.fast_digest.as_deref()
+    }
+
+    /// Attaches the fast digest of the commitment point, see `fast_digest`.
+    #[cfg(feature = "fast-digest")]
+    pub fn with_fast_digest(mut self) -> Self {
+        self.fast_digest = Some(fast_digest(&self.commitment_point));
+        self
+    }
+
+    // Attach a decoded `digest` if it is the fast digest of the point.
+    #[cfg(feature = "fast-digest")]
+    fn with_checked_fast_digest(mut self, digest: Option<Vec<u8>>) -> Self {
+        self.fast_digest = digest.filter(|digest| *digest == fast_digest(&self.commitment_point));
+        self
+    }
+
+    #[cfg(not(feature = "fast-digest"))]
+    fn with_checked_fast_digest(self, _digest: Option<Vec<u8>>) -> Self {
+        self
+    }
+
+    /// Commits to a random message under randomly generated parameters.
+    ///
+    /// This replaces the `Default` implementation of 0.1, which could not report a failure of the
+    /// computation.
+    ///
+    /// # Returns
+    /// The `RevelerResult`, or the `CommitError` of the computation.
+    #[cfg(feature = "prover")]
+    pub fn random() -> Result<Self, CommitError> {
+        let (a, b) = utils::generate_params();
+
+        let mut rng = rand::thread_rng();
+        let m: Vec<u64> = (0..fft::MESSAGE_LEN).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+        let r = utils::generate_randomness(&mut rng);
+
+        RevelerCommit::new(a, b, m, r).commit()
+    }
+
+    /// Consumes the result, returning the commitment point and its hash.
+    pub fn into_parts(self) -> (Vec<u64>, Vec<u8>) {
+        (self.commitment_point, self.commitment_hash)
+    }
+
+    /// Adds two commitments homomorphically.
+    ///
+    /// The commitment is linear, so if `self` opens to `(m1, r1)` and `other` to `(m2, r2)` under
+    /// the same parameters, the sum opens to `(m1 + m2, r1 + r2) mod q`, see `key::Opening::add`.
+    ///
+    /// # Returns
+    /// The `RevelerResult` of the coordinate-wise sum of the points modulo `LOCAL_Q`, with its hash.
+    pub fn add(&self, other: &RevelerResult) -> RevelerResult {
+        bind_point(add_mod(&self.commitment_point, &other.commitment_point, fft::LOCAL_Q), HashPolicy::default())
+    }
+
+    /// Multiplies a commitment by the scalar `k` homomorphically.
+    ///
+    /// If `self` opens to `(m, r)`, the result opens to `(k·m, k·r) mod q`, see
+    /// `key::Opening::scale`. With `add` this yields commitments to linear combinations.
+    ///
+    /// # Returns
+    /// The `RevelerResult` of the point multiplied by `k` modulo `LOCAL_Q`, with its hash.
+    pub fn scale(&self, k: u64) -> RevelerResult {
+        bind_point(scale_mod(&self.commitment_point, k, fft::LOCAL_Q), HashPolicy::default())
+    }
+
+    /// Encodes the result as the scheme header, the length-prefixed commitment point and the hash,
+    /// followed by the length-prefixed fast digest if the result carries one.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend(utils::encode_vector(&self.commitment_point));
+        bytes.extend(utils::encode_bytes(&self.commitment_hash));
+        if let Some(digest) = &self.fast_digest {
+            bytes.extend(utils::encode_bytes(digest));
+        }
+        bytes
+    }
+
+    /// Decodes a result written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `RevelerResult`, without the fast digest if it does not match the point,
+    /// `CommitError::UnsupportedScheme` if the header names an unregistered scheme,
+    /// `CommitError::LimitExceeded` if the point is longer than the installed
+    /// `limits::DecodeLimits` allow, or `CommitError::MalformedEncoding` if the input is
+    /// truncated, carries trailing bytes, or announces more elements than it contains.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (commitment_point, rest) = utils::decode_vector(rest)?;
+        let (commitment_hash, rest) = utils::decode_bytes(rest)?;
+        let result = RevelerResult::new(commitment_point, commitment_hash);
+        if rest.is_empty() {
+            return Ok(result);
+        }
+        let (digest, rest) = utils::decode_bytes(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
         }
+        Ok(result.with_checked_fast_digest(Some(digest)))
     }
 }
 
+/// The `(A, B, m, r)` inputs of a `RevelerCommit`.
+pub type CommitParts = (Vec<Vec<u64>>, Vec<Vec<u64>>, Vec<u64>, Vec<u64>);
+
 /// Struct to hold the commitment point and its corresponding hash.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RevelerCommit {
-    pub local_a: Vec<Vec<u64>>,
-    pub local_b: Vec<Vec<u64>>,
-    pub local_m: Vec<u64>,
-    pub local_r: Vec<u64>,
+    #[serde(deserialize_with = "limits::deserialize_matrix")]
+    local_a: Vec<Vec<u64>>,
+    #[serde(deserialize_with = "limits::deserialize_matrix")]
+    local_b: Vec<Vec<u64>>,
+    #[serde(deserialize_with = "limits::deserialize_vector")]
+    local_m: Vec<u64>,
+    #[serde(deserialize_with = "limits::deserialize_vector")]
+    local_r: Vec<u64>,
 }
 
 impl RevelerCommit {
@@ -59,6 +348,67 @@ impl RevelerCommit {
         }
     }
 
+    /// Returns the matrix `A`.
+    pub fn local_a(&self) -> &[Vec<u64>] {
+        &self.local_a
+    }
+
+    /// Returns the matrix `B`.
+    pub fn local_b(&self) -> &[Vec<u64>] {
+        &self.local_b
+    }
+
+    /// Returns the message `m`.
+    pub fn local_m(&self) -> &[u64] {
+        &self.local_m
+    }
+
+    /// Returns the randomness `r`.
+    pub fn local_r(&self) -> &[u64] {
+        &self.local_r
+    }
+
+    /// Consumes the commitment inputs, returning `(A, B, m, r)`.
+    pub fn into_parts(self) -> CommitParts {
+        (self.local_a, self.local_b, self.local_m, self.local_r)
+    }
+
+    /// Encodes the commitment inputs into a canonical byte representation.
+    ///
+    /// Two `RevelerCommit` values produce the same bytes if and only if they hold the same
+    /// matrices, message and randomness, which makes the encoding suitable as a cache key.
+    ///
+    /// # Returns
+    /// A vector of `u8` containing the scheme header and the length-prefixed `a`, `b`, `m` and `r`
+    /// parameters.
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend(utils::encode_matrix(&self.local_a));
+        bytes.extend(utils::encode_matrix(&self.local_b));
+        bytes.extend(utils::encode_vector(&self.local_m));
+        bytes.extend(utils::encode_vector(&self.local_r));
+        bytes
+    }
+
+    /// Decodes commitment inputs written by `to_canonical_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `RevelerCommit`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// canonical encoding.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (local_a, rest) = utils::decode_matrix(rest)?;
+        let (local_b, rest) = utils::decode_matrix(rest)?;
+        let (local_m, rest) = utils::decode_vector(rest)?;
+        let (local_r, rest) = utils::decode_vector(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(RevelerCommit::new(local_a, local_b, local_m, local_r))
+    }
+
     /// Generates a cryptographic commitment using parallel computation.
     ///
     /// # Parameters
@@ -69,87 +419,687 @@ impl RevelerCommit {
     ///
     /// # Returns
     /// A `RevelerCommit` containing the computed commitment point and its hash.
+    #[cfg(feature = "prover")]
     pub fn commit(&self) -> Result<RevelerResult, CommitError> {
-        let thread_count = utils::get_optimal_thread_count();
-        let chunk_size = (fft::LOCAL_N + thread_count - 1) / thread_count;
-
-        let mut thread_results = vec![vec![0u64; chunk_size]; thread_count];
-
-        let handles: Vec<_> = (0..thread_count).map(|thread_id| {
-            let start = thread_id * chunk_size;
-            let end = cmp::min((thread_id + 1) * chunk_size, fft::LOCAL_N);
-
-            let a_chunk = self.local_a[start..end].to_vec();
-            let b_chunk = self.local_b[start..end].to_vec();
-            let m = self.local_m.clone();
-            let r = self.local_r.clone();
-
-            thread::spawn(move || {
-                let mut result_chunk = vec![0u64; end - start];
-                for (i, (a_row, b_row)) in a_chunk.iter().zip(b_chunk.iter()).enumerate() {
-                    let m_res = fft::fft_matrix_multiply(a_row, &m);
-                    let r_res = fft::fft_matrix_multiply(b_row, &r);
-
-                    result_chunk[i] = m_res.iter().zip(r_res.iter())
-                        .fold(0u64, |acc, (&x, &y)| (acc + x + y) % fft::LOCAL_Q);
-                }
-                result_chunk
-            })
-        }).collect();
-
-        // 合并线程结果
-        for (thread_id, handle) in handles.into_iter().enumerate() {
-            thread_results[thread_id] = handle.join().unwrap_or_else(|_| vec![]);
-        }
+        self.commit_with_config(&CommitConfig::default())
+    }
 
-        let commitment_point: Vec<u64> = thread_results.into_iter().flatten().collect();
+    /// Generates a cryptographic commitment using the given configuration.
+    ///
+    /// When the message fits in the first `small_message_limit` coordinates, the commitment point
+    /// is computed inline, whatever the length of the randomness. Otherwise the rows are processed in
+    /// blocks of `block_rows`, distributed across worker threads unless the workload is smaller
+    /// than `inline_work_threshold`.
+    ///
+    /// # Parameters
+    /// - `config`: A reference to the `CommitConfig` selecting the computation strategy.
+    ///
+    /// # Returns
+    /// A `RevelerResult` containing the computed commitment point and its hash.
+    #[cfg(feature = "prover")]
+    pub fn commit_with_config(&self, config: &CommitConfig) -> Result<RevelerResult, CommitError> {
+        let commitment_point = compute_point(&self.local_a, &self.local_b, &self.local_m, &self.local_r, config)?;
 
         // 计算哈希
-        let commitment_hash = utils::hash_to_commitment(
-            &commitment_point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>(),
-        );
-
-        Ok(RevelerResult::new(commitment_point, commitment_hash))
+        Ok(bind_with_config(commitment_point, config))
     }
 
     /// Verifies the validity of a given cryptographic commitment.
     ///
+    /// This only checks that the hash matches the commitment point, so any self-consistent point
+    /// passes: use `verify_opening` to check a commitment against its message and randomness.
+    /// With the `fast-digest` feature, a fast digest carried by the result must match the point
+    /// too.
+    ///
     /// # Parameters
     /// - `commitment`: A reference to a `RevelerCommit` instance to be verified.
     ///
     /// # Returns
     /// `true` if the commitment is valid, otherwise `false`.
     pub fn verify(commitment: &RevelerResult) -> bool {
-        let recomputed_commitment_hash = utils::hash_to_commitment(
-            &commitment.commitment_point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>(),
-        );
+        #[cfg(feature = "fast-digest")]
+        if commitment.fast_digest.as_ref().is_some_and(|digest| *digest != fast_digest(&commitment.commitment_point)) {
+            return false;
+        }
+        hash_commitment_point(&commitment.commitment_point, HashPolicy::default()) == commitment.commitment_hash
+    }
 
-        recomputed_commitment_hash == commitment.commitment_hash
+    /// Verifies that `m` and `r` open a commitment under the given parameters.
+    ///
+    /// Checks that the randomness is that of a fresh commitment, within `policy::RANDOMNESS_BOUND`,
+    /// recomputes the point with the default configuration and compares it with the commitment
+    /// point, and the hash of the point with the commitment hash. Openings of homomorphic sums
+    /// carry larger randomness and are checked with `policy::VerifyPolicy::for_sum`.
+    ///
+    /// # Parameters
+    /// - `params`: The `(A, B)` matrices the commitment was computed with.
+    /// - `commitment`: A reference to the `RevelerResult` to be opened.
+    /// - `local_m`: The message vector.
+    /// - `local_r`: The randomness vector.
+    ///
+    /// # Returns
+    /// `true` if the opening reproduces both the point and the hash, otherwise `false`, also when
+    /// the randomness exceeds the bound; `CommitError::ParamsRevoked` or `CommitError::ParamsExpired`
+    /// if `revocation::check` refuses the parameters, or the error of the computation if the point cannot be computed,
+    /// e.g. for a message longer than `fft::MESSAGE_LEN` coordinates.
+    pub fn verify_opening(
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        commitment: &RevelerResult,
+        local_m: &[u64],
+        local_r: &[u64],
+    ) -> Result<bool, CommitError> {
+        match policy::VerifyPolicy::new().verify_opening(params, commitment, local_m, local_r) {
+            Err(CommitError::PolicyViolation) => Ok(false),
+            result => result,
+        }
     }
 }
 
-/// Creates a default `RevelerCommit` using randomly generated parameters.
+/// Computes the commitment point `A·m + B·r mod q`, the arithmetic step of a commitment.
+///
+/// Coordinate `k` of the point is `Σ_i A[k][i]·m_i + Σ_i B[k][i]·r_i + r_(N+k) mod q` for
+/// `i < LOCAL_N`: the randomness holds `fft::RANDOMNESS_LEN` coordinates, the first `LOCAL_N`
+/// multiplied by B and the last `LOCAL_N` added to the point, i.e. multiplied by `[B | I]`. The
+/// message holds at most `fft::MESSAGE_LEN` coordinates, so a point fixes the message of any opening with short
+/// randomness, see `policy::RANDOMNESS_BOUND`.
 ///
-/// This constructor generates random `a` and `b` matrices, and computes the
-/// commitment point and its hash based on these parameters.
+/// Together with `bind_point` this splits `RevelerCommit::commit_with_config` in two, so the
+/// arithmetic runs on one machine or accelerator and the binding on another, more trusted one.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices.
+/// - `local_m`: The message vector.
+/// - `local_r`: The randomness vector.
+/// - `config`: A reference to the `CommitConfig` selecting the computation strategy.
 ///
 /// # Returns
-/// A default instance of `RevelerCommit`.
-impl Default for RevelerResult {
-    fn default() -> Self {
-        let (a, b) = utils::generate_params();
+/// The commitment point, or the `CommitError` of the computation: `CommitError::ComputationError`
+/// if the message or the randomness has non-zero coordinates past `fft::MESSAGE_LEN` or
+/// `fft::RANDOMNESS_LEN`, or if a matrix has fewer than `LOCAL_N` rows of `LOCAL_N` entries.
+pub fn commit_point(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    local_r: &[u64],
+    config: &CommitConfig,
+) -> Result<Vec<u64>, CommitError> {
+    compute_point(params.0, params.1, local_m, local_r, config)
+}
 
-        let mut rng = rand::thread_rng();
-        let m: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
-        let r: Vec<u64> = (0..fft::LOCAL_N).map(|_| rng.gen_range(0..fft::LOCAL_Q)).collect();
+/// Binds a commitment point with its hash, the second step of a commitment after `commit_point`.
+///
+/// # Returns
+/// The `RevelerResult` of the point and `hash_commitment_point(&point, policy)`.
+pub fn bind_point(commitment_point: Vec<u64>, policy: HashPolicy) -> RevelerResult {
+    let commitment_hash = hash_commitment_point(&commitment_point, policy);
+    RevelerResult::new(commitment_point, commitment_hash)
+}
 
-        let commitment_point = RevelerCommit::new(a, b, m, r);
-        let commitment_result = commitment_point.commit().unwrap_or_else(|err| {
-            panic!("Commitment computation failed: {:?}", err);
-        });
+// 按配置绑定承诺点，需要时附加快速摘要
+#[cfg(feature = "prover")]
+pub(crate) fn bind_with_config(commitment_point: Vec<u64>, config: &CommitConfig) -> RevelerResult {
+    #[allow(unused_mut)]
+    let mut result = bind_point(commitment_point, HashPolicy::default());
+    #[cfg(feature = "fault-injection")]
+    if let Some(len) = faults::truncated_hash() {
+        result.commitment_hash.truncate(len);
+    }
+    #[cfg(feature = "fast-digest")]
+    if config.fast_digest {
+        return result.with_fast_digest();
+    }
+    let _ = config;
+    result
+}
 
-        commitment_result
+/// Computes the BLAKE3 digest of a commitment point, for indexing and deduplication.
+///
+/// The digest is computed over `FAST_DIGEST_DOMAIN` and the length-prefixed coordinates, so it
+/// never equals a binding hash or a BLAKE3 digest of another object. It is not a binding hash:
+/// treat a match as a candidate and check the `commitment_hash` of the result.
+///
+/// # Returns
+/// The 32-byte digest.
+#[cfg(feature = "fast-digest")]
+pub fn fast_digest(commitment_point: &[u64]) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(FAST_DIGEST_DOMAIN);
+    hasher.update(&utils::encode_vector(commitment_point));
+    hasher.finalize().as_bytes().to_vec()
+}
+
+/// Computes the hash binding a commitment point, without recomputing the point.
+///
+/// Systems storing points computed elsewhere produce or refresh the hash of a `RevelerResult`
+/// with it, and migrations apply another hasher through `HashPolicy::Custom`.
+///
+/// # Parameters
+/// - `commitment_point`: The coordinates of the point.
+/// - `policy`: The `HashPolicy` selecting the hash function.
+///
+/// # Returns
+/// The hash of the big-endian encoding of the coordinates. With `HashPolicy::BlueHash256` this is
+/// the `commitment_hash` `RevelerCommit::commit` returns and `RevelerCommit::verify` checks.
+pub fn hash_commitment_point(commitment_point: &[u64], policy: HashPolicy) -> Vec<u8> {
+    let encoded: Vec<u8> = commitment_point.iter().flat_map(|&x| x.to_be_bytes()).collect();
+    match policy {
+        HashPolicy::BlueHash256 => utils::hash_to_commitment(&encoded),
+        HashPolicy::Custom(hash) => hash(&encoded),
+    }
+}
+
+// Add two vectors coordinate-wise modulo q, the shorter one padded with zeros.
+pub(crate) fn add_mod(x: &[u64], y: &[u64], q: u64) -> Vec<u64> {
+    (0..x.len().max(y.len()))
+        .map(|i| {
+            let (a, b) = (x.get(i).map_or(0, |&a| a % q), y.get(i).map_or(0, |&b| b % q));
+            // a + b < 2q 不会溢出
+            (a + b) % q
+        })
+        .collect()
+}
+
+// Multiply a vector coordinate-wise by k modulo q.
+pub(crate) fn scale_mod(x: &[u64], k: u64, q: u64) -> Vec<u64> {
+    x.iter().map(|&a| ((a as u128 * k as u128) % q as u128) as u64).collect()
+}
+
+// Compute the commitment point of `a`, `b`, `m` and `r`: coordinate k is
+// Σ_i A[k][i]·m_i + Σ_i B[k][i]·r_i + r_(N+k) mod q, the product of the message with A and of the
+// randomness with [B | I]. With the `prover` feature, large workloads are split into blocks of rows
+// computed by worker threads; every path returns the same point.
+pub(crate) fn compute_point(
+    local_a: &[Vec<u64>],
+    local_b: &[Vec<u64>],
+    local_m: &[u64],
+    local_r: &[u64],
+    config: &CommitConfig,
+) -> Result<Vec<u64>, CommitError> {
+    if config.modulus < 2 || config.modulus > arith::MAX_SUPPORTED_Q {
+        return Err(CommitError::UnsupportedModulus);
+    }
+
+    #[cfg(feature = "fault-injection")]
+    if faults::corrupted_chunk() {
+        let mut point = compute_point_unfaulted(local_a, local_b, local_m, local_r, config)?;
+        if let Some(x) = point.first_mut() {
+            *x = (*x + 1) % config.modulus;
+        }
+        return Ok(point);
     }
+    compute_point_unfaulted(local_a, local_b, local_m, local_r, config)
 }
 
+fn compute_point_unfaulted(
+    local_a: &[Vec<u64>],
+    local_b: &[Vec<u64>],
+    local_m: &[u64],
+    local_r: &[u64],
+    config: &CommitConfig,
+) -> Result<Vec<u64>, CommitError> {
+    let params = leading_rows(local_a, local_b)?;
+    let (m, r) = supports(local_m, local_r)?;
+
+    #[cfg(feature = "fault-injection")]
+    if faults::worker_panic() {
+        return commit_point_blocks(params, m, r, &config.with_inline_work_threshold(0));
+    }
+
+    #[cfg(feature = "prover")]
+    if !is_small_message(m, config) {
+        return commit_point_blocks(params, m, r, config);
+    }
+
+    commit_rows(params, m, r, 0..fft::LOCAL_N, config)
+}
+
+// Rows of A and B.
+type Rows<'a> = (&'a [Vec<u64>], &'a [Vec<u64>]);
+
+// The first `LOCAL_N` rows of A and B, the rows the point is computed from.
+fn leading_rows<'a>(local_a: &'a [Vec<u64>], local_b: &'a [Vec<u64>]) -> Result<Rows<'a>, CommitError> {
+    match (local_a.get(..fft::LOCAL_N), local_b.get(..fft::LOCAL_N)) {
+        (Some(a_rows), Some(b_rows)) => Ok((a_rows, b_rows)),
+        _ => Err(CommitError::ComputationError),
+    }
+}
+
+// The message and the randomness up to their last non-zero coordinate, which must lie within the
+// first `MESSAGE_LEN` and `RANDOMNESS_LEN` coordinates.
+fn supports<'a>(local_m: &'a [u64], local_r: &'a [u64]) -> Result<(&'a [u64], &'a [u64]), CommitError> {
+    match (local_m.get(..utils::support_len(local_m)), local_r.get(..utils::support_len(local_r))) {
+        (Some(m), Some(r)) if m.len() <= fft::MESSAGE_LEN && r.len() <= fft::RANDOMNESS_LEN => Ok((m, r)),
+        _ => Err(CommitError::ComputationError),
+    }
+}
+
+#[cfg(feature = "prover")]
+// The randomness is left out: a commitment with short randomness is not hiding, so a full support
+// of `r` is the common case and must not push a short message onto the block path.
+fn is_small_message(local_m: &[u64], config: &CommitConfig) -> bool {
+    utils::support_len(local_m) <= config.small_message_limit
+}
 
+// Number of matrix elements the rows of the point of the supports `m` and `r` read.
+#[cfg(feature = "prover")]
+fn point_work(m: &[u64], r: &[u64]) -> usize {
+    fft::LOCAL_N * (m.len() + r.len())
+}
+
+#[cfg(feature = "prover")]
+fn commit_point_blocks(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    m: &[u64],
+    r: &[u64],
+    config: &CommitConfig,
+) -> Result<Vec<u64>, CommitError> {
+    // 按固定块大小划分，与线程数无关
+    let block_rows = cmp::max(config.block_rows, 1);
+    let blocks: Vec<std::ops::Range<usize>> = (0..fft::LOCAL_N)
+        .step_by(block_rows)
+        .map(|start| start..cmp::min(start + block_rows, fft::LOCAL_N))
+        .collect();
+
+    let block_results: Vec<Vec<u64>> = if point_work(m, r) < config.inline_work_threshold {
+        blocks.iter()
+            .map(|rows| commit_rows(params, m, r, rows.clone(), config))
+            .collect::<Result<_, _>>()?
+    } else {
+        let thread_count = cmp::min(utils::get_optimal_thread_count(), blocks.len());
+        let mut block_results = vec![Vec::new(); blocks.len()];
+        #[cfg(feature = "fault-injection")]
+        let panic_worker = faults::worker_panic();
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..thread_count).map(|thread_id| {
+                let blocks = &blocks;
+                scope.spawn(move || {
+                    #[cfg(feature = "fault-injection")]
+                    if panic_worker && thread_id == 0 {
+                        faults::panic_worker();
+                    }
+                    blocks.iter().enumerate()
+                        .skip(thread_id)
+                        .step_by(thread_count)
+                        .map(|(block_id, rows)| (block_id, commit_rows(params, m, r, rows.clone(), config)))
+                        .collect::<Vec<_>>()
+                })
+            }).collect();
+
+            // 合并线程结果
+            for handle in handles {
+                for (block_id, result) in handle.join().map_err(|_| CommitError::ComputationError)? {
+                    *block_results.get_mut(block_id).ok_or(CommitError::ComputationError)? = result?;
+                }
+            }
+            Ok(())
+        })?;
+
+        block_results
+    };
+
+    Ok(block_results.into_iter().flatten().collect())
+}
+
+// Compute the points of many openings, splitting the rows of all points into chunks taken by
+// worker threads with the `prover` feature. Every point equals the one `compute_point` returns for
+// the same opening.
+pub(crate) fn compute_points_batch(
+    local_a: &[Vec<u64>],
+    local_b: &[Vec<u64>],
+    openings: &[(&[u64], &[u64])],
+    batch: &BatchConfig,
+) -> Result<Vec<Vec<u64>>, CommitError> {
+    let config = &batch.commit;
+    if config.modulus < 2 || config.modulus > arith::MAX_SUPPORTED_Q {
+        return Err(CommitError::UnsupportedModulus);
+    }
+
+    #[cfg(feature = "prover")]
+    if !openings.iter().all(|(m, _)| is_small_message(m, config)) {
+        return compute_points_chunked(local_a, local_b, openings, batch);
+    }
+
+    openings.iter().map(|(m, r)| compute_point(local_a, local_b, m, r, config)).collect()
+}
+
+// Split the rows of all points of the batch into chunks of `BatchConfig::rows_per_task` rows and
+// let the workers take the next chunk as soon as they are done with the last one, so that chunks
+// of different cost are balanced across the workers.
+#[cfg(feature = "prover")]
+fn compute_points_chunked(
+    local_a: &[Vec<u64>],
+    local_b: &[Vec<u64>],
+    openings: &[(&[u64], &[u64])],
+    batch: &BatchConfig,
+) -> Result<Vec<Vec<u64>>, CommitError> {
+    let config = &batch.commit;
+    let params = leading_rows(local_a, local_b)?;
+    let openings = openings.iter().map(|(m, r)| supports(m, r)).collect::<Result<Vec<_>, _>>()?;
+    let total_rows = openings.len() * fft::LOCAL_N;
+    let thread_count = utils::get_optimal_thread_count();
+    let chunk_rows = batch.rows_per_task(total_rows, thread_count);
+    let chunk_count = total_rows.div_ceil(chunk_rows);
+
+    let run_chunk = |chunk: usize| -> Result<Vec<u64>, CommitError> {
+        let (start, end) = (chunk * chunk_rows, cmp::min((chunk + 1) * chunk_rows, total_rows));
+        let mut coordinates = Vec::with_capacity(end - start);
+        let mut position = start;
+        while position < end {
+            let (index, first_row) = (position / fft::LOCAL_N, position % fft::LOCAL_N);
+            let last_row = cmp::min(fft::LOCAL_N, first_row + end - position);
+            let &(m, r) = openings.get(index).ok_or(CommitError::ComputationError)?;
+            coordinates.extend(commit_rows(params, m, r, first_row..last_row, config)?);
+            position += last_row - first_row;
+        }
+        Ok(coordinates)
+    };
+
+    let chunk_results: Vec<Vec<u64>> = if openings.iter().map(|&(m, r)| point_work(m, r)).sum::<usize>() < config.inline_work_threshold {
+        (0..chunk_count).map(run_chunk).collect::<Result<_, _>>()?
+    } else {
+        let next_chunk = AtomicUsize::new(0);
+        let mut chunk_results = vec![Vec::new(); chunk_count];
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..cmp::min(thread_count, chunk_count)).map(|_| {
+                let (next_chunk, run_chunk) = (&next_chunk, &run_chunk);
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    loop {
+                        let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        if chunk >= chunk_count {
+                            return done;
+                        }
+                        done.push((chunk, run_chunk(chunk)));
+                    }
+                })
+            }).collect();
+
+            // 合并线程结果
+            for handle in handles {
+                for (chunk, result) in handle.join().map_err(|_| CommitError::ComputationError)? {
+                    *chunk_results.get_mut(chunk).ok_or(CommitError::ComputationError)? = result?;
+                }
+            }
+            Ok(())
+        })?;
+        chunk_results
+    };
+
+    Ok(chunk_results.concat().chunks_exact(fft::LOCAL_N).map(<[u64]>::to_vec).collect())
+}
+
+// Compute the coordinates `rows` of the point of the supports `m` and `r`.
+fn commit_rows(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    m: &[u64],
+    r: &[u64],
+    rows: std::ops::Range<usize>,
+    config: &CommitConfig,
+) -> Result<Vec<u64>, CommitError> {
+    rows.map(|row| {
+        let (Some(a_row), Some(b_row)) = (params.0.get(row), params.1.get(row)) else {
+            return Err(CommitError::ComputationError);
+        };
+        accumulate_point(row, (a_row, b_row), m, r, config)
+    })
+    .collect()
+}
+
+// Compute coordinate `row` of the point: the terms A[row][i]·m_i + B[row][i]·r_i mod q for every
+// i < LOCAL_N, followed by r_(N+row), summed in the fixed tree order of
+// `ArithmeticPolicy::tree_sum_mod`. Terms past both supports are zero and left out.
+fn accumulate_point(row: usize, rows: (&[u64], &[u64]), m: &[u64], r: &[u64], config: &CommitConfig) -> Result<u64, CommitError> {
+    let (q, policy) = (config.modulus, config.arithmetic);
+    let (Some(a_row), Some(b_row)) = (rows.0.get(..fft::LOCAL_N), rows.1.get(..fft::LOCAL_N)) else {
+        return Err(CommitError::ComputationError);
+    };
+    if arith::is_narrow(q) {
+        return Ok(accumulate_point_narrow(row, (a_row, b_row), m, r, q));
+    }
+    let mut terms = point_terms((a_row, b_row), m, r, config)?;
+    terms.push(r.get(fft::LOCAL_N + row).map_or(0, |&e| e % q));
+    policy.tree_sum_mod(&terms, q)
+}
+
+// `accumulate_point` for a modulus of the narrow parameter sets: the entries and the supports are
+// reduced into `u32` values, their products summed in a `u64` and the sum reduced once. A row has
+// at most `2·LOCAL_N + 1` products below `NARROW_MAX_Q^2`, so the sum stays below 2^42 and equals
+// the tree sum modulo q under every policy.
+fn accumulate_point_narrow(row: usize, rows: (&[u64], &[u64]), m: &[u64], r: &[u64], q: u64) -> u64 {
+    let narrow = |x: u64| u64::from((x % q) as u32);
+    let a_terms: u64 = rows.0.iter().zip(m).map(|(&a, &x)| narrow(a) * narrow(x)).sum();
+    let b_terms: u64 = rows.1.iter().zip(r).map(|(&b, &y)| narrow(b) * narrow(y)).sum();
+    (a_terms + b_terms + r.get(fft::LOCAL_N + row).map_or(0, |&e| narrow(e))) % q
+}
+
+// The terms A[row][i]·m_i + B[row][i]·r_i mod q of rows of `LOCAL_N` entries, for every i below the
+// longer support.
+#[cfg(not(feature = "fast-unsafe"))]
+fn point_terms(rows: (&[u64], &[u64]), m: &[u64], r: &[u64], config: &CommitConfig) -> Result<Vec<u64>, CommitError> {
+    let (q, policy) = (config.modulus, config.arithmetic);
+    let product = |entry: u64, x: Option<&u64>| x.map_or(Ok(0), |&x| policy.mul_add_mod(0, entry % q, x % q, q));
+    rows.0.iter().zip(rows.1).enumerate()
+        .take(m.len().max(r.len()))
+        .map(|(i, (&a, &b))| policy.accumulate_mod(0, product(a, m.get(i))?, product(b, r.get(i))?, q))
+        .collect()
+}
+
+// `point_terms` without the bounds checks: the lengths are validated once, then the rows and the
+// supports are read with `get_unchecked`, first where both supports have a coordinate and then
+// along the longer one.
+#[cfg(feature = "fast-unsafe")]
+fn point_terms(rows: (&[u64], &[u64]), m: &[u64], r: &[u64], config: &CommitConfig) -> Result<Vec<u64>, CommitError> {
+    let (q, policy) = (config.modulus, config.arithmetic);
+    let (a_row, b_row) = rows;
+    if a_row.len() < fft::LOCAL_N || b_row.len() < fft::LOCAL_N {
+        return Err(CommitError::ComputationError);
+    }
+    let both = m.len().min(r.len()).min(fft::LOCAL_N);
+    let (longer, entries) = if m.len() > r.len() { (m, a_row) } else { (r, b_row) };
+    let len = longer.len().min(fft::LOCAL_N);
+    let mut terms = Vec::with_capacity(len);
+    for i in 0..both {
+        // SAFETY: i < both, which is at most the length of each row and of each support.
+        let (a, b, x, y) = unsafe { (*a_row.get_unchecked(i), *b_row.get_unchecked(i), *m.get_unchecked(i), *r.get_unchecked(i)) };
+        terms.push(policy.accumulate_mod(0, policy.mul_add_mod(0, a % q, x % q, q)?, policy.mul_add_mod(0, b % q, y % q, q)?, q)?);
+    }
+    for i in both..len {
+        // SAFETY: i < len, which is at most the length of `longer` and of its row `entries`.
+        let (entry, x) = unsafe { (*entries.get_unchecked(i), *longer.get_unchecked(i)) };
+        terms.push(policy.mul_add_mod(0, entry % q, x % q, q)?);
+    }
+    Ok(terms)
+}
+
+impl From<(Vec<u64>, Vec<u8>)> for RevelerResult {
+    fn from((commitment_point, commitment_hash): (Vec<u64>, Vec<u8>)) -> Self {
+        RevelerResult::new(commitment_point, commitment_hash)
+    }
+}
+
+impl From<RevelerResult> for (Vec<u64>, Vec<u8>) {
+    fn from(result: RevelerResult) -> Self {
+        result.into_parts()
+    }
+}
+
+impl From<CommitParts> for RevelerCommit {
+    fn from((local_a, local_b, local_m, local_r): CommitParts) -> Self {
+        RevelerCommit::new(local_a, local_b, local_m, local_r)
+    }
+}
+
+impl From<RevelerCommit> for CommitParts {
+    fn from(commit: RevelerCommit) -> Self {
+        commit.into_parts()
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn narrow_rows_match_the_tree_sum() {
+        // 取最大系数，检验 u32 内核与逐项树形求和一致
+        let (a, b, _) = setup();
+        for q in [fft::LOCAL_Q, arith::NARROW_MAX_Q] {
+            let config = CommitConfig::default().with_modulus(q);
+            let (m, r) = (vec![q - 1; fft::MESSAGE_LEN], vec![q - 1; fft::RANDOMNESS_LEN]);
+            for row in [0, 1, fft::LOCAL_N - 1] {
+                let mut terms = point_terms((&a[row], &b[row]), &m, &r, &config).unwrap();
+                terms.push(r[fft::LOCAL_N + row] % q);
+                let expected = config.arithmetic.tree_sum_mod(&terms, q).unwrap();
+                assert_eq!(accumulate_point(row, (&a[row], &b[row]), &m, &r, &config), Ok(expected));
+            }
+        }
+    }
+
+    fn setup() -> (Vec<Vec<u64>>, Vec<Vec<u64>>, Vec<u64>) {
+        let mut rng = StdRng::seed_from_u64(251);
+        let (a, b) = utils::generate_params_with_rng(&mut rng);
+        let r = utils::generate_randomness(&mut rng);
+        (a, b, r)
+    }
+
+    #[test]
+    fn honest_opening_verifies() {
+        let (a, b, r) = setup();
+        let m = vec![7, 0, 65534, 12];
+        let commitment = RevelerCommit::new(a.clone(), b.clone(), m.clone(), r.clone()).commit().unwrap();
+        assert!(RevelerCommit::verify_opening((&a, &b), &commitment, &m, &r).unwrap());
+    }
+
+    #[test]
+    fn opening_with_equal_sums_is_rejected() {
+        // 两个打开的消息坐标之和相同，随机数相同
+        let (a, b, r) = setup();
+        for (committed, claimed) in [(vec![1, 0], vec![0, 1]), (vec![3, 5], vec![5, 3]), (vec![2, 0, 0], vec![0, 1, 1])] {
+            let commitment = RevelerCommit::new(a.clone(), b.clone(), committed, r.clone()).commit().unwrap();
+            assert!(!RevelerCommit::verify_opening((&a, &b), &commitment, &claimed, &r).unwrap());
+        }
+    }
+
+    #[test]
+    fn randomness_outside_the_bound_is_rejected() {
+        let (a, b, mut r) = setup();
+        r[0] = 2;
+        let m = vec![9, 4];
+        let commitment = RevelerCommit::new(a.clone(), b.clone(), m.clone(), r.clone()).commit().unwrap();
+        assert!(!RevelerCommit::verify_opening((&a, &b), &commitment, &m, &r).unwrap());
+        assert!(policy::VerifyPolicy::for_sum(2).verify_opening((&a, &b), &commitment, &m, &r).unwrap());
+    }
+
+    #[test]
+    fn message_past_its_length_is_rejected() {
+        let (a, b, r) = setup();
+        let mut m = vec![0; fft::MESSAGE_LEN + 1];
+        m[fft::MESSAGE_LEN] = 1;
+        assert_eq!(RevelerCommit::new(a, b, m, r).commit(), Err(CommitError::ComputationError));
+    }
+
+    #[cfg(feature = "fast-digest")]
+    #[test]
+    fn mismatched_fast_digests_are_dropped_when_decoding() {
+        let (a, b, r) = setup();
+        let commitment = RevelerCommit::new(a, b, vec![5, 6], r).commit().unwrap().with_fast_digest();
+        let mut bytes = commitment.to_bytes();
+        assert_eq!(RevelerResult::from_bytes(&bytes).unwrap().fast_digest(), commitment.fast_digest());
+        *bytes.last_mut().unwrap() ^= 1;
+        assert_eq!(RevelerResult::from_bytes(&bytes).unwrap().fast_digest(), None);
+
+        #[cfg(feature = "json")]
+        {
+            let mut forged = commitment.clone();
+            forged.fast_digest = Some(vec![0; 32]);
+            let decoded: RevelerResult = serde_json::from_str(&serde_json::to_string(&forged).unwrap()).unwrap();
+            assert_eq!(decoded.fast_digest(), None);
+            let decoded: RevelerResult = serde_json::from_str(&serde_json::to_string(&commitment).unwrap()).unwrap();
+            assert_eq!(decoded.fast_digest(), commitment.fast_digest());
+        }
+    }
+
+    #[test]
+    fn short_messages_take_the_direct_path_with_full_randomness() {
+        // 随机数占满全部坐标，短消息仍走直接路径
+        let (a, b, r) = setup();
+        let config = CommitConfig::default();
+        let m = vec![7; config.small_message_limit];
+        assert!(utils::support_len(&r) > config.small_message_limit);
+        assert!(is_small_message(&m, &config));
+        assert!(!is_small_message(&vec![7; config.small_message_limit + 1], &config));
+
+        let direct = compute_point(&a, &b, &m, &r, &config).unwrap();
+        let blocks = compute_point(&a, &b, &m, &r, &config.with_small_message_limit(0)).unwrap();
+        assert_eq!(direct, blocks);
+    }
+
+    #[test]
+    fn short_supports_stay_below_the_default_threshold() {
+        // 超过 small_message_limit，但工作量仍小于默认阈值
+        let (a, b, r) = setup();
+        let config = CommitConfig::default();
+        let (m, short_r) = (vec![3; 20], r[..200].to_vec());
+        assert!(point_work(&m, &short_r) < config.inline_work_threshold);
+        assert!(point_work(&vec![3; fft::MESSAGE_LEN], &r) >= config.inline_work_threshold);
+
+        let inline = compute_point(&a, &b, &m, &short_r, &config).unwrap();
+        let threaded = compute_point(&a, &b, &m, &short_r, &config.with_inline_work_threshold(0)).unwrap();
+        assert_eq!(inline, threaded);
+    }
+}
+
+// `#[no_panic]` fails the link when the optimizer cannot remove every panic path of a function, so
+// these tests only build with optimizations.
+#[cfg(all(test, feature = "prover", feature = "strict-no-panic", not(debug_assertions)))]
+mod no_panic_tests {
+    use no_panic::no_panic;
+    use crate::key::CommitmentKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    // 工作线程的 spawn 可能 panic，直接路径在调用线程上计算
+    fn inline() -> CommitConfig {
+        CommitConfig::new().with_small_message_limit(usize::MAX)
+    }
+
+    #[no_panic]
+    fn commit(key: &CommitmentKey, local_m: &[u64], local_r: &[u64]) -> Result<RevelerResult, CommitError> {
+        key.commit_with_config(local_m, local_r, &inline())
+    }
+
+    #[no_panic]
+    fn verify(key: &CommitmentKey, commitment: &RevelerResult, local_m: &[u64], local_r: &[u64]) -> Result<bool, CommitError> {
+        policy::VerifyPolicy::new().verify_opening_with_config(key.as_pair(), commitment, local_m, local_r, &inline())
+    }
+
+    #[no_panic]
+    fn decode(bytes: &[u8]) -> Result<RevelerResult, CommitError> {
+        RevelerResult::from_bytes(bytes)
+    }
+
+    #[test]
+    fn malformed_inputs_return_errors() {
+        let mut rng = StdRng::seed_from_u64(253);
+        let key = CommitmentKey::setup(&mut rng);
+        let r = utils::generate_randomness(&mut rng);
+        let commitment = commit(&key, &[1, 2, 3], &r).unwrap_or_else(|_| RevelerResult::new(Vec::new(), Vec::new()));
+        assert_eq!(verify(&key, &commitment, &[1, 2, 3], &r), Ok(true));
+
+        let truncated = CommitmentKey::new(Vec::new(), Vec::new());
+        assert_eq!(commit(&truncated, &[1, 2, 3], &r), Err(CommitError::ComputationError));
+        assert!(verify(&truncated, &commitment, &[1, 2, 3], &r).is_err());
+        assert_eq!(commit(&key, &vec![1; fft::RANDOMNESS_LEN + 1], &r), Err(CommitError::ComputationError));
+
+        let bytes = commitment.to_bytes();
+        assert_eq!(decode(&bytes), Ok(commitment));
+        for len in 0..bytes.len() {
+            assert!(decode(bytes.get(..len).unwrap_or_default()).is_err());
+        }
+        assert!(decode(&[0xff; 64]).is_err());
+    }
+}
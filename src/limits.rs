@@ -0,0 +1,182 @@
+//! This module provides `DecodeLimits`, the largest sizes the decoders of the crate accept, so a
+//! verifier service handed hostile bytes or JSON fails fast with `CommitError::LimitExceeded`
+//! instead of allocating whatever a length prefix announces.
+//!
+//! The limits are process-wide, like the scheme registry: `install` replaces them and every
+//! decoder reads them with `current`. They bound:
+//! - `max_dimension`: the rows and columns of a decoded matrix, i.e. the dimension `N` of keys,
+//!   parameters and archives.
+//! - `max_point_len`: the coordinates of a decoded point, message or randomness vector.
+//! - `max_proof_size`: the length of the canonical JSON text of a proof or any other artifact
+//!   decoded by `json`, of which proofs are the largest.
+//! - `max_record_size`: the length of an archive checked by `archive::verify_any`, and of every
+//!   entry of a decoded `archive::ArchiveCollection`.
+//!
+//! The byte decoders of `utils` check the limits before allocating, and the `Deserialize`
+//! implementations of `RevelerResult`, `RevelerCommit` and `key::CommitmentKey` count the elements
+//! of their vectors and matrices while reading them, so a serde format cannot memory-bomb them
+//! either. The defaults leave room for four times the dimension of the current scheme.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{OnceLock, RwLock};
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::fft::LOCAL_N;
+
+/// Default largest dimension of a decoded matrix.
+pub const DEFAULT_MAX_DIMENSION: usize = 4 * LOCAL_N;
+
+/// Default largest number of coordinates of a decoded vector.
+pub const DEFAULT_MAX_POINT_LEN: usize = 4 * LOCAL_N;
+
+/// Default largest canonical JSON text of a proof, in bytes.
+pub const DEFAULT_MAX_PROOF_SIZE: usize = 64 << 20;
+
+/// Default largest archive record, in bytes.
+pub const DEFAULT_MAX_RECORD_SIZE: usize = 16 << 20;
+
+/// Largest sizes accepted by the decoders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeLimits {
+    pub max_dimension: usize,   // The rows and columns of a matrix
+    pub max_point_len: usize,   // The coordinates of a vector
+    pub max_proof_size: usize,  // The bytes of the canonical JSON text of an artifact
+    pub max_record_size: usize, // The bytes of an archive or of an entry of a collection
+}
+
+impl DecodeLimits {
+    /// Creates limits holding the default values.
+    pub fn new() -> Self {
+        DecodeLimits {
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            max_point_len: DEFAULT_MAX_POINT_LEN,
+            max_proof_size: DEFAULT_MAX_PROOF_SIZE,
+            max_record_size: DEFAULT_MAX_RECORD_SIZE,
+        }
+    }
+
+    /// Sets the largest dimension of a decoded matrix.
+    pub fn with_max_dimension(mut self, max_dimension: usize) -> Self {
+        self.max_dimension = max_dimension;
+        self
+    }
+
+    /// Sets the largest number of coordinates of a decoded vector.
+    pub fn with_max_point_len(mut self, max_point_len: usize) -> Self {
+        self.max_point_len = max_point_len;
+        self
+    }
+
+    /// Sets the largest canonical JSON text of a proof, in bytes.
+    pub fn with_max_proof_size(mut self, max_proof_size: usize) -> Self {
+        self.max_proof_size = max_proof_size;
+        self
+    }
+
+    /// Sets the largest archive record, in bytes.
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.max_record_size = max_record_size;
+        self
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits::new()
+    }
+}
+
+fn installed() -> &'static RwLock<DecodeLimits> {
+    static LIMITS: OnceLock<RwLock<DecodeLimits>> = OnceLock::new();
+    LIMITS.get_or_init(|| RwLock::new(DecodeLimits::new()))
+}
+
+/// Installs the limits every decoder of the process checks, replacing the previous ones.
+pub fn install(limits: DecodeLimits) {
+    *installed().write().unwrap_or_else(|poisoned| poisoned.into_inner()) = limits;
+}
+
+/// Returns the installed limits, the defaults unless `install` was called.
+pub fn current() -> DecodeLimits {
+    *installed().read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Checks a size against a limit.
+///
+/// # Returns
+/// `Ok(())`, or `CommitError::LimitExceeded` if `size` exceeds `limit`.
+pub fn check(size: usize, limit: usize) -> Result<(), CommitError> {
+    if size > limit {
+        return Err(CommitError::LimitExceeded);
+    }
+    Ok(())
+}
+
+// 反序列化时逐个元素计数，超过上限立即失败，不按声明的长度预先分配
+pub(crate) fn deserialize_vector<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    deserializer.deserialize_seq(Bounded::<u64>::new(current().max_point_len))
+}
+
+pub(crate) fn deserialize_matrix<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u64>>, D::Error> {
+    deserializer.deserialize_seq(Bounded::<Vec<u64>>::new(current().max_dimension))
+}
+
+// Sequence of at most `max` elements; the rows of a matrix are bounded by the same `max`.
+struct Bounded<T> {
+    max: usize,
+    element: PhantomData<T>,
+}
+
+impl<T> Bounded<T> {
+    fn new(max: usize) -> Self {
+        Bounded { max, element: PhantomData }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Bounded<u64> {
+    type Value = Vec<u64>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<u64>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Bounded<u64> {
+    type Value = Vec<u64>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence of at most {} integers", self.max)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u64>, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(self.max));
+        while let Some(value) = seq.next_element()? {
+            if values.len() == self.max {
+                return Err(de::Error::custom("sequence exceeds the decode limits"));
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+impl<'de> Visitor<'de> for Bounded<Vec<u64>> {
+    type Value = Vec<Vec<u64>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a matrix of at most {0} rows of at most {0} integers", self.max)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Vec<u64>>, A::Error> {
+        let mut rows = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(self.max));
+        while let Some(row) = seq.next_element_seed(Bounded::<u64>::new(self.max))? {
+            if rows.len() == self.max {
+                return Err(de::Error::custom("matrix exceeds the decode limits"));
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
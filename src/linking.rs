@@ -0,0 +1,364 @@
+//! This module provides proofs linking commitments made in different protocol sessions.
+//!
+//! When the same message `m` is committed as `c1 = A1·m + [B1 | I]·r1` under one set of
+//! parameters and as `c2 = A2·m + [B2 | I]·r2` under another, `prove_same_message_across`
+//! convinces an auditor that both commitments hide the same message without revealing it.
+//!
+//! The proof is a `binary` proof of the relation `(A1·m + [B1 | I]·r1, A2·m + [B2 | I]·r2) =
+//! (c1, c2)` with `m` free and `r1`, `r2` ternary. Both randomness vectors are part of the
+//! permuted witness, so a prover that passes knows openings of `c1` and `c2` to the same `m` with
+//! ternary randomness, and by the binding of each commitment that is the message of every short
+//! opening of either. Without the bound the identity block of `[B | I]` would open `c2` to `m`
+//! whatever it hides.
+//!
+//! `prove_equal` and `verify_equal` take the commitments with their `CommitmentKey`s and
+//! `Opening`s, e.g. to link commitments across the phases of a protocol.
+//!
+//! The protocol is repeated until the soundness error drops below `2^-SOUNDNESS_BITS`. The
+//! `_with_config` variants take the number of rounds from a `ProofConfig` instead, see
+//! `ProofConfig::binary_rounds`.
+
+use serde::{Serialize, Deserialize};
+use crate::binary::{self, BinaryRound, Block, Relation};
+#[cfg(feature = "prover")]
+use crate::binary::Witness;
+use crate::commit_error::CommitError;
+#[cfg(feature = "prover")]
+use crate::utils::Workers;
+#[cfg(feature = "prover")]
+use crate::config::CommitConfig;
+use crate::config::ProofConfig;
+use crate::fft::{MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+use crate::{RevelerCommit, RevelerResult};
+
+pub(crate) const LINKING_DOMAIN: &[u8] = b"reveler/linking";
+
+/// Soundness target of the linking proof in bits.
+pub const SOUNDNESS_BITS: u32 = 128;
+
+/// Proof that two commitments under different parameters hide the same message: a `binary` proof
+/// over the message and both randomness vectors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkingProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+// Parameters, opening and commitment of one side of a linking proof.
+#[cfg(feature = "prover")]
+#[derive(Clone, Copy)]
+struct Session<'a> {
+    params: (&'a [Vec<u64>], &'a [Vec<u64>]),
+    local_m: &'a [u64],
+    local_r: &'a [u64],
+    commitment: &'a RevelerResult,
+}
+
+#[cfg(feature = "prover")]
+impl<'a> Session<'a> {
+    fn of(opening: &'a RevelerCommit, commitment: &'a RevelerResult) -> Self {
+        Session { params: (&opening.local_a, &opening.local_b), local_m: &opening.local_m, local_r: &opening.local_r, commitment }
+    }
+
+    fn keyed(key: &'a CommitmentKey, opening: &'a Opening, commitment: &'a RevelerResult) -> Self {
+        Session { params: key.as_pair(), local_m: opening.local_m(), local_r: opening.local_r(), commitment }
+    }
+}
+
+/// Proves that `c1` and `c2` commit to the same message.
+///
+/// # Parameters
+/// - `opening1`: A reference to the `RevelerCommit` holding the first session's parameters,
+///   message and randomness.
+/// - `c1`: A reference to the first session's `RevelerResult`.
+/// - `opening2`: A reference to the `RevelerCommit` of the second session.
+/// - `c2`: A reference to the second session's `RevelerResult`.
+///
+/// # Returns
+/// The `LinkingProof`, or `CommitError::InvalidOpening` if the two openings hold different messages
+/// or one of them does not open its commitment with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_same_message_across(
+    opening1: &RevelerCommit,
+    c1: &RevelerResult,
+    opening2: &RevelerCommit,
+    c2: &RevelerResult,
+) -> Result<LinkingProof, CommitError> {
+    prove_same_message_across_with_config(opening1, c1, opening2, c2, &ProofConfig::default())
+}
+
+/// Proves that `c1` and `c2` commit to the same message with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `LinkingProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for
+/// binary proofs, or `CommitError::InvalidOpening` if the two openings hold different messages or
+/// one of them does not open its commitment with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_same_message_across_with_config(
+    opening1: &RevelerCommit,
+    c1: &RevelerResult,
+    opening2: &RevelerCommit,
+    c2: &RevelerResult,
+    proof_config: &ProofConfig,
+) -> Result<LinkingProof, CommitError> {
+    prove_same_message_across_on(Session::of(opening1, c1), Session::of(opening2, c2), proof_config, Workers::Threads)
+}
+
+/// Proves that `c1` and `c2` commit to the same message, generating the rounds on `pool`.
+///
+/// # Returns
+/// The same proof distribution and errors as `prove_same_message_across_with_config`.
+#[cfg(feature = "parallel")]
+pub fn prove_same_message_across_with_pool(
+    opening1: &RevelerCommit,
+    c1: &RevelerResult,
+    opening2: &RevelerCommit,
+    c2: &RevelerResult,
+    proof_config: &ProofConfig,
+    pool: &rayon::ThreadPool,
+) -> Result<LinkingProof, CommitError> {
+    prove_same_message_across_on(Session::of(opening1, c1), Session::of(opening2, c2), proof_config, Workers::Pool(pool))
+}
+
+/// Proves that `c1` under `key1` and `c2` under `key2` hide the same message, without revealing
+/// it.
+///
+/// This is `prove_same_message_across` for commitments made with `CommitmentKey`, borrowing the
+/// matrices of the keys instead of holding them in a `RevelerCommit`. The keys may be equal.
+///
+/// # Parameters
+/// - `key1`: The key of the first commitment.
+/// - `c1`: The first commitment.
+/// - `o1`: Its opening.
+/// - `key2`: The key of the second commitment.
+/// - `c2`: The second commitment.
+/// - `o2`: Its opening.
+///
+/// # Returns
+/// The `LinkingProof`, or `CommitError::InvalidOpening` if the two openings hold different
+/// messages or one of them does not open its commitment with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_equal(
+    key1: &CommitmentKey,
+    c1: &RevelerResult,
+    o1: &Opening,
+    key2: &CommitmentKey,
+    c2: &RevelerResult,
+    o2: &Opening,
+) -> Result<LinkingProof, CommitError> {
+    prove_equal_with_config(key1, c1, o1, key2, c2, o2, &ProofConfig::default())
+}
+
+/// Proves that `c1` and `c2` hide the same message with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `LinkingProof`, or the errors of `prove_same_message_across_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_equal_with_config(
+    key1: &CommitmentKey,
+    c1: &RevelerResult,
+    o1: &Opening,
+    key2: &CommitmentKey,
+    c2: &RevelerResult,
+    o2: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<LinkingProof, CommitError> {
+    prove_same_message_across_on(Session::keyed(key1, o1, c1), Session::keyed(key2, o2, c2), proof_config, Workers::Threads)
+}
+
+#[cfg(feature = "prover")]
+fn prove_same_message_across_on(
+    session1: Session,
+    session2: Session,
+    proof_config: &ProofConfig,
+    workers: Workers,
+) -> Result<LinkingProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    let q = CommitConfig::default().modulus;
+    let m = reduce(session1.local_m, MESSAGE_LEN, q);
+    if m != reduce(session2.local_m, MESSAGE_LEN, q) {
+        return Err(CommitError::InvalidOpening);
+    }
+    for session in [session1, session2] {
+        if !RevelerCommit::verify_opening(session.params, session.commitment, session.local_m, session.local_r)? {
+            return Err(CommitError::InvalidOpening);
+        }
+    }
+    let relation = linking_relation(session1.params, session1.commitment, session2.params, session2.commitment);
+    let witness = Witness { free: m, blocks: vec![session1.local_r.to_vec(), session2.local_r.to_vec()] };
+    Ok(LinkingProof { rounds: binary::prove_relation(&relation, &witness, rounds, workers)?.rounds })
+}
+
+/// Verifies a proof that `c1` under `params1` and `c2` under `params2` hide the same message.
+///
+/// # Parameters
+/// - `params1`: The `(A1, B1)` matrices of the first session.
+/// - `c1`: A reference to the first session's `RevelerResult`.
+/// - `params2`: The `(A2, B2)` matrices of the second session.
+/// - `c2`: A reference to the second session's `RevelerResult`.
+/// - `proof`: A reference to the `LinkingProof`.
+///
+/// # Returns
+/// `true` if both commitments verify and every round of the proof checks out.
+pub fn verify_same_message_across(
+    params1: (&[Vec<u64>], &[Vec<u64>]),
+    c1: &RevelerResult,
+    params2: (&[Vec<u64>], &[Vec<u64>]),
+    c2: &RevelerResult,
+    proof: &LinkingProof,
+) -> bool {
+    verify_same_message_across_with_config(params1, c1, params2, c2, proof, &ProofConfig::default())
+}
+
+/// Verifies a linking proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `true` if `proof_config` is valid for binary proofs, both commitments verify, and the proof
+/// has the rounds of `proof_config`, each of which checks out.
+pub fn verify_same_message_across_with_config(
+    params1: (&[Vec<u64>], &[Vec<u64>]),
+    c1: &RevelerResult,
+    params2: (&[Vec<u64>], &[Vec<u64>]),
+    c2: &RevelerResult,
+    proof: &LinkingProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    RevelerCommit::verify(c1)
+        && RevelerCommit::verify(c2)
+        && binary::verify_relation(&linking_relation(params1, c1, params2, c2), &proof.rounds, rounds)
+}
+
+/// Verifies a proof of `prove_equal` that `c1` under `key1` and `c2` under `key2` hide the same
+/// message.
+///
+/// # Returns
+/// `true` if both commitments verify and every round of the proof checks out.
+pub fn verify_equal(key1: &CommitmentKey, c1: &RevelerResult, key2: &CommitmentKey, c2: &RevelerResult, proof: &LinkingProof) -> bool {
+    verify_same_message_across(key1.as_pair(), c1, key2.as_pair(), c2, proof)
+}
+
+/// Verifies a proof of `prove_equal_with_config` made with the soundness of `proof_config`.
+pub fn verify_equal_with_config(
+    key1: &CommitmentKey,
+    c1: &RevelerResult,
+    key2: &CommitmentKey,
+    c2: &RevelerResult,
+    proof: &LinkingProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    verify_same_message_across_with_config(key1.as_pair(), c1, key2.as_pair(), c2, proof, proof_config)
+}
+
+// Reduce a vector modulo q and pad it to len coordinates: MESSAGE_LEN for a message,
+// RANDOMNESS_LEN for randomness.
+#[cfg(feature = "prover")]
+pub(crate) fn reduce(values: &[u64], len: usize, q: u64) -> Vec<u64> {
+    let mut reduced: Vec<u64> = values.iter().map(|&x| x % q).collect();
+    reduced.resize(len, 0);
+    reduced
+}
+
+// The statement of a linking proof: `(c1, c2)` opens to one free message with ternary randomness
+// under each pair of matrices.
+pub(crate) fn linking_relation<'a>(
+    params1: (&'a [Vec<u64>], &'a [Vec<u64>]),
+    c1: &RevelerResult,
+    params2: (&'a [Vec<u64>], &'a [Vec<u64>]),
+    c2: &RevelerResult,
+) -> Relation<'a> {
+    let mut statement = binary::params_digest(params1);
+    statement.extend(binary::params_digest(params2));
+    Relation {
+        label: LINKING_DOMAIN,
+        statement,
+        free: MESSAGE_LEN,
+        blocks: vec![Block::Ternary(RANDOMNESS_LEN), Block::Ternary(RANDOMNESS_LEN)],
+        map: Box::new(move |m, heads| match heads {
+            [r1, r2] => Ok([binary::point(params1, m, r1)?, binary::point(params2, m, r2)?].concat()),
+            _ => Err(CommitError::ComputationError),
+        }),
+        target: [c1.commitment_point(), c2.commitment_point()].concat(),
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::fft::LOCAL_N;
+    use crate::utils;
+    use super::*;
+
+    #[test]
+    fn sessions_with_the_same_message_link() {
+        let mut rng = StdRng::seed_from_u64(216);
+        let ((a1, b1), (a2, b2)) = (utils::generate_params_with_rng(&mut rng), utils::generate_params_with_rng(&mut rng));
+        let s1 = RevelerCommit::new(a1.clone(), b1.clone(), vec![7, 0, 3], utils::generate_randomness(&mut rng));
+        let s2 = RevelerCommit::new(a2.clone(), b2.clone(), vec![7, 0, 3], utils::generate_randomness(&mut rng));
+        let (c1, c2) = (s1.commit().unwrap(), s2.commit().unwrap());
+        let proof = prove_same_message_across(&s1, &c1, &s2, &c2).unwrap();
+        assert!(verify_same_message_across((&a1, &b1), &c1, (&a2, &b2), &c2, &proof));
+        // 交换两边的参数后证明不再成立
+        assert!(!verify_same_message_across((&a2, &b2), &c1, (&a1, &b1), &c2, &proof));
+        assert!(!verify_same_message_across((&a2, &b2), &c2, (&a1, &b1), &c1, &proof));
+    }
+
+    #[test]
+    fn sessions_with_different_messages_do_not_link() {
+        let mut rng = StdRng::seed_from_u64(216);
+        let ((a1, b1), (a2, b2)) = (utils::generate_params_with_rng(&mut rng), utils::generate_params_with_rng(&mut rng));
+        let s1 = RevelerCommit::new(a1.clone(), b1.clone(), vec![7, 0, 3], utils::generate_randomness(&mut rng));
+        let s2 = RevelerCommit::new(a2.clone(), b2.clone(), vec![7, 0, 4], utils::generate_randomness(&mut rng));
+        let (c1, c2) = (s1.commit().unwrap(), s2.commit().unwrap());
+        assert_eq!(prove_same_message_across(&s1, &c1, &s2, &c2), Err(CommitError::InvalidOpening));
+
+        // 绕过检查直接证明关系也无法通过验证
+        let relation = linking_relation((&a1, &b1), &c1, (&a2, &b2), &c2);
+        let witness = Witness { free: s1.local_m.clone(), blocks: vec![s1.local_r.clone(), s2.local_r.clone()] };
+        let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), Workers::Threads).unwrap().rounds;
+        assert!(!verify_same_message_across((&a1, &b1), &c1, (&a2, &b2), &c2, &LinkingProof { rounds }));
+    }
+
+    #[test]
+    fn equal_messages_verify_across_keys() {
+        let mut rng = StdRng::seed_from_u64(266);
+        let (key1, key2) = (CommitmentKey::setup(&mut rng), CommitmentKey::setup(&mut rng));
+        let o1 = Opening::new(vec![4, 8, 15], utils::generate_randomness(&mut rng));
+        let o2 = Opening::new(vec![4, 8, 15], utils::generate_randomness(&mut rng));
+        let (c1, c2) = (key1.commit_opening(&o1).unwrap(), key2.commit_opening(&o2).unwrap());
+        let proof = prove_equal(&key1, &c1, &o1, &key2, &c2, &o2).unwrap();
+        assert!(verify_equal(&key1, &c1, &key2, &c2, &proof));
+        assert!(!verify_equal(&key2, &c2, &key1, &c1, &proof));
+        assert!(!verify_equal(&key1, &c1, &key1, &c2, &proof));
+    }
+
+    #[test]
+    fn forged_openings_of_other_messages_are_rejected() {
+        // c2 承诺另一条消息，伪造者把差值塞进 [B | I] 的单位块，使 o2 也"打开"为 m
+        let mut rng = StdRng::seed_from_u64(266);
+        let (key1, key2) = (CommitmentKey::setup(&mut rng), CommitmentKey::setup(&mut rng));
+        let q = CommitConfig::default().modulus;
+        let o1 = Opening::new(vec![4, 8, 15], utils::generate_randomness(&mut rng));
+        let hidden = Opening::new(vec![4, 8, 16], utils::generate_randomness(&mut rng));
+        let (c1, c2) = (key1.commit_opening(&o1).unwrap(), key2.commit_opening(&hidden).unwrap());
+        let shift = key2.commit(&[0, 0, 1], &[]).unwrap();
+        let mut forged_r = hidden.local_r().to_vec();
+        for (slot, &x) in forged_r.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(shift.commitment_point()) {
+            *slot = (*slot + x) % q;
+        }
+        let o2 = Opening::new(o1.local_m().to_vec(), forged_r);
+        assert!(key2.reproduces(&c2, &o2).unwrap());
+        assert_eq!(prove_equal(&key1, &c1, &o1, &key2, &c2, &o2), Err(CommitError::InvalidOpening));
+
+        let relation = linking_relation(key1.as_pair(), &c1, key2.as_pair(), &c2);
+        let witness = Witness { free: o1.local_m().to_vec(), blocks: vec![o1.local_r().to_vec(), o2.local_r().to_vec()] };
+        let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), Workers::Threads).unwrap().rounds;
+        assert!(!verify_equal(&key1, &c1, &key2, &c2, &LinkingProof { rounds }));
+    }
+}
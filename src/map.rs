@@ -0,0 +1,189 @@
+//! This module provides map commitments: commitments to key-value maps with per-key openings.
+//!
+//! `commit_map` sorts the entries of a `BTreeMap` by key and commits to them in the hash tree of
+//! `hierarchy`, so a `MapCommitment` is independent of the insertion order. `CommittedMap` keeps
+//! the tree to prove statements about single keys:
+//! - `prove_presence` returns the `InclusionProof` of the entry of a key, checked with
+//!   `verify_presence` against the key and its value.
+//! - `prove_absence` returns the `AbsenceProof` of a missing key: the entries immediately below and
+//!   above it, at adjacent indices, or the single neighbour at the edge of the map. It is checked
+//!   with `verify_absence`.
+//!
+//! The commitment is history-independent: the tree depends only on the sorted entries and their
+//! number, so two parties building the same key-value set independently, in any order and with any
+//! sequence of `CommittedMap::insert` and `CommittedMap::remove`, reach the same `MapCommitment`
+//! and the same proofs. The updates rebuild the tree from the sorted entries rather than patching
+//! it, which costs one hash per entry and keeps no trace of the previous layout.
+//!
+//! Absence proofs rely on the entries being sorted, which the verifier cannot check from the root
+//! alone: they convince of absence as far as the commitment was produced by `commit_map`. Values
+//! are arbitrary bytes and are not hidden by the commitment; to keep them secret, store the
+//! `RevelerResult::to_bytes` of a commitment to the value instead.
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::hierarchy::{self, AggregateCommitment, AggregateTree, InclusionProof};
+use crate::utils;
+
+pub(crate) const ENTRY_DOMAIN: &[u8] = b"reveler/map/entry";
+pub(crate) const EMPTY_DOMAIN: &[u8] = b"reveler/map/empty";
+
+/// Commitment to a key-value map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapCommitment {
+    pub count: u64,    // The number of entries
+    pub root: Vec<u8>, // The root of the hash tree over the sorted entries
+}
+
+/// Entry of a map together with the proof of its index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapNeighbour {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub proof: InclusionProof,
+}
+
+/// Proof that a key is not in a map.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbsenceProof {
+    pub lower: Option<MapNeighbour>, // The entry with the largest key below the missing key
+    pub upper: Option<MapNeighbour>, // The entry with the smallest key above the missing key
+}
+
+/// Committed map, kept by its producer to issue presence and absence proofs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommittedMap {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    tree: Option<AggregateTree>, // None for the empty map
+    commitment: MapCommitment,
+}
+
+/// Commits to a map.
+///
+/// # Returns
+/// The `CommittedMap`, or `CommitError::ComputationError` if the hash tree cannot be built.
+pub fn commit_map(entries: BTreeMap<Vec<u8>, Vec<u8>>) -> Result<CommittedMap, CommitError> {
+    if entries.is_empty() {
+        let commitment = MapCommitment { count: 0, root: utils::hash_to_commitment(EMPTY_DOMAIN) };
+        return Ok(CommittedMap { entries, tree: None, commitment });
+    }
+    let children: Vec<Vec<u8>> = entries.iter().map(|(key, value)| encode_entry(key, value)).collect();
+    let tree = hierarchy::build(0, &children)?;
+    let aggregate = tree.aggregate();
+    let commitment = MapCommitment { count: aggregate.count, root: aggregate.root.clone() };
+    Ok(CommittedMap { entries, tree: Some(tree), commitment })
+}
+
+impl CommittedMap {
+    /// Returns the commitment to the map.
+    pub fn commitment(&self) -> &MapCommitment {
+        &self.commitment
+    }
+
+    /// Returns the committed entries.
+    pub fn entries(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        &self.entries
+    }
+
+    /// Returns the value of `key`, or `None` if the map has no such key.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    /// Sets the value of `key`, recommitting to the updated map.
+    ///
+    /// # Returns
+    /// The previous value of `key`, if any, or `CommitError::ComputationError` if the hash tree
+    /// cannot be built.
+    pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<Option<Vec<u8>>, CommitError> {
+        let mut entries = self.entries.clone();
+        let previous = entries.insert(key, value);
+        *self = commit_map(entries)?;
+        Ok(previous)
+    }
+
+    /// Removes `key`, recommitting to the updated map.
+    ///
+    /// # Returns
+    /// The removed value, if any, or `CommitError::ComputationError` if the hash tree cannot be
+    /// built.
+    pub fn remove(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, CommitError> {
+        let mut entries = self.entries.clone();
+        let Some(previous) = entries.remove(key) else {
+            return Ok(None);
+        };
+        *self = commit_map(entries)?;
+        Ok(Some(previous))
+    }
+
+    /// Returns the value of `key` and the proof of its entry, or `None` if the map has no such key.
+    pub fn prove_presence(&self, key: &[u8]) -> Option<(&[u8], InclusionProof)> {
+        let value = self.entries.get(key)?;
+        let proof = self.tree.as_ref()?.prove(self.position(key) as u64)?;
+        Some((value, proof))
+    }
+
+    /// Returns the proof that `key` is missing, or `None` if the map has the key.
+    pub fn prove_absence(&self, key: &[u8]) -> Option<AbsenceProof> {
+        if self.entries.contains_key(key) {
+            return None;
+        }
+        // 插入位置即上邻居的下标
+        let position = self.position(key);
+        let neighbour = |index: usize| -> Option<MapNeighbour> {
+            let (entry_key, value) = self.entries.iter().nth(index)?;
+            let proof = self.tree.as_ref()?.prove(index as u64)?;
+            Some(MapNeighbour { key: entry_key.clone(), value: value.clone(), proof })
+        };
+        let lower = position.checked_sub(1).and_then(neighbour);
+        let upper = neighbour(position);
+        Some(AbsenceProof { lower, upper })
+    }
+
+    // Number of entries with a key below `key`.
+    fn position(&self, key: &[u8]) -> usize {
+        self.entries.keys().take_while(|entry_key| entry_key.as_slice() < key).count()
+    }
+}
+
+/// Checks that `key` maps to `value` in the committed map.
+pub fn verify_presence(commitment: &MapCommitment, key: &[u8], value: &[u8], proof: &InclusionProof) -> bool {
+    hierarchy::verify_path(&as_aggregate(commitment), &encode_entry(key, value), proof)
+}
+
+/// Checks that `key` is missing from the committed map.
+///
+/// The proof holds if both neighbours are included at adjacent indices with keys on either side
+/// of `key`, if a single neighbour is the first entry with a larger key or the last entry with a
+/// smaller key, or if the map is empty and the proof has no neighbour.
+pub fn verify_absence(commitment: &MapCommitment, key: &[u8], proof: &AbsenceProof) -> bool {
+    let included = |neighbour: &MapNeighbour| verify_presence(commitment, &neighbour.key, &neighbour.value, &neighbour.proof);
+    match (&proof.lower, &proof.upper) {
+        (None, None) => commitment.count == 0 && commitment.root == utils::hash_to_commitment(EMPTY_DOMAIN),
+        (Some(lower), None) => {
+            lower.key.as_slice() < key && lower.proof.index.checked_add(1) == Some(commitment.count) && included(lower)
+        }
+        (None, Some(upper)) => key < upper.key.as_slice() && upper.proof.index == 0 && included(upper),
+        (Some(lower), Some(upper)) => {
+            lower.key.as_slice() < key
+                && key < upper.key.as_slice()
+                && lower.proof.index.checked_add(1) == Some(upper.proof.index)
+                && included(lower)
+                && included(upper)
+        }
+    }
+}
+
+// The encoding starts with the zero high byte of the domain length, unlike the scheme header of
+// `RevelerResult::to_bytes`, so map entries and commitments never share a leaf of level 0.
+fn encode_entry(key: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut bytes = utils::encode_bytes(ENTRY_DOMAIN);
+    bytes.extend(utils::encode_bytes(key));
+    bytes.extend(utils::encode_bytes(value));
+    bytes
+}
+
+fn as_aggregate(commitment: &MapCommitment) -> AggregateCommitment {
+    AggregateCommitment { level: 0, count: commitment.count, root: commitment.root.clone() }
+}
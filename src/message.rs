@@ -0,0 +1,347 @@
+//! This module encodes byte strings as message vectors, so callers commit to bytes directly.
+//!
+//! The encoding of a byte string of length `len` is a vector of `MESSAGE_LEN` coordinates:
+//! - coordinate 0 holds `len`,
+//! - the following coordinates hold the bits of the bytes, most significant first, in chunks of
+//!   `BITS_PER_COORDINATE` bits, the last chunk padded with zero bits,
+//! - every remaining coordinate is zero.
+//!
+//! Every chunk is below `2^15 < LOCAL_Q`, so each coordinate is a valid field element, and the
+//! length binds the number of meaningful bits, so strings differing only by trailing zero bytes
+//! have distinct encodings. `decode_message` accepts exactly the vectors `encode_message` produces
+//! and rejects every other one, so the encoding is injective and an opening decodes to a single
+//! byte string. Strings of up to `MAX_MESSAGE_BYTES` bytes fit in one message.
+//!
+//! Longer strings are split by `encode_message_chunks` into one message per `CHUNK_BYTES` bytes,
+//! each committed separately with `CommitmentKey::commit_bytes_chunked`. A chunk starts with the
+//! `SCALAR_LIMBS` limbs of the length of the whole string and the limbs of the index of the chunk,
+//! followed by its bytes packed as above. `decode_message_chunks` requires every chunk to carry
+//! the same length and its own index and the number of chunks to match the length, so chunks
+//! cannot be dropped, reordered or taken from another string. A string short enough for one message
+//! is a single chunk holding `encode_message`, so both encodings agree wherever both apply, and a
+//! split string is longer than `MAX_MESSAGE_BYTES`, so no sequence of chunks decodes both ways.
+//!
+//! A `u64` scalar such as a bid, a nonce or a vote is committed with `CommitmentKey::commit_scalar`:
+//! its `SCALAR_LIMBS` chunks of `BITS_PER_COORDINATE` bits, least significant first, fill the
+//! first coordinates and the fresh randomness hides them.
+//! `CommitmentKey::open_scalar` checks the opening and reads the scalar back from its limbs.
+
+use crate::commit_error::CommitError;
+use crate::fft::MESSAGE_LEN;
+use crate::key::{CommitmentKey, Opening};
+#[cfg(feature = "prover")]
+use crate::utils;
+use crate::RevelerResult;
+
+/// Number of message bits carried by each coordinate after the length.
+pub const BITS_PER_COORDINATE: usize = 15;
+
+/// Largest byte string `encode_message` accepts.
+pub const MAX_MESSAGE_BYTES: usize = (MESSAGE_LEN - 1) * BITS_PER_COORDINATE / 8;
+
+/// Number of coordinates holding a scalar committed with `CommitmentKey::commit_scalar`.
+pub const SCALAR_LIMBS: usize = 64usize.div_ceil(BITS_PER_COORDINATE);
+
+/// Number of bytes of a string longer than `MAX_MESSAGE_BYTES` carried by each chunk, after the
+/// limbs of the length and of the index.
+pub const CHUNK_BYTES: usize = (MESSAGE_LEN - 2 * SCALAR_LIMBS) * BITS_PER_COORDINATE / 8;
+
+/// Encodes a byte string as a message vector of `MESSAGE_LEN` coordinates.
+///
+/// # Returns
+/// The message vector, or `CommitError::ComputationError` if `bytes` is longer than
+/// `MAX_MESSAGE_BYTES`.
+pub fn encode_message(bytes: &[u8]) -> Result<Vec<u64>, CommitError> {
+    if bytes.len() > MAX_MESSAGE_BYTES {
+        return Err(CommitError::ComputationError);
+    }
+    let mut message = Vec::with_capacity(MESSAGE_LEN);
+    message.push(bytes.len() as u64);
+    pack_bits(bytes, &mut message);
+    Ok(message)
+}
+
+// Append the bits of `bytes` in chunks of `BITS_PER_COORDINATE` bits, most significant first, then
+// pad `message` with zeros to `MESSAGE_LEN` coordinates.
+fn pack_bits(bytes: &[u8], message: &mut Vec<u64>) {
+    // 按位累积，每满 15 位输出一个坐标
+    let (mut buffer, mut bits) = (0u64, 0usize);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u64::from(byte);
+        bits += 8;
+        if bits >= BITS_PER_COORDINATE {
+            bits -= BITS_PER_COORDINATE;
+            message.push(buffer >> bits);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bits > 0 {
+        message.push(buffer << (BITS_PER_COORDINATE - bits));
+    }
+    message.resize(MESSAGE_LEN, 0);
+}
+
+/// Decodes a message vector written by `encode_message`.
+///
+/// # Returns
+/// The byte string, or `CommitError::MalformedEncoding` if the vector is not the encoding of any
+/// byte string: a wrong number of coordinates, a length above `MAX_MESSAGE_BYTES`, a chunk of
+/// `2^15` or more, non-zero padding bits or a non-zero coordinate past the last chunk.
+pub fn decode_message(message: &[u64]) -> Result<Vec<u8>, CommitError> {
+    let Some((&len, chunks)) = message.split_first() else {
+        return Err(CommitError::MalformedEncoding);
+    };
+    if message.len() != MESSAGE_LEN || len > MAX_MESSAGE_BYTES as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    unpack_bits(chunks, len as usize)
+}
+
+// Read `len` bytes from the chunks written by `pack_bits`, rejecting chunks of `2^15` or more,
+// non-zero padding bits and non-zero coordinates past the last chunk.
+fn unpack_bits(chunks: &[u64], len: usize) -> Result<Vec<u8>, CommitError> {
+    let used = (len * 8).div_ceil(BITS_PER_COORDINATE);
+    let (used_chunks, rest) = chunks.split_at(used.min(chunks.len()));
+    if used > chunks.len() || rest.iter().any(|&x| x != 0) || used_chunks.iter().any(|&x| x >> BITS_PER_COORDINATE != 0) {
+        return Err(CommitError::MalformedEncoding);
+    }
+
+    let mut bytes = Vec::with_capacity(len);
+    let (mut buffer, mut bits) = (0u64, 0usize);
+    for &chunk in used_chunks {
+        buffer = (buffer << BITS_PER_COORDINATE) | chunk;
+        bits += BITS_PER_COORDINATE;
+        while bits >= 8 && bytes.len() < len {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // 剩余的填充位必须为零
+    if buffer != 0 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok(bytes)
+}
+
+/// Encodes a byte string of any length as message vectors of `MESSAGE_LEN` coordinates.
+///
+/// # Returns
+/// `encode_message(bytes)` alone if `bytes` holds at most `MAX_MESSAGE_BYTES` bytes, otherwise one
+/// message per `CHUNK_BYTES` bytes: the limbs of the length of `bytes`, the limbs of the index of
+/// the chunk, then its bytes packed as by `encode_message`.
+pub fn encode_message_chunks(bytes: &[u8]) -> Vec<Vec<u64>> {
+    if let Ok(message) = encode_message(bytes) {
+        return vec![message];
+    }
+    bytes.chunks(CHUNK_BYTES).enumerate().map(|(index, chunk)| {
+        let mut message = Vec::with_capacity(MESSAGE_LEN);
+        message.extend(encode_scalar(bytes.len() as u64));
+        message.extend(encode_scalar(index as u64));
+        pack_bits(chunk, &mut message);
+        message
+    }).collect()
+}
+
+/// Decodes message vectors written by `encode_message_chunks`.
+///
+/// # Returns
+/// The byte string, or `CommitError::MalformedEncoding` if the vectors are not the chunks of any
+/// byte string: a single vector that `decode_message` rejects, or chunks disagreeing on the
+/// length, out of order, fewer or more than the length requires, or with malformed bytes.
+pub fn decode_message_chunks(messages: &[Vec<u64>]) -> Result<Vec<u8>, CommitError> {
+    let [first, ..] = messages else {
+        return Err(CommitError::MalformedEncoding);
+    };
+    if messages.len() == 1 {
+        return decode_message(first);
+    }
+    let len = usize::try_from(decode_scalar(first)?).map_err(|_| CommitError::MalformedEncoding)?;
+    if len <= MAX_MESSAGE_BYTES || messages.len() != len.div_ceil(CHUNK_BYTES) {
+        return Err(CommitError::MalformedEncoding);
+    }
+
+    let mut bytes = Vec::with_capacity(len);
+    for (index, message) in messages.iter().enumerate() {
+        let header = message.get(..2 * SCALAR_LIMBS).ok_or(CommitError::MalformedEncoding)?;
+        let (chunk_len, chunk_index) = header.split_at(SCALAR_LIMBS);
+        if message.len() != MESSAGE_LEN
+            || decode_scalar(chunk_len)? != len as u64
+            || decode_scalar(chunk_index)? != index as u64
+        {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let chunk_bytes = CHUNK_BYTES.min(len - bytes.len());
+        bytes.extend(unpack_bits(message.get(2 * SCALAR_LIMBS..).unwrap_or_default(), chunk_bytes)?);
+    }
+    Ok(bytes)
+}
+
+/// Splits a scalar into its `SCALAR_LIMBS` chunks of `BITS_PER_COORDINATE` bits, least
+/// significant first.
+pub fn encode_scalar(value: u64) -> Vec<u64> {
+    (0..SCALAR_LIMBS).map(|limb| (value >> (limb * BITS_PER_COORDINATE)) & ((1 << BITS_PER_COORDINATE) - 1)).collect()
+}
+
+/// Reads a scalar from the first `SCALAR_LIMBS` coordinates of a message.
+///
+/// # Returns
+/// The scalar, or `CommitError::MalformedEncoding` if the message is too short or a limb does not
+/// fit its chunk of the 64 bits.
+pub fn decode_scalar(message: &[u64]) -> Result<u64, CommitError> {
+    let limbs = message.get(..SCALAR_LIMBS).ok_or(CommitError::MalformedEncoding)?;
+    let mut value = 0u64;
+    for (limb, &chunk) in limbs.iter().enumerate() {
+        let shift = limb * BITS_PER_COORDINATE;
+        // 最高的一块只剩 64 - 60 = 4 位
+        let width = BITS_PER_COORDINATE.min(64 - shift);
+        if chunk >> width != 0 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        value |= chunk << shift;
+    }
+    Ok(value)
+}
+
+impl CommitmentKey {
+    /// Commits to a byte string under this key with fresh randomness.
+    ///
+    /// # Returns
+    /// The `RevelerResult` and the `Opening` of the encoded message, or
+    /// `CommitError::ComputationError` if `bytes` is longer than `MAX_MESSAGE_BYTES`: commit to
+    /// longer strings with `commit_bytes_chunked`.
+    #[cfg(feature = "prover")]
+    pub fn commit_bytes(&self, bytes: &[u8]) -> Result<(RevelerResult, Opening), CommitError> {
+        let local_r = utils::generate_randomness(&mut rand::thread_rng());
+        let opening = Opening::new(encode_message(bytes)?, local_r);
+        let commitment = self.commit_opening(&opening)?;
+        Ok((commitment, opening))
+    }
+
+    /// Checks an opening of a byte-string commitment and decodes the byte string.
+    ///
+    /// # Returns
+    /// The committed byte string, `CommitError::InvalidOpening` if `opening` does not open
+    /// `commitment` under this key, or `CommitError::MalformedEncoding` if its message is not the
+    /// encoding of a byte string.
+    pub fn open_bytes(&self, commitment: &RevelerResult, opening: &Opening) -> Result<Vec<u8>, CommitError> {
+        if !self.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        decode_message(opening.local_m())
+    }
+
+    /// Commits to a byte string of any length under this key, one commitment per message of
+    /// `encode_message_chunks`, each with fresh randomness.
+    ///
+    /// # Returns
+    /// The commitments and the `Opening`s of the chunks, in order; a single commitment equal in
+    /// form to that of `commit_bytes` if `bytes` fits in one message.
+    #[cfg(feature = "prover")]
+    pub fn commit_bytes_chunked(&self, bytes: &[u8]) -> Result<(Vec<RevelerResult>, Vec<Opening>), CommitError> {
+        let mut rng = rand::thread_rng();
+        let openings: Vec<Opening> = encode_message_chunks(bytes)
+            .into_iter()
+            .map(|message| Opening::new(message, utils::generate_randomness(&mut rng)))
+            .collect();
+        let commitments = openings.iter().map(|opening| self.commit_opening(opening)).collect::<Result<_, _>>()?;
+        Ok((commitments, openings))
+    }
+
+    /// Checks the openings of the chunks of a byte-string commitment and decodes the byte string.
+    ///
+    /// # Returns
+    /// The committed byte string, `CommitError::InvalidOpening` if the numbers of commitments and
+    /// openings differ or an opening does not open its commitment under this key, or
+    /// `CommitError::MalformedEncoding` if the messages are not the chunks of a byte string.
+    pub fn open_bytes_chunked(&self, commitments: &[RevelerResult], openings: &[Opening]) -> Result<Vec<u8>, CommitError> {
+        if commitments.len() != openings.len() {
+            return Err(CommitError::InvalidOpening);
+        }
+        for (commitment, opening) in commitments.iter().zip(openings) {
+            if !self.verify(commitment, opening)? {
+                return Err(CommitError::InvalidOpening);
+            }
+        }
+        let messages: Vec<Vec<u64>> = openings.iter().map(|opening| opening.local_m().to_vec()).collect();
+        decode_message_chunks(&messages)
+    }
+
+    /// Commits to a scalar under this key with fresh randomness.
+    ///
+    /// The message holds the limbs of `value`.
+    ///
+    /// # Returns
+    /// The `RevelerResult` and the `Opening` of the message.
+    #[cfg(feature = "prover")]
+    pub fn commit_scalar(&self, value: u64) -> Result<(RevelerResult, Opening), CommitError> {
+        let opening = Opening::new(encode_scalar(value), utils::generate_randomness(&mut rand::thread_rng()));
+        let commitment = self.commit_opening(&opening)?;
+        Ok((commitment, opening))
+    }
+
+    /// Checks an opening of a scalar commitment and reads the scalar.
+    ///
+    /// # Returns
+    /// The committed scalar, `CommitError::InvalidOpening` if `opening` does not open `commitment`
+    /// under this key, or `CommitError::MalformedEncoding` if its message does not start with the
+    /// limbs of a scalar.
+    pub fn open_scalar(&self, commitment: &RevelerResult, opening: &Opening) -> Result<u64, CommitError> {
+        if !self.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        decode_scalar(opening.local_m())
+    }
+
+    /// Checks that `opening` opens `commitment` under this key to the scalar `value`.
+    pub fn verify_scalar(&self, commitment: &RevelerResult, opening: &Opening, value: u64) -> Result<bool, CommitError> {
+        match self.open_scalar(commitment, opening) {
+            Ok(opened) => Ok(opened == value),
+            Err(CommitError::InvalidOpening | CommitError::MalformedEncoding) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use super::*;
+
+    #[test]
+    fn chunks_round_trip_at_every_boundary() {
+        let mut rng = StdRng::seed_from_u64(257);
+        for len in [0, 1, MAX_MESSAGE_BYTES, MAX_MESSAGE_BYTES + 1, 2 * CHUNK_BYTES, 2 * CHUNK_BYTES + 1, 5000] {
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let messages = encode_message_chunks(&bytes);
+            assert_eq!(messages.len(), if len <= MAX_MESSAGE_BYTES { 1 } else { len.div_ceil(CHUNK_BYTES) });
+            assert!(messages.iter().all(|message| message.len() == MESSAGE_LEN && message.iter().all(|&x| x < crate::fft::LOCAL_Q)));
+            assert_eq!(decode_message_chunks(&messages), Ok(bytes));
+        }
+        assert_eq!(encode_message_chunks(b"short"), vec![encode_message(b"short").unwrap()]);
+    }
+
+    #[test]
+    fn tampered_chunks_are_rejected() {
+        let bytes = vec![0xa5; 3 * CHUNK_BYTES];
+        let messages = encode_message_chunks(&bytes);
+        // 丢弃、交换或混入其他字符串的块
+        assert!(decode_message_chunks(&messages[..2]).is_err());
+        assert!(decode_message_chunks(&[messages[1].clone(), messages[0].clone(), messages[2].clone()]).is_err());
+        let other = encode_message_chunks(&vec![0xa5; 3 * CHUNK_BYTES - 1]);
+        assert!(decode_message_chunks(&[messages[0].clone(), messages[1].clone(), other[2].clone()]).is_err());
+        assert!(decode_message_chunks(&[]).is_err());
+    }
+
+    #[test]
+    fn long_strings_open_through_the_key() {
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(257));
+        let bytes: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let (commitments, openings) = key.commit_bytes_chunked(&bytes).unwrap();
+        assert_eq!(key.open_bytes_chunked(&commitments, &openings), Ok(bytes));
+        assert_eq!(key.open_bytes_chunked(&commitments[1..], &openings[1..]), Err(CommitError::MalformedEncoding));
+        assert_eq!(key.open_bytes_chunked(&commitments[..1], &openings[1..2]), Err(CommitError::InvalidOpening));
+    }
+}
@@ -0,0 +1,280 @@
+//! This module provides public parameter sets: the `(A, B)` matrices commitments are computed
+//! under, together with their modulus.
+//!
+//! `PublicParams::from_seed` expands the matrices from a `Seed` deterministically, so every party
+//! holding the seed recomputes the same parameters. Row `i` of a matrix is
+//! `utils::hash_to_range(domain, seed ‖ i, LOCAL_N, q)`, so the rows are expanded in parallel.
+//! Expansion goes through the process-wide `cache::params_cache()`, which applications enable once
+//! to expand every seed only once.
+//!
+//! `spec` returns the constants and derivation rules of the scheme as a serializable `SchemeSpec`,
+//! for circuit writers and foreign implementations.
+//!
+//! A parameter set may carry an expiry time, and `PublicParams::check` refuses it once expired or
+//! revoked by the installed `revocation::ParamsRevocationList`. Verifiers only see the matrices:
+//! `revocation::register_expiry` makes every verification of the process enforce the expiry.
+
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use crate::arith::REDUCTION_ARITY;
+use crate::cache;
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::fft::{LOCAL_N, LOCAL_Q, MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::policy::RANDOMNESS_BOUND;
+use crate::scheme::SchemeId;
+use crate::seed::Seed;
+use crate::{revocation, utils};
+
+const PARAMS_A_DOMAIN: &[u8] = b"reveler/seed/params/a";
+const PARAMS_B_DOMAIN: &[u8] = b"reveler/seed/params/b";
+const PARAMS_KEY_DOMAIN: &[u8] = b"reveler/params/key";
+// 沿用握手时的域名，摘要与旧版本保持一致
+const PARAMS_DIGEST_DOMAIN: &[u8] = b"reveler/transport/params";
+
+/// Machine-readable description of the constants and derivation rules of the scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemeSpec {
+    pub scheme: SchemeId,        // The identifier written in artifact headers
+    pub modulus: u64,            // The modulus q
+    pub dimension: usize,        // The dimension N
+    pub ntt_root: Option<u64>,   // A primitive N-th root of unity modulo q, if q has one
+    pub hash: HashSpec,          // The hash every digest and challenge is built on
+    pub rules: Vec<SpecEntry>,   // The derivation rules, by name
+    pub domains: Vec<SpecEntry>, // The domain tags, by the name of their constant
+}
+
+/// Hash function of a `SchemeSpec`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashSpec {
+    pub function: String,    // The underlying hash function
+    pub output_bytes: usize, // The length of a digest
+    pub extra_rounds: usize, // The number of times the output is fed back before it is returned
+}
+
+/// Named entry of a `SchemeSpec`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecEntry {
+    pub name: String,
+    pub value: String,
+}
+
+/// Public parameter set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicParams {
+    local_a: Vec<Vec<u64>>,  // The matrix A applied to the message
+    local_b: Vec<Vec<u64>>,  // The matrix B applied to the randomness
+    modulus: u64,            // The modulus q the matrix entries are reduced by
+    expires_at: Option<u64>, // The time after which the parameters are stale, if any
+}
+
+impl PublicParams {
+    /// Creates a parameter set from its matrices and modulus.
+    pub fn new(local_a: Vec<Vec<u64>>, local_b: Vec<Vec<u64>>, modulus: u64) -> Self {
+        PublicParams { local_a, local_b, modulus, expires_at: None }
+    }
+
+    /// Sets the time, in seconds since the Unix epoch, after which the parameters are stale.
+    pub fn with_expiry(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Returns the parameters expanded from `seed` for `config`, through the parameters cache.
+    ///
+    /// # Parameters
+    /// - `seed`: A reference to the `Seed` of the parameters.
+    /// - `config`: A reference to the `CommitConfig` the parameters are used with. Only its
+    ///   modulus affects the expansion.
+    ///
+    /// # Returns
+    /// The shared `PublicParams`, expanded on the first call for the seed and modulus, or on every
+    /// call while the cache is disabled.
+    pub fn from_seed(seed: &Seed, config: &CommitConfig) -> Arc<PublicParams> {
+        let mut key = PARAMS_KEY_DOMAIN.to_vec();
+        key.extend(utils::encode_bytes(seed.as_bytes()));
+        key.extend_from_slice(&config.modulus.to_be_bytes());
+        cache::params_cache().get_or_expand(utils::hash_to_commitment(&key), || Self::expand(seed, config))
+    }
+
+    /// Expands the parameters from `seed` for `config` without consulting the cache.
+    pub fn expand(seed: &Seed, config: &CommitConfig) -> PublicParams {
+        let (local_a, local_b) = expand_matrices(seed.as_bytes(), config.modulus);
+        PublicParams::new(local_a, local_b, config.modulus)
+    }
+
+    /// Returns the matrix A.
+    pub fn local_a(&self) -> &[Vec<u64>] {
+        &self.local_a
+    }
+
+    /// Returns the matrix B.
+    pub fn local_b(&self) -> &[Vec<u64>] {
+        &self.local_b
+    }
+
+    /// Returns the modulus.
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// Returns the expiry time, if any.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Checks that the parameters are still in use.
+    ///
+    /// # Returns
+    /// `Ok(())`, `CommitError::ParamsExpired` if the expiry time is before the current time of
+    /// `clock`, or `CommitError::ParamsRevoked` if the installed revocation list names the
+    /// parameters.
+    pub fn check(&self, clock: &dyn Clock) -> Result<(), CommitError> {
+        if self.expires_at.is_some_and(|expires_at| expires_at < clock.now()) {
+            return Err(CommitError::ParamsExpired);
+        }
+        revocation::check(self.as_pair())
+    }
+
+    /// Returns the `(A, B)` pair the commitment and proof functions take.
+    pub fn as_pair(&self) -> (&[Vec<u64>], &[Vec<u64>]) {
+        (&self.local_a, &self.local_b)
+    }
+
+    /// Returns the digest of the matrices, see `params_digest`.
+    pub fn digest(&self) -> Vec<u8> {
+        params_digest(self.as_pair())
+    }
+}
+
+/// Computes the digest identifying the `(A, B)` matrices of a round, as announced in transport
+/// handshakes and opening envelopes.
+pub fn params_digest(params: (&[Vec<u64>], &[Vec<u64>])) -> Vec<u8> {
+    let mut input = PARAMS_DIGEST_DOMAIN.to_vec();
+    input.extend(utils::encode_matrix(params.0));
+    input.extend(utils::encode_matrix(params.1));
+    utils::hash_to_commitment(&input)
+}
+
+/// Returns the constants and derivation rules of the scheme implemented by this build.
+///
+/// The rules are written against the byte encodings of `utils`: `u64` values are 8-byte
+/// big-endian, `len(x) ‖ x` prefixes a byte string with its length and `H` is `hash_to_commitment`.
+/// `ntt_root` is `None`: `q = 65535 = 3·5·17·257` has no primitive `N`-th root of unity, and the
+/// point is a plain matrix-vector product computed with exact integer arithmetic. Domain tags of
+/// modules behind disabled features are left out.
+pub fn spec() -> SchemeSpec {
+    let entry = |name: &str, value: &str| SpecEntry { name: name.to_string(), value: value.to_string() };
+    let rules = vec![
+        entry("commitment_point", &format!("point_i = Σ_k (A_i,k·m_k + B_i,k·r_k) + r_(N+i) mod q for i < N, i.e. A·m + [B | I]·r, where m holds at most {MESSAGE_LEN} and r at most {RANDOMNESS_LEN} coordinates, both zero-padded")),
+        entry("randomness", &format!("a fresh commitment draws r uniformly from {{q - 1, 0, 1}}^{RANDOMNESS_LEN}; an opening verifies only if every coordinate of r, read in (-q/2, q/2], has absolute value at most {RANDOMNESS_BOUND}, or k·{RANDOMNESS_BOUND} for the opening of a sum of k commitments")),
+        entry("reduction_order", &format!("point_i sums the terms s_k = (A_i,k·m_k + B_i,k·r_k) mod q for k below the longer of the last non-zero coordinates of m and r within the first N, followed by r_(N+i), in a tree of arity {REDUCTION_ARITY}: each level replaces every run of {REDUCTION_ARITY} consecutive values, the last possibly shorter, by its sum mod q taken left to right, until one value remains; rows are computed independently and chunks of rows are concatenated in row order")),
+        entry("commitment_hash", "H(point_0 ‖ … ‖ point_(N-1)), every coordinate as u64"),
+        entry("hash", "H(x): h = BlueHash256(x), then extra_rounds times h = BlueHash256 of the same hasher state updated with h"),
+        entry("hash_to_range", "seed = H(len(label) ‖ label ‖ len(input) ‖ input); block c = H(seed ‖ c) for c = 0, 1, …, each read as four u64 words; a word w is accepted if w < ⌊(2^64 - 1) / bound⌋·bound and yields w mod bound"),
+        entry("matrix_expansion", "row i of A = hash_to_range(PARAMS_A_DOMAIN, seed ‖ i, N, q), row i of B likewise with PARAMS_B_DOMAIN"),
+        entry("params_digest", "H(PARAMS_DIGEST_DOMAIN ‖ len(A) ‖ rows of A ‖ len(B) ‖ rows of B), every row as len(row) ‖ row"),
+        entry("artifact_header", "one byte len(id) followed by id = reveler/v<version>/<backend>-q<q>-n<N>/<hasher>"),
+    ];
+
+    let domains = vec![
+        ("PARAMS_A_DOMAIN", PARAMS_A_DOMAIN),
+        ("PARAMS_B_DOMAIN", PARAMS_B_DOMAIN),
+        ("PARAMS_KEY_DOMAIN", PARAMS_KEY_DOMAIN),
+        ("PARAMS_DIGEST_DOMAIN", PARAMS_DIGEST_DOMAIN),
+        ("amounts::BALANCE_LABEL", crate::amounts::BALANCE_LABEL),
+        ("audit::AUDIT_ID_DOMAIN", crate::audit::AUDIT_ID_DOMAIN),
+        ("audit::AUDIT_SCOPE_DOMAIN", crate::audit::AUDIT_SCOPE_DOMAIN),
+        ("audit::AUDIT_WRAP_DOMAIN", crate::audit::AUDIT_WRAP_DOMAIN),
+        ("audit::AUDIT_TAG_DOMAIN", crate::audit::AUDIT_TAG_DOMAIN),
+        ("batch::VERIFY_BATCH_DOMAIN", crate::batch::VERIFY_BATCH_DOMAIN),
+        ("binary::BINARY_DOMAIN", crate::binary::BINARY_DOMAIN),
+        ("binary::BITS_LABEL", crate::binary::BITS_LABEL),
+        ("bloom::INDEX_DOMAIN", crate::bloom::INDEX_DOMAIN),
+        ("bloom::DIGEST_DOMAIN", crate::bloom::DIGEST_DOMAIN),
+        ("delta::SEED_DOMAIN", crate::delta::SEED_DOMAIN),
+        ("disclosure::DISCLOSURE_DOMAIN", crate::disclosure::DISCLOSURE_DOMAIN),
+        ("envelope::ENVELOPE_SIGNING_DOMAIN", crate::envelope::ENVELOPE_SIGNING_DOMAIN),
+        ("envelope::ENVELOPE_CHECKSUM_DOMAIN", crate::envelope::ENVELOPE_CHECKSUM_DOMAIN),
+        ("hierarchy::LEAF_DOMAIN", crate::hierarchy::LEAF_DOMAIN),
+        ("hierarchy::NODE_DOMAIN", crate::hierarchy::NODE_DOMAIN),
+        ("hierarchy::ROOT_DOMAIN", crate::hierarchy::ROOT_DOMAIN),
+        ("linking::LINKING_DOMAIN", crate::linking::LINKING_DOMAIN),
+        ("map::ENTRY_DOMAIN", crate::map::ENTRY_DOMAIN),
+        ("map::EMPTY_DOMAIN", crate::map::EMPTY_DOMAIN),
+        ("pow::POW_DOMAIN", crate::pow::POW_DOMAIN),
+        ("proofs::KNOWLEDGE_DOMAIN", crate::proofs::KNOWLEDGE_DOMAIN),
+        ("proofs::BIT_DOMAIN", crate::proofs::BIT_DOMAIN),
+        ("proofs::SUM_DOMAIN", crate::proofs::SUM_DOMAIN),
+        ("proofs::AND_DOMAIN", crate::proofs::AND_DOMAIN),
+        ("proofs::OR_DOMAIN", crate::proofs::OR_DOMAIN),
+        ("provenance::PARAMS_FILE_SIGNING_DOMAIN", crate::provenance::PARAMS_FILE_SIGNING_DOMAIN),
+        ("protocol::messages::MESSAGE_SIGNING_DOMAIN", crate::protocol::messages::MESSAGE_SIGNING_DOMAIN),
+        ("protocol::round::ROUND_DESCRIPTOR_DOMAIN", crate::protocol::round::ROUND_DESCRIPTOR_DOMAIN),
+        ("range::RANGE_DOMAIN", crate::range::RANGE_DOMAIN),
+        ("revocation::REVOCATION_SIGNING_DOMAIN", crate::revocation::REVOCATION_SIGNING_DOMAIN),
+        ("seed::DERIVE_DOMAIN", crate::seed::DERIVE_DOMAIN),
+        ("seed::RANDOMNESS_DOMAIN", crate::seed::RANDOMNESS_DOMAIN),
+        ("seed::NONCE_DOMAIN", crate::seed::NONCE_DOMAIN),
+        ("tenant::TENANT_DOMAIN", crate::tenant::TENANT_DOMAIN),
+        ("transcript::TRANSCRIPT_DOMAIN", crate::transcript::TRANSCRIPT_DOMAIN),
+        ("transition::GENESIS_DOMAIN", crate::transition::GENESIS_DOMAIN),
+        ("transition::LINK_DOMAIN", crate::transition::LINK_DOMAIN),
+        ("transition::WITNESS_DOMAIN", crate::transition::WITNESS_DOMAIN),
+        ("vector::ENTRY_DOMAIN", crate::vector::ENTRY_DOMAIN),
+        ("vector::SALT_DOMAIN", crate::vector::SALT_DOMAIN),
+    ];
+    #[cfg(feature = "committee")]
+    let domains = [domains, vec![
+        ("committee::COMMITTEE_KEY_DOMAIN", crate::committee::COMMITTEE_KEY_DOMAIN),
+        ("committee::COMMITTEE_TAG_DOMAIN", crate::committee::COMMITTEE_TAG_DOMAIN),
+        ("committee::COMMITTEE_PROOF_DOMAIN", crate::committee::COMMITTEE_PROOF_DOMAIN),
+    ]].concat();
+    #[cfg(feature = "curve")]
+    let domains = [domains, vec![
+        ("pedersen::GENERATOR_DOMAIN", crate::pedersen::GENERATOR_DOMAIN),
+        ("pedersen::KEY_DIGEST_DOMAIN", crate::pedersen::KEY_DIGEST_DOMAIN),
+    ]].concat();
+    #[cfg(feature = "fast-digest")]
+    let domains = [domains, vec![("FAST_DIGEST_DOMAIN", crate::FAST_DIGEST_DOMAIN)]].concat();
+    #[cfg(feature = "timelock")]
+    let domains = [domains, vec![
+        ("timelock::TIMELOCK_KEY_DOMAIN", crate::timelock::TIMELOCK_KEY_DOMAIN),
+        ("timelock::TIMELOCK_TAG_DOMAIN", crate::timelock::TIMELOCK_TAG_DOMAIN),
+    ]].concat();
+
+    SchemeSpec {
+        scheme: SchemeId::current(),
+        modulus: LOCAL_Q,
+        dimension: LOCAL_N,
+        ntt_root: None,
+        hash: HashSpec {
+            function: "bluehash256".to_string(),
+            output_bytes: utils::hash_to_commitment(&[]).len(),
+            extra_rounds: utils::HASH_EXTRA_ROUNDS,
+        },
+        rules,
+        domains: domains.into_iter().map(|(name, tag)| entry(name, &String::from_utf8_lossy(tag))).collect(),
+    }
+}
+
+// Expand the A and B matrices from the seed with entries uniform modulo `modulus`.
+pub(crate) fn expand_matrices(seed: &[u8], modulus: u64) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    (expand_matrix(PARAMS_A_DOMAIN, seed, modulus), expand_matrix(PARAMS_B_DOMAIN, seed, modulus))
+}
+
+// Expand every row from seed ‖ row index, so that rows are independent and expanded in parallel.
+fn expand_matrix(domain: &[u8], seed: &[u8], modulus: u64) -> Vec<Vec<u64>> {
+    let row = |index: usize| {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&(index as u64).to_be_bytes());
+        utils::hash_to_range(domain, &input, LOCAL_N, modulus)
+    };
+
+    #[cfg(feature = "prover")]
+    if let Ok(rows) = utils::run_indexed(utils::Workers::Threads, LOCAL_N, |index| Ok(row(index))) {
+        return rows;
+    }
+    (0..LOCAL_N).map(row).collect()
+}
@@ -0,0 +1,234 @@
+//! This module provides Pedersen commitments over the Ristretto group as a `CommitmentScheme`.
+//!
+//! A commitment to the scalar `m` with blinding `r` is the point `m·G + r·H`, where `G` is the
+//! Ristretto basepoint and `H` a second generator nobody knows the discrete logarithm of:
+//! `PedersenKey::standard` derives it from a fixed domain, `PedersenKey::from_seed` from a seed,
+//! in both cases by mapping 64 bytes of `sampling::Xof` output to the group with
+//! `RistrettoPoint::from_uniform_bytes`. The commitment is perfectly hiding and computationally
+//! binding under the discrete logarithm assumption, and interoperates with protocols that expect
+//! Pedersen commitments over Ristretto.
+//!
+//! The commitment is additively homomorphic: `PedersenCommitment::add` of commitments to `m1` and
+//! `m2` opens with `PedersenOpening::add` to `m1 + m2`, and `scale` multiplies both by a scalar.
+//!
+//! Commitments are carried as compressed 32-byte points. The opening holds the scalars, is not
+//! serializable and its `Debug` output is redacted, like `key::Opening`.
+//!
+//! The module is compiled with the `curve` feature.
+
+use std::fmt;
+#[cfg(feature = "prover")]
+use rand::Rng;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use serde::{Serialize, Deserialize};
+use crate::backend::{CommitmentScheme, SchemeCapabilities};
+use crate::commit_error::CommitError;
+use crate::sampling::Xof;
+use crate::utils;
+
+pub(crate) const GENERATOR_DOMAIN: &[u8] = b"reveler/pedersen/generator";
+pub(crate) const KEY_DIGEST_DOMAIN: &[u8] = b"reveler/pedersen/key";
+
+/// Identifier of the scheme in `CommitmentScheme::capabilities`.
+pub const SCHEME_NAME: &str = "pedersen/ristretto255";
+
+/// Generators `G` and `H` of a Pedersen commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PedersenKey {
+    g: RistrettoPoint, // The generator the message is multiplied by
+    h: RistrettoPoint, // The generator the blinding is multiplied by
+}
+
+/// Pedersen commitment, a compressed Ristretto point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PedersenCommitment {
+    pub point: [u8; 32], // The compressed point m·G + r·H
+}
+
+/// Message and blinding of a Pedersen commitment.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PedersenOpening {
+    value: Scalar,    // The committed message
+    blinding: Scalar, // The blinding factor
+}
+
+/// Pedersen commitments over Ristretto as a `CommitmentScheme`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PedersenScheme;
+
+impl PedersenKey {
+    /// Returns the key with `H` derived from the fixed domain of the crate, shared by every party.
+    pub fn standard() -> Self {
+        PedersenKey::from_seed(&[])
+    }
+
+    /// Returns the key with `H` derived from `seed`.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let mut xof = Xof::new(GENERATOR_DOMAIN, seed);
+        let mut uniform = [0u8; 64];
+        for (chunk, word) in uniform.chunks_exact_mut(8).zip(xof.next_words(8)) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        PedersenKey { g: RISTRETTO_BASEPOINT_POINT, h: RistrettoPoint::from_uniform_bytes(&uniform) }
+    }
+
+    /// Returns the key with the generator `H` given in compressed form, as returned by `h`.
+    ///
+    /// # Returns
+    /// The key, or `CommitError::MalformedEncoding` if `h` is not the canonical encoding of a point.
+    pub fn from_h(h: [u8; 32]) -> Result<Self, CommitError> {
+        let h = CompressedRistretto(h).decompress().ok_or(CommitError::MalformedEncoding)?;
+        Ok(PedersenKey { g: RISTRETTO_BASEPOINT_POINT, h })
+    }
+
+    /// Returns the generator `H` in compressed form.
+    pub fn h(&self) -> [u8; 32] {
+        self.h.compress().to_bytes()
+    }
+
+    /// Returns the digest identifying the key, the counterpart of `params::params_digest` for the
+    /// lattice matrices.
+    pub fn digest(&self) -> Vec<u8> {
+        let mut input = KEY_DIGEST_DOMAIN.to_vec();
+        input.extend_from_slice(&self.g.compress().to_bytes());
+        input.extend_from_slice(&self.h());
+        utils::hash_to_commitment(&input)
+    }
+
+    /// Commits to `value` with the blinding `blinding`.
+    pub fn commit_with_blinding(&self, value: Scalar, blinding: Scalar) -> PedersenCommitment {
+        PedersenCommitment { point: (value * self.g + blinding * self.h).compress().to_bytes() }
+    }
+}
+
+impl PedersenCommitment {
+    /// Adds two commitments homomorphically.
+    ///
+    /// # Returns
+    /// The commitment to the sum of the messages, opened by `PedersenOpening::add` of the openings,
+    /// or `CommitError::MalformedEncoding` if either point is not the canonical encoding of a point.
+    pub fn add(&self, other: &PedersenCommitment) -> Result<PedersenCommitment, CommitError> {
+        let sum = self.decompress()? + other.decompress()?;
+        Ok(PedersenCommitment { point: sum.compress().to_bytes() })
+    }
+
+    /// Multiplies a commitment by the scalar `k` homomorphically.
+    ///
+    /// # Returns
+    /// The commitment to `k` times the message, opened by `PedersenOpening::scale`, or
+    /// `CommitError::MalformedEncoding` if the point is not the canonical encoding of a point.
+    pub fn scale(&self, k: Scalar) -> Result<PedersenCommitment, CommitError> {
+        Ok(PedersenCommitment { point: (k * self.decompress()?).compress().to_bytes() })
+    }
+
+    fn decompress(&self) -> Result<RistrettoPoint, CommitError> {
+        CompressedRistretto(self.point).decompress().ok_or(CommitError::MalformedEncoding)
+    }
+}
+
+impl PedersenOpening {
+    /// Creates an opening from its message and blinding.
+    pub fn new(value: Scalar, blinding: Scalar) -> Self {
+        PedersenOpening { value, blinding }
+    }
+
+    /// Returns the committed message.
+    pub fn value(&self) -> Scalar {
+        self.value
+    }
+
+    /// Returns the blinding factor.
+    pub fn blinding(&self) -> Scalar {
+        self.blinding
+    }
+
+    /// Adds two openings, the opening of `PedersenCommitment::add` of their commitments.
+    pub fn add(&self, other: &PedersenOpening) -> PedersenOpening {
+        PedersenOpening::new(self.value + other.value, self.blinding + other.blinding)
+    }
+
+    /// Multiplies an opening by `k`, the opening of `PedersenCommitment::scale` of its commitment.
+    pub fn scale(&self, k: Scalar) -> PedersenOpening {
+        PedersenOpening::new(k * self.value, k * self.blinding)
+    }
+}
+
+impl fmt::Debug for PedersenOpening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PedersenOpening(..)")
+    }
+}
+
+impl CommitmentScheme for PedersenScheme {
+    type Key = PedersenKey;
+    type Commitment = PedersenCommitment;
+    type Opening = PedersenOpening;
+    type Message = Scalar;
+
+    fn capabilities() -> SchemeCapabilities {
+        SchemeCapabilities {
+            scheme: SCHEME_NAME.to_string(),
+            homomorphic: true,
+            rerandomizable: false,
+            proofs: Vec::new(),
+            hashers: Vec::new(),
+            max_message_len: 1,
+            commitment_size: 32,
+        }
+    }
+
+    #[cfg(feature = "prover")]
+    fn setup<R: Rng>(rng: &mut R) -> PedersenKey {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        PedersenKey::from_seed(&seed)
+    }
+
+    #[cfg(feature = "prover")]
+    fn commit<R: Rng>(key: &PedersenKey, message: &Scalar, rng: &mut R) -> Result<(PedersenCommitment, PedersenOpening), CommitError> {
+        let mut wide = [0u8; 64];
+        rng.fill_bytes(&mut wide);
+        let opening = PedersenOpening::new(*message, Scalar::from_bytes_mod_order_wide(&wide));
+        Ok((key.commit_with_blinding(opening.value, opening.blinding), opening))
+    }
+
+    fn open(key: &PedersenKey, commitment: &PedersenCommitment, opening: &PedersenOpening) -> Result<Scalar, CommitError> {
+        if !Self::verify(key, commitment, &opening.value, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        Ok(opening.value)
+    }
+
+    fn verify(key: &PedersenKey, commitment: &PedersenCommitment, message: &Scalar, opening: &PedersenOpening) -> Result<bool, CommitError> {
+        // 拒绝非规范编码的点
+        commitment.decompress()?;
+        Ok(opening.value == *message && key.commit_with_blinding(opening.value, opening.blinding) == *commitment)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn sums_and_multiples_open_to_the_combined_messages() {
+        let mut rng = StdRng::seed_from_u64(262);
+        let key = PedersenScheme::setup(&mut rng);
+        let (first, first_opening) = PedersenScheme::commit(&key, &Scalar::from(3u64), &mut rng).unwrap();
+        let (second, second_opening) = PedersenScheme::commit(&key, &Scalar::from(4u64), &mut rng).unwrap();
+        assert!(PedersenScheme::capabilities().homomorphic);
+
+        // 3·(3 + 4) = 21
+        let combined = first.add(&second).unwrap().scale(Scalar::from(3u64)).unwrap();
+        let opening = first_opening.add(&second_opening).scale(Scalar::from(3u64));
+        assert_eq!(PedersenScheme::open(&key, &combined, &opening), Ok(Scalar::from(21u64)));
+
+        let malformed = PedersenCommitment { point: [0xff; 32] };
+        assert_eq!(first.add(&malformed), Err(CommitError::MalformedEncoding));
+    }
+}
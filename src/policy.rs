@@ -0,0 +1,241 @@
+//! This module provides `VerifyPolicy`, the rules a verifier applies to an opening beyond the
+//! commitment equation, as a value both parties agree on instead of code on one side.
+//!
+//! A policy names:
+//! - the norm metric and bound of the message and of the randomness of an opening, if any, with
+//!   coordinates read as centered representatives in `(-q/2, q/2]`, as for
+//!   `batch::OpeningBatch::infinity_norms`;
+//! - whether the binding hash of the commitment is checked, which a verifier holding the point
+//!   from a trusted source may skip.
+//!
+//! `VerifyPolicy::new` holds the rules of `RevelerCommit::verify_opening`: the randomness of a
+//! fresh commitment, with every coordinate in `{-1, 0, 1}`, and the hash checked. The bound is what
+//! makes the commitment binding: with `fft::MESSAGE_LEN` message coordinates, two openings of one
+//! point whose randomness differs by at most `2·β` per coordinate exist with probability at most
+//! `(4β + 1)^RANDOMNESS_LEN · q^(MESSAGE_LEN - LOCAL_N)` over the matrices, below `2^-1800` for
+//! `β = RANDOMNESS_BOUND`. Without a bound on the randomness, any message opens any point.
+//!
+//! The opening of a sum of `k` commitments carries randomness up to `k·RANDOMNESS_BOUND`, which
+//! `VerifyPolicy::for_sum` admits. The probability above stays below `2^-128` up to
+//! `MAX_BINDING_SUMMANDS` summands; past it, two openings may exist and binding rests on the
+//! hardness of finding them, a short-integer-solution problem. An `envelope::OpeningEnvelope` binds the policy of its sender under the
+//! signature and the checksum, and `OpeningEnvelope::verify_with_policy` refuses an envelope bound
+//! to another policy than the verifier's with `CommitError::PolicyViolation`, so neither party
+//! can quietly check less than agreed.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::{compute_point, revocation, RevelerCommit, RevelerResult};
+
+/// Largest centered coordinate of the randomness of a fresh commitment.
+pub const RANDOMNESS_BOUND: u64 = 1;
+
+/// Largest number of summands whose sum `VerifyPolicy::for_sum` still binds statistically.
+pub const MAX_BINDING_SUMMANDS: u64 = 13;
+
+/// Norm of a vector of centered coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormMetric {
+    /// The largest absolute value.
+    Infinity,
+    /// The sum of the absolute values.
+    L1,
+    /// The Euclidean norm, compared through its square.
+    L2,
+}
+
+/// Bound on the norm of a vector under a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormBound {
+    pub metric: NormMetric, // The metric the norm is measured in
+    pub bound: u64,         // The largest accepted norm
+}
+
+/// Rules a verifier applies to an opening.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyPolicy {
+    pub message_bound: Option<NormBound>,    // The bound on the message, if any
+    pub randomness_bound: Option<NormBound>, // The bound on the randomness, if any
+    pub check_hash: bool,                    // Whether the binding hash of the commitment is checked
+}
+
+impl NormMetric {
+    /// Checks that the norm of `values` modulo `q` is at most `bound`.
+    pub fn within(self, values: &[u64], q: u64, bound: u64) -> bool {
+        let q = q.max(1);
+        let centered = values.iter().map(|&x| {
+            let x = x % q;
+            x.min(q - x)
+        });
+        match self {
+            NormMetric::Infinity => centered.max().unwrap_or(0) <= bound,
+            NormMetric::L1 => centered.map(u128::from).sum::<u128>() <= u128::from(bound),
+            NormMetric::L2 => centered.map(|x| u128::from(x) * u128::from(x)).sum::<u128>() <= u128::from(bound) * u128::from(bound),
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            NormMetric::Infinity => 0,
+            NormMetric::L1 => 1,
+            NormMetric::L2 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self, CommitError> {
+        match tag {
+            0 => Ok(NormMetric::Infinity),
+            1 => Ok(NormMetric::L1),
+            2 => Ok(NormMetric::L2),
+            _ => Err(CommitError::MalformedEncoding),
+        }
+    }
+}
+
+impl VerifyPolicy {
+    /// Creates the policy of `RevelerCommit::verify_opening`: the randomness within
+    /// `RANDOMNESS_BOUND` in the infinity norm, no bound on the message, the hash checked.
+    pub fn new() -> Self {
+        VerifyPolicy::for_sum(1)
+    }
+
+    /// Creates the policy for the opening of a sum of `summands` fresh commitments, e.g. a tally:
+    /// the randomness within `summands·RANDOMNESS_BOUND` in the infinity norm, the hash checked.
+    ///
+    /// Past `MAX_BINDING_SUMMANDS` summands, binding is computational only, see the module
+    /// documentation.
+    pub fn for_sum(summands: u64) -> Self {
+        let randomness_bound = NormBound { metric: NormMetric::Infinity, bound: summands.max(1).saturating_mul(RANDOMNESS_BOUND) };
+        VerifyPolicy { message_bound: None, randomness_bound: Some(randomness_bound), check_hash: true }
+    }
+
+    /// Bounds the norm of the message under `metric`.
+    pub fn with_message_bound(mut self, metric: NormMetric, bound: u64) -> Self {
+        self.message_bound = Some(NormBound { metric, bound });
+        self
+    }
+
+    /// Bounds the norm of the randomness under `metric`.
+    pub fn with_randomness_bound(mut self, metric: NormMetric, bound: u64) -> Self {
+        self.randomness_bound = Some(NormBound { metric, bound });
+        self
+    }
+
+    /// Sets whether the binding hash of the commitment is checked.
+    pub fn with_hash_check(mut self, check_hash: bool) -> Self {
+        self.check_hash = check_hash;
+        self
+    }
+
+    /// Checks the norms of an opening modulo `q`.
+    ///
+    /// # Returns
+    /// `Ok(())`, or `CommitError::PolicyViolation` if the message or the randomness exceeds its
+    /// bound.
+    pub fn check_norms(&self, local_m: &[u64], local_r: &[u64], q: u64) -> Result<(), CommitError> {
+        let within = |values: &[u64], bound: Option<NormBound>| bound.is_none_or(|NormBound { metric, bound }| metric.within(values, q, bound));
+        if !within(local_m, self.message_bound) || !within(local_r, self.randomness_bound) {
+            return Err(CommitError::PolicyViolation);
+        }
+        Ok(())
+    }
+
+    /// Checks an opening of `commitment` under the policy.
+    ///
+    /// # Parameters
+    /// - `params`: The `(A, B)` matrices of the commitment.
+    /// - `commitment`: The commitment.
+    /// - `local_m`: The message vector.
+    /// - `local_r`: The randomness vector.
+    ///
+    /// # Returns
+    /// `true` if the opening reproduces the point and, if the policy checks it, the hash, `false`
+    /// otherwise; `CommitError::PolicyViolation` if the opening exceeds a norm bound,
+    /// `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if `revocation::check` refuses
+    /// the parameters, or the error of the computation if the point cannot be computed.
+    pub fn verify_opening(
+        &self,
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        commitment: &RevelerResult,
+        local_m: &[u64],
+        local_r: &[u64],
+    ) -> Result<bool, CommitError> {
+        self.verify_opening_with_config(params, commitment, local_m, local_r, &CommitConfig::default())
+    }
+
+    /// Checks an opening of `commitment` under the policy, recomputing the point with `config`.
+    ///
+    /// Services built with `strict-no-panic` pass a configuration such as
+    /// `CommitConfig::new().with_small_message_limit(usize::MAX)`, which computes the point on the
+    /// calling thread: spawning a worker thread panics if the system cannot create it.
+    ///
+    /// # Returns
+    /// As `verify_opening`, with the norms checked modulo the modulus of `config`.
+    pub fn verify_opening_with_config(
+        &self,
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        commitment: &RevelerResult,
+        local_m: &[u64],
+        local_r: &[u64],
+        config: &CommitConfig,
+    ) -> Result<bool, CommitError> {
+        self.check_norms(local_m, local_r, config.modulus)?;
+        revocation::check(params)?;
+        let point = compute_point(params.0, params.1, local_m, local_r, config)?;
+        Ok(point == commitment.commitment_point && (!self.check_hash || RevelerCommit::verify(commitment)))
+    }
+
+    /// Encodes the policy as a byte per bound, `0` if unset or `1` followed by the metric byte and
+    /// the `u64` bound, and the hash flag byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for bound in [self.message_bound, self.randomness_bound] {
+            match bound {
+                Some(NormBound { metric, bound }) => {
+                    bytes.push(1);
+                    bytes.push(metric.tag());
+                    bytes.extend_from_slice(&bound.to_be_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+        bytes.push(u8::from(self.check_hash));
+        bytes
+    }
+
+    /// Decodes a policy written by `to_bytes` from the front of `bytes`.
+    ///
+    /// # Returns
+    /// The policy and the remaining bytes, or `CommitError::MalformedEncoding` if the input does not
+    /// start with a policy.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), CommitError> {
+        let (message_bound, rest) = decode_bound(bytes)?;
+        let (randomness_bound, rest) = decode_bound(rest)?;
+        let (&flag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let check_hash = match flag {
+            0 => false,
+            1 => true,
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        Ok((VerifyPolicy { message_bound, randomness_bound, check_hash }, rest))
+    }
+}
+
+impl Default for VerifyPolicy {
+    fn default() -> Self {
+        VerifyPolicy::new()
+    }
+}
+
+fn decode_bound(bytes: &[u8]) -> Result<(Option<NormBound>, &[u8]), CommitError> {
+    match bytes.split_first() {
+        Some((0, rest)) => Ok((None, rest)),
+        Some((1, rest)) => {
+            let (&tag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+            let (bound, rest) = rest.split_first_chunk::<8>().ok_or(CommitError::MalformedEncoding)?;
+            Ok((Some(NormBound { metric: NormMetric::from_tag(tag)?, bound: u64::from_be_bytes(*bound) }), rest))
+        }
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
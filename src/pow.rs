@@ -0,0 +1,88 @@
+//! This module provides an optional proof-of-work binding for commitments.
+//!
+//! In open commit-reveal games a participant can grind through many commitments looking for a
+//! favourable one. Requiring a proof of work over every commitment makes each attempt cost
+//! roughly `2^difficulty` hash evaluations.
+//!
+//! A proof of work is a nonce such that `H("reveler/pow" ‖ commitment ‖ nonce)` starts with at
+//! least `difficulty` zero bits, where `commitment` is the encoding from `RevelerResult::to_bytes`
+//! and `H` is `hash_to_commitment`.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::{utils, RevelerCommit, RevelerResult};
+
+pub(crate) const POW_DOMAIN: &[u8] = b"reveler/pow";
+
+/// Number of bits in the proof-of-work digest, the largest meaningful difficulty.
+pub const MAX_DIFFICULTY: u32 = 256;
+
+/// Nonce binding a proof of work to a commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofOfWork {
+    pub nonce: u64,
+    pub difficulty: u32,
+}
+
+/// Computes the proof-of-work digest of a commitment and nonce.
+pub fn pow_digest(commitment: &RevelerResult, nonce: u64) -> Vec<u8> {
+    let mut input = POW_DOMAIN.to_vec();
+    input.extend(commitment.to_bytes());
+    input.extend_from_slice(&nonce.to_be_bytes());
+    utils::hash_to_commitment(&input)
+}
+
+/// Counts the leading zero bits of a digest.
+pub fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in digest {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Searches for a nonce whose digest has at least `difficulty` leading zero bits.
+///
+/// # Parameters
+/// - `commitment`: A reference to the `RevelerResult` to bind the work to.
+/// - `difficulty`: The required number of leading zero bits.
+///
+/// # Returns
+/// The `ProofOfWork` holding the first matching nonce, or `CommitError::DifficultyTooHigh` if
+/// `difficulty` exceeds `MAX_DIFFICULTY`.
+pub fn solve(commitment: &RevelerResult, difficulty: u32) -> Result<ProofOfWork, CommitError> {
+    if difficulty > MAX_DIFFICULTY {
+        return Err(CommitError::DifficultyTooHigh);
+    }
+
+    (0..=u64::MAX)
+        .find(|&nonce| leading_zero_bits(&pow_digest(commitment, nonce)) >= difficulty)
+        .map(|nonce| ProofOfWork { nonce, difficulty })
+        .ok_or(CommitError::ComputationError)
+}
+
+/// Checks a proof of work against a commitment.
+///
+/// # Parameters
+/// - `commitment`: A reference to the `RevelerResult` the work is bound to.
+/// - `proof`: A reference to the `ProofOfWork` to check.
+/// - `min_difficulty`: The difficulty the verifier requires, regardless of the claimed one.
+///
+/// # Returns
+/// `true` if the claimed difficulty is at least `min_difficulty` and the digest meets it.
+pub fn verify(commitment: &RevelerResult, proof: &ProofOfWork, min_difficulty: u32) -> bool {
+    proof.difficulty >= min_difficulty
+        && leading_zero_bits(&pow_digest(commitment, proof.nonce)) >= proof.difficulty
+}
+
+/// Verifies a commitment together with its proof of work.
+///
+/// # Returns
+/// `true` if `RevelerCommit::verify` accepts the commitment and the proof of work meets
+/// `min_difficulty`.
+pub fn verify_commitment(commitment: &RevelerResult, proof: &ProofOfWork, min_difficulty: u32) -> bool {
+    RevelerCommit::verify(commitment) && verify(commitment, proof, min_difficulty)
+}
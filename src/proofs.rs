@@ -0,0 +1,1058 @@
+//! This module provides proofs of knowledge of an opening: the prover convinces a verifier that it
+//! can open a commitment `c = A·m + [B | I]·r` with ternary randomness without revealing `(m, r)`.
+//!
+//! The proof is a `binary` proof of the relation `A·m + [B | I]·r = c` with `m` free and `r`
+//! ternary, three moves repeated in parallel rounds:
+//! 1. Commit: the prover sends the salted hashes `c1`, `c2` and `c3` of every round.
+//! 2. Challenge: the verifier answers with a challenge in `{0, 1, 2}` per round.
+//! 3. Response: the prover opens two of the three hashes, as the challenge asks.
+//!
+//! The randomness is part of the permuted witness, so a prover that passes every round knows an
+//! opening with ternary randomness, and by the binding of the commitment that is the opening of
+//! `c`: the identity block of `[B | I]`, which opens any commitment to any message with unbounded
+//! randomness, does not help. The number of rounds is that of `ProofConfig::binary_rounds`.
+//!
+//! The interactive protocol runs with `KnowledgeProver::start`, `KnowledgeChallenge::random` and
+//! `KnowledgeProver::respond`, checked with `verify_response`. `respond` consumes the prover: a
+//! second response to the same announcement would reveal the opening. `prove_knowledge` is the
+//! non-interactive variant, with the challenges derived from the key, the commitment and the
+//! announcement by Fiat–Shamir through a `transcript::Transcript`, checked with `verify_knowledge`.
+//! `prove_knowledge_with_transcript` and `verify_knowledge_with_transcript` run on the transcript
+//! of an enclosing protocol instead, any `transcript::ProofTranscript`.
+//!
+//! `prove_bit` shows that coordinate `i` of the message is 0 or 1, the building block of range
+//! proofs and voting protocols, without a separate bit commitment. It is a `binary` proof of the
+//! relation `A·m + [B | I]·r = c` with `m_i` a bit, `r` ternary and the other coordinates of `m`
+//! free, so the proof does not tell which value is committed. The randomness is part of the
+//! permuted witness, so a coordinate that is not a bit cannot be hidden in the identity block of
+//! `[B | I]`.
+//!
+//! `prove_sum` shows that coordinate `i` of the messages of several commitments under one key
+//! adds up to a public total, for auditable tallies of budgets or votes. The commitment is
+//! linear, so the sum `C` of `k` commitments, see `amounts::sum`, commits to the sums `(M, R)` of
+//! the openings, with `R` within `k` per coordinate. The proof is a `binary` proof of the relation
+//! `A·M + [B | I]·R = C` with `M_i` fixed to the total, the other coordinates of `M` free and `R`
+//! split into ternary blocks with the weights of `binary::weights(k)`. A prover that passes holds
+//! an opening of `C` with short randomness whose coordinate `i` is the total, and by the binding of
+//! the commitment that is the sum of the values. Binding is statistical up to
+//! `policy::MAX_BINDING_SUMMANDS` commitments and rests on the hardness of SIS past it. Sums are
+//! taken modulo `q`, so totals are exact below `q`.
+//!
+//! `prove_and` and `prove_or` compose openings of a list of commitments `c_1, …, c_n` under one
+//! transcript, so higher-level protocols need no challenge bookkeeping of their own. Both are
+//! `binary` proofs with ternary randomness in the permuted witness:
+//! - AND: the prover can open every commitment. The relation is `A·m_k + [B | I]·r_k = c_k` for
+//!   every `k`, with every `m_k` free and every `r_k` ternary.
+//! - OR: the prover can open at least one commitment, without revealing which. The relation is
+//!   `A·m + [B | I]·r - Σ_k s_k·c_k = 0` with `m` free, `r` ternary and a one-hot selector `s`,
+//!   which the permutation of its block hides, so a prover that passes opens the selected `c_k`.
+//!
+//! Both bind the key and every commitment, in order, to the transcript, and the
+//! `_with_transcript` variants run on the transcript of an enclosing protocol.
+
+#[cfg(feature = "prover")]
+use std::fmt;
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::amounts;
+use crate::binary::{self, BinaryResponse, BinaryRound, Block, Relation};
+#[cfg(feature = "prover")]
+use crate::binary::Witness;
+use crate::config::{CommitConfig, ProofConfig};
+use crate::fft::{LOCAL_N, MESSAGE_LEN, RANDOMNESS_LEN};
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+#[cfg(feature = "prover")]
+use crate::linking::reduce;
+use crate::transcript::{ProofTranscript, Transcript};
+#[cfg(feature = "prover")]
+use crate::{add_mod, utils};
+use crate::{RevelerCommit, RevelerResult};
+
+pub(crate) const KNOWLEDGE_DOMAIN: &[u8] = b"reveler/proofs/knowledge";
+pub(crate) const BIT_DOMAIN: &[u8] = b"reveler/proofs/bit";
+pub(crate) const SUM_DOMAIN: &[u8] = b"reveler/proofs/sum";
+pub(crate) const AND_DOMAIN: &[u8] = b"reveler/proofs/and";
+pub(crate) const OR_DOMAIN: &[u8] = b"reveler/proofs/or";
+
+/// First move of the interactive protocol: the salted hashes of every round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnowledgeAnnouncement {
+    pub commitments: Vec<Vec<Vec<u8>>>, // c1, c2 and c3 per round
+}
+
+/// Second move of the interactive protocol: one challenge per round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnowledgeChallenge {
+    pub challenges: Vec<u64>, // In {0, 1, 2} per round
+}
+
+/// Third move of the interactive protocol: the response of every round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnowledgeResponse {
+    pub responses: Vec<BinaryResponse>,
+}
+
+/// Non-interactive proof of knowledge of an opening: a `binary` proof over the message and the
+/// randomness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnowledgeProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+/// Proof that a coordinate of the messages of several commitments adds up to a public total: a
+/// `binary` proof over the other coordinates and the randomness of their sum.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+
+/// Proof that a coordinate of a committed message is 0 or 1: a `binary` proof over the bit, the
+/// other coordinates and the randomness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BitProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+/// Proof that the prover can open every commitment of a list: a `binary` proof over the opening
+/// of every commitment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AndProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+/// Proof that the prover can open at least one commitment of a list: a `binary` proof over an
+/// opening and a hidden selector of the commitment it opens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+/// Prover state of the interactive protocol between the announcement and the response.
+#[cfg(feature = "prover")]
+pub struct KnowledgeProver {
+    announcement: binary::Announcement, // The permutations, masks and salts of every round
+}
+
+#[cfg(feature = "prover")]
+impl KnowledgeProver {
+    /// Starts the protocol for `commitment`, with the soundness of `proof_config`.
+    ///
+    /// # Returns
+    /// The prover state and the announcement to send, `CommitError::InvalidProofConfig` if
+    /// `proof_config` is not valid for binary proofs, or `CommitError::InvalidOpening` if `opening`
+    /// does not open `commitment` under `key` with ternary randomness.
+    pub fn start(
+        key: &CommitmentKey,
+        commitment: &RevelerResult,
+        opening: &Opening,
+        proof_config: &ProofConfig,
+    ) -> Result<(KnowledgeProver, KnowledgeAnnouncement), CommitError> {
+        let rounds = proof_config.binary_rounds()?;
+        let witness = knowledge_witness(key, commitment, opening)?;
+        let announcement = binary::announce(&knowledge_relation(key, commitment), &witness, rounds, utils::Workers::Threads)?;
+        let commitments = announcement.commitments.clone();
+        Ok((KnowledgeProver { announcement }, KnowledgeAnnouncement { commitments }))
+    }
+
+    /// Answers the verifier's challenge, consuming the prover state.
+    ///
+    /// # Returns
+    /// The response, or `CommitError::InvalidProofConfig` if the challenge does not have one
+    /// value in `{0, 1, 2}` per round.
+    pub fn respond(self, challenge: &KnowledgeChallenge) -> Result<KnowledgeResponse, CommitError> {
+        if challenge.challenges.len() != self.announcement.commitments.len() || challenge.challenges.iter().any(|&e| e > 2) {
+            return Err(CommitError::InvalidProofConfig);
+        }
+        let responses = self.announcement.respond(&challenge.challenges).into_iter().map(|round| round.response).collect();
+        Ok(KnowledgeResponse { responses })
+    }
+}
+
+// 不在调试输出中泄露掩码与打开值
+#[cfg(feature = "prover")]
+impl fmt::Debug for KnowledgeProver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KnowledgeProver(..)")
+    }
+}
+
+impl KnowledgeChallenge {
+    /// Draws the verifier's challenge for the soundness of `proof_config`.
+    ///
+    /// # Returns
+    /// The challenge, or `CommitError::InvalidProofConfig` if `proof_config` is not valid for
+    /// binary proofs.
+    #[cfg(feature = "prover")]
+    pub fn random<R: Rng>(rng: &mut R, proof_config: &ProofConfig) -> Result<Self, CommitError> {
+        let rounds = proof_config.binary_rounds()?;
+        Ok(KnowledgeChallenge { challenges: (0..rounds).map(|_| rng.gen_range(0..3)).collect() })
+    }
+}
+
+/// Checks the transcript of an interactive proof of knowledge of an opening of `commitment`.
+///
+/// # Returns
+/// `true` if `proof_config` is valid for binary proofs, the commitment hash is valid, the
+/// transcript has the rounds of `proof_config` and every round checks out against its challenge.
+pub fn verify_response(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    announcement: &KnowledgeAnnouncement,
+    challenge: &KnowledgeChallenge,
+    response: &KnowledgeResponse,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    let complete = [announcement.commitments.len(), challenge.challenges.len(), response.responses.len()]
+        .iter()
+        .all(|&len| len == rounds);
+    let relation = knowledge_relation(key, commitment);
+    complete
+        && RevelerCommit::verify(commitment)
+        && announcement.commitments.iter().zip(&challenge.challenges).zip(&response.responses)
+            .all(|((commitments, &e), response)| binary::verify_round(&relation, commitments, response, e))
+}
+
+/// Proves knowledge of an opening of `commitment` non-interactively.
+///
+/// # Returns
+/// The `KnowledgeProof`, or `CommitError::InvalidOpening` if `opening` does not open `commitment`
+/// under `key` with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_knowledge(key: &CommitmentKey, commitment: &RevelerResult, opening: &Opening) -> Result<KnowledgeProof, CommitError> {
+    prove_knowledge_with_config(key, commitment, opening, &ProofConfig::default())
+}
+
+/// Proves knowledge of an opening of `commitment` with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `KnowledgeProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for
+/// binary proofs, or the errors of `prove_knowledge`.
+#[cfg(feature = "prover")]
+pub fn prove_knowledge_with_config(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<KnowledgeProof, CommitError> {
+    prove_with(&mut Transcript::new(KNOWLEDGE_DOMAIN, key, commitment), key, commitment, opening, proof_config)
+}
+
+/// Proves knowledge of an opening of `commitment` on the transcript of an enclosing protocol.
+///
+/// # Parameters
+/// - `transcript`: The `ProofTranscript` of the enclosing protocol, e.g. a `merlin::Transcript`
+///   with the `merlin` feature. The statement and the proof are appended to it.
+/// - `key`, `commitment`, `opening`: As for `prove_knowledge`.
+/// - `proof_config`: The soundness of the proof.
+///
+/// # Returns
+/// The `KnowledgeProof`, or the errors of `prove_knowledge_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_knowledge_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<KnowledgeProof, CommitError> {
+    transcript.bind_statement(KNOWLEDGE_DOMAIN, key, commitment);
+    prove_with(transcript, key, commitment, opening, proof_config)
+}
+#[cfg(feature = "prover")]
+fn prove_with<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<KnowledgeProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    let witness = knowledge_witness(key, commitment, opening)?;
+    Ok(KnowledgeProof { rounds: prove_on(transcript, &knowledge_relation(key, commitment), &witness, rounds)? })
+}
+
+/// Verifies a non-interactive proof of knowledge of an opening of `commitment`.
+///
+/// # Returns
+/// `true` if the commitment hash is valid and every round of the proof checks out.
+pub fn verify_knowledge(key: &CommitmentKey, commitment: &RevelerResult, proof: &KnowledgeProof) -> bool {
+    verify_knowledge_with_config(key, commitment, proof, &ProofConfig::default())
+}
+
+/// Verifies a proof of knowledge made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `true` if `proof_config` is valid for binary proofs, the commitment hash is valid, and the
+/// proof has the rounds of `proof_config`, each of which checks out.
+pub fn verify_knowledge_with_config(key: &CommitmentKey, commitment: &RevelerResult, proof: &KnowledgeProof, proof_config: &ProofConfig) -> bool {
+    verify_with(&mut Transcript::new(KNOWLEDGE_DOMAIN, key, commitment), key, commitment, proof, proof_config)
+}
+
+/// Verifies a proof of knowledge made with `prove_knowledge_with_transcript`.
+///
+/// # Parameters
+/// - `transcript`: The `ProofTranscript` of the enclosing protocol, in the state the prover's was
+///   in. The statement and the proof are appended to it.
+/// - `key`, `commitment`, `proof`: As for `verify_knowledge`.
+/// - `proof_config`: The soundness the proof was made with.
+///
+/// # Returns
+/// The outcome of `verify_knowledge_with_config`, with the challenges drawn from `transcript`.
+pub fn verify_knowledge_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    proof: &KnowledgeProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    transcript.bind_statement(KNOWLEDGE_DOMAIN, key, commitment);
+    verify_with(transcript, key, commitment, proof, proof_config)
+}
+
+fn verify_with<T: ProofTranscript>(transcript: &mut T, key: &CommitmentKey, commitment: &RevelerResult, proof: &KnowledgeProof, proof_config: &ProofConfig) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    RevelerCommit::verify(commitment) && verify_on(transcript, &knowledge_relation(key, commitment), &proof.rounds, rounds)
+}
+
+/// Proves that coordinate `index` of the message of `commitment` is 0 or 1.
+///
+/// # Parameters
+/// - `key`: The commitment key of `commitment`.
+/// - `commitment`: The commitment.
+/// - `opening`: Its opening, which stays with the prover.
+/// - `index`: The coordinate of the message.
+///
+/// # Returns
+/// The `BitProof`, `CommitError::ComputationError` if `index` is not below `MESSAGE_LEN`, or
+/// `CommitError::InvalidOpening` if `opening` does not open `commitment` with ternary randomness
+/// or the coordinate is not a bit.
+#[cfg(feature = "prover")]
+pub fn prove_bit(key: &CommitmentKey, commitment: &RevelerResult, opening: &Opening, index: usize) -> Result<BitProof, CommitError> {
+    prove_bit_with_config(key, commitment, opening, index, &ProofConfig::default())
+}
+
+/// Proves that a coordinate is 0 or 1 with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `BitProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_bit`.
+#[cfg(feature = "prover")]
+pub fn prove_bit_with_config(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    index: usize,
+    proof_config: &ProofConfig,
+) -> Result<BitProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    if index >= MESSAGE_LEN {
+        return Err(CommitError::ComputationError);
+    }
+    let q = CommitConfig::default().modulus;
+    if !key.verify(commitment, opening)? || opening.local_m().get(index).is_some_and(|&x| x % q > 1) {
+        return Err(CommitError::InvalidOpening);
+    }
+    let relation = binary::coordinate_relation(key.as_pair(), BIT_DOMAIN, index, 1, 1, commitment);
+    let witness = binary::coordinate_witness(opening.local_m(), opening.local_r(), index, 1, 1);
+    Ok(BitProof { rounds: binary::prove_relation(&relation, &witness, rounds, utils::Workers::Threads)?.rounds })
+}
+
+/// Verifies a proof that coordinate `index` of the message of `commitment` is 0 or 1.
+///
+/// # Returns
+/// `true` if `index` is below `MESSAGE_LEN`, the commitment hash is valid, and every round of the
+/// proof checks out.
+pub fn verify_bit(key: &CommitmentKey, commitment: &RevelerResult, index: usize, proof: &BitProof) -> bool {
+    verify_bit_with_config(key, commitment, index, proof, &ProofConfig::default())
+}
+
+/// Verifies a bit proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_bit`.
+pub fn verify_bit_with_config(key: &CommitmentKey, commitment: &RevelerResult, index: usize, proof: &BitProof, proof_config: &ProofConfig) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    index < MESSAGE_LEN
+        && RevelerCommit::verify(commitment)
+        && binary::verify_relation(&binary::coordinate_relation(key.as_pair(), BIT_DOMAIN, index, 1, 1, commitment), &proof.rounds, rounds)
+}
+
+/// Proves that coordinate `index` of the messages of `commitments` adds up to `total` modulo `q`.
+///
+/// # Parameters
+/// - `key`: The commitment key of every commitment.
+/// - `commitments`: The commitments.
+/// - `openings`: Their openings in the same order, which stay with the prover.
+/// - `index`: The coordinate of the messages holding the values.
+/// - `total`: The public total, below `q`.
+///
+/// # Returns
+/// The `SumProof`, `CommitError::ComputationError` if there are no commitments, the numbers of
+/// commitments and openings differ, `index` is not below `MESSAGE_LEN` or `total` is not below `q`,
+/// or `CommitError::InvalidOpening` if an opening does not open its commitment with ternary
+/// randomness or the values do not add up to `total`.
+#[cfg(feature = "prover")]
+pub fn prove_sum(key: &CommitmentKey, commitments: &[RevelerResult], openings: &[Opening], index: usize, total: u64) -> Result<SumProof, CommitError> {
+    prove_sum_with_config(key, commitments, openings, index, total, &ProofConfig::default())
+}
+
+/// Proves that a coordinate adds up to `total` with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `SumProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_sum`.
+#[cfg(feature = "prover")]
+pub fn prove_sum_with_config(
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    openings: &[Opening],
+    index: usize,
+    total: u64,
+    proof_config: &ProofConfig,
+) -> Result<SumProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    let q = CommitConfig::default().modulus;
+    if commitments.is_empty() || commitments.len() != openings.len() || index >= MESSAGE_LEN || total >= q {
+        return Err(CommitError::ComputationError);
+    }
+    let mut local_m = vec![0; MESSAGE_LEN];
+    let mut local_r = vec![0; RANDOMNESS_LEN];
+    for (commitment, opening) in commitments.iter().zip(openings) {
+        if !key.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        local_m = add_mod(&local_m, &reduce(opening.local_m(), MESSAGE_LEN, q), q);
+        local_r = add_mod(&local_r, &reduce(opening.local_r(), RANDOMNESS_LEN, q), q);
+    }
+    if local_m.get(index) != Some(&total) {
+        return Err(CommitError::InvalidOpening);
+    }
+
+    let relation = sum_relation(key, commitments, index, total)?;
+    local_m.remove(index);
+    let witness = Witness { free: local_m, blocks: binary::decompose(&local_r, commitments.len() as u64, q) };
+    Ok(SumProof { rounds: binary::prove_relation(&relation, &witness, rounds, utils::Workers::Threads)?.rounds })
+}
+
+/// Verifies a proof that coordinate `index` of the messages of `commitments` adds up to `total`.
+///
+/// # Returns
+/// `true` if there are commitments, `index` is below `MESSAGE_LEN`, `total` is below `q`, every
+/// commitment hash is valid, and every round of the proof checks out against the sum of the
+/// commitments.
+pub fn verify_sum(key: &CommitmentKey, commitments: &[RevelerResult], index: usize, total: u64, proof: &SumProof) -> bool {
+    verify_sum_with_config(key, commitments, index, total, proof, &ProofConfig::default())
+}
+
+/// Verifies a sum proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_sum`.
+pub fn verify_sum_with_config(
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    index: usize,
+    total: u64,
+    proof: &SumProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    let well_formed = !commitments.is_empty()
+        && index < MESSAGE_LEN
+        && total < CommitConfig::default().modulus
+        && commitments.iter().all(RevelerCommit::verify);
+    well_formed
+        && sum_relation(key, commitments, index, total)
+            .is_ok_and(|relation| binary::verify_relation(&relation, &proof.rounds, rounds))
+}
+
+/// Proves that the prover can open every commitment of `commitments`.
+///
+/// # Parameters
+/// - `key`: The commitment key of every commitment.
+/// - `commitments`: The commitments.
+/// - `openings`: Their openings in the same order, which stay with the prover.
+///
+/// # Returns
+/// The `AndProof`, `CommitError::ComputationError` if there are no commitments or the numbers of
+/// commitments and openings differ, or `CommitError::InvalidOpening` if an opening does not open
+/// its commitment with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_and(key: &CommitmentKey, commitments: &[RevelerResult], openings: &[Opening]) -> Result<AndProof, CommitError> {
+    prove_and_with_config(key, commitments, openings, &ProofConfig::default())
+}
+
+/// Proves that the prover can open every commitment with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `AndProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_and`.
+#[cfg(feature = "prover")]
+pub fn prove_and_with_config(
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    openings: &[Opening],
+    proof_config: &ProofConfig,
+) -> Result<AndProof, CommitError> {
+    let first = commitments.first().ok_or(CommitError::ComputationError)?;
+    prove_and_on(&mut Transcript::new(AND_DOMAIN, key, first), key, commitments, openings, proof_config)
+}
+
+/// Proves that the prover can open every commitment on the transcript of an enclosing protocol.
+///
+/// # Returns
+/// The `AndProof`, or the errors of `prove_and_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_and_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    openings: &[Opening],
+    proof_config: &ProofConfig,
+) -> Result<AndProof, CommitError> {
+    let first = commitments.first().ok_or(CommitError::ComputationError)?;
+    transcript.bind_statement(AND_DOMAIN, key, first);
+    prove_and_on(transcript, key, commitments, openings, proof_config)
+}
+
+#[cfg(feature = "prover")]
+fn prove_and_on<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    openings: &[Opening],
+    proof_config: &ProofConfig,
+) -> Result<AndProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    if commitments.len() != openings.len() {
+        return Err(CommitError::ComputationError);
+    }
+    let q = CommitConfig::default().modulus;
+    let mut free = Vec::with_capacity(commitments.len() * MESSAGE_LEN);
+    for (commitment, opening) in commitments.iter().zip(openings) {
+        if !key.verify(commitment, opening)? {
+            return Err(CommitError::InvalidOpening);
+        }
+        free.extend(reduce(opening.local_m(), MESSAGE_LEN, q));
+    }
+    let witness = Witness { free, blocks: openings.iter().map(|opening| opening.local_r().to_vec()).collect() };
+    Ok(AndProof { rounds: prove_on(transcript, &and_relation(key, commitments), &witness, rounds)? })
+}
+
+/// Verifies a proof that the prover can open every commitment of `commitments`.
+///
+/// # Returns
+/// `true` if there are commitments, every commitment hash is valid, and every round of the proof
+/// checks out.
+pub fn verify_and(key: &CommitmentKey, commitments: &[RevelerResult], proof: &AndProof) -> bool {
+    verify_and_with_config(key, commitments, proof, &ProofConfig::default())
+}
+
+/// Verifies an AND proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_and`.
+pub fn verify_and_with_config(key: &CommitmentKey, commitments: &[RevelerResult], proof: &AndProof, proof_config: &ProofConfig) -> bool {
+    let Some(first) = commitments.first() else {
+        return false;
+    };
+    verify_and_on(&mut Transcript::new(AND_DOMAIN, key, first), key, commitments, proof, proof_config)
+}
+
+/// Verifies an AND proof made with `prove_and_with_transcript`.
+///
+/// # Returns
+/// The outcome of `verify_and_with_config`, with the challenges drawn from `transcript`.
+pub fn verify_and_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    proof: &AndProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Some(first) = commitments.first() else {
+        return false;
+    };
+    transcript.bind_statement(AND_DOMAIN, key, first);
+    verify_and_on(transcript, key, commitments, proof, proof_config)
+}
+
+fn verify_and_on<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    proof: &AndProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    commitments.iter().all(RevelerCommit::verify) && verify_on(transcript, &and_relation(key, commitments), &proof.rounds, rounds)
+}
+
+/// Proves that the prover can open at least one commitment of `commitments`.
+///
+/// # Parameters
+/// - `key`: The commitment key of every commitment.
+/// - `commitments`: The commitments.
+/// - `index`: The position of the commitment the prover can open, which stays hidden.
+/// - `opening`: Its opening, which stays with the prover.
+///
+/// # Returns
+/// The `OrProof`, `CommitError::ComputationError` if `index` is not a position of
+/// `commitments`, or `CommitError::InvalidOpening` if `opening` does not open the commitment at
+/// `index` with ternary randomness.
+#[cfg(feature = "prover")]
+pub fn prove_or(key: &CommitmentKey, commitments: &[RevelerResult], index: usize, opening: &Opening) -> Result<OrProof, CommitError> {
+    prove_or_with_config(key, commitments, index, opening, &ProofConfig::default())
+}
+
+/// Proves that the prover can open one commitment with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `OrProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_or`.
+#[cfg(feature = "prover")]
+pub fn prove_or_with_config(
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    index: usize,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<OrProof, CommitError> {
+    let first = commitments.first().ok_or(CommitError::ComputationError)?;
+    prove_or_on(&mut Transcript::new(OR_DOMAIN, key, first), key, commitments, index, opening, proof_config)
+}
+
+/// Proves that the prover can open one commitment on the transcript of an enclosing protocol.
+///
+/// # Returns
+/// The `OrProof`, or the errors of `prove_or_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_or_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    index: usize,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<OrProof, CommitError> {
+    let first = commitments.first().ok_or(CommitError::ComputationError)?;
+    transcript.bind_statement(OR_DOMAIN, key, first);
+    prove_or_on(transcript, key, commitments, index, opening, proof_config)
+}
+
+#[cfg(feature = "prover")]
+fn prove_or_on<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    index: usize,
+    opening: &Opening,
+    proof_config: &ProofConfig,
+) -> Result<OrProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    let own = commitments.get(index).ok_or(CommitError::ComputationError)?;
+    if !key.verify(own, opening)? {
+        return Err(CommitError::InvalidOpening);
+    }
+    let mut selector = vec![0; commitments.len()];
+    if let Some(slot) = selector.get_mut(index) {
+        *slot = 1;
+    }
+    let witness = Witness { free: opening.local_m().to_vec(), blocks: vec![opening.local_r().to_vec(), selector] };
+    Ok(OrProof { rounds: prove_on(transcript, &or_relation(key, commitments), &witness, rounds)? })
+}
+
+/// Verifies a proof that the prover can open at least one commitment of `commitments`.
+///
+/// # Returns
+/// `true` if there are commitments, every commitment hash is valid, and every round of the proof
+/// checks out.
+pub fn verify_or(key: &CommitmentKey, commitments: &[RevelerResult], proof: &OrProof) -> bool {
+    verify_or_with_config(key, commitments, proof, &ProofConfig::default())
+}
+
+/// Verifies an OR proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_or`.
+pub fn verify_or_with_config(key: &CommitmentKey, commitments: &[RevelerResult], proof: &OrProof, proof_config: &ProofConfig) -> bool {
+    let Some(first) = commitments.first() else {
+        return false;
+    };
+    verify_or_on(&mut Transcript::new(OR_DOMAIN, key, first), key, commitments, proof, proof_config)
+}
+
+/// Verifies an OR proof made with `prove_or_with_transcript`.
+///
+/// # Returns
+/// The outcome of `verify_or_with_config`, with the challenges drawn from `transcript`.
+pub fn verify_or_with_transcript<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    proof: &OrProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Some(first) = commitments.first() else {
+        return false;
+    };
+    transcript.bind_statement(OR_DOMAIN, key, first);
+    verify_or_on(transcript, key, commitments, proof, proof_config)
+}
+
+fn verify_or_on<T: ProofTranscript>(
+    transcript: &mut T,
+    key: &CommitmentKey,
+    commitments: &[RevelerResult],
+    proof: &OrProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    commitments.iter().all(RevelerCommit::verify) && verify_on(transcript, &or_relation(key, commitments), &proof.rounds, rounds)
+}
+
+// The statement of a proof of knowledge: `commitment` opens to a free message with ternary
+// randomness.
+fn knowledge_relation<'a>(key: &'a CommitmentKey, commitment: &RevelerResult) -> Relation<'a> {
+    let params = key.as_pair();
+    Relation {
+        label: KNOWLEDGE_DOMAIN,
+        statement: binary::params_digest(params),
+        free: MESSAGE_LEN,
+        blocks: vec![Block::Ternary(RANDOMNESS_LEN)],
+        map: Box::new(move |m, heads| match heads {
+            [r] => binary::point(params, m, r),
+            _ => Err(CommitError::ComputationError),
+        }),
+        target: commitment.commitment_point().to_vec(),
+    }
+}
+
+// Witness of `knowledge_relation`, once `opening` is checked to open `commitment` with ternary
+// randomness.
+#[cfg(feature = "prover")]
+fn knowledge_witness(key: &CommitmentKey, commitment: &RevelerResult, opening: &Opening) -> Result<Witness, CommitError> {
+    if !key.verify(commitment, opening)? {
+        return Err(CommitError::InvalidOpening);
+    }
+    Ok(Witness { free: opening.local_m().to_vec(), blocks: vec![opening.local_r().to_vec()] })
+}
+
+// The statement of `prove_sum`: the sum of `commitments` opens to a message whose coordinate
+// `index` is `total`, with the other coordinates free and randomness within the number of
+// commitments. The total is moved into the target, `C - A·(total·e_index)`, so the map is linear.
+fn sum_relation<'a>(key: &'a CommitmentKey, commitments: &[RevelerResult], index: usize, total: u64) -> Result<Relation<'a>, CommitError> {
+    let params = key.as_pair();
+    let bound = commitments.len() as u64;
+    let weights = binary::weights(bound);
+    let mut fixed = vec![0; MESSAGE_LEN];
+    if let Some(slot) = fixed.get_mut(index) {
+        *slot = total;
+    }
+    let offset = binary::point(params, &fixed, &[])?;
+    let q = CommitConfig::default().modulus;
+    let target = amounts::sum(commitments).commitment_point().iter().zip(&offset)
+        .map(|(&c, &x)| (c % q + q - x % q) % q)
+        .collect();
+
+    let mut statement = binary::params_digest(params);
+    statement.extend((index as u64).to_be_bytes());
+    statement.extend(total.to_be_bytes());
+    statement.extend(bound.to_be_bytes());
+    for commitment in commitments {
+        statement.extend(commitment.to_bytes());
+    }
+    Ok(Relation {
+        label: SUM_DOMAIN,
+        statement,
+        free: MESSAGE_LEN - 1,
+        blocks: weights.iter().map(|_| Block::Ternary(RANDOMNESS_LEN)).collect(),
+        map: Box::new(move |free, pieces| {
+            let mut m = free.to_vec();
+            m.insert(index.min(m.len()), 0);
+            binary::point(params, &m, &binary::recompose(pieces, &weights, q))
+        }),
+        target,
+    })
+}
+
+// Prove `relation` for `witness` with the challenges drawn from `transcript`.
+#[cfg(feature = "prover")]
+fn prove_on<T: ProofTranscript>(transcript: &mut T, relation: &Relation, witness: &Witness, rounds: usize) -> Result<Vec<BinaryRound>, CommitError> {
+    let announcement = binary::announce(relation, witness, rounds, utils::Workers::Threads)?;
+    let challenges = round_challenges(transcript, relation, &announcement.commitments);
+    Ok(announcement.respond(&challenges))
+}
+
+// Verify a proof of `relation` with `count` rounds and the challenges drawn from `transcript`.
+fn verify_on<T: ProofTranscript>(transcript: &mut T, relation: &Relation, rounds: &[BinaryRound], count: usize) -> bool {
+    if rounds.len() != count {
+        return false;
+    }
+    let commitments: Vec<Vec<Vec<u8>>> = rounds.iter().map(|round| round.commitments.clone()).collect();
+    let challenges = round_challenges(transcript, relation, &commitments);
+    rounds.iter().zip(challenges)
+        .all(|(round, challenge)| binary::verify_round(relation, &round.commitments, &round.response, challenge))
+}
+
+// Append the relation and the commitments of every round, then draw a challenge in {0, 1, 2} per
+// round.
+fn round_challenges<T: ProofTranscript>(transcript: &mut T, relation: &Relation, commitments: &[Vec<Vec<u8>>]) -> Vec<u64> {
+    transcript.append_message(b"relation", &binary::transcript(relation).finalize());
+    for round in commitments {
+        for commitment in round {
+            transcript.append_message(b"round-commitment", commitment);
+        }
+    }
+    transcript.challenge_values(b"round-challenges", commitments.len(), 3)
+}
+
+// The statement of `prove_and`: every commitment opens to its own free message with ternary
+// randomness.
+fn and_relation<'a>(key: &'a CommitmentKey, commitments: &[RevelerResult]) -> Relation<'a> {
+    let params = key.as_pair();
+    let mut statement = binary::params_digest(params);
+    statement.extend((commitments.len() as u64).to_be_bytes());
+    Relation {
+        label: AND_DOMAIN,
+        statement,
+        free: commitments.len() * MESSAGE_LEN,
+        blocks: vec![Block::Ternary(RANDOMNESS_LEN); commitments.len()],
+        map: Box::new(move |free, heads| {
+            let mut image = Vec::with_capacity(heads.len() * LOCAL_N);
+            for (m, r) in free.chunks(MESSAGE_LEN).zip(heads) {
+                image.extend(binary::point(params, m, r)?);
+            }
+            Ok(image)
+        }),
+        target: commitments.iter().flat_map(|commitment| commitment.commitment_point().iter().copied()).collect(),
+    }
+}
+
+// The statement of `prove_or`: `A·m + [B | I]·r - Σ_k s_k·c_k = 0` with `m` free, `r` ternary and
+// `s` a one-hot selector of the commitments.
+fn or_relation<'a>(key: &'a CommitmentKey, commitments: &[RevelerResult]) -> Relation<'a> {
+    let params = key.as_pair();
+    let q = CommitConfig::default().modulus;
+    let points: Vec<Vec<u64>> = commitments.iter().map(|commitment| commitment.commitment_point().to_vec()).collect();
+    let mut statement = binary::params_digest(params);
+    statement.extend((commitments.len() as u64).to_be_bytes());
+    for commitment in commitments {
+        statement.extend(commitment.to_bytes());
+    }
+    Relation {
+        label: OR_DOMAIN,
+        statement,
+        free: MESSAGE_LEN,
+        blocks: vec![Block::Ternary(RANDOMNESS_LEN), Block::OneHot(commitments.len())],
+        map: Box::new(move |m, heads| {
+            let [r, selector] = heads else {
+                return Err(CommitError::ComputationError);
+            };
+            let mut image = binary::point(params, m, r)?;
+            for (point, &s) in points.iter().zip(selector.iter()) {
+                for (acc, &c) in image.iter_mut().zip(point) {
+                    *acc = (*acc + q - s % q * (c % q) % q) % q;
+                }
+            }
+            Ok(image)
+        }),
+        target: vec![0; LOCAL_N],
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::fft::LOCAL_N;
+    use super::*;
+
+    // Randomness opening `commitment = A·m + [B | I]·r` to m + Δ·e_index instead: r - A·(Δ·e_index)
+    // in the identity block, which is no longer ternary.
+    fn forge(key: &CommitmentKey, r: &[u64], index: usize, delta: u64) -> Vec<u64> {
+        let q = CommitConfig::default().modulus;
+        let mut forged = r.to_vec();
+        for (slot, row) in forged.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(key.as_pair().0) {
+            *slot = (*slot + q - row[index] * delta % q) % q;
+        }
+        forged
+    }
+
+    #[test]
+    fn bits_verify_and_other_values_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(271);
+        let key = CommitmentKey::setup(&mut rng);
+        let r = utils::generate_randomness(&mut rng);
+        let m = vec![5, 1, 0, 2];
+        let commitment = key.commit(&m, &r).unwrap();
+        let opening = Opening::new(m.clone(), r.clone());
+        for index in [1, 2] {
+            let proof = prove_bit(&key, &commitment, &opening, index).unwrap();
+            assert!(verify_bit(&key, &commitment, index, &proof));
+            assert!(!verify_bit(&key, &commitment, 3 - index, &proof));
+        }
+        assert_eq!(prove_bit(&key, &commitment, &opening, 3), Err(CommitError::InvalidOpening));
+
+        // 坐标 3 实为 2，伪造者声称是 1，把差值塞进 [B | I] 的单位块
+        let q = CommitConfig::default().modulus;
+        let forged = Opening::new(vec![5, 1, 0, 1], forge(&key, &r, 3, q - 1));
+        assert!(key.reproduces(&commitment, &forged).unwrap());
+        assert_eq!(prove_bit(&key, &commitment, &forged, 3), Err(CommitError::InvalidOpening));
+        let relation = binary::coordinate_relation(key.as_pair(), BIT_DOMAIN, 3, 1, 1, &commitment);
+        for (m, r) in [(forged.local_m(), forged.local_r()), (m.as_slice(), r.as_slice())] {
+            let witness = binary::coordinate_witness(m, r, 3, 1, 1);
+            let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap().rounds;
+            assert!(!verify_bit(&key, &commitment, 3, &BitProof { rounds }));
+        }
+    }
+
+    #[test]
+    fn sums_verify_and_other_totals_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(272);
+        let key = CommitmentKey::setup(&mut rng);
+        let openings: Vec<Opening> = [10, 20, 30].iter().map(|&v| Opening::new(vec![v, 7], utils::generate_randomness(&mut rng))).collect();
+        let commitments: Vec<RevelerResult> = openings.iter().map(|opening| key.commit_opening(opening).unwrap()).collect();
+        let proof = prove_sum(&key, &commitments, &openings, 0, 60).unwrap();
+        assert!(verify_sum(&key, &commitments, 0, 60, &proof));
+        assert!(!verify_sum(&key, &commitments, 0, 61, &proof));
+        assert!(!verify_sum(&key, &commitments, 1, 60, &proof));
+        assert!(!verify_sum(&key, &commitments[..2], 0, 60, &proof));
+        assert_eq!(prove_sum(&key, &commitments, &openings, 0, 61), Err(CommitError::InvalidOpening));
+        let proof = prove_sum(&key, &commitments, &openings, 1, 21).unwrap();
+        assert!(verify_sum(&key, &commitments, 1, 21, &proof));
+    }
+
+    #[test]
+    fn forged_sums_are_rejected() {
+        // 最后一个承诺实为 30，伪造者声称 31，把差值塞进 [B | I] 的单位块
+        let mut rng = StdRng::seed_from_u64(272);
+        let key = CommitmentKey::setup(&mut rng);
+        let mut openings: Vec<Opening> = [10, 20, 30].iter().map(|&v| Opening::new(vec![v], utils::generate_randomness(&mut rng))).collect();
+        let commitments: Vec<RevelerResult> = openings.iter().map(|opening| key.commit_opening(opening).unwrap()).collect();
+        let forged = Opening::new(vec![31], forge(&key, openings[2].local_r(), 0, 1));
+        assert!(key.reproduces(&commitments[2], &forged).unwrap());
+        openings[2] = forged;
+        assert_eq!(prove_sum(&key, &commitments, &openings, 0, 61), Err(CommitError::InvalidOpening));
+
+        let q = CommitConfig::default().modulus;
+        let r = openings.iter().fold(vec![0; RANDOMNESS_LEN], |acc, opening| add_mod(&acc, opening.local_r(), q));
+        let relation = sum_relation(&key, &commitments, 0, 61).unwrap();
+        let witness = Witness { free: vec![0; MESSAGE_LEN - 1], blocks: binary::decompose(&r, 3, q) };
+        let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap().rounds;
+        assert!(!verify_sum(&key, &commitments, 0, 61, &SumProof { rounds }));
+    }
+
+    #[test]
+    fn and_proofs_need_every_opening() {
+        let mut rng = StdRng::seed_from_u64(275);
+        let key = CommitmentKey::setup(&mut rng);
+        let mut openings: Vec<Opening> = [3, 5].iter().map(|&v| Opening::new(vec![v, 1], utils::generate_randomness(&mut rng))).collect();
+        let commitments: Vec<RevelerResult> = openings.iter().map(|opening| key.commit_opening(opening).unwrap()).collect();
+        let proof = prove_and(&key, &commitments, &openings).unwrap();
+        assert!(verify_and(&key, &commitments, &proof));
+        assert!(!verify_and(&key, &commitments[..1], &proof));
+        assert!(!verify_and(&key, &[commitments[1].clone(), commitments[0].clone()], &proof));
+
+        // 第二个承诺只有经单位块伪造的打开
+        openings[1] = Opening::new(vec![6, 1], forge(&key, openings[1].local_r(), 0, 1));
+        assert!(key.reproduces(&commitments[1], &openings[1]).unwrap());
+        assert_eq!(prove_and(&key, &commitments, &openings), Err(CommitError::InvalidOpening));
+        let q = CommitConfig::default().modulus;
+        let witness = Witness {
+            free: openings.iter().flat_map(|opening| reduce(opening.local_m(), MESSAGE_LEN, q)).collect(),
+            blocks: openings.iter().map(|opening| opening.local_r().to_vec()).collect(),
+        };
+        let mut transcript = Transcript::new(AND_DOMAIN, &key, &commitments[0]);
+        let rounds = prove_on(&mut transcript, &and_relation(&key, &commitments), &witness, binary::round_count()).unwrap();
+        assert!(!verify_and(&key, &commitments, &AndProof { rounds }));
+    }
+
+    #[test]
+    fn or_proofs_need_one_opening() {
+        let mut rng = StdRng::seed_from_u64(275);
+        let key = CommitmentKey::setup(&mut rng);
+        let openings: Vec<Opening> = [3, 5, 8].iter().map(|&v| Opening::new(vec![v], utils::generate_randomness(&mut rng))).collect();
+        let commitments: Vec<RevelerResult> = openings.iter().map(|opening| key.commit_opening(opening).unwrap()).collect();
+        for (index, opening) in openings.iter().enumerate().take(2) {
+            let proof = prove_or(&key, &commitments[..2], index, opening).unwrap();
+            assert!(verify_or(&key, &commitments[..2], &proof));
+            assert!(!verify_or(&key, &commitments[1..], &proof));
+        }
+        assert_eq!(prove_or(&key, &commitments[..2], 0, &openings[1]), Err(CommitError::InvalidOpening));
+
+        // 伪造者只持有经单位块改写、随机数不再是三元的打开
+        let forged = Opening::new(vec![4], forge(&key, openings[0].local_r(), 0, 1));
+        assert!(key.reproduces(&commitments[0], &forged).unwrap());
+        assert_eq!(prove_or(&key, &commitments[..2], 0, &forged), Err(CommitError::InvalidOpening));
+        let witness = Witness { free: forged.local_m().to_vec(), blocks: vec![forged.local_r().to_vec(), vec![1, 0]] };
+        let mut transcript = Transcript::new(OR_DOMAIN, &key, &commitments[0]);
+        let rounds = prove_on(&mut transcript, &or_relation(&key, &commitments[..2]), &witness, binary::round_count()).unwrap();
+        assert!(!verify_or(&key, &commitments[..2], &OrProof { rounds }));
+    }
+
+    #[test]
+    fn knowledge_proofs_verify_interactively_and_not() {
+        let mut rng = StdRng::seed_from_u64(267);
+        let (key, other) = (CommitmentKey::setup(&mut rng), CommitmentKey::setup(&mut rng));
+        let opening = Opening::new(vec![2, 7, 1], utils::generate_randomness(&mut rng));
+        let commitment = key.commit_opening(&opening).unwrap();
+        let proof = prove_knowledge(&key, &commitment, &opening).unwrap();
+        assert!(verify_knowledge(&key, &commitment, &proof));
+        assert!(!verify_knowledge(&other, &commitment, &proof));
+
+        let config = ProofConfig::default();
+        let (prover, announcement) = KnowledgeProver::start(&key, &commitment, &opening, &config).unwrap();
+        let challenge = KnowledgeChallenge::random(&mut rng, &config).unwrap();
+        let response = prover.respond(&challenge).unwrap();
+        assert!(verify_response(&key, &commitment, &announcement, &challenge, &response, &config));
+        let mut shifted = challenge.clone();
+        shifted.challenges[0] = (shifted.challenges[0] + 1) % 3;
+        assert!(!verify_response(&key, &commitment, &announcement, &shifted, &response, &config));
+    }
+
+    #[test]
+    fn knowledge_of_unbounded_openings_is_rejected() {
+        // 任何承诺都可借单位块"打开"，但随机数不再是三元的
+        let mut rng = StdRng::seed_from_u64(267);
+        let key = CommitmentKey::setup(&mut rng);
+        let commitment = key.commit(&[2, 7, 1], &utils::generate_randomness(&mut rng)).unwrap();
+        let shift = key.commit(&[5], &[]).unwrap();
+        let q = CommitConfig::default().modulus;
+        let mut r = vec![0; RANDOMNESS_LEN];
+        for ((slot, &c), &x) in r.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(commitment.commitment_point()).zip(shift.commitment_point()) {
+            *slot = (c + q - x) % q;
+        }
+        let forged = Opening::new(vec![5], r);
+        assert!(key.reproduces(&commitment, &forged).unwrap());
+        assert_eq!(prove_knowledge(&key, &commitment, &forged), Err(CommitError::InvalidOpening));
+
+        let witness = Witness { free: forged.local_m().to_vec(), blocks: vec![forged.local_r().to_vec()] };
+        let mut transcript = Transcript::new(KNOWLEDGE_DOMAIN, &key, &commitment);
+        let rounds = prove_on(&mut transcript, &knowledge_relation(&key, &commitment), &witness, binary::round_count()).unwrap();
+        assert!(!verify_knowledge(&key, &commitment, &KnowledgeProof { rounds }));
+    }
+}
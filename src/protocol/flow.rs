@@ -0,0 +1,325 @@
+//! This module provides `Flow`, a declarative definition of a commit-reveal flow.
+//!
+//! A flow names the participants allowed to take part, the artifacts every participant commits
+//! to, the length and grace period of each phase and the policies of the round. From it
+//! `Flow::open` builds the `Round` coordinator of one round, wrapped in a `FlowRound` that refuses
+//! messages from unlisted participants, and `Flow::encode`/`Flow::decode` lay the named artifacts
+//! out in the committed message vector. A new game or auction variant is then a few lines:
+//!
+//! ```ignore
+//! let auction = Flow::new("sealed-bid")
+//!     .with_participants(vec![b"alice".to_vec(), b"bob".to_vec()])
+//!     .with_artifact("bid", 1)
+//!     .with_artifact("bidder-nonce", 4)
+//!     .with_commit_phase(300, 30)
+//!     .with_reveal_phase(120, 0)
+//!     .with_quorum(2);
+//! let mut round = auction.open(7, opens_at, params)?;
+//! ```
+
+use std::collections::BTreeMap;
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+use crate::fft::MESSAGE_LEN;
+use super::evidence::MisbehaviorProof;
+#[cfg(feature = "prover")]
+use super::roles::Committer;
+use super::messages::{CommitMessage, RevealMessage};
+use super::round::{LateRevealPolicy, Round, RoundDescriptor, RoundPhase};
+
+/// Named artifacts of one participant, by artifact name.
+pub type Artifacts = BTreeMap<String, Vec<u64>>;
+
+/// Named part of the committed message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub name: String, // The name of the artifact
+    pub width: usize, // The number of message coordinates it occupies
+}
+
+/// Declarative definition of a commit-reveal flow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flow {
+    pub name: String,                         // The name of the flow
+    pub participants: Vec<Vec<u8>>,           // The allowed participants, empty for an open flow
+    pub artifacts: Vec<Artifact>,             // The artifacts, in message order
+    pub commit_duration: u64,                 // The length of the commit phase
+    pub commit_grace: u64,                    // The extra time commitments are accepted for
+    pub reveal_duration: u64,                 // The length of the reveal phase
+    pub reveal_grace: u64,                    // The extra time reveals are on time for
+    pub late_reveal_policy: LateRevealPolicy, // The handling of reveals after the grace period
+    pub quorum: usize,                        // The number of openings for the outcome to settle
+}
+
+/// Result of a closed flow round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowOutcome {
+    pub opened: BTreeMap<Vec<u8>, Artifacts>, // The decoded artifacts of every valid opening
+    pub malformed: Vec<Vec<u8>>,              // The participants whose opening does not decode
+    pub missing: Vec<Vec<u8>>,                // The listed or committed participants without opening
+    pub settled: bool,                        // Whether at least `quorum` participants opened
+}
+
+impl Flow {
+    /// Creates an open flow without artifacts, with 60-second phases, no grace periods, late
+    /// reveals rejected and a quorum of one.
+    pub fn new(name: &str) -> Self {
+        Flow {
+            name: name.to_string(),
+            participants: Vec::new(),
+            artifacts: Vec::new(),
+            commit_duration: 60,
+            commit_grace: 0,
+            reveal_duration: 60,
+            reveal_grace: 0,
+            late_reveal_policy: LateRevealPolicy::Reject,
+            quorum: 1,
+        }
+    }
+
+    /// Restricts the flow to the given participants.
+    pub fn with_participants(mut self, participants: Vec<Vec<u8>>) -> Self {
+        self.participants = participants;
+        self
+    }
+
+    /// Appends an artifact of `width` coordinates to the committed message.
+    pub fn with_artifact(mut self, name: &str, width: usize) -> Self {
+        self.artifacts.push(Artifact { name: name.to_string(), width });
+        self
+    }
+
+    /// Sets the length and grace period of the commit phase.
+    pub fn with_commit_phase(mut self, duration: u64, grace: u64) -> Self {
+        self.commit_duration = duration;
+        self.commit_grace = grace;
+        self
+    }
+
+    /// Sets the length and grace period of the reveal phase.
+    pub fn with_reveal_phase(mut self, duration: u64, grace: u64) -> Self {
+        self.reveal_duration = duration;
+        self.reveal_grace = grace;
+        self
+    }
+
+    /// Sets the handling of late reveals.
+    pub fn with_late_reveal_policy(mut self, late_reveal_policy: LateRevealPolicy) -> Self {
+        self.late_reveal_policy = late_reveal_policy;
+        self
+    }
+
+    /// Sets the number of valid openings needed for the outcome to settle.
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    /// Checks the definition.
+    ///
+    /// # Returns
+    /// `Ok(())`, or `CommitError::ComputationError` if an artifact has no width, two artifacts
+    /// share a name, the artifacts exceed `MESSAGE_LEN` coordinates, a participant is listed twice, or
+    /// the quorum is zero or exceeds the listed participants.
+    pub fn validate(&self) -> Result<(), CommitError> {
+        let mut names: Vec<&str> = self.artifacts.iter().map(|artifact| artifact.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        let mut participants = self.participants.clone();
+        participants.sort_unstable();
+        participants.dedup();
+
+        let valid = self.artifacts.iter().all(|artifact| artifact.width > 0)
+            && names.len() == self.artifacts.len()
+            && self.message_width() <= MESSAGE_LEN
+            && participants.len() == self.participants.len()
+            && self.quorum > 0
+            && (self.participants.is_empty() || self.quorum <= self.participants.len());
+        if !valid {
+            return Err(CommitError::ComputationError);
+        }
+        Ok(())
+    }
+
+    /// Returns the number of message coordinates taken by the artifacts.
+    pub fn message_width(&self) -> usize {
+        self.artifacts.iter().map(|artifact| artifact.width).sum()
+    }
+
+    /// Returns whether `participant` may take part.
+    pub fn admits(&self, participant: &[u8]) -> bool {
+        self.participants.is_empty() || self.participants.iter().any(|allowed| allowed == participant)
+    }
+
+    /// Returns the rules of round `round` of the flow opening at `opens_at`.
+    pub fn descriptor(&self, round: u64, opens_at: u64) -> RoundDescriptor {
+        RoundDescriptor::new(round, opens_at, self.commit_duration, self.reveal_duration)
+            .with_grace(self.commit_grace, self.reveal_grace)
+            .with_late_reveal_policy(self.late_reveal_policy)
+    }
+
+    /// Opens round `round` of the flow.
+    ///
+    /// # Parameters
+    /// - `round`: The identifier of the round.
+    /// - `opens_at`: The time at which the commit phase opens.
+    /// - `params`: The `(A, B)` matrices of the round.
+    ///
+    /// # Returns
+    /// The `FlowRound` in the commit phase, or `CommitError::ComputationError` if the definition
+    /// does not pass `validate`.
+    pub fn open(&self, round: u64, opens_at: u64, params: (Vec<Vec<u64>>, Vec<Vec<u64>>)) -> Result<FlowRound, CommitError> {
+        self.validate()?;
+        Ok(FlowRound { flow: self.clone(), round: Round::new(self.descriptor(round, opens_at), params) })
+    }
+
+    /// Lays named artifacts out in a message vector.
+    ///
+    /// Every artifact is written at its offset and padded with zeros to its width.
+    ///
+    /// # Returns
+    /// The message of `message_width()` coordinates, or `CommitError::ComputationError` if an
+    /// artifact is missing, unknown or wider than declared.
+    pub fn encode(&self, artifacts: &Artifacts) -> Result<Vec<u64>, CommitError> {
+        if artifacts.keys().any(|name| !self.artifacts.iter().any(|artifact| artifact.name == *name)) {
+            return Err(CommitError::ComputationError);
+        }
+        let mut message = Vec::with_capacity(self.message_width());
+        for artifact in &self.artifacts {
+            let values = artifacts.get(&artifact.name).ok_or(CommitError::ComputationError)?;
+            if values.len() > artifact.width {
+                return Err(CommitError::ComputationError);
+            }
+            message.extend_from_slice(values);
+            message.resize(message.len() + artifact.width - values.len(), 0);
+        }
+        Ok(message)
+    }
+
+    /// Splits a message vector into the named artifacts.
+    ///
+    /// # Returns
+    /// The artifacts, each of its declared width, or `CommitError::MalformedEncoding` if the
+    /// message is shorter than `message_width()` or holds a non-zero coordinate past it.
+    pub fn decode(&self, message: &[u64]) -> Result<Artifacts, CommitError> {
+        let width = self.message_width();
+        if message.get(width..).is_none_or(|tail| tail.iter().any(|&x| x != 0)) {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let mut offset = 0;
+        let mut artifacts = Artifacts::new();
+        for artifact in &self.artifacts {
+            let values = message.get(offset..offset + artifact.width).ok_or(CommitError::MalformedEncoding)?;
+            artifacts.insert(artifact.name.clone(), values.to_vec());
+            offset += artifact.width;
+        }
+        Ok(artifacts)
+    }
+
+    /// Commits `committer` to its artifacts in a round of the flow.
+    ///
+    /// # Returns
+    /// The `CommitMessage` to publish, `CommitError::InvalidTransition` if the participant is not
+    /// admitted or already committed, or `CommitError::ComputationError` if the artifacts do not
+    /// match the flow.
+    #[cfg(feature = "prover")]
+    pub fn commit<R: Rng>(
+        &self,
+        committer: &mut Committer,
+        descriptor: &RoundDescriptor,
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        artifacts: &Artifacts,
+        rng: &mut R,
+        clock: &dyn Clock,
+    ) -> Result<CommitMessage, CommitError> {
+        if !self.admits(committer.participant()) {
+            return Err(CommitError::InvalidTransition);
+        }
+        committer.commit(descriptor, params, self.encode(artifacts)?, rng, clock)
+    }
+}
+
+/// Round of a `Flow`: the `Round` coordinator restricted to the participants of the flow.
+#[derive(Debug)]
+pub struct FlowRound {
+    flow: Flow,
+    round: Round,
+}
+
+impl FlowRound {
+    /// Returns the definition of the flow.
+    pub fn flow(&self) -> &Flow {
+        &self.flow
+    }
+
+    /// Returns the underlying coordinator.
+    pub fn round(&self) -> &Round {
+        &self.round
+    }
+
+    /// Returns the underlying coordinator, e.g. to attach a store or subscribe.
+    pub fn round_mut(&mut self) -> &mut Round {
+        &mut self.round
+    }
+
+    /// Moves the round to the phase it is in at the current time of `clock`.
+    pub fn advance(&mut self, clock: &dyn Clock) -> Vec<RoundPhase> {
+        self.round.advance(clock)
+    }
+
+    /// Accepts a commitment of an admitted participant, see `Round::submit_commit`.
+    ///
+    /// # Returns
+    /// The evidence produced by the message, or `CommitError::InvalidTransition` if the
+    /// participant is not admitted or the round refuses it.
+    pub fn submit_commit(&mut self, commit: &CommitMessage, clock: &dyn Clock) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        if !self.flow.admits(&commit.participant) {
+            return Err(CommitError::InvalidTransition);
+        }
+        self.round.submit_commit(commit, clock)
+    }
+
+    /// Accepts a reveal of an admitted participant, see `Round::submit_reveal`.
+    pub fn submit_reveal(&mut self, reveal: &RevealMessage, clock: &dyn Clock) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        if !self.flow.admits(&reveal.participant) {
+            return Err(CommitError::InvalidTransition);
+        }
+        self.round.submit_reveal(reveal, clock)
+    }
+
+    /// Returns the outcome of the round once it is closed.
+    ///
+    /// # Returns
+    /// The `FlowOutcome`, or `None` before the round reaches `RoundPhase::Closed`.
+    pub fn outcome(&self) -> Option<FlowOutcome> {
+        if self.round.phase() != RoundPhase::Closed {
+            return None;
+        }
+        let verifier = self.round.verifier();
+        let mut opened = BTreeMap::new();
+        let mut malformed = Vec::new();
+        for (participant, message) in verifier.openings() {
+            match self.flow.decode(message) {
+                Ok(artifacts) => {
+                    opened.insert(participant.clone(), artifacts);
+                }
+                Err(_) => malformed.push(participant.clone()),
+            }
+        }
+
+        // 名单制时按名单统计，开放制时按已提交者统计
+        let expected: Vec<Vec<u8>> = if self.flow.participants.is_empty() {
+            verifier.statuses().keys().cloned().collect()
+        } else {
+            self.flow.participants.clone()
+        };
+        let missing = expected
+            .into_iter()
+            .filter(|participant| !verifier.openings().contains_key(participant))
+            .collect();
+        Some(FlowOutcome { settled: opened.len() >= self.flow.quorum, opened, malformed, missing })
+    }
+}
@@ -0,0 +1,183 @@
+//! This module provides libp2p integration for decentralized commit-reveal rounds.
+//!
+//! Every named round has its own gossipsub topic `reveler/round/<name>`. `RoundBehaviour` gossips
+//! the canonical bytes of `ProtocolMessage`s on these topics, and `feed_round` hands the messages
+//! received from the swarm to a `Round` coordinator. Gossip messages are signed with the peer's
+//! libp2p identity and validated strictly, so every message can be attributed to its sender.
+//! `ParticipantPeers` binds every participant to the one peer allowed to speak for it, and
+//! `feed_round` drops a message whose source is not the peer of the participant it names.
+//!
+//! The module is compiled with the `p2p` feature.
+
+use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity, ValidationMode};
+use libp2p::identity::Keypair;
+use libp2p::PeerId;
+use std::collections::HashMap;
+use libp2p::swarm::NetworkBehaviour;
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+use super::evidence::MisbehaviorProof;
+use super::messages::ProtocolMessage;
+use super::round::Round;
+
+const ROUND_TOPIC_PREFIX: &str = "reveler/round/";
+
+/// Returns the gossipsub topic of the round named `round_name`.
+pub fn round_topic(round_name: &str) -> IdentTopic {
+    IdentTopic::new(format!("{ROUND_TOPIC_PREFIX}{round_name}"))
+}
+
+/// Binding of the participants of a round to their libp2p peers.
+#[derive(Debug, Clone, Default)]
+pub struct ParticipantPeers {
+    peers: HashMap<Vec<u8>, PeerId>, // The peer of every participant, by participant identifier
+}
+
+impl ParticipantPeers {
+    /// Creates a binding without participants.
+    pub fn new() -> Self {
+        ParticipantPeers::default()
+    }
+
+    /// Binds `participant` to `peer`, replacing any previous peer.
+    pub fn bind(mut self, participant: Vec<u8>, peer: PeerId) -> Self {
+        self.peers.insert(participant, peer);
+        self
+    }
+
+    /// Returns the peer bound to `participant`, if any.
+    pub fn peer(&self, participant: &[u8]) -> Option<&PeerId> {
+        self.peers.get(participant)
+    }
+
+    /// Returns `true` if `source` is the peer bound to `participant`.
+    pub fn admits(&self, participant: &[u8], source: Option<&PeerId>) -> bool {
+        source.is_some_and(|source| self.peer(participant) == Some(source))
+    }
+}
+
+/// Network behaviour gossiping commitments and reveals.
+#[derive(NetworkBehaviour)]
+pub struct RoundBehaviour {
+    pub gossipsub: gossipsub::Behaviour,
+}
+
+impl RoundBehaviour {
+    /// Creates the behaviour signing its messages with `keypair`.
+    ///
+    /// # Returns
+    /// The `RoundBehaviour`, or `CommitError::TransportError` if gossipsub cannot be configured.
+    pub fn new(keypair: &Keypair) -> Result<Self, CommitError> {
+        let config = gossipsub::ConfigBuilder::default()
+            .validation_mode(ValidationMode::Strict)
+            .build()
+            .map_err(|_| CommitError::TransportError)?;
+        let gossipsub = gossipsub::Behaviour::new(MessageAuthenticity::Signed(keypair.clone()), config)
+            .map_err(|_| CommitError::TransportError)?;
+        Ok(RoundBehaviour { gossipsub })
+    }
+
+    /// Subscribes to the topic of the round named `round_name`.
+    pub fn join(&mut self, round_name: &str) -> Result<(), CommitError> {
+        self.gossipsub.subscribe(&round_topic(round_name)).map_err(|_| CommitError::TransportError)?;
+        Ok(())
+    }
+
+    /// Unsubscribes from the topic of the round named `round_name`.
+    pub fn leave(&mut self, round_name: &str) -> Result<(), CommitError> {
+        self.gossipsub.unsubscribe(&round_topic(round_name)).map_err(|_| CommitError::TransportError)?;
+        Ok(())
+    }
+
+    /// Gossips a message on the topic of the round named `round_name`.
+    ///
+    /// # Returns
+    /// `CommitError::TransportError` if gossipsub refuses the message, e.g. without connected peers.
+    pub fn publish(&mut self, round_name: &str, message: &ProtocolMessage) -> Result<(), CommitError> {
+        self.gossipsub
+            .publish(round_topic(round_name), message.to_bytes())
+            .map_err(|_| CommitError::TransportError)?;
+        Ok(())
+    }
+}
+
+/// Feeds a swarm event into the coordinator of the round named `round_name`.
+///
+/// The round is first advanced to the current time of `clock`, then the gossiped commitment or
+/// reveal is submitted to it if its signed source is the peer `peers` binds to its participant.
+///
+/// # Returns
+/// `None` for events that carry no message of this round, otherwise the result of the
+/// submission, `CommitError::MalformedEncoding` for an undecodable message, or
+/// `CommitError::InvalidTransition` for a message without a source or from another peer than the
+/// one bound to its participant, which is dropped.
+pub fn feed_round(
+    round: &mut Round,
+    round_name: &str,
+    peers: &ParticipantPeers,
+    clock: &dyn Clock,
+    event: &RoundBehaviourEvent,
+) -> Option<Result<Vec<MisbehaviorProof>, CommitError>> {
+    let RoundBehaviourEvent::Gossipsub(gossipsub::Event::Message { message, .. }) = event else {
+        return None;
+    };
+    if message.topic != round_topic(round_name).hash() {
+        return None;
+    }
+
+    round.advance(clock);
+    let source = message.source.as_ref();
+    Some(ProtocolMessage::from_bytes(&message.data).and_then(|message| {
+        if !peers.admits(message.participant(), source) {
+            return Err(CommitError::InvalidTransition);
+        }
+        match message {
+            ProtocolMessage::Commit(commit) => round.submit_commit(&commit, clock),
+            ProtocolMessage::Reveal(reveal) => round.submit_reveal(&reveal, clock),
+        }
+    }))
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::clock::VirtualClock;
+    use crate::utils;
+    use super::super::roles::Committer;
+    use super::super::round::RoundDescriptor;
+    use super::*;
+
+    fn gossiped(message: &ProtocolMessage, source: Option<PeerId>) -> RoundBehaviourEvent {
+        RoundBehaviourEvent::Gossipsub(gossipsub::Event::Message {
+            propagation_source: PeerId::random(),
+            message_id: gossipsub::MessageId::new(b"id"),
+            message: gossipsub::Message { source, data: message.to_bytes(), sequence_number: None, topic: round_topic("beacon").hash() },
+        })
+    }
+
+    #[test]
+    fn messages_from_other_peers_are_dropped() {
+        let mut rng = StdRng::seed_from_u64(222);
+        let params = utils::generate_params_with_rng(&mut rng);
+        let descriptor = RoundDescriptor::new(1, 0, 10, 10);
+        let mut round = Round::new(descriptor.clone(), params.clone());
+        let clock = VirtualClock::new(5);
+        let commit = Committer::new(b"alice".to_vec()).commit(&descriptor, (&params.0, &params.1), vec![1], &mut rng, &clock).unwrap();
+        let message = ProtocolMessage::Commit(commit);
+        let (alice, mallory) = (PeerId::random(), PeerId::random());
+        let peers = ParticipantPeers::new().bind(b"alice".to_vec(), alice);
+
+        // 冒名的对等节点、缺少来源的消息都不会进入轮次
+        for source in [Some(mallory), None] {
+            let result = feed_round(&mut round, "beacon", &peers, &clock, &gossiped(&message, source));
+            assert_eq!(result, Some(Err(CommitError::InvalidTransition)));
+        }
+        assert!(round.verifier().statuses().is_empty());
+
+        let result = feed_round(&mut round, "beacon", &peers, &clock, &gossiped(&message, Some(alice)));
+        assert_eq!(result, Some(Ok(Vec::new())));
+        assert_eq!(round.verifier().statuses().len(), 1);
+    }
+}
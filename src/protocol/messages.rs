@@ -0,0 +1,239 @@
+//! This module defines the messages exchanged in a commit-reveal round.
+//!
+//! A participant signs its messages with the key identified by its participant identifier, through
+//! the `envelope::EnvelopeSigner` and `envelope::SignatureVerifier` traits. The signature covers the
+//! whole message but itself, so a signed message binds its sender to the commitment, the opening and
+//! the timestamp it claims, as `evidence::MisbehaviorProof` requires.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::envelope::{EnvelopeSigner, SignatureVerifier};
+use crate::policy::VerifyPolicy;
+use crate::{compute_point, scheme, utils, RevelerCommit, RevelerResult};
+
+const COMMIT_TAG: u8 = 1;
+const REVEAL_TAG: u8 = 2;
+pub(crate) const MESSAGE_SIGNING_DOMAIN: &[u8] = b"reveler/protocol/message/signing";
+
+/// Commitment published by a participant for a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitMessage {
+    pub round: u64,
+    pub participant: Vec<u8>,
+    pub rules_digest: Vec<u8>, // The digest of the `RoundDescriptor` the commitment was made under
+    pub commitment: RevelerResult,
+    pub timestamp: u64,     // Seconds since the Unix epoch at which the message was sent
+    pub signature: Vec<u8>, // The signature of the participant over `signing_bytes`, empty if unsigned
+}
+
+/// Opening revealed by a participant for a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealMessage {
+    pub round: u64,
+    pub participant: Vec<u8>,
+    pub local_m: Vec<u64>,
+    pub local_r: Vec<u64>,
+    pub timestamp: u64,     // Seconds since the Unix epoch at which the message was sent
+    pub signature: Vec<u8>, // The signature of the participant over `signing_bytes`, empty if unsigned
+}
+
+impl CommitMessage {
+    /// Encodes the message as the scheme header, the round, the length-prefixed participant and
+    /// rules digest, the commitment point and hash, the timestamp and the length-prefixed
+    /// signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.body();
+        bytes.extend(utils::encode_bytes(&self.signature));
+        bytes
+    }
+
+    /// Returns the bytes the signature covers: a fixed domain, the kind tag of the message and its
+    /// encoding without the signature.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = MESSAGE_SIGNING_DOMAIN.to_vec();
+        bytes.push(COMMIT_TAG);
+        bytes.extend(self.body());
+        bytes
+    }
+
+    /// Signs the message with `signer`, which holds the key identified by the participant.
+    pub fn with_signature(mut self, signer: &dyn EnvelopeSigner) -> Self {
+        self.signature = signer.sign(&self.signing_bytes());
+        self
+    }
+
+    /// Checks that the participant signed the message.
+    ///
+    /// # Returns
+    /// `true` if the message carries a signature that `verifier` accepts under the key identified
+    /// by the participant.
+    pub fn verify_signature(&self, verifier: &dyn SignatureVerifier) -> bool {
+        !self.signature.is_empty() && verifier.verify(&self.participant, &self.signing_bytes(), &self.signature)
+    }
+
+    // The encoding without the signature.
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        bytes.extend(utils::encode_bytes(&self.participant));
+        bytes.extend(utils::encode_bytes(&self.rules_digest));
+        bytes.extend(utils::encode_vector(&self.commitment.commitment_point));
+        bytes.extend(utils::encode_bytes(&self.commitment.commitment_hash));
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    /// Decodes a message written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `CommitMessage`, `CommitError::MalformedEncoding` if the input is not a
+    /// complete canonical encoding, or `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (round, rest) = utils::decode_u64(rest)?;
+        let (participant, rest) = utils::decode_bytes(rest)?;
+        let (rules_digest, rest) = utils::decode_bytes(rest)?;
+        let (commitment_point, rest) = utils::decode_vector(rest)?;
+        let (commitment_hash, rest) = utils::decode_bytes(rest)?;
+        let (timestamp, rest) = utils::decode_u64(rest)?;
+        let (signature, rest) = utils::decode_bytes(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(CommitMessage {
+            round,
+            participant,
+            rules_digest,
+            commitment: RevelerResult::new(commitment_point, commitment_hash),
+            timestamp,
+            signature,
+        })
+    }
+}
+
+impl RevealMessage {
+    /// Encodes the message as the scheme header, the round, the length-prefixed participant,
+    /// message and randomness, the timestamp and the length-prefixed signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.body();
+        bytes.extend(utils::encode_bytes(&self.signature));
+        bytes
+    }
+
+    /// Returns the bytes the signature covers: a fixed domain, the kind tag of the message and its
+    /// encoding without the signature.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = MESSAGE_SIGNING_DOMAIN.to_vec();
+        bytes.push(REVEAL_TAG);
+        bytes.extend(self.body());
+        bytes
+    }
+
+    /// Signs the message with `signer`, which holds the key identified by the participant.
+    pub fn with_signature(mut self, signer: &dyn EnvelopeSigner) -> Self {
+        self.signature = signer.sign(&self.signing_bytes());
+        self
+    }
+
+    /// Checks that the participant signed the message, as `CommitMessage::verify_signature`.
+    pub fn verify_signature(&self, verifier: &dyn SignatureVerifier) -> bool {
+        !self.signature.is_empty() && verifier.verify(&self.participant, &self.signing_bytes(), &self.signature)
+    }
+
+    // The encoding without the signature.
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        bytes.extend(utils::encode_bytes(&self.participant));
+        bytes.extend(utils::encode_vector(&self.local_m));
+        bytes.extend(utils::encode_vector(&self.local_r));
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    /// Decodes a message written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `RevealMessage`, `CommitError::MalformedEncoding` if the input is not a
+    /// complete canonical encoding, or `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (round, rest) = utils::decode_u64(rest)?;
+        let (participant, rest) = utils::decode_bytes(rest)?;
+        let (local_m, rest) = utils::decode_vector(rest)?;
+        let (local_r, rest) = utils::decode_vector(rest)?;
+        let (timestamp, rest) = utils::decode_u64(rest)?;
+        let (signature, rest) = utils::decode_bytes(rest)?;
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(RevealMessage { round, participant, local_m, local_r, timestamp, signature })
+    }
+
+    /// Checks that the revealed opening reproduces the commitment under the given parameters.
+    ///
+    /// # Parameters
+    /// - `commit`: A reference to the `CommitMessage` the reveal answers.
+    /// - `params`: The `(A, B)` matrices of the round.
+    ///
+    /// # Returns
+    /// `true` if both messages belong to the same round and participant, the commitment hash is
+    /// valid, the randomness lies within the bounds of `VerifyPolicy::new`, and `A·m + B·r` equals
+    /// the committed point.
+    pub fn opens(&self, commit: &CommitMessage, params: (&[Vec<u64>], &[Vec<u64>])) -> bool {
+        self.round == commit.round
+            && self.participant == commit.participant
+            && RevelerCommit::verify(&commit.commitment)
+            && VerifyPolicy::new().check_norms(&self.local_m, &self.local_r, CommitConfig::default().modulus).is_ok()
+            && compute_point(params.0, params.1, &self.local_m, &self.local_r, &CommitConfig::default())
+                .map(|point| point == commit.commitment.commitment_point)
+                .unwrap_or(false)
+    }
+}
+
+/// Any message exchanged between peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtocolMessage {
+    Commit(CommitMessage),
+    Reveal(RevealMessage),
+}
+
+impl ProtocolMessage {
+    /// Returns the participant that sent the message.
+    pub fn participant(&self) -> &[u8] {
+        match self {
+            ProtocolMessage::Commit(commit) => &commit.participant,
+            ProtocolMessage::Reveal(reveal) => &reveal.participant,
+        }
+    }
+
+    /// Encodes the message as a one-byte kind tag followed by the canonical message bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (tag, body) = match self {
+            ProtocolMessage::Commit(commit) => (COMMIT_TAG, commit.to_bytes()),
+            ProtocolMessage::Reveal(reveal) => (REVEAL_TAG, reveal.to_bytes()),
+        };
+        let mut bytes = vec![tag];
+        bytes.extend(body);
+        bytes
+    }
+
+    /// Decodes a message written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `ProtocolMessage`, `CommitError::MalformedEncoding` for an unknown kind tag or a
+    /// malformed body, or `CommitError::UnsupportedScheme` if the body names an unregistered
+    /// scheme.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        match bytes.split_first() {
+            Some((&COMMIT_TAG, body)) => Ok(ProtocolMessage::Commit(CommitMessage::from_bytes(body)?)),
+            Some((&REVEAL_TAG, body)) => Ok(ProtocolMessage::Reveal(RevealMessage::from_bytes(body)?)),
+            _ => Err(CommitError::MalformedEncoding),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! This module provides the building blocks of commit-reveal protocols.
+//!
+//! Participants first publish a `CommitMessage` for a round and later a `RevealMessage` holding
+//! the opening. The `roles` submodule holds the state machines of the committing, revealing and
+//! verifying sides, `round` the coordinator enforcing the phase deadlines, `store` the backends
+//! the coordinator persists its state to for crash recovery, and `evidence` turns protocol
+//! violations into `MisbehaviorProof`s that third parties can check independently. `watch`
+//! delivers the events of a round to subscribers over channels. `flow` defines a variant of the
+//! protocol declaratively, with its participants, artifacts, phases and policies, and builds the
+//! coordinator of its rounds.
+//! With the `prover` feature, `simulate` replays whole rounds deterministically, and with the
+//! `transport` feature, `transport` exchanges the messages between peers over the network. The
+//! `p2p` feature adds `gossip`, which spreads them over libp2p gossipsub.
+//!
+//! Messages are assumed to be authenticated by the transport or bulletin board they travel on:
+//! the evidence proves that the presented messages constitute misbehavior, not who sent them.
+
+pub mod evidence;
+pub mod flow;
+#[cfg(feature = "p2p")]
+pub mod gossip;
+pub mod messages;
+pub mod roles;
+pub mod round;
+#[cfg(feature = "prover")]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::indexing_slicing))]
+pub mod simulate;
+pub mod store;
+#[cfg(feature = "transport")]
+pub mod transport;
+pub mod watch;
+
+pub use evidence::{MisbehaviorDetector, MisbehaviorProof};
+pub use flow::{Flow, FlowOutcome, FlowRound};
+pub use messages::{CommitMessage, ProtocolMessage, RevealMessage};
+pub use roles::{Committer, ParticipantStatus, Revealer, Verifier};
+pub use round::{LateRevealPolicy, LoggedMessage, Round, RoundDescriptor, RoundPhase, RoundState};
+#[cfg(feature = "prover")]
+pub use simulate::{simulate, Behavior, SimulatedSignatures, SimulationConfig};
+pub use store::{FileRoundStore, MemoryRoundStore, RoundStore};
+pub use watch::{WatchEvent, Watcher};
@@ -0,0 +1,287 @@
+//! This module provides the state machines of the protocol roles.
+//!
+//! - `Committer`: `Idle` → `Committed` → `HandedOver`. Publishes one commitment per round and
+//!   hands the opening to a `Revealer`.
+//! - `Revealer`: `Holding` → `Revealed`. Publishes the opening once the reveal phase starts.
+//! - `Verifier`: tracks every participant of a round through `Committed` → `Opened`,
+//!   `OpenedLate` or `Rejected`, collecting `MisbehaviorProof`s along the way.
+//!
+//! Calling an operation in the wrong state returns `CommitError::InvalidTransition`.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+#[cfg(feature = "prover")]
+use crate::{utils, RevelerCommit};
+use super::evidence::{MisbehaviorDetector, MisbehaviorProof};
+use super::messages::{CommitMessage, RevealMessage};
+use super::round::{LateRevealPolicy, RoundDescriptor};
+
+/// State of a `Committer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommitterState {
+    Idle,
+    Committed,
+    HandedOver,
+}
+
+/// Participant side that commits to a message.
+#[derive(Debug, Clone)]
+pub struct Committer {
+    participant: Vec<u8>,
+    state: CommitterState,
+    pending: Option<Revealer>,
+}
+
+impl Committer {
+    /// Creates an idle committer for `participant`.
+    pub fn new(participant: Vec<u8>) -> Self {
+        Committer { participant, state: CommitterState::Idle, pending: None }
+    }
+
+    /// Returns the identity of the participant.
+    pub fn participant(&self) -> &[u8] {
+        &self.participant
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> CommitterState {
+        self.state
+    }
+
+    /// Commits to `local_m` with fresh randomness drawn from `rng`.
+    ///
+    /// # Parameters
+    /// - `descriptor`: The rules of the round, bound into the message through their digest.
+    /// - `params`: The `(A, B)` matrices of the round.
+    /// - `local_m`: The message vector.
+    /// - `rng`: The random number generator the randomness vector is drawn from.
+    /// - `clock`: The clock timestamping the message.
+    ///
+    /// # Returns
+    /// The `CommitMessage` to publish, or `CommitError::InvalidTransition` if the committer
+    /// already committed.
+    #[cfg(feature = "prover")]
+    pub fn commit<R: Rng>(
+        &mut self,
+        descriptor: &RoundDescriptor,
+        params: (&[Vec<u64>], &[Vec<u64>]),
+        local_m: Vec<u64>,
+        rng: &mut R,
+        clock: &dyn Clock,
+    ) -> Result<CommitMessage, CommitError> {
+        if self.state != CommitterState::Idle {
+            return Err(CommitError::InvalidTransition);
+        }
+
+        let local_r = utils::generate_randomness(rng);
+        let opening = RevelerCommit::new(params.0.to_vec(), params.1.to_vec(), local_m, local_r);
+        let commitment = opening.commit()?;
+
+        self.pending = Some(Revealer::new(descriptor.round, self.participant.clone(), opening.local_m, opening.local_r));
+        self.state = CommitterState::Committed;
+        Ok(CommitMessage {
+            round: descriptor.round,
+            participant: self.participant.clone(),
+            rules_digest: descriptor.digest(),
+            commitment,
+            timestamp: clock.now(),
+            signature: Vec::new(),
+        })
+    }
+
+    /// Hands the opening over to the `Revealer` that will publish it.
+    ///
+    /// # Returns
+    /// The `Revealer`, or `CommitError::InvalidTransition` if nothing was committed or the
+    /// opening was already handed over.
+    pub fn hand_over(&mut self) -> Result<Revealer, CommitError> {
+        if self.state != CommitterState::Committed {
+            return Err(CommitError::InvalidTransition);
+        }
+        let revealer = self.pending.take().ok_or(CommitError::InvalidTransition)?;
+        self.state = CommitterState::HandedOver;
+        Ok(revealer)
+    }
+}
+
+/// State of a `Revealer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RevealerState {
+    Holding,
+    Revealed,
+}
+
+/// Participant side that holds an opening until the reveal phase.
+#[derive(Debug, Clone)]
+pub struct Revealer {
+    round: u64,
+    participant: Vec<u8>,
+    local_m: Vec<u64>,
+    local_r: Vec<u64>,
+    state: RevealerState,
+}
+
+impl Revealer {
+    /// Creates a revealer holding the opening `(local_m, local_r)` of `participant` in `round`.
+    pub fn new(round: u64, participant: Vec<u8>, local_m: Vec<u64>, local_r: Vec<u64>) -> Self {
+        Revealer { round, participant, local_m, local_r, state: RevealerState::Holding }
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> RevealerState {
+        self.state
+    }
+
+    /// Reveals the opening, timestamped by `clock`.
+    ///
+    /// # Returns
+    /// The `RevealMessage` to publish, or `CommitError::InvalidTransition` if it was already
+    /// revealed.
+    pub fn reveal(&mut self, clock: &dyn Clock) -> Result<RevealMessage, CommitError> {
+        if self.state != RevealerState::Holding {
+            return Err(CommitError::InvalidTransition);
+        }
+        self.state = RevealerState::Revealed;
+        Ok(RevealMessage {
+            round: self.round,
+            participant: self.participant.clone(),
+            local_m: self.local_m.clone(),
+            local_r: self.local_r.clone(),
+            timestamp: clock.now(),
+            signature: Vec::new(),
+        })
+    }
+}
+
+/// State of a participant as seen by a `Verifier`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParticipantStatus {
+    Committed,
+    Opened,
+    OpenedLate,
+    Rejected,
+}
+
+/// Checks the messages of one round.
+#[derive(Debug)]
+pub struct Verifier {
+    descriptor: RoundDescriptor,
+    rules_digest: Vec<u8>,
+    params: (Vec<Vec<u64>>, Vec<Vec<u64>>),
+    detector: MisbehaviorDetector,
+    statuses: BTreeMap<Vec<u8>, ParticipantStatus>,
+    openings: BTreeMap<Vec<u8>, Vec<u64>>,
+    evidence: Vec<MisbehaviorProof>,
+}
+
+impl Verifier {
+    /// Creates a verifier for the round described by `descriptor` under the `(A, B)` matrices
+    /// `params`.
+    pub fn new(descriptor: RoundDescriptor, params: (Vec<Vec<u64>>, Vec<Vec<u64>>)) -> Self {
+        Verifier {
+            rules_digest: descriptor.digest(),
+            descriptor,
+            params,
+            detector: MisbehaviorDetector::new(),
+            statuses: BTreeMap::new(),
+            openings: BTreeMap::new(),
+            evidence: Vec::new(),
+        }
+    }
+
+    /// Returns the rules of the round.
+    pub fn descriptor(&self) -> &RoundDescriptor {
+        &self.descriptor
+    }
+
+    /// Returns the `(A, B)` matrices of the round.
+    pub fn params(&self) -> (&[Vec<u64>], &[Vec<u64>]) {
+        (&self.params.0, &self.params.1)
+    }
+
+    /// Processes a commitment.
+    ///
+    /// # Returns
+    /// The evidence produced by the message, or `CommitError::InvalidTransition` if it belongs to
+    /// another round or was made under other rules.
+    pub fn on_commit(&mut self, commit: &CommitMessage) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        self.check_commit(commit)?;
+
+        let found: Vec<MisbehaviorProof> = self.detector.observe_commit(commit).into_iter().collect();
+        if found.is_empty() {
+            self.statuses.entry(commit.participant.clone()).or_insert(ParticipantStatus::Committed);
+        } else {
+            self.statuses.insert(commit.participant.clone(), ParticipantStatus::Rejected);
+        }
+        self.evidence.extend(found.iter().cloned());
+        Ok(found)
+    }
+
+    /// Processes a reveal received at time `received_at`.
+    ///
+    /// Reveals received after the reveal cutoff are rejected, or flagged as `OpenedLate` under
+    /// `LateRevealPolicy::AcceptWithFlag`, whatever their timestamp says. A rejected late reveal
+    /// also yields `LateReveal` evidence if the participant timestamped it after the cutoff, since
+    /// only its own timestamp is a claim third parties can check.
+    ///
+    /// # Returns
+    /// The evidence produced by the message, or `CommitError::InvalidTransition` if it belongs to
+    /// another round or the participant has no pending commitment.
+    pub fn on_reveal(&mut self, reveal: &RevealMessage, received_at: u64) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        self.check_reveal(reveal)?;
+
+        let cutoff = self.descriptor.reveal_cutoff();
+        let late = received_at > cutoff;
+        let rejects_late = late && self.descriptor.late_reveal_policy == LateRevealPolicy::Reject;
+        let mut found = self.detector.observe_reveal(reveal, self.params(), cutoff);
+        if !rejects_late {
+            found.retain(|proof| !matches!(proof, MisbehaviorProof::LateReveal { .. }));
+        }
+        if found.is_empty() && !rejects_late {
+            let status = if late { ParticipantStatus::OpenedLate } else { ParticipantStatus::Opened };
+            self.statuses.insert(reveal.participant.clone(), status);
+            self.openings.insert(reveal.participant.clone(), reveal.local_m.clone());
+        } else {
+            self.statuses.insert(reveal.participant.clone(), ParticipantStatus::Rejected);
+        }
+        self.evidence.extend(found.iter().cloned());
+        Ok(found)
+    }
+
+    // Refuse a commitment `on_commit` would refuse, without changing the verifier.
+    pub(crate) fn check_commit(&self, commit: &CommitMessage) -> Result<(), CommitError> {
+        if commit.round != self.descriptor.round || commit.rules_digest != self.rules_digest {
+            return Err(CommitError::InvalidTransition);
+        }
+        Ok(())
+    }
+
+    // Refuse a reveal `on_reveal` would refuse, without changing the verifier.
+    pub(crate) fn check_reveal(&self, reveal: &RevealMessage) -> Result<(), CommitError> {
+        if reveal.round != self.descriptor.round
+            || self.statuses.get(&reveal.participant) != Some(&ParticipantStatus::Committed)
+        {
+            return Err(CommitError::InvalidTransition);
+        }
+        Ok(())
+    }
+
+    /// Returns the status of every participant that committed.
+    pub fn statuses(&self) -> &BTreeMap<Vec<u8>, ParticipantStatus> {
+        &self.statuses
+    }
+
+    /// Returns the verified messages of the participants that opened their commitment.
+    pub fn openings(&self) -> &BTreeMap<Vec<u8>, Vec<u64>> {
+        &self.openings
+    }
+
+    /// Returns all evidence collected so far.
+    pub fn evidence(&self) -> &[MisbehaviorProof] {
+        &self.evidence
+    }
+}
@@ -0,0 +1,627 @@
+//! This module provides the `Round` coordinator.
+//!
+//! A round goes through three phases driven by `Round::advance`:
+//! - `Commit` until the commit deadline plus its grace period: commitments received before the
+//!   end of the grace period are accepted.
+//! - `Reveal` for `reveal_duration` after that, plus its grace period: reveals are on time.
+//! - `Closed` afterwards: late reveals are handled according to the `LateRevealPolicy`.
+//!
+//! The `RoundDescriptor` holding these rules is bound into every commitment through its digest,
+//! so participants cannot dispute the rules of a round afterwards.
+//!
+//! Deadlines are judged by the time the coordinator receives a message, read from the `Clock`
+//! passed to `Round::submit_commit` and `Round::submit_reveal`, never by the timestamp its sender
+//! wrote into it.
+//!
+//! The state of a round is its descriptor, parameters, phase and the log of accepted messages with
+//! the times they were received.
+//! With a `RoundStore` attached, every accepted message is appended to the journal of the store
+//! before the round applies it, so a message the store refused leaves the round unchanged. The
+//! full state, parameters included, is saved only when the store is attached and on phase changes,
+//! which empties the journal. `Round::resume` rebuilds the coordinator after a crash by replaying
+//! the saved messages and the journal.
+//!
+//! Subscribers registered with `Round::subscribe` receive a `WatchEvent` for every accepted
+//! message, every piece of evidence and the closing of the round.
+
+use std::fmt;
+use std::sync::mpsc::Receiver;
+use serde::{Serialize, Deserialize};
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+use crate::{scheme, utils};
+use super::evidence::MisbehaviorProof;
+use super::messages::{CommitMessage, ProtocolMessage, RevealMessage};
+use super::roles::Verifier;
+use super::store::RoundStore;
+use super::watch::{WatchEvent, Watcher};
+
+pub(crate) const ROUND_DESCRIPTOR_DOMAIN: &[u8] = b"reveler/round/descriptor";
+
+/// Phase of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RoundPhase {
+    Commit,
+    Reveal,
+    Closed,
+}
+
+impl RoundPhase {
+    fn to_byte(self) -> u8 {
+        match self {
+            RoundPhase::Commit => 0,
+            RoundPhase::Reveal => 1,
+            RoundPhase::Closed => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, CommitError> {
+        match byte {
+            0 => Ok(RoundPhase::Commit),
+            1 => Ok(RoundPhase::Reveal),
+            2 => Ok(RoundPhase::Closed),
+            _ => Err(CommitError::MalformedEncoding),
+        }
+    }
+}
+
+/// Handling of reveals received after the reveal grace period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LateRevealPolicy {
+    Reject,         // The participant is rejected, with `LateReveal` evidence if it also timestamped the reveal late
+    AcceptWithFlag, // A valid opening is accepted with the `OpenedLate` status
+}
+
+/// Public rules of a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundDescriptor {
+    pub round: u64,                           // The identifier of the round
+    pub opens_at: u64,                        // The time at which the commit phase opens
+    pub commit_duration: u64,                 // The length of the commit phase
+    pub reveal_duration: u64,                 // The length of the reveal phase
+    pub commit_grace: u64,                    // The extra time commitments are accepted for
+    pub reveal_grace: u64,                    // The extra time reveals are on time for
+    pub late_reveal_policy: LateRevealPolicy, // The handling of reveals after the grace period
+}
+
+impl RoundDescriptor {
+    /// Creates the rules of a round opening at `opens_at`, without grace periods and rejecting
+    /// late reveals.
+    pub fn new(round: u64, opens_at: u64, commit_duration: u64, reveal_duration: u64) -> Self {
+        RoundDescriptor {
+            round,
+            opens_at,
+            commit_duration,
+            reveal_duration,
+            commit_grace: 0,
+            reveal_grace: 0,
+            late_reveal_policy: LateRevealPolicy::Reject,
+        }
+    }
+
+    /// Sets the grace periods of the commit and reveal phases.
+    pub fn with_grace(mut self, commit_grace: u64, reveal_grace: u64) -> Self {
+        self.commit_grace = commit_grace;
+        self.reveal_grace = reveal_grace;
+        self
+    }
+
+    /// Sets the handling of late reveals.
+    pub fn with_late_reveal_policy(mut self, late_reveal_policy: LateRevealPolicy) -> Self {
+        self.late_reveal_policy = late_reveal_policy;
+        self
+    }
+
+    /// Returns the nominal end of the commit phase.
+    pub fn commit_deadline(&self) -> u64 {
+        self.opens_at.saturating_add(self.commit_duration)
+    }
+
+    /// Returns the time from which commitments are refused and reveals are accepted.
+    pub fn commit_cutoff(&self) -> u64 {
+        self.commit_deadline().saturating_add(self.commit_grace)
+    }
+
+    /// Returns the nominal end of the reveal phase.
+    pub fn reveal_deadline(&self) -> u64 {
+        self.commit_cutoff().saturating_add(self.reveal_duration)
+    }
+
+    /// Returns the last time at which a reveal is on time.
+    pub fn reveal_cutoff(&self) -> u64 {
+        self.reveal_deadline().saturating_add(self.reveal_grace)
+    }
+
+    /// Returns the phase a round with these rules is in at time `now`.
+    pub fn phase_at(&self, now: u64) -> RoundPhase {
+        if now < self.commit_cutoff() {
+            RoundPhase::Commit
+        } else if now <= self.reveal_cutoff() {
+            RoundPhase::Reveal
+        } else {
+            RoundPhase::Closed
+        }
+    }
+
+    /// Encodes the descriptor as the scheme header, its big-endian fields and the policy byte.
+    ///
+    /// The header makes the digest bind the scheme as well as the rules.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + 6 * 8 + 1);
+        scheme::write_header(&mut bytes);
+        for field in [
+            self.round,
+            self.opens_at,
+            self.commit_duration,
+            self.reveal_duration,
+            self.commit_grace,
+            self.reveal_grace,
+        ] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.push(match self.late_reveal_policy {
+            LateRevealPolicy::Reject => 0,
+            LateRevealPolicy::AcceptWithFlag => 1,
+        });
+        bytes
+    }
+
+    /// Computes the digest binding these rules into commitments.
+    pub fn digest(&self) -> Vec<u8> {
+        let mut input = ROUND_DESCRIPTOR_DOMAIN.to_vec();
+        input.extend(self.to_bytes());
+        utils::hash_to_commitment(&input)
+    }
+
+    // Decode a descriptor written by `to_bytes`, returning the remaining input.
+    fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (round, rest) = utils::decode_u64(rest)?;
+        let (opens_at, rest) = utils::decode_u64(rest)?;
+        let (commit_duration, rest) = utils::decode_u64(rest)?;
+        let (reveal_duration, rest) = utils::decode_u64(rest)?;
+        let (commit_grace, rest) = utils::decode_u64(rest)?;
+        let (reveal_grace, rest) = utils::decode_u64(rest)?;
+        let (&policy, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let late_reveal_policy = match policy {
+            0 => LateRevealPolicy::Reject,
+            1 => LateRevealPolicy::AcceptWithFlag,
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        let descriptor = RoundDescriptor {
+            round,
+            opens_at,
+            commit_duration,
+            reveal_duration,
+            commit_grace,
+            reveal_grace,
+            late_reveal_policy,
+        };
+        Ok((descriptor, rest))
+    }
+}
+
+/// Message accepted by a `Round`, with the time it was received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggedMessage {
+    pub received_at: u64,         // The time of the coordinator's clock when the message was accepted
+    pub message: ProtocolMessage, // The accepted message
+}
+
+impl LoggedMessage {
+    // Encode the receipt time followed by the length-prefixed message.
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.received_at.to_be_bytes().to_vec();
+        bytes.extend(utils::encode_bytes(&self.message.to_bytes()));
+        bytes
+    }
+
+    // Decode an entry written by `encode`, returning the remaining input.
+    fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), CommitError> {
+        let (received_at, rest) = utils::decode_u64(bytes)?;
+        let (message, rest) = utils::decode_bytes(rest)?;
+        Ok((LoggedMessage { received_at, message: ProtocolMessage::from_bytes(&message)? }, rest))
+    }
+}
+
+/// Everything needed to rebuild a `Round`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundState {
+    pub descriptor: RoundDescriptor,            // The rules of the round
+    pub params: (Vec<Vec<u64>>, Vec<Vec<u64>>), // The (A, B) matrices of the round
+    pub phase: RoundPhase,                      // The phase reached
+    pub messages: Vec<LoggedMessage>,           // The accepted messages in arrival order
+}
+
+impl RoundState {
+    /// Encodes the state as the descriptor, the phase, the parameters and the messages, each as its
+    /// receipt time followed by the length-prefixed message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.descriptor.to_bytes();
+        bytes.push(self.phase.to_byte());
+        bytes.extend(utils::encode_matrix(&self.params.0));
+        bytes.extend(utils::encode_matrix(&self.params.1));
+        bytes.extend_from_slice(&(self.messages.len() as u64).to_be_bytes());
+        for logged in &self.messages {
+            bytes.extend(logged.encode());
+        }
+        bytes
+    }
+
+    /// Decodes a state written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `RoundState`, `CommitError::MalformedEncoding` if the input is not a complete
+    /// canonical encoding, or `CommitError::UnsupportedScheme` if it names an unregistered scheme.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (descriptor, rest) = RoundDescriptor::decode(bytes)?;
+        let (&phase, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let (local_a, rest) = utils::decode_matrix(rest)?;
+        let (local_b, rest) = utils::decode_matrix(rest)?;
+        let (count, mut rest) = utils::decode_u64(rest)?;
+        // 每条消息至少占 8 字节接收时间和 8 字节长度前缀，先检查数量避免恶意前缀导致大量内存分配
+        if count > (rest.len() / 16) as u64 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let mut messages = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (logged, next) = LoggedMessage::decode(rest)?;
+            messages.push(logged);
+            rest = next;
+        }
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(RoundState { descriptor, params: (local_a, local_b), phase: RoundPhase::from_byte(phase)?, messages })
+    }
+
+    /// Reads the state saved in `store`, with the messages of its journal appended.
+    ///
+    /// Each journal record is the position of the message in the log followed by the message as
+    /// `to_bytes` encodes it, so records the snapshot already holds are skipped.
+    ///
+    /// # Returns
+    /// The saved `RoundState`, `None` if nothing was saved yet, `CommitError::StorageError` if the
+    /// store is unreadable, or `CommitError::MalformedEncoding` if the state or a record is
+    /// corrupted or a record is missing.
+    pub fn load(store: &dyn RoundStore) -> Result<Option<Self>, CommitError> {
+        let Some(bytes) = store.load()? else {
+            return Ok(None);
+        };
+        let mut state = RoundState::from_bytes(&bytes)?;
+        for record in store.journal()? {
+            let (position, rest) = utils::decode_u64(&record)?;
+            let (logged, rest) = LoggedMessage::decode(rest)?;
+            if !rest.is_empty() || position > state.messages.len() as u64 {
+                return Err(CommitError::MalformedEncoding);
+            }
+            if position == state.messages.len() as u64 {
+                state.messages.push(logged);
+            }
+        }
+        Ok(Some(state))
+    }
+}
+
+/// Coordinator enforcing the phases of one round.
+pub struct Round {
+    descriptor: RoundDescriptor,
+    phase: RoundPhase,
+    verifier: Verifier,
+    log: Vec<LoggedMessage>,
+    store: Option<Box<dyn RoundStore>>,
+    watcher: Watcher,
+}
+
+impl Round {
+    /// Opens a round in the commit phase.
+    ///
+    /// # Parameters
+    /// - `descriptor`: The `RoundDescriptor` holding the round identifier and timing rules.
+    /// - `params`: The `(A, B)` matrices of the round.
+    pub fn new(descriptor: RoundDescriptor, params: (Vec<Vec<u64>>, Vec<Vec<u64>>)) -> Self {
+        let verifier = Verifier::new(descriptor.clone(), params);
+        Round { descriptor, phase: RoundPhase::Commit, verifier, log: Vec::new(), store: None, watcher: Watcher::new() }
+    }
+
+    /// Rebuilds a round from a saved state by replaying its messages.
+    ///
+    /// The phase is restored as saved; call `advance` afterwards to catch up with the deadlines
+    /// that passed while the coordinator was down.
+    pub fn from_state(state: RoundState) -> Result<Self, CommitError> {
+        let mut round = Round::new(state.descriptor, state.params);
+        for logged in state.messages {
+            match &logged.message {
+                ProtocolMessage::Commit(commit) => round.verifier.on_commit(commit)?,
+                ProtocolMessage::Reveal(reveal) => round.verifier.on_reveal(reveal, logged.received_at)?,
+            };
+            round.log.push(logged);
+        }
+        round.phase = state.phase;
+        Ok(round)
+    }
+
+    /// Recovers the round saved in `store` and keeps persisting to it.
+    ///
+    /// # Returns
+    /// The recovered `Round`, `CommitError::StorageError` if the store is empty or unreadable, or
+    /// `CommitError::MalformedEncoding` if the saved state is corrupted.
+    pub fn resume(store: Box<dyn RoundStore>) -> Result<Self, CommitError> {
+        let state = RoundState::load(store.as_ref())?.ok_or(CommitError::StorageError)?;
+        let mut round = Round::from_state(state)?;
+        round.store = Some(store);
+        Ok(round)
+    }
+
+    /// Saves the current state to `store` and keeps it up to date as the round progresses.
+    pub fn attach_store(&mut self, store: Box<dyn RoundStore>) -> Result<(), CommitError> {
+        store.save(&self.state().to_bytes())?;
+        self.store = Some(store);
+        Ok(())
+    }
+
+    /// Returns a snapshot of the state.
+    pub fn state(&self) -> RoundState {
+        let params = self.verifier.params();
+        RoundState {
+            descriptor: self.descriptor.clone(),
+            params: (params.0.to_vec(), params.1.to_vec()),
+            phase: self.phase,
+            messages: self.log.clone(),
+        }
+    }
+
+    /// Registers a subscriber for the events of this round.
+    pub fn subscribe(&mut self) -> Receiver<WatchEvent> {
+        self.watcher.subscribe()
+    }
+
+    /// Returns the rules of the round.
+    pub fn descriptor(&self) -> &RoundDescriptor {
+        &self.descriptor
+    }
+
+    /// Returns the current phase.
+    pub fn phase(&self) -> RoundPhase {
+        self.phase
+    }
+
+    /// Returns the verifier holding the participant statuses and the collected evidence.
+    pub fn verifier(&self) -> &Verifier {
+        &self.verifier
+    }
+
+    /// Moves the round to the phase it is in at the current time of `clock`. Phases never go
+    /// backwards.
+    ///
+    /// # Returns
+    /// Every phase entered, in order.
+    pub fn advance(&mut self, clock: &dyn Clock) -> Vec<RoundPhase> {
+        let target = self.descriptor.phase_at(clock.now());
+        let mut entered = Vec::new();
+        while self.phase < target {
+            self.phase = match self.phase {
+                RoundPhase::Commit => RoundPhase::Reveal,
+                _ => RoundPhase::Closed,
+            };
+            entered.push(self.phase);
+        }
+        if entered.contains(&RoundPhase::Closed) {
+            self.watcher.emit(WatchEvent::RoundClosed { round: self.descriptor.round });
+        }
+        if let (Some(store), false) = (&self.store, entered.is_empty()) {
+            // 阶段可由时钟重新推出，保存失败时日志仍然完整，恢复后再次调用 advance 即可
+            let _ = store.save(&self.state().to_bytes());
+        }
+        entered
+    }
+
+    /// Accepts a commitment during the commit phase.
+    ///
+    /// # Parameters
+    /// - `commit`: A reference to the received `CommitMessage`.
+    /// - `clock`: The clock of the coordinator, giving the time the message is received at.
+    ///
+    /// # Returns
+    /// The evidence produced by the message, `CommitError::InvalidTransition` outside the commit
+    /// phase, for another round or other rules, or if received at or after the commit cutoff, or
+    /// `CommitError::StorageError` if the accepted message could not be persisted.
+    pub fn submit_commit(&mut self, commit: &CommitMessage, clock: &dyn Clock) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        let received_at = clock.now();
+        if self.phase != RoundPhase::Commit || received_at >= self.descriptor.commit_cutoff() {
+            return Err(CommitError::InvalidTransition);
+        }
+        self.verifier.check_commit(commit)?;
+        self.persist(LoggedMessage { received_at, message: ProtocolMessage::Commit(commit.clone()) })?;
+        let found = self.verifier.on_commit(commit)?;
+        self.watcher.emit(WatchEvent::NewCommitment(commit.clone()));
+        self.report(&found);
+        Ok(found)
+    }
+
+    /// Accepts a reveal once the commit phase is over.
+    ///
+    /// Whether the reveal is late is decided by the time `clock` shows, see `Verifier::on_reveal`.
+    ///
+    /// # Returns
+    /// The evidence produced by the message, `CommitError::InvalidTransition` during the commit
+    /// phase, for another round, or without a pending commitment, or `CommitError::StorageError`
+    /// if the accepted message could not be persisted.
+    pub fn submit_reveal(&mut self, reveal: &RevealMessage, clock: &dyn Clock) -> Result<Vec<MisbehaviorProof>, CommitError> {
+        let received_at = clock.now();
+        if self.phase == RoundPhase::Commit || received_at < self.descriptor.commit_cutoff() {
+            return Err(CommitError::InvalidTransition);
+        }
+        self.verifier.check_reveal(reveal)?;
+        self.persist(LoggedMessage { received_at, message: ProtocolMessage::Reveal(reveal.clone()) })?;
+        let found = self.verifier.on_reveal(reveal, received_at)?;
+        self.watcher.emit(WatchEvent::RevealReceived(reveal.clone()));
+        self.report(&found);
+        Ok(found)
+    }
+
+    // Append an accepted message to the journal of the store, then to the log. Nothing changes if
+    // the store refuses it.
+    fn persist(&mut self, logged: LoggedMessage) -> Result<(), CommitError> {
+        if let Some(store) = &self.store {
+            let mut record = (self.log.len() as u64).to_be_bytes().to_vec();
+            record.extend(logged.encode());
+            store.append(&record)?;
+        }
+        self.log.push(logged);
+        Ok(())
+    }
+
+    // Report the evidence of an accepted message.
+    fn report(&mut self, found: &[MisbehaviorProof]) {
+        for proof in found {
+            self.watcher.emit(WatchEvent::VerificationFailed(proof.clone()));
+        }
+    }
+}
+
+impl fmt::Debug for Round {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Round")
+            .field("descriptor", &self.descriptor)
+            .field("phase", &self.phase)
+            .field("messages", &self.log.len())
+            .field("persistent", &self.store.is_some())
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use crate::clock::VirtualClock;
+    use super::super::roles::{Committer, ParticipantStatus};
+    use super::super::store::MemoryRoundStore;
+    use super::*;
+
+    // 共享的内存存储，统计快照次数，可设置为拒绝写入
+    #[derive(Clone, Default)]
+    struct Probe(Arc<(MemoryRoundStore, AtomicUsize, AtomicBool)>);
+
+    impl RoundStore for Probe {
+        fn save(&self, bytes: &[u8]) -> Result<(), CommitError> {
+            self.0.1.fetch_add(1, Ordering::Relaxed);
+            self.0.0.save(bytes)
+        }
+
+        fn load(&self) -> Result<Option<Vec<u8>>, CommitError> {
+            self.0.0.load()
+        }
+
+        fn append(&self, record: &[u8]) -> Result<(), CommitError> {
+            if self.0.2.load(Ordering::Relaxed) {
+                return Err(CommitError::StorageError);
+            }
+            self.0.0.append(record)
+        }
+
+        fn journal(&self) -> Result<Vec<Vec<u8>>, CommitError> {
+            self.0.0.journal()
+        }
+    }
+
+    type Params = (Vec<Vec<u64>>, Vec<Vec<u64>>);
+
+    fn commits(count: usize) -> (RoundDescriptor, Params, Vec<CommitMessage>) {
+        let mut rng = StdRng::seed_from_u64(223);
+        let params = utils::generate_params_with_rng(&mut rng);
+        let descriptor = RoundDescriptor::new(1, 0, 10, 10);
+        let clock = VirtualClock::new(1);
+        let commits = (0..count)
+            .map(|i| Committer::new(vec![i as u8]).commit(&descriptor, (&params.0, &params.1), vec![i as u64], &mut rng, &clock).unwrap())
+            .collect();
+        (descriptor, params, commits)
+    }
+
+    // 提交于时间 1，揭示消息自称发送于 `sent_at`，协调者在 `received_at` 收到
+    fn reveal_status(policy: LateRevealPolicy, sent_at: u64, received_at: u64) -> (ParticipantStatus, usize) {
+        let mut rng = StdRng::seed_from_u64(225);
+        let params = utils::generate_params_with_rng(&mut rng);
+        let descriptor = RoundDescriptor::new(1, 0, 10, 10).with_late_reveal_policy(policy);
+        let mut round = Round::new(descriptor.clone(), params.clone());
+        let clock = VirtualClock::new(1);
+        let mut committer = Committer::new(b"alice".to_vec());
+        let commit = committer.commit(&descriptor, (&params.0, &params.1), vec![1, 2], &mut rng, &clock).unwrap();
+        round.submit_commit(&commit, &clock).unwrap();
+
+        clock.set(sent_at);
+        let reveal = committer.hand_over().unwrap().reveal(&clock).unwrap();
+        clock.set(received_at);
+        round.advance(&clock);
+        let found = round.submit_reveal(&reveal, &clock).unwrap();
+        (round.verifier().statuses()[b"alice".as_slice()], found.len())
+    }
+
+    #[test]
+    fn lateness_is_judged_by_receipt_time() {
+        // 截止时间为 20
+        assert_eq!(reveal_status(LateRevealPolicy::Reject, 15, 15), (ParticipantStatus::Opened, 0));
+        assert_eq!(reveal_status(LateRevealPolicy::Reject, 30, 15), (ParticipantStatus::Opened, 0));
+        assert_eq!(reveal_status(LateRevealPolicy::Reject, 15, 30), (ParticipantStatus::Rejected, 0));
+        assert_eq!(reveal_status(LateRevealPolicy::Reject, 30, 30), (ParticipantStatus::Rejected, 1));
+        assert_eq!(reveal_status(LateRevealPolicy::AcceptWithFlag, 15, 30), (ParticipantStatus::OpenedLate, 0));
+        assert_eq!(reveal_status(LateRevealPolicy::AcceptWithFlag, 30, 15), (ParticipantStatus::Opened, 0));
+    }
+
+    #[test]
+    fn commitments_received_after_the_cutoff_are_refused() {
+        let mut rng = StdRng::seed_from_u64(225);
+        let params = utils::generate_params_with_rng(&mut rng);
+        let descriptor = RoundDescriptor::new(1, 0, 10, 10);
+        let mut round = Round::new(descriptor.clone(), params.clone());
+        let clock = VirtualClock::new(5);
+        let commit = Committer::new(b"alice".to_vec()).commit(&descriptor, (&params.0, &params.1), vec![1], &mut rng, &clock).unwrap();
+        clock.set(10);
+        assert_eq!(round.submit_commit(&commit, &clock), Err(CommitError::InvalidTransition));
+    }
+
+    #[test]
+    fn accepted_messages_are_journaled_and_replayed() {
+        let (descriptor, params, commits) = commits(3);
+        let probe = Probe::default();
+        let mut round = Round::new(descriptor, params);
+        round.attach_store(Box::new(probe.clone())).unwrap();
+        let clock = VirtualClock::new(2);
+        for commit in &commits {
+            round.submit_commit(commit, &clock).unwrap();
+        }
+        // 只在挂载时保存一次完整状态
+        assert_eq!(probe.0.1.load(Ordering::Relaxed), 1);
+        assert_eq!(probe.journal().unwrap().len(), 3);
+
+        let resumed = Round::resume(Box::new(probe.clone())).unwrap();
+        assert_eq!(resumed.state(), round.state());
+        assert_eq!(resumed.verifier().statuses(), round.verifier().statuses());
+
+        clock.set(10);
+        round.advance(&clock);
+        assert!(probe.journal().unwrap().is_empty());
+        assert_eq!(RoundState::load(&probe).unwrap(), Some(round.state()));
+    }
+
+    #[test]
+    fn refused_messages_leave_the_round_unchanged() {
+        let (descriptor, params, commits) = commits(1);
+        let probe = Probe::default();
+        let mut round = Round::new(descriptor, params);
+        round.attach_store(Box::new(probe.clone())).unwrap();
+        let clock = VirtualClock::new(2);
+        probe.0.2.store(true, Ordering::Relaxed);
+        assert_eq!(round.submit_commit(&commits[0], &clock), Err(CommitError::StorageError));
+        assert!(round.state().messages.is_empty());
+        assert!(round.verifier().statuses().is_empty());
+
+        probe.0.2.store(false, Ordering::Relaxed);
+        assert_eq!(round.submit_commit(&commits[0], &clock), Ok(Vec::new()));
+        assert_eq!(round.verifier().statuses().len(), 1);
+    }
+}
@@ -0,0 +1,229 @@
+//! This module provides a deterministic replay harness for the protocol.
+//!
+//! `simulate` runs one round end to end without networking: it generates the parameters and
+//! every participant's message from a seeded RNG, schedules the messages on a virtual clock, drives
+//! the `Committer`, `Revealer` and `Round` state machines, and records every phase change, message
+//! and piece of evidence in a trace. The same `SimulationConfig` always yields the same trace.
+//!
+//! Each participant follows a `Behavior`, so integrators can exercise every transition, including
+//! the misbehaving ones. Participants sign their messages with `SimulatedSignatures`, so the
+//! evidence in the trace verifies under it.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use crate::clock::{Clock, VirtualClock};
+use crate::commit_error::CommitError;
+use crate::envelope::{EnvelopeSigner, SignatureVerifier};
+use crate::fft::{LOCAL_Q, MESSAGE_LEN};
+use crate::utils;
+use super::evidence::MisbehaviorProof;
+use super::messages::{CommitMessage, RevealMessage};
+use super::roles::{Committer, ParticipantStatus, Revealer};
+use super::round::{Round, RoundDescriptor, RoundPhase};
+
+/// Scripted behavior of a simulated participant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Behavior {
+    Honest,             // Commits, then reveals during the reveal phase
+    Withhold,           // Commits but never reveals
+    RevealLate,         // Reveals after the reveal deadline
+    RevealInconsistent, // Reveals a message that does not open the commitment
+    DoubleCommit,       // Publishes a second, different commitment in the commit phase
+}
+
+/// Settings of a simulated round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    pub seed: u64,                   // The seed of the RNG driving the whole simulation
+    pub descriptor: RoundDescriptor, // The rules of the simulated round
+    pub behaviors: Vec<Behavior>,    // One behavior per participant
+}
+
+impl SimulationConfig {
+    /// Creates a configuration for one participant per behavior, in round 0 opening at time 0
+    /// with 60-second phases.
+    pub fn new(seed: u64, behaviors: Vec<Behavior>) -> Self {
+        SimulationConfig {
+            seed,
+            descriptor: RoundDescriptor::new(0, 0, 60, 60),
+            behaviors,
+        }
+    }
+
+    /// Sets the rules of the simulated round.
+    pub fn with_descriptor(mut self, descriptor: RoundDescriptor) -> Self {
+        self.descriptor = descriptor;
+        self
+    }
+}
+
+/// Event recorded by the simulation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    PhaseChanged(RoundPhase),
+    Commit(CommitMessage),
+    Reveal(RevealMessage),
+    Misbehavior(MisbehaviorProof),
+}
+
+/// Event together with the virtual time it happened at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub time: u64,
+    pub event: TraceEvent,
+}
+
+/// Result of a simulated round.
+#[derive(Debug, Clone)]
+pub struct SimulationOutcome {
+    pub descriptor: RoundDescriptor,                    // The rules of the simulated round
+    pub params: (Vec<Vec<u64>>, Vec<Vec<u64>>),         // The generated (A, B) matrices
+    pub trace: Vec<TraceEntry>,                         // Every event in time order
+    pub statuses: BTreeMap<Vec<u8>, ParticipantStatus>, // The final status of every participant
+    pub openings: BTreeMap<Vec<u8>, Vec<u64>>,          // The verified messages
+}
+
+/// Signatures of the simulated participants.
+///
+/// A signature is the hash of the participant identifier and the message, which anyone can
+/// compute: it lets evidence from a simulation verify, and proves nothing outside of it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimulatedSignatures;
+
+impl SignatureVerifier for SimulatedSignatures {
+    fn verify(&self, key_id: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        SimulatedSigner(key_id).sign(message) == signature
+    }
+}
+
+// Signer of one simulated participant.
+struct SimulatedSigner<'a>(&'a [u8]);
+
+impl EnvelopeSigner for SimulatedSigner<'_> {
+    fn key_id(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let mut input = utils::encode_bytes(self.0);
+        input.extend_from_slice(message);
+        utils::hash_to_commitment(&input)
+    }
+}
+
+// Action scheduled on the virtual clock for participant `index`.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Commit(usize),
+    SecondCommit(usize),
+    Reveal(usize),
+}
+
+/// Runs one round under the given configuration.
+///
+/// # Parameters
+/// - `config`: A reference to the `SimulationConfig`.
+///
+/// # Returns
+/// The `SimulationOutcome` with the full trace, or the `CommitError` of a failed commitment.
+pub fn simulate(config: &SimulationConfig) -> Result<SimulationOutcome, CommitError> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let params = utils::generate_params_with_rng(&mut rng);
+
+    let descriptor = config.descriptor.clone();
+    let mut round = Round::new(descriptor.clone(), params.clone());
+
+    // Commitments are sent before the nominal commit deadline and reveals during the nominal
+    // reveal phase, so the grace periods only matter to the late behaviors.
+    let commit_window = descriptor.commit_duration.max(1);
+    let reveal_window = descriptor.reveal_duration.max(1);
+    let mut schedule = Vec::new();
+    for (index, behavior) in config.behaviors.iter().enumerate() {
+        let commit_time = descriptor.opens_at + rng.gen_range(0..commit_window);
+        schedule.push((commit_time, Action::Commit(index)));
+        let reveal_time = descriptor.commit_cutoff() + rng.gen_range(0..reveal_window);
+        match behavior {
+            Behavior::Withhold => {}
+            Behavior::RevealLate => {
+                let time = descriptor.reveal_cutoff() + 1 + rng.gen_range(0..reveal_window);
+                schedule.push((time, Action::Reveal(index)));
+            }
+            Behavior::DoubleCommit => {
+                let second_time = rng.gen_range(commit_time..descriptor.opens_at + commit_window);
+                schedule.push((second_time, Action::SecondCommit(index)));
+                schedule.push((reveal_time, Action::Reveal(index)));
+            }
+            _ => schedule.push((reveal_time, Action::Reveal(index))),
+        }
+    }
+    // At equal times a participant's first commitment goes ahead of its second one.
+    schedule.sort_by_key(|&(time, action)| (time, matches!(action, Action::SecondCommit(_))));
+
+    let mut committers: Vec<Committer> = (0..config.behaviors.len())
+        .map(|index| Committer::new(format!("participant-{index}").into_bytes()))
+        .collect();
+    let mut revealers: Vec<Option<Revealer>> = vec![None; config.behaviors.len()];
+    let mut trace = vec![TraceEntry { time: descriptor.opens_at, event: TraceEvent::PhaseChanged(RoundPhase::Commit) }];
+    let clock = VirtualClock::new(descriptor.opens_at);
+
+    for (time, action) in schedule {
+        clock.set(time);
+        for phase in round.advance(&clock) {
+            trace.push(TraceEntry { time, event: TraceEvent::PhaseChanged(phase) });
+        }
+
+        let evidence = match action {
+            Action::Commit(index) => {
+                let message = random_vector(&mut rng);
+                let commit = committers[index].commit(&descriptor, (&params.0, &params.1), message, &mut rng, &clock)?
+                    .with_signature(&SimulatedSigner(committers[index].participant()));
+                revealers[index] = Some(committers[index].hand_over()?);
+                trace.push(TraceEntry { time, event: TraceEvent::Commit(commit.clone()) });
+                round.submit_commit(&commit, &clock)
+            }
+            Action::SecondCommit(index) => {
+                let mut second = Committer::new(committers[index].participant().to_vec());
+                let message = random_vector(&mut rng);
+                let commit = second.commit(&descriptor, (&params.0, &params.1), message, &mut rng, &clock)?
+                    .with_signature(&SimulatedSigner(second.participant()));
+                trace.push(TraceEntry { time, event: TraceEvent::Commit(commit.clone()) });
+                round.submit_commit(&commit, &clock)
+            }
+            Action::Reveal(index) => {
+                let revealer = revealers[index].as_mut().ok_or(CommitError::InvalidTransition)?;
+                let mut reveal = revealer.reveal(&clock)?;
+                if config.behaviors[index] == Behavior::RevealInconsistent {
+                    reveal.local_m[0] = (reveal.local_m[0] + 1) % LOCAL_Q;
+                }
+                let reveal = reveal.with_signature(&SimulatedSigner(committers[index].participant()));
+                trace.push(TraceEntry { time, event: TraceEvent::Reveal(reveal.clone()) });
+                round.submit_reveal(&reveal, &clock)
+            }
+        };
+
+        // Messages refused by the coordinator are part of the trace but produce no evidence.
+        for proof in evidence.unwrap_or_default() {
+            trace.push(TraceEntry { time, event: TraceEvent::Misbehavior(proof) });
+        }
+    }
+
+    let end = clock.now().max(descriptor.reveal_cutoff() + 1);
+    clock.set(end);
+    for phase in round.advance(&clock) {
+        trace.push(TraceEntry { time: end, event: TraceEvent::PhaseChanged(phase) });
+    }
+
+    Ok(SimulationOutcome {
+        statuses: round.verifier().statuses().clone(),
+        openings: round.verifier().openings().clone(),
+        descriptor,
+        params,
+        trace,
+    })
+}
+
+fn random_vector<R: Rng>(rng: &mut R) -> Vec<u64> {
+    (0..MESSAGE_LEN).map(|_| rng.gen_range(0..LOCAL_Q)).collect()
+}
@@ -0,0 +1,132 @@
+//! This module provides the storage backends a `Round` persists its state to.
+//!
+//! A store holds a snapshot of the encoded `RoundState` of one round and a journal of the records
+//! appended since, so a round persists each accepted message without rewriting its parameters.
+//! Saving a new snapshot empties the journal. `FileRoundStore` writes the snapshot to a temporary
+//! file and renames it into place, and appends the records to a second file, so a crash never
+//! leaves a half-written state behind: a record cut short by a crash is dropped when loading.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::commit_error::CommitError;
+use crate::utils;
+
+/// Durable storage for the encoded state of one round.
+pub trait RoundStore: Send {
+    /// Replaces the stored snapshot with `bytes` and empties the journal.
+    fn save(&self, bytes: &[u8]) -> Result<(), CommitError>;
+
+    /// Returns the stored snapshot, or `None` if nothing was saved yet.
+    fn load(&self) -> Result<Option<Vec<u8>>, CommitError>;
+
+    /// Appends `record` to the journal.
+    fn append(&self, record: &[u8]) -> Result<(), CommitError>;
+
+    /// Returns the records appended since the last snapshot, in order.
+    fn journal(&self) -> Result<Vec<Vec<u8>>, CommitError>;
+}
+
+/// Store keeping the state in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct MemoryRoundStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    snapshot: Option<Vec<u8>>, // The last saved state
+    journal: Vec<Vec<u8>>,     // The records appended since
+}
+
+impl MemoryRoundStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        MemoryRoundStore::default()
+    }
+}
+
+impl RoundStore for MemoryRoundStore {
+    fn save(&self, bytes: &[u8]) -> Result<(), CommitError> {
+        *self.state.lock().map_err(|_| CommitError::StorageError)? = MemoryState { snapshot: Some(bytes.to_vec()), journal: Vec::new() };
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Option<Vec<u8>>, CommitError> {
+        Ok(self.state.lock().map_err(|_| CommitError::StorageError)?.snapshot.clone())
+    }
+
+    fn append(&self, record: &[u8]) -> Result<(), CommitError> {
+        self.state.lock().map_err(|_| CommitError::StorageError)?.journal.push(record.to_vec());
+        Ok(())
+    }
+
+    fn journal(&self) -> Result<Vec<Vec<u8>>, CommitError> {
+        Ok(self.state.lock().map_err(|_| CommitError::StorageError)?.journal.clone())
+    }
+}
+
+/// Store keeping the snapshot in a file and the journal next to it, with the `.log` extension
+/// appended.
+#[derive(Debug, Clone)]
+pub struct FileRoundStore {
+    path: PathBuf,
+}
+
+impl FileRoundStore {
+    /// Creates a store writing to `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileRoundStore { path: path.into() }
+    }
+
+    // The path of the file next to the snapshot with `extension` appended.
+    fn sibling(&self, extension: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(extension);
+        path.into()
+    }
+}
+
+impl RoundStore for FileRoundStore {
+    fn save(&self, bytes: &[u8]) -> Result<(), CommitError> {
+        let temporary = self.sibling(".tmp");
+        fs::write(&temporary, bytes).map_err(|_| CommitError::StorageError)?;
+        fs::rename(&temporary, &self.path).map_err(|_| CommitError::StorageError)?;
+        // 快照已包含日志中的记录，删除失败时重放会跳过它们
+        match fs::remove_file(self.sibling(".log")) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(CommitError::StorageError),
+            _ => Ok(()),
+        }
+    }
+
+    fn load(&self) -> Result<Option<Vec<u8>>, CommitError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(_) => Err(CommitError::StorageError),
+        }
+    }
+
+    fn append(&self, record: &[u8]) -> Result<(), CommitError> {
+        let mut log = OpenOptions::new().create(true).append(true).open(self.sibling(".log")).map_err(|_| CommitError::StorageError)?;
+        log.write_all(&utils::encode_bytes(record)).map_err(|_| CommitError::StorageError)?;
+        log.sync_data().map_err(|_| CommitError::StorageError)
+    }
+
+    fn journal(&self) -> Result<Vec<Vec<u8>>, CommitError> {
+        let bytes = match fs::read(self.sibling(".log")) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(_) => return Err(CommitError::StorageError),
+        };
+        // 崩溃时最后一条记录可能只写了一部分，它未被确认，直接丢弃
+        let mut records = Vec::new();
+        let mut rest = bytes.as_slice();
+        while let Ok((record, next)) = utils::decode_bytes(rest) {
+            records.push(record);
+            rest = next;
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,269 @@
+//! This module provides network transport adapters for the protocol messages.
+//!
+//! Every message travels as a frame: a big-endian `u32` length followed by the canonical bytes of
+//! a `ProtocolMessage`. Before exchanging messages both peers send a `Handshake` announcing the
+//! protocol version, the scheme identifier and a digest of the round parameters, and abort with
+//! `CommitError::HandshakeFailed` if the other side disagrees.
+//!
+//! `StreamTransport` frames messages over any byte stream such as a `TcpStream`. With the
+//! `websocket` feature, `WebSocketTransport` carries the same frames in binary WebSocket messages.
+//!
+//! The module is compiled with the `transport` feature.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use crate::commit_error::CommitError;
+use crate::params;
+use crate::scheme::{self, SchemeId};
+use crate::utils;
+use super::messages::ProtocolMessage;
+
+const HANDSHAKE_MAGIC: &[u8; 4] = b"RVLR";
+
+/// Version of the wire protocol announced in the handshake.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest frame accepted from a peer, in bytes.
+pub const MAX_FRAME_LEN: usize = 16 << 20;
+
+/// Greeting exchanged when a connection opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handshake {
+    pub version: u32,           // The wire protocol version
+    pub scheme: SchemeId,       // The scheme of the exchanged artifacts
+    pub params_digest: Vec<u8>, // The digest of the (A, B) matrices of the round
+}
+
+impl Handshake {
+    /// Creates the handshake of this crate's protocol version and scheme for the given parameters.
+    pub fn new(params: (&[Vec<u64>], &[Vec<u64>])) -> Self {
+        Handshake { version: PROTOCOL_VERSION, scheme: SchemeId::current(), params_digest: params_digest(params) }
+    }
+
+    /// Encodes the handshake as the magic bytes, the version, the length-prefixed scheme
+    /// identifier and the length-prefixed digest.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = HANDSHAKE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_be_bytes());
+        bytes.extend(utils::encode_bytes(self.scheme.to_string().as_bytes()));
+        bytes.extend(utils::encode_bytes(&self.params_digest));
+        bytes
+    }
+
+    /// Decodes a handshake written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `Handshake`, or `CommitError::HandshakeFailed` if the input is not one or names
+    /// an unregistered scheme.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let Some((magic, rest)) = bytes.split_first_chunk::<4>() else {
+            return Err(CommitError::HandshakeFailed);
+        };
+        let Some((version, rest)) = rest.split_first_chunk::<4>() else {
+            return Err(CommitError::HandshakeFailed);
+        };
+        if magic != HANDSHAKE_MAGIC {
+            return Err(CommitError::HandshakeFailed);
+        }
+        let (scheme, rest) = utils::decode_bytes(rest).map_err(|_| CommitError::HandshakeFailed)?;
+        let scheme = std::str::from_utf8(&scheme)
+            .map_err(|_| CommitError::HandshakeFailed)
+            .and_then(|id| scheme::resolve(id).map_err(|_| CommitError::HandshakeFailed))?;
+        let (params_digest, rest) = utils::decode_bytes(rest).map_err(|_| CommitError::HandshakeFailed)?;
+        if !rest.is_empty() {
+            return Err(CommitError::HandshakeFailed);
+        }
+        Ok(Handshake { version: u32::from_be_bytes(*version), scheme, params_digest })
+    }
+}
+
+/// Computes the digest of the `(A, B)` matrices announced in the handshake.
+pub fn params_digest(params: (&[Vec<u64>], &[Vec<u64>])) -> Vec<u8> {
+    params::params_digest(params)
+}
+
+/// Writes one length-prefixed frame.
+///
+/// # Returns
+/// `CommitError::TransportError` if the payload exceeds `MAX_FRAME_LEN` or the write fails.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> Result<(), CommitError> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(CommitError::TransportError);
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes()).map_err(|_| CommitError::TransportError)?;
+    writer.write_all(payload).map_err(|_| CommitError::TransportError)?;
+    writer.flush().map_err(|_| CommitError::TransportError)
+}
+
+/// Reads one length-prefixed frame.
+///
+/// # Returns
+/// The payload, or `CommitError::TransportError` if the peer announces more than `MAX_FRAME_LEN`
+/// bytes or the read fails.
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Vec<u8>, CommitError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).map_err(|_| CommitError::TransportError)?;
+    let len = u32::from_be_bytes(len) as usize;
+    // 先检查长度，避免恶意长度前缀导致大量内存分配
+    if len > MAX_FRAME_LEN {
+        return Err(CommitError::TransportError);
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).map_err(|_| CommitError::TransportError)?;
+    Ok(payload)
+}
+
+/// Connection carrying frames between two peers.
+pub trait FrameTransport {
+    /// Sends one frame.
+    fn send_frame(&mut self, payload: &[u8]) -> Result<(), CommitError>;
+
+    /// Receives one frame.
+    fn receive_frame(&mut self) -> Result<Vec<u8>, CommitError>;
+
+    /// Sends a protocol message.
+    fn send_message(&mut self, message: &ProtocolMessage) -> Result<(), CommitError> {
+        self.send_frame(&message.to_bytes())
+    }
+
+    /// Receives a protocol message.
+    fn receive_message(&mut self) -> Result<ProtocolMessage, CommitError> {
+        ProtocolMessage::from_bytes(&self.receive_frame()?)
+    }
+
+    /// Exchanges handshakes with the peer.
+    ///
+    /// # Returns
+    /// The peer's `Handshake`, or `CommitError::HandshakeFailed` if its version, scheme or
+    /// parameters digest differ from `local`.
+    fn handshake(&mut self, local: &Handshake) -> Result<Handshake, CommitError> {
+        self.send_frame(&local.to_bytes())?;
+        let remote = Handshake::from_bytes(&self.receive_frame()?)?;
+        if remote.version != local.version || remote.scheme != local.scheme || remote.params_digest != local.params_digest {
+            return Err(CommitError::HandshakeFailed);
+        }
+        Ok(remote)
+    }
+}
+
+/// Frames messages over a byte stream.
+#[derive(Debug)]
+pub struct StreamTransport<S: Read + Write> {
+    stream: S,
+}
+
+impl<S: Read + Write> StreamTransport<S> {
+    /// Wraps a stream without performing the handshake.
+    pub fn new(stream: S) -> Self {
+        StreamTransport { stream }
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl StreamTransport<TcpStream> {
+    /// Connects to a peer over TCP and performs the handshake.
+    pub fn connect<A: ToSocketAddrs>(addr: A, local: &Handshake) -> Result<Self, CommitError> {
+        let stream = TcpStream::connect(addr).map_err(|_| CommitError::TransportError)?;
+        let mut transport = StreamTransport::new(stream);
+        transport.handshake(local)?;
+        Ok(transport)
+    }
+
+    /// Accepts a peer on a TCP listener and performs the handshake.
+    pub fn accept(listener: &TcpListener, local: &Handshake) -> Result<Self, CommitError> {
+        let (stream, _) = listener.accept().map_err(|_| CommitError::TransportError)?;
+        let mut transport = StreamTransport::new(stream);
+        transport.handshake(local)?;
+        Ok(transport)
+    }
+}
+
+impl<S: Read + Write> FrameTransport for StreamTransport<S> {
+    fn send_frame(&mut self, payload: &[u8]) -> Result<(), CommitError> {
+        write_frame(&mut self.stream, payload)
+    }
+
+    fn receive_frame(&mut self) -> Result<Vec<u8>, CommitError> {
+        read_frame(&mut self.stream)
+    }
+}
+
+#[cfg(feature = "websocket")]
+pub use self::websocket::WebSocketTransport;
+
+#[cfg(feature = "websocket")]
+mod websocket {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use tungstenite::stream::MaybeTlsStream;
+    use tungstenite::{Message, WebSocket};
+    use crate::commit_error::CommitError;
+    use super::{read_frame, write_frame, FrameTransport, Handshake};
+
+    /// Frames messages in binary WebSocket messages.
+    pub struct WebSocketTransport<S: Read + Write> {
+        socket: WebSocket<S>,
+    }
+
+    impl<S: Read + Write> WebSocketTransport<S> {
+        /// Wraps an established WebSocket without performing the handshake.
+        pub fn new(socket: WebSocket<S>) -> Self {
+            WebSocketTransport { socket }
+        }
+
+        /// Returns the underlying WebSocket.
+        pub fn into_inner(self) -> WebSocket<S> {
+            self.socket
+        }
+    }
+
+    impl WebSocketTransport<MaybeTlsStream<TcpStream>> {
+        /// Connects to a `ws://` URL and performs the handshake.
+        pub fn connect(url: &str, local: &Handshake) -> Result<Self, CommitError> {
+            let (socket, _) = tungstenite::connect(url).map_err(|_| CommitError::TransportError)?;
+            let mut transport = WebSocketTransport::new(socket);
+            transport.handshake(local)?;
+            Ok(transport)
+        }
+    }
+
+    impl WebSocketTransport<TcpStream> {
+        /// Accepts a WebSocket upgrade on a TCP stream and performs the handshake.
+        pub fn accept(stream: TcpStream, local: &Handshake) -> Result<Self, CommitError> {
+            let socket = tungstenite::accept(stream).map_err(|_| CommitError::TransportError)?;
+            let mut transport = WebSocketTransport::new(socket);
+            transport.handshake(local)?;
+            Ok(transport)
+        }
+    }
+
+    impl<S: Read + Write> FrameTransport for WebSocketTransport<S> {
+        fn send_frame(&mut self, payload: &[u8]) -> Result<(), CommitError> {
+            let mut frame = Vec::with_capacity(4 + payload.len());
+            write_frame(&mut frame, payload)?;
+            self.socket.send(Message::Binary(frame)).map_err(|_| CommitError::TransportError)
+        }
+
+        fn receive_frame(&mut self) -> Result<Vec<u8>, CommitError> {
+            loop {
+                match self.socket.read().map_err(|_| CommitError::TransportError)? {
+                    Message::Binary(frame) => {
+                        let mut reader = frame.as_slice();
+                        let payload = read_frame(&mut reader)?;
+                        if !reader.is_empty() {
+                            return Err(CommitError::TransportError);
+                        }
+                        return Ok(payload);
+                    }
+                    // 控制帧由 tungstenite 自动应答
+                    Message::Ping(_) | Message::Pong(_) => continue,
+                    _ => return Err(CommitError::TransportError),
+                }
+            }
+        }
+    }
+}
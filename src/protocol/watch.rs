@@ -0,0 +1,90 @@
+//! This module provides the `Watcher` subscription API.
+//!
+//! A `Watcher` fans typed `WatchEvent`s out to any number of channel subscribers. Every `Round`
+//! owns one and emits events as messages are accepted and phases change, see `Round::subscribe`.
+//! A standalone watcher can also follow a `RoundStore` written by another process:
+//! `Watcher::observe_store` replays the saved state and its journal and emits the events that
+//! happened since the previous call.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use crate::commit_error::CommitError;
+use super::evidence::MisbehaviorProof;
+use super::messages::{CommitMessage, ProtocolMessage, RevealMessage};
+use super::round::{Round, RoundPhase, RoundState};
+use super::store::RoundStore;
+
+/// Event emitted to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    NewCommitment(CommitMessage),
+    RevealReceived(RevealMessage),
+    VerificationFailed(MisbehaviorProof),
+    RoundClosed { round: u64 },
+}
+
+/// Fan-out of `WatchEvent`s to channel subscribers.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    subscribers: Vec<Sender<WatchEvent>>,
+    seen_messages: usize,
+    seen_evidence: usize,
+    closed: bool,
+}
+
+impl Watcher {
+    /// Creates a watcher without subscribers.
+    pub fn new() -> Self {
+        Watcher::default()
+    }
+
+    /// Registers a new subscriber.
+    ///
+    /// # Returns
+    /// The receiving end of the subscriber's channel. Dropping it unsubscribes.
+    pub fn subscribe(&mut self) -> Receiver<WatchEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Sends an event to every subscriber, forgetting those that hung up.
+    pub fn emit(&mut self, event: WatchEvent) {
+        self.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Emits the events recorded in `store` since the previous call.
+    ///
+    /// # Returns
+    /// The number of events emitted, `CommitError::StorageError` if the store is unreadable, or
+    /// `CommitError::MalformedEncoding` if the saved state is corrupted.
+    pub fn observe_store(&mut self, store: &dyn RoundStore) -> Result<usize, CommitError> {
+        let Some(state) = RoundState::load(store)? else {
+            return Ok(0);
+        };
+        let round_id = state.descriptor.round;
+        let mut events: Vec<WatchEvent> = state.messages.iter().skip(self.seen_messages)
+            .map(|logged| match &logged.message {
+                ProtocolMessage::Commit(commit) => WatchEvent::NewCommitment(commit.clone()),
+                ProtocolMessage::Reveal(reveal) => WatchEvent::RevealReceived(reveal.clone()),
+            })
+            .collect();
+        self.seen_messages = self.seen_messages.max(state.messages.len());
+        let closed = state.phase == RoundPhase::Closed;
+
+        let round = Round::from_state(state)?;
+        let evidence = round.verifier().evidence();
+        events.extend(evidence.iter().skip(self.seen_evidence).cloned().map(WatchEvent::VerificationFailed));
+        self.seen_evidence = self.seen_evidence.max(evidence.len());
+
+        if closed && !self.closed {
+            self.closed = true;
+            events.push(WatchEvent::RoundClosed { round: round_id });
+        }
+
+        let emitted = events.len();
+        for event in events {
+            self.emit(event);
+        }
+        Ok(emitted)
+    }
+}
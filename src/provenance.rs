@@ -0,0 +1,285 @@
+//! This module provides the `.rvparams` file format, which distributes a parameter set with its
+//! provenance and an optional signature, so organizations hand out parameters with a verifiable
+//! chain of custody.
+//!
+//! A `.rvparams` file is `PARAMS_FILE_MAGIC`, the scheme header and the body:
+//! - the modulus, and a flag byte followed by the expiry time if set,
+//! - a flag byte followed by the seed if the parameters were expanded from one, otherwise the
+//!   matrices `A` and `B`, so seeded parameters fit in a few hundred bytes,
+//! - the length-prefixed `params::params_digest` of the matrices,
+//! - the length-prefixed creator identity and the creation time, in seconds since the Unix epoch,
+//!
+//! then a signature flag byte followed by the length-prefixed key identifier and signature if set.
+//! The signature covers `PARAMS_FILE_SIGNING_DOMAIN` followed by the body, so it binds the
+//! matrices through their digest together with the provenance; it is produced with
+//! `ParamsFile::with_signature`, or elsewhere over `ParamsFile::signing_bytes` and attached with
+//! `ParamsFile::with_detached_signature`.
+//!
+//! Decoding expands the seed or reads the matrices and recomputes their digest, so a file whose
+//! matrices do not match its digest is rejected before any signature is checked.
+//! `PublicParams::load_verified` accepts a file only if it is signed by one of the trusted keys;
+//! expiry and revocation remain the business of `PublicParams::check`.
+
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::envelope::{EnvelopeSignature, EnvelopeSigner, SignatureVerifier};
+use crate::params::{self, PublicParams};
+use crate::seed::{Seed, SEED_LEN};
+use crate::{scheme, utils};
+
+pub(crate) const PARAMS_FILE_SIGNING_DOMAIN: &[u8] = b"reveler/provenance/signing";
+
+/// Magic bytes every `.rvparams` file starts with.
+pub const PARAMS_FILE_MAGIC: &[u8] = b"RVPARAMS";
+
+/// Extension of parameter files.
+pub const PARAMS_FILE_EXTENSION: &str = "rvparams";
+
+/// Origin of a parameter set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsProvenance {
+    pub creator: String,              // The identity of the creator, e.g. an organization
+    pub created_at: u64,              // The creation time, in seconds since the Unix epoch
+    pub seed: Option<[u8; SEED_LEN]>, // The seed the matrices were expanded from, if any
+}
+
+impl ParamsProvenance {
+    /// Creates the provenance of parameters created by `creator` at `created_at`.
+    pub fn new(creator: impl Into<String>, created_at: u64) -> Self {
+        ParamsProvenance { creator: creator.into(), created_at, seed: None }
+    }
+
+    /// Records the seed the matrices were expanded from, with `PublicParams::expand`.
+    pub fn with_seed(mut self, seed: &Seed) -> Self {
+        self.seed = Some(*seed.as_bytes());
+        self
+    }
+}
+
+/// Contents of a `.rvparams` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsFile {
+    pub params: PublicParams,                 // The parameter set
+    pub provenance: ParamsProvenance,         // Its creator, creation time and seed
+    pub signature: Option<EnvelopeSignature>, // The optional signature over `signing_bytes`
+}
+
+impl ParamsFile {
+    /// Creates an unsigned file for `params`.
+    ///
+    /// # Returns
+    /// The `ParamsFile`, or `CommitError::MalformedEncoding` if `provenance` names a seed that
+    /// does not expand to the matrices of `params`.
+    pub fn new(params: PublicParams, provenance: ParamsProvenance) -> Result<Self, CommitError> {
+        if let Some(seed) = &provenance.seed {
+            let expanded = params::expand_matrices(seed, params.modulus());
+            if params::params_digest((&expanded.0, &expanded.1)) != params.digest() {
+                return Err(CommitError::MalformedEncoding);
+            }
+        }
+        Ok(ParamsFile { params, provenance, signature: None })
+    }
+
+    /// Signs the file with `signer`, replacing any previous signature.
+    pub fn with_signature(mut self, signer: &dyn EnvelopeSigner) -> Self {
+        let signature = signer.sign(&self.signing_bytes());
+        self.signature = Some(EnvelopeSignature { key_id: signer.key_id(), signature });
+        self
+    }
+
+    /// Attaches a signature produced elsewhere over `signing_bytes`, replacing any previous one.
+    pub fn with_detached_signature(mut self, signature: EnvelopeSignature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    /// Returns the bytes a signature covers: a fixed domain followed by the body of the file.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = PARAMS_FILE_SIGNING_DOMAIN.to_vec();
+        bytes.extend(self.body());
+        bytes
+    }
+
+    /// Checks the signature of the file against the trusted keys.
+    ///
+    /// # Parameters
+    /// - `trusted_keys`: The identifiers of the keys allowed to sign parameters.
+    /// - `verifier`: The `SignatureVerifier` of the signature scheme.
+    ///
+    /// # Returns
+    /// The identifier of the signing key, or `CommitError::InvalidOpening` if the file is
+    /// unsigned, signed by a key outside `trusted_keys`, or its signature does not verify.
+    pub fn verify_signature(&self, trusted_keys: &[Vec<u8>], verifier: &dyn SignatureVerifier) -> Result<&[u8], CommitError> {
+        match &self.signature {
+            Some(signed) if trusted_keys.contains(&signed.key_id) && verifier.verify(&signed.key_id, &self.signing_bytes(), &signed.signature) => {
+                Ok(&signed.key_id)
+            }
+            _ => Err(CommitError::InvalidOpening),
+        }
+    }
+
+    /// Encodes the file, see the module documentation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = PARAMS_FILE_MAGIC.to_vec();
+        bytes.extend(self.body());
+        match &self.signature {
+            Some(signed) => {
+                bytes.push(1);
+                bytes.extend(utils::encode_bytes(&signed.key_id));
+                bytes.extend(utils::encode_bytes(&signed.signature));
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    /// Decodes a file written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `ParamsFile`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding or the matrices do not match the recorded digest.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let rest = bytes.strip_prefix(PARAMS_FILE_MAGIC).ok_or(CommitError::MalformedEncoding)?;
+        let (_, rest) = scheme::read_header(rest)?;
+        let (modulus, rest) = utils::decode_u64(rest)?;
+        if modulus < 2 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let (expires_at, rest) = decode_optional_u64(rest)?;
+        let (&flag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let (seed, (local_a, local_b), rest) = match flag {
+            0 => {
+                let (local_a, rest) = utils::decode_matrix(rest)?;
+                let (local_b, rest) = utils::decode_matrix(rest)?;
+                (None, (local_a, local_b), rest)
+            }
+            1 => {
+                let seed: [u8; SEED_LEN] = rest.get(..SEED_LEN).and_then(|seed| seed.try_into().ok()).ok_or(CommitError::MalformedEncoding)?;
+                // 由种子展开矩阵，文件中不重复存储
+                (Some(seed), params::expand_matrices(&seed, modulus), rest.get(SEED_LEN..).unwrap_or_default())
+            }
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        let (digest, rest) = utils::decode_bytes(rest)?;
+        let (creator, rest) = utils::decode_bytes(rest)?;
+        let creator = String::from_utf8(creator).map_err(|_| CommitError::MalformedEncoding)?;
+        let (created_at, rest) = utils::decode_u64(rest)?;
+        let (&flag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let (signature, rest) = match flag {
+            0 => (None, rest),
+            1 => {
+                let (key_id, rest) = utils::decode_bytes(rest)?;
+                let (signature, rest) = utils::decode_bytes(rest)?;
+                (Some(EnvelopeSignature { key_id, signature }), rest)
+            }
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        if !rest.is_empty() || params::params_digest((&local_a, &local_b)) != digest {
+            return Err(CommitError::MalformedEncoding);
+        }
+
+        let params = PublicParams::new(local_a, local_b, modulus);
+        let params = match expires_at {
+            Some(expires_at) => params.with_expiry(expires_at),
+            None => params,
+        };
+        Ok(ParamsFile { params, provenance: ParamsProvenance { creator, created_at, seed }, signature })
+    }
+
+    /// Writes the file to `path`, replacing any existing file.
+    ///
+    /// # Returns
+    /// `Ok(())`, or `CommitError::StorageError` if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CommitError> {
+        fs::write(path, self.to_bytes()).map_err(|_| CommitError::StorageError)
+    }
+
+    /// Reads the file at `path` without checking its signature.
+    ///
+    /// # Returns
+    /// The `ParamsFile`, `CommitError::StorageError` if the file cannot be read, or the errors of
+    /// `from_bytes`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CommitError> {
+        let bytes = fs::read(path).map_err(|_| CommitError::StorageError)?;
+        Self::from_bytes(&bytes)
+    }
+
+    // Encode the signed part: everything but the magic and the signature.
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.params.modulus().to_be_bytes());
+        match self.params.expires_at() {
+            Some(expires_at) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&expires_at.to_be_bytes());
+            }
+            None => bytes.push(0),
+        }
+        match &self.provenance.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(seed);
+            }
+            None => {
+                bytes.push(0);
+                bytes.extend(utils::encode_matrix(self.params.local_a()));
+                bytes.extend(utils::encode_matrix(self.params.local_b()));
+            }
+        }
+        bytes.extend(utils::encode_bytes(&self.params.digest()));
+        bytes.extend(utils::encode_bytes(self.provenance.creator.as_bytes()));
+        bytes.extend_from_slice(&self.provenance.created_at.to_be_bytes());
+        bytes
+    }
+}
+
+impl PublicParams {
+    /// Writes the parameters with their provenance to the `.rvparams` file at `path`, signed by
+    /// `signer`.
+    ///
+    /// # Parameters
+    /// - `path`: The file to write, replaced if it exists.
+    /// - `provenance`: The creator, creation time and, for seeded parameters, the seed.
+    /// - `signer`: The `EnvelopeSigner` of the creator.
+    ///
+    /// # Returns
+    /// `Ok(())`, `CommitError::MalformedEncoding` if the seed of `provenance` does not expand to
+    /// these parameters, or `CommitError::StorageError` if the file cannot be written.
+    pub fn save_signed(&self, path: impl AsRef<Path>, provenance: &ParamsProvenance, signer: &dyn EnvelopeSigner) -> Result<(), CommitError> {
+        ParamsFile::new(self.clone(), provenance.clone())?.with_signature(signer).save(path)
+    }
+
+    /// Reads the `.rvparams` file at `path` and checks that a trusted key signed it.
+    ///
+    /// # Parameters
+    /// - `path`: The file to read.
+    /// - `trusted_keys`: The identifiers of the keys allowed to sign parameters.
+    /// - `verifier`: The `SignatureVerifier` of the signature scheme.
+    ///
+    /// # Returns
+    /// The parameters and their provenance, `CommitError::StorageError` if the file cannot be
+    /// read, the errors of `ParamsFile::from_bytes`, or `CommitError::InvalidOpening` if the
+    /// file is not signed by one of `trusted_keys`.
+    pub fn load_verified(
+        path: impl AsRef<Path>,
+        trusted_keys: &[Vec<u8>],
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(PublicParams, ParamsProvenance), CommitError> {
+        let file = ParamsFile::load(path)?;
+        file.verify_signature(trusted_keys, verifier)?;
+        Ok((file.params, file.provenance))
+    }
+}
+
+fn decode_optional_u64(bytes: &[u8]) -> Result<(Option<u64>, &[u8]), CommitError> {
+    let (&flag, rest) = bytes.split_first().ok_or(CommitError::MalformedEncoding)?;
+    match flag {
+        0 => Ok((None, rest)),
+        1 => utils::decode_u64(rest).map(|(value, rest)| (Some(value), rest)),
+        _ => Err(CommitError::MalformedEncoding),
+    }
+}
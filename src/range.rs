@@ -0,0 +1,351 @@
+//! This module provides range proofs for committed values: the prover shows that coordinate `i`
+//! of the message of a commitment `c = A·m + [B | I]·r` lies in `[0, 2^k)` without revealing it,
+//! e.g. for the bids of a sealed-bid auction or confidential amounts.
+//!
+//! The proof is a `binary` proof of the relation `A·m + [B | I]·r = c` over the bits `b` of
+//! `v = m_i`, with `m_i = Σ_j 2^j·b_j mod q`, the other coordinates of `m` free and `r` ternary.
+//! The bits and the randomness are part of the permuted witness, so a prover that passes knows an
+//! opening of `c` with ternary randomness and a coordinate below `2^k`, and by the binding of the
+//! commitment that is the coordinate of every short opening of `c`.
+//!
+//! For `k < 16` the sum of the bits stays below `q = 65535`, so the equation modulo `q` holds over
+//! the integers and `v < 2^k`; every reduced coordinate lies below `2^16`, so `MAX_RANGE_BITS` is
+//! 16. The challenges bind the key, the commitment, the coordinate and the width, and the number
+//! of rounds is that of binary proofs, see `ProofConfig::binary_rounds`.
+//!
+//! `prove_leq` builds comparison proofs on range proofs, e.g. for auctions or threshold checks on
+//! committed amounts: it shows `a ≤ b` for coordinate `i` of the messages of two commitments
+//! `c_a` and `c_b` under one key. The commitment is linear, so `c_b - c_a` commits to `b - a` with
+//! the randomness `r_b - r_a`, whose coordinates lie in `[-2, 2]`, and the proof holds two range
+//! proofs of width `k`, one that `a` lies in `[0, 2^k)` and one that `b - a mod q` does, with the
+//! randomness of the second bounded by 2 and split into ternary blocks with `binary::weights`.
+//! Then `c_b` opens to `a + (b - a)` with randomness bounded by 3, which binds under
+//! `policy::VerifyPolicy::for_sum(3)`, and `a + (b - a)` is below `2^(k+1) - 1`, which for
+//! `k ≤ MAX_LEQ_BITS` is below `q`, so `b` equals it over the integers and `a ≤ b`. The prover
+//! needs `a < 2^k` and `b - a < 2^k`, which holds for any `a ≤ b` below `2^k`.
+
+use serde::{Serialize, Deserialize};
+use crate::binary::{self, BinaryRound};
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+use crate::config::{CommitConfig, ProofConfig};
+use crate::fft::MESSAGE_LEN;
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+#[cfg(feature = "prover")]
+use crate::utils;
+use crate::{RevelerCommit, RevelerResult};
+
+pub(crate) const RANGE_DOMAIN: &[u8] = b"reveler/range";
+
+/// Largest width of a range proof: every reduced coordinate lies below `2^MAX_RANGE_BITS`.
+pub const MAX_RANGE_BITS: usize = 16;
+
+/// Largest width of a comparison proof, such that `2^(k+1) - 1` stays below `q`.
+pub const MAX_LEQ_BITS: usize = MAX_RANGE_BITS - 1;
+
+// Bound of the randomness of c_b - c_a, the difference of two ternary vectors.
+const GAP_BOUND: u64 = 2;
+
+/// Proof that a coordinate of a committed message lies in `[0, 2^k)`: a `binary` proof over the
+/// bits of the coordinate, the other coordinates and the randomness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeProof {
+    pub rounds: Vec<BinaryRound>,
+}
+
+/// Proof that a coordinate of a committed message is at most that of another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeqProof {
+    pub lower: RangeProof, // a lies in [0, 2^k)
+    pub gap: RangeProof,   // b - a lies in [0, 2^k), over c_b - c_a
+}
+
+/// Proves that coordinate `index` of the message of `commitment` lies in `[0, 2^bits)`.
+///
+/// # Parameters
+/// - `key`: The commitment key of `commitment`.
+/// - `commitment`: The commitment.
+/// - `opening`: Its opening, which stays with the prover.
+/// - `index`: The coordinate of the message.
+/// - `bits`: The width `k` of the range, from 1 to `MAX_RANGE_BITS`.
+///
+/// # Returns
+/// The `RangeProof`, `CommitError::ComputationError` if `index` is not below `MESSAGE_LEN` or `bits`
+/// is out of bounds, or `CommitError::InvalidOpening` if `opening` does not open `commitment` with
+/// ternary randomness or the coordinate does not lie in the range.
+#[cfg(feature = "prover")]
+pub fn prove_range(key: &CommitmentKey, commitment: &RevelerResult, opening: &Opening, index: usize, bits: usize) -> Result<RangeProof, CommitError> {
+    prove_range_with_config(key, commitment, opening, index, bits, &ProofConfig::default())
+}
+
+/// Proves that a coordinate lies in `[0, 2^bits)` with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `RangeProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_range`.
+#[cfg(feature = "prover")]
+pub fn prove_range_with_config(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    index: usize,
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> Result<RangeProof, CommitError> {
+    if index >= MESSAGE_LEN || !(1..=MAX_RANGE_BITS).contains(&bits) {
+        return Err(CommitError::ComputationError);
+    }
+    if !key.verify(commitment, opening)? {
+        return Err(CommitError::InvalidOpening);
+    }
+    prove_coordinate(key, commitment, opening, index, bits, 1, proof_config)
+}
+
+/// Verifies a proof that coordinate `index` of the message of `commitment` lies in `[0, 2^bits)`.
+///
+/// # Returns
+/// `true` if `index` is below `MESSAGE_LEN`, `bits` is from 1 to `MAX_RANGE_BITS`, the commitment
+/// hash is valid, and every round of the proof checks out.
+pub fn verify_range(key: &CommitmentKey, commitment: &RevelerResult, index: usize, bits: usize, proof: &RangeProof) -> bool {
+    verify_range_with_config(key, commitment, index, bits, proof, &ProofConfig::default())
+}
+
+/// Verifies a range proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_range`.
+pub fn verify_range_with_config(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    index: usize,
+    bits: usize,
+    proof: &RangeProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    verify_coordinate(key, commitment, index, bits, 1, proof, proof_config)
+}
+
+/// Proves that coordinate `index` of the message of `a` is at most that of `b`.
+///
+/// # Parameters
+/// - `key`: The commitment key of both commitments.
+/// - `a`: The commitment to the smaller value and its opening.
+/// - `b`: The commitment to the larger value and its opening.
+/// - `index`: The coordinate of the messages.
+/// - `bits`: The width `k`, from 1 to `MAX_LEQ_BITS`.
+///
+/// # Returns
+/// The `LeqProof`, `CommitError::ComputationError` if `index` is not below `MESSAGE_LEN` or `bits` is
+/// out of bounds, or `CommitError::InvalidOpening` if an opening does not open its commitment
+/// with ternary randomness, `a` is not below `2^k`, or `b - a` does not lie in `[0, 2^k)`.
+#[cfg(feature = "prover")]
+pub fn prove_leq(
+    key: &CommitmentKey,
+    a: (&RevelerResult, &Opening),
+    b: (&RevelerResult, &Opening),
+    index: usize,
+    bits: usize,
+) -> Result<LeqProof, CommitError> {
+    prove_leq_with_config(key, a, b, index, bits, &ProofConfig::default())
+}
+
+/// Proves `a ≤ b` for coordinate `index` with the soundness of `proof_config`.
+///
+/// # Returns
+/// The `LeqProof`, `CommitError::InvalidProofConfig` if `proof_config` is not valid for binary
+/// proofs, or the errors of `prove_leq`.
+#[cfg(feature = "prover")]
+pub fn prove_leq_with_config(
+    key: &CommitmentKey,
+    (a, a_opening): (&RevelerResult, &Opening),
+    (b, b_opening): (&RevelerResult, &Opening),
+    index: usize,
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> Result<LeqProof, CommitError> {
+    if !(1..=MAX_LEQ_BITS).contains(&bits) {
+        return Err(CommitError::ComputationError);
+    }
+    if !key.verify(a, a_opening)? || !key.verify(b, b_opening)? {
+        return Err(CommitError::InvalidOpening);
+    }
+    let q = CommitConfig::default().modulus;
+    let lower = prove_coordinate(key, a, a_opening, index, bits, 1, proof_config)?;
+    let gap_opening = b_opening.add(&a_opening.scale(q - 1));
+    let gap = prove_coordinate(key, &difference(a, b), &gap_opening, index, bits, GAP_BOUND, proof_config)?;
+    Ok(LeqProof { lower, gap })
+}
+
+/// Verifies a proof that coordinate `index` of the message of `a` is at most that of `b`.
+///
+/// # Returns
+/// `true` if `bits` is from 1 to `MAX_LEQ_BITS`, the commitment hashes are valid, and both range
+/// proofs check out.
+pub fn verify_leq(key: &CommitmentKey, a: &RevelerResult, b: &RevelerResult, index: usize, bits: usize, proof: &LeqProof) -> bool {
+    verify_leq_with_config(key, a, b, index, bits, proof, &ProofConfig::default())
+}
+
+/// Verifies a comparison proof made with the soundness of `proof_config`.
+///
+/// # Returns
+/// `false` if `proof_config` is not valid for binary proofs, otherwise the outcome of
+/// `verify_leq`.
+pub fn verify_leq_with_config(
+    key: &CommitmentKey,
+    a: &RevelerResult,
+    b: &RevelerResult,
+    index: usize,
+    bits: usize,
+    proof: &LeqProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    (1..=MAX_LEQ_BITS).contains(&bits)
+        && RevelerCommit::verify(b)
+        && verify_coordinate(key, a, index, bits, 1, &proof.lower, proof_config)
+        && verify_coordinate(key, &difference(a, b), index, bits, GAP_BOUND, &proof.gap, proof_config)
+}
+
+// Prove that coordinate `index` of the message of `commitment` lies in [0, 2^bits) with the
+// randomness of `opening` bounded by `bound`, which the caller checks.
+#[cfg(feature = "prover")]
+fn prove_coordinate(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    opening: &Opening,
+    index: usize,
+    bits: usize,
+    bound: u64,
+    proof_config: &ProofConfig,
+) -> Result<RangeProof, CommitError> {
+    let rounds = proof_config.binary_rounds()?;
+    if index >= MESSAGE_LEN || !(1..=MAX_RANGE_BITS).contains(&bits) {
+        return Err(CommitError::ComputationError);
+    }
+    let q = CommitConfig::default().modulus;
+    if opening.local_m().get(index).is_some_and(|&value| (value % q) >> bits != 0) {
+        return Err(CommitError::InvalidOpening);
+    }
+    let relation = binary::coordinate_relation(key.as_pair(), RANGE_DOMAIN, index, bits, bound, commitment);
+    let witness = binary::coordinate_witness(opening.local_m(), opening.local_r(), index, bits, bound);
+    Ok(RangeProof { rounds: binary::prove_relation(&relation, &witness, rounds, utils::Workers::Threads)?.rounds })
+}
+
+fn verify_coordinate(
+    key: &CommitmentKey,
+    commitment: &RevelerResult,
+    index: usize,
+    bits: usize,
+    bound: u64,
+    proof: &RangeProof,
+    proof_config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = proof_config.binary_rounds() else {
+        return false;
+    };
+    index < MESSAGE_LEN
+        && (1..=MAX_RANGE_BITS).contains(&bits)
+        && RevelerCommit::verify(commitment)
+        && binary::verify_relation(
+            &binary::coordinate_relation(key.as_pair(), RANGE_DOMAIN, index, bits, bound, commitment),
+            &proof.rounds,
+            rounds,
+        )
+}
+
+// c_b - c_a, which commits to (m_b - m_a, r_b - r_a)
+fn difference(a: &RevelerResult, b: &RevelerResult) -> RevelerResult {
+    b.add(&a.scale(CommitConfig::default().modulus - 1))
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::fft::{LOCAL_N, RANDOMNESS_LEN};
+    use super::*;
+
+    // Opening of `commitment` to `m` with the difference of the messages moved into the identity
+    // block of [B | I], which leaves the randomness unbounded.
+    fn forge(key: &CommitmentKey, opening: &Opening, m: Vec<u64>) -> Opening {
+        let q = CommitConfig::default().modulus;
+        let delta: Vec<u64> = opening.local_m().iter().zip(&m).map(|(&x, &y)| (x + q - y) % q).collect();
+        let shift = key.commit(&delta, &[]).unwrap();
+        let mut r = opening.local_r().to_vec();
+        for (slot, &x) in r.iter_mut().skip(RANDOMNESS_LEN - LOCAL_N).zip(shift.commitment_point()) {
+            *slot = (*slot + x) % q;
+        }
+        Opening::new(m, r)
+    }
+
+    #[test]
+    fn values_in_range_verify() {
+        let mut rng = StdRng::seed_from_u64(270);
+        let key = CommitmentKey::setup(&mut rng);
+        let opening = Opening::new(vec![7, 300], utils::generate_randomness(&mut rng));
+        let commitment = key.commit_opening(&opening).unwrap();
+        let proof = prove_range(&key, &commitment, &opening, 1, 9).unwrap();
+        assert!(verify_range(&key, &commitment, 1, 9, &proof));
+        assert!(!verify_range(&key, &commitment, 1, 8, &proof));
+        assert!(!verify_range(&key, &commitment, 0, 9, &proof));
+        assert_eq!(prove_range(&key, &commitment, &opening, 1, 8), Err(CommitError::InvalidOpening));
+    }
+
+    #[test]
+    fn forged_openings_are_rejected() {
+        // 承诺 60000，伪造成 3 的打开，再证明它在 [0, 4) 内
+        let mut rng = StdRng::seed_from_u64(270);
+        let key = CommitmentKey::setup(&mut rng);
+        let opening = Opening::new(vec![60000], utils::generate_randomness(&mut rng));
+        let commitment = key.commit_opening(&opening).unwrap();
+        let forged = forge(&key, &opening, vec![3]);
+        assert!(key.reproduces(&commitment, &forged).unwrap());
+        assert_eq!(prove_range(&key, &commitment, &forged, 0, 2), Err(CommitError::InvalidOpening));
+
+        let relation = binary::coordinate_relation(key.as_pair(), RANGE_DOMAIN, 0, 2, 1, &commitment);
+        for candidate in [&forged, &opening] {
+            let witness = binary::coordinate_witness(candidate.local_m(), candidate.local_r(), 0, 2, 1);
+            let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap().rounds;
+            assert!(!verify_range(&key, &commitment, 0, 2, &RangeProof { rounds }));
+        }
+    }
+    #[test]
+    fn ordered_values_verify() {
+        let mut rng = StdRng::seed_from_u64(274);
+        let key = CommitmentKey::setup(&mut rng);
+        let a = Opening::new(vec![0, 5], utils::generate_randomness(&mut rng));
+        let b = Opening::new(vec![0, 9], utils::generate_randomness(&mut rng));
+        let (c_a, c_b) = (key.commit_opening(&a).unwrap(), key.commit_opening(&b).unwrap());
+        let proof = prove_leq(&key, (&c_a, &a), (&c_b, &b), 1, 4).unwrap();
+        assert!(verify_leq(&key, &c_a, &c_b, 1, 4, &proof));
+        assert!(!verify_leq(&key, &c_b, &c_a, 1, 4, &proof));
+        assert_eq!(prove_leq(&key, (&c_b, &b), (&c_a, &a), 1, 4), Err(CommitError::InvalidOpening));
+    }
+
+    #[test]
+    fn forged_gaps_are_rejected() {
+        // a = 9 > b = 5：c_b - c_a 打开为 -4，伪造者声称差值为 3
+        let mut rng = StdRng::seed_from_u64(274);
+        let key = CommitmentKey::setup(&mut rng);
+        let q = CommitConfig::default().modulus;
+        let a = Opening::new(vec![9], utils::generate_randomness(&mut rng));
+        let b = Opening::new(vec![5], utils::generate_randomness(&mut rng));
+        let (c_a, c_b) = (key.commit_opening(&a).unwrap(), key.commit_opening(&b).unwrap());
+        let gap = difference(&c_a, &c_b);
+        let gap_opening = b.add(&a.scale(q - 1));
+        let forged = forge(&key, &gap_opening, vec![3]);
+        assert!(key.reproduces(&gap, &forged).unwrap());
+
+        let lower = prove_range(&key, &c_a, &a, 0, 4).unwrap();
+        let relation = binary::coordinate_relation(key.as_pair(), RANGE_DOMAIN, 0, 4, GAP_BOUND, &gap);
+        for candidate in [&forged, &gap_opening] {
+            let witness = binary::coordinate_witness(candidate.local_m(), candidate.local_r(), 0, 4, GAP_BOUND);
+            let rounds = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap().rounds;
+            let proof = LeqProof { lower: lower.clone(), gap: RangeProof { rounds } };
+            assert!(!verify_leq(&key, &c_a, &c_b, 0, 4, &proof));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! This module provides commitment receipts: a compact URI form of a `RevelerResult` that can be
+//! printed on paper tickets and scanned back for verification offline.
+//!
+//! A receipt reads `reveler:v1;params=matvec-q65535-n256;hash=bluehash256;c=<base64url>`. The
+//! `params` and `hash` fields name the scheme, and `c` holds the commitment point followed by its
+//! hash, without padding. Each coordinate of the point is written big-endian in the fewest bytes
+//! that hold `q - 1`, two bytes for the default modulus, which keeps a receipt of the default
+//! scheme under 800 characters. The exact grammar of a receipt is the `receipt` of `grammar`.
+//!
+//! With the `qr` feature, `to_qr_svg` and `to_qr_text` render a receipt as a QR code.
+
+use crate::commit_error::CommitError;
+use crate::grammar;
+use crate::scheme::{self, SchemeId};
+use crate::RevelerResult;
+
+const URI_SCHEME: &str = "reveler:";
+
+impl RevelerResult {
+    /// Encodes the result as a receipt URI of the current scheme.
+    ///
+    /// # Returns
+    /// The URI, or `CommitError::MalformedEncoding` if the point does not have one coordinate
+    /// below `q` per dimension of the scheme.
+    pub fn to_uri(&self) -> Result<String, CommitError> {
+        let scheme = SchemeId::current();
+        if self.commitment_point.len() != scheme.dimension || self.commitment_point.iter().any(|&x| x >= scheme.modulus) {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let width = coordinate_width(scheme.modulus);
+        let mut payload = Vec::with_capacity(self.commitment_point.len() * width + self.commitment_hash.len());
+        for &x in &self.commitment_point {
+            payload.extend(x.to_be_bytes().into_iter().skip(8 - width));
+        }
+        payload.extend_from_slice(&self.commitment_hash);
+
+        Ok(format!(
+            "{URI_SCHEME}v{};params={}-q{}-n{};hash={};c={}",
+            scheme.version,
+            scheme.backend,
+            scheme.modulus,
+            scheme.dimension,
+            scheme.hasher,
+            grammar::encode_base64url(&payload)
+        ))
+    }
+
+    /// Decodes a receipt URI written by `to_uri`.
+    ///
+    /// The hash is not checked, call `RevelerCommit::verify` on the result.
+    ///
+    /// # Returns
+    /// The decoded `RevelerResult`, `CommitError::UnsupportedScheme` if the URI names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if it is not a receipt or its
+    /// payload does not hold a point of the scheme.
+    pub fn from_uri(uri: &str) -> Result<Self, CommitError> {
+        let body = uri.strip_prefix(URI_SCHEME).ok_or(CommitError::MalformedEncoding)?;
+        let fields: Vec<&str> = body.split(';').collect();
+        let [version, params, hash, payload] = fields.as_slice() else {
+            return Err(CommitError::MalformedEncoding);
+        };
+        let (Some(params), Some(hasher), Some(payload)) =
+            (params.strip_prefix("params="), hash.strip_prefix("hash="), payload.strip_prefix("c="))
+        else {
+            return Err(CommitError::MalformedEncoding);
+        };
+        let scheme = scheme::resolve(&format!("reveler/{version}/{params}/{hasher}"))?;
+
+        let payload = grammar::decode_base64url(payload)?;
+        let width = coordinate_width(scheme.modulus);
+        let point_len = scheme.dimension.checked_mul(width).ok_or(CommitError::MalformedEncoding)?;
+        if payload.len() <= point_len {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let (point, commitment_hash) = payload.split_at(point_len);
+        let commitment_point: Vec<u64> = point
+            .chunks_exact(width)
+            .map(|coordinate| coordinate.iter().fold(0u64, |acc, &byte| acc << 8 | u64::from(byte)))
+            .collect();
+        if commitment_point.iter().any(|&x| x >= scheme.modulus) {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(RevelerResult::new(commitment_point, commitment_hash.to_vec()))
+    }
+
+    /// Renders the receipt URI as a QR code in SVG.
+    ///
+    /// # Returns
+    /// The SVG document, `CommitError::MalformedEncoding` if the result has no receipt, or
+    /// `CommitError::ComputationError` if the receipt does not fit in a QR code.
+    #[cfg(feature = "qr")]
+    pub fn to_qr_svg(&self) -> Result<String, CommitError> {
+        Ok(qr_code(&self.to_uri()?)?.render::<qrcode::render::svg::Color>().build())
+    }
+
+    /// Renders the receipt URI as a QR code in Unicode half blocks, for terminals.
+    ///
+    /// # Returns
+    /// The rendered lines, `CommitError::MalformedEncoding` if the result has no receipt, or
+    /// `CommitError::ComputationError` if the receipt does not fit in a QR code.
+    #[cfg(feature = "qr")]
+    pub fn to_qr_text(&self) -> Result<String, CommitError> {
+        Ok(qr_code(&self.to_uri()?)?.render::<qrcode::render::unicode::Dense1x2>().build())
+    }
+}
+
+#[cfg(feature = "qr")]
+fn qr_code(uri: &str) -> Result<qrcode::QrCode, CommitError> {
+    // 票据需经受打印磨损，使用中等纠错等级
+    qrcode::QrCode::with_error_correction_level(uri, qrcode::EcLevel::M).map_err(|_| CommitError::ComputationError)
+}
+
+// Number of bytes holding every residue modulo `modulus`.
+fn coordinate_width(modulus: u64) -> usize {
+    let bits = u64::BITS - modulus.saturating_sub(1).leading_zeros();
+    (bits as usize).div_ceil(8).max(1)
+}
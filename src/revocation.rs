@@ -0,0 +1,283 @@
+//! This module provides revocation lists retiring compromised or deprecated parameter sets.
+//!
+//! A `ParamsRevocationList` names parameter sets by their `params::params_digest`. The operator of
+//! a deployment signs each new list through the `envelope::EnvelopeSigner` trait and distributes
+//! it to the verifiers, which check the signature and `install` it. From then on `check`, called
+//! by `PublicParams::check`, `RevelerCommit::verify_opening`, `CommitmentKey::verify_batch`,
+//! `OpeningEnvelope::verify_against`, `archive::verify_any` and the other verifiers of the crate,
+//! refuses the revoked parameters with `CommitError::ParamsRevoked`.
+//!
+//! Lists carry a sequence number and `install` only accepts a list newer than the installed one,
+//! so a stale list cannot be replayed to un-revoke parameters.
+//!
+//! Parameters expire the same way: `register_expiry` records the expiry of a `PublicParams`, and
+//! from the expiry time on, as read from the clock installed with `install_clock`, the same
+//! verifications refuse them with `CommitError::ParamsExpired`. Verifiers only receive the
+//! matrices, so an expiry is enforced once registered, not from the `PublicParams` alone.
+
+use std::sync::{Arc, OnceLock, RwLock};
+use serde::{Serialize, Deserialize};
+use crate::clock::{Clock, SystemClock};
+use crate::commit_error::CommitError;
+use crate::envelope::{EnvelopeSignature, EnvelopeSigner, SignatureVerifier};
+use crate::params::PublicParams;
+use crate::{params, scheme, utils};
+
+pub(crate) const REVOCATION_SIGNING_DOMAIN: &[u8] = b"reveler/revocation/signing";
+
+/// Parameter set named by a revocation list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedParams {
+    pub params_digest: Vec<u8>, // The `params::params_digest` of the revoked matrices
+    pub revoked_at: u64,        // The time of the revocation, in seconds since the Unix epoch
+}
+
+/// Signed list of revoked parameter sets.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ParamsRevocationList {
+    pub sequence: u64,                        // The number of the list, increasing with every issue
+    pub issued_at: u64,                       // The time the list was issued at
+    pub revoked: Vec<RevokedParams>,          // The revoked parameter sets
+    pub signature: Option<EnvelopeSignature>, // The optional signature of the issuer
+}
+
+impl ParamsRevocationList {
+    /// Creates an empty, unsigned list.
+    pub fn new(sequence: u64, issued_at: u64) -> Self {
+        ParamsRevocationList { sequence, issued_at, revoked: Vec::new(), signature: None }
+    }
+
+    /// Adds the parameters `params` revoked at `revoked_at`, dropping any signature.
+    pub fn with_revoked(mut self, params: (&[Vec<u64>], &[Vec<u64>]), revoked_at: u64) -> Self {
+        self.revoked.push(RevokedParams { params_digest: params::params_digest(params), revoked_at });
+        self.signature = None;
+        self
+    }
+
+    /// Signs the list with `signer`, replacing any previous signature.
+    pub fn with_signature(mut self, signer: &dyn EnvelopeSigner) -> Self {
+        let signature = signer.sign(&self.signing_bytes());
+        self.signature = Some(EnvelopeSignature { key_id: signer.key_id(), signature });
+        self
+    }
+
+    /// Returns whether the list names the parameters of digest `params_digest`.
+    pub fn is_revoked(&self, params_digest: &[u8]) -> bool {
+        self.revoked.iter().any(|entry| entry.params_digest == params_digest)
+    }
+
+    /// Returns the bytes a signature covers: a fixed domain followed by the encoding of the list
+    /// without its signature.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = REVOCATION_SIGNING_DOMAIN.to_vec();
+        bytes.extend(self.body());
+        bytes
+    }
+
+    /// Checks the signature of the list.
+    ///
+    /// # Returns
+    /// The identifier of the signing key, or `CommitError::InvalidOpening` if the list is
+    /// unsigned or its signature does not verify under `verifier`.
+    pub fn verify_signature(&self, verifier: &dyn SignatureVerifier) -> Result<&[u8], CommitError> {
+        match &self.signature {
+            Some(signed) if verifier.verify(&signed.key_id, &self.signing_bytes(), &signed.signature) => Ok(&signed.key_id),
+            _ => Err(CommitError::InvalidOpening),
+        }
+    }
+
+    /// Encodes the list as the scheme header, the sequence number, the issue time, the number of
+    /// entries, every entry as its length-prefixed digest and revocation time, and a signature
+    /// flag byte followed by the length-prefixed key identifier and signature if set.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.body();
+        match &self.signature {
+            Some(signed) => {
+                bytes.push(1);
+                bytes.extend(utils::encode_bytes(&signed.key_id));
+                bytes.extend(utils::encode_bytes(&signed.signature));
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    /// Decodes a list written by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded `ParamsRevocationList`, `CommitError::UnsupportedScheme` if the header names an
+    /// unregistered scheme, or `CommitError::MalformedEncoding` if the input is not a complete
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let (_, rest) = scheme::read_header(bytes)?;
+        let (sequence, rest) = utils::decode_u64(rest)?;
+        let (issued_at, rest) = utils::decode_u64(rest)?;
+        let (count, mut rest) = utils::decode_u64(rest)?;
+        // 每个条目至少占 16 字节，先检查数量避免恶意前缀导致大量内存分配
+        if count > (rest.len() / 16) as u64 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let mut revoked = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (params_digest, next) = utils::decode_bytes(rest)?;
+            let (revoked_at, next) = utils::decode_u64(next)?;
+            revoked.push(RevokedParams { params_digest, revoked_at });
+            rest = next;
+        }
+        let (&flag, rest) = rest.split_first().ok_or(CommitError::MalformedEncoding)?;
+        let (signature, rest) = match flag {
+            0 => (None, rest),
+            1 => {
+                let (key_id, rest) = utils::decode_bytes(rest)?;
+                let (signature, rest) = utils::decode_bytes(rest)?;
+                (Some(EnvelopeSignature { key_id, signature }), rest)
+            }
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(ParamsRevocationList { sequence, issued_at, revoked, signature })
+    }
+
+    // Encode the signed part: everything but the signature.
+    fn body(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scheme::write_header(&mut bytes);
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        bytes.extend_from_slice(&self.issued_at.to_be_bytes());
+        bytes.extend_from_slice(&(self.revoked.len() as u64).to_be_bytes());
+        for entry in &self.revoked {
+            bytes.extend(utils::encode_bytes(&entry.params_digest));
+            bytes.extend_from_slice(&entry.revoked_at.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+fn installed_list() -> &'static RwLock<Option<ParamsRevocationList>> {
+    static INSTALLED: OnceLock<RwLock<Option<ParamsRevocationList>>> = OnceLock::new();
+    INSTALLED.get_or_init(|| RwLock::new(None))
+}
+
+/// Installs the revocation list checked by every verification of this process.
+///
+/// The signature is not checked here: verify it with `verify_signature` before installing a list
+/// received from elsewhere.
+///
+/// # Returns
+/// `Ok(())`, or `CommitError::InvalidTransition` if the installed list has a sequence number
+/// greater than or equal to that of `list`.
+pub fn install(list: ParamsRevocationList) -> Result<(), CommitError> {
+    let mut installed = installed_list().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if installed.as_ref().is_some_and(|current| current.sequence >= list.sequence) {
+        return Err(CommitError::InvalidTransition);
+    }
+    *installed = Some(list);
+    Ok(())
+}
+
+/// Returns the installed revocation list, if any.
+pub fn installed() -> Option<ParamsRevocationList> {
+    installed_list().read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+// Registered expiry times, by `params::params_digest`.
+type Expiries = RwLock<Vec<(Vec<u8>, u64)>>;
+
+fn expiries() -> &'static Expiries {
+    static EXPIRIES: OnceLock<Expiries> = OnceLock::new();
+    EXPIRIES.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+fn installed_clock() -> &'static RwLock<Arc<dyn Clock>> {
+    static CLOCK: OnceLock<RwLock<Arc<dyn Clock>>> = OnceLock::new();
+    CLOCK.get_or_init(|| RwLock::new(Arc::new(SystemClock)))
+}
+
+/// Registers the expiry of `params` with every verification of this process, replacing any
+/// expiry registered for the same matrices before. Parameters without an expiry are left out.
+pub fn register_expiry(params: &PublicParams) {
+    let Some(expires_at) = params.expires_at() else {
+        return;
+    };
+    let digest = params::params_digest(params.as_pair());
+    let mut registered = expiries().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match registered.iter_mut().find(|(known, _)| *known == digest) {
+        Some(entry) => entry.1 = expires_at,
+        None => registered.push((digest, expires_at)),
+    }
+}
+
+/// Installs the clock the registered expiries are compared against, the system clock until then.
+pub fn install_clock(clock: Arc<dyn Clock>) {
+    *installed_clock().write().unwrap_or_else(|poisoned| poisoned.into_inner()) = clock;
+}
+
+/// Checks the parameters against the installed revocation list and the registered expiries.
+///
+/// # Returns
+/// `Ok(())`, `CommitError::ParamsRevoked` if the installed list names the parameters, or
+/// `CommitError::ParamsExpired` if their registered expiry is before the time of the installed
+/// clock.
+pub fn check(params: (&[Vec<u64>], &[Vec<u64>])) -> Result<(), CommitError> {
+    let installed = installed_list().read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let expiries = expiries().read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let revocable = installed.as_ref().is_some_and(|list| !list.revoked.is_empty());
+    // 列表和到期表都为空时跳过摘要计算
+    if !revocable && expiries.is_empty() {
+        return Ok(());
+    }
+    let digest = params::params_digest(params);
+    if installed.as_ref().is_some_and(|list| list.is_revoked(&digest)) {
+        return Err(CommitError::ParamsRevoked);
+    }
+    let expired = |&(_, expires_at): &(Vec<u8>, u64)| {
+        expires_at < installed_clock().read().unwrap_or_else(|poisoned| poisoned.into_inner()).now()
+    };
+    if expiries.iter().find(|(known, _)| *known == digest).is_some_and(expired) {
+        return Err(CommitError::ParamsExpired);
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::archive;
+    use crate::clock::VirtualClock;
+    use crate::envelope::OpeningEnvelope;
+    use crate::key::{CommitmentKey, Opening};
+    use crate::{utils, RevelerCommit};
+    use super::*;
+
+    #[test]
+    fn registered_expiries_are_enforced_by_every_verifier() {
+        let mut rng = StdRng::seed_from_u64(248);
+        let key = CommitmentKey::setup(&mut rng);
+        let (local_a, local_b) = (key.as_pair().0.to_vec(), key.as_pair().1.to_vec());
+        let r = utils::generate_randomness(&mut rng);
+        let opening = RevelerCommit::new(local_a.clone(), local_b.clone(), vec![1, 2], r.clone());
+        let commitment = opening.commit().unwrap();
+        let claims = vec![(commitment.clone(), Opening::new(vec![1, 2], r.clone()))];
+        let envelope = OpeningEnvelope::new(&commitment, &opening);
+        let archived = archive::to_archive_bytes(&commitment, &opening);
+
+        // 其他测试不注册到期时间，替换全局时钟不影响它们
+        let clock = Arc::new(VirtualClock::new(100));
+        install_clock(clock.clone());
+        register_expiry(&PublicParams::new(local_a, local_b, crate::fft::LOCAL_Q).with_expiry(100));
+        assert_eq!(RevelerCommit::verify_opening(key.as_pair(), &commitment, &[1, 2], &r), Ok(true));
+        assert_eq!(key.verify_batch(&claims), Ok(Vec::new()));
+        assert_eq!(envelope.verify_against(&commitment, key.as_pair()), Ok(()));
+        assert!(archive::verify_any(&archived, &key.digest()).is_ok());
+
+        clock.advance(1);
+        assert_eq!(RevelerCommit::verify_opening(key.as_pair(), &commitment, &[1, 2], &r), Err(CommitError::ParamsExpired));
+        assert_eq!(key.verify_batch(&claims), Err(CommitError::ParamsExpired));
+        assert_eq!(envelope.verify_against(&commitment, key.as_pair()), Err(CommitError::ParamsExpired));
+        assert_eq!(archive::verify_any(&archived, &key.digest()), Err(CommitError::ParamsExpired));
+    }
+}
@@ -0,0 +1,113 @@
+//! This module provides uniform sampling modulo `q` from hash output.
+//!
+//! An `Xof` turns a label and an input into an unbounded stream of `u64` words: the seed
+//! `hash_to_commitment(len(label) ‖ label ‖ len(input) ‖ input)` is expanded into the blocks
+//! `hash_to_commitment(seed ‖ counter)` for `counter = 0, 1, …`, each read as four big-endian
+//! words.
+//!
+//! `uniform_mod_q` draws words until one lies below `⌊(2^64 - 1) / q⌋ · q` and returns it modulo
+//! `q`. Every residue is then hit by exactly `⌊(2^64 - 1) / q⌋` accepted words, so the result is
+//! exactly uniform, and a word is rejected with probability at most `q / 2^64`. Parameter
+//! expansion and every Fiat–Shamir challenge of the crate sample this way.
+
+use crate::utils;
+
+// Number of u64 words in one block of `hash_to_commitment` output.
+const WORDS_PER_BLOCK: usize = 4;
+
+/// Extendable output from a label and an input.
+#[derive(Debug, Clone)]
+pub struct Xof {
+    seed: Vec<u8>,
+    counter: u64,      // The index of the next block
+    pending: Vec<u64>, // The unread words of the current block, last word first
+}
+
+impl Xof {
+    /// Creates the output stream of `input` under the domain `label`.
+    pub fn new(label: &[u8], input: &[u8]) -> Self {
+        Xof {
+            seed: utils::hash_to_commitment(&[utils::encode_bytes(label), utils::encode_bytes(input)].concat()),
+            counter: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the next word of the stream.
+    pub fn next_word(&mut self) -> u64 {
+        if self.pending.is_empty() {
+            self.refill(1);
+        }
+        self.pending.pop().unwrap_or_default()
+    }
+
+    /// Returns the next `count` words of the stream.
+    pub fn next_words(&mut self, count: usize) -> Vec<u64> {
+        if self.pending.len() < count {
+            self.refill(count - self.pending.len());
+        }
+        let split = self.pending.len() - count;
+        let mut words = self.pending.split_off(split);
+        words.reverse();
+        words
+    }
+
+    // Append enough blocks to hold `missing` more words.
+    fn refill(&mut self, missing: usize) {
+        let blocks = missing.div_ceil(WORDS_PER_BLOCK) as u64;
+        let mut words: Vec<u64> = (self.counter..self.counter + blocks)
+            .flat_map(|block| {
+                let mut block_input = self.seed.clone();
+                block_input.extend_from_slice(&block.to_be_bytes());
+                utils::hash_to_commitment(&block_input)
+            })
+            .collect::<Vec<u8>>()
+            .chunks_exact(8)
+            .map(|word| {
+                let mut buffer = [0u8; 8];
+                buffer.copy_from_slice(word);
+                u64::from_be_bytes(buffer)
+            })
+            .collect();
+        self.counter += blocks;
+        // 新块排在未读字之后，栈顶是下一个字
+        words.reverse();
+        words.append(&mut self.pending);
+        self.pending = words;
+    }
+}
+
+/// Samples an element uniform modulo `q` from the stream.
+///
+/// # Parameters
+/// - `xof`: The `Xof` to draw words from.
+/// - `q`: The modulus. A modulus of `0` is treated as `1`.
+///
+/// # Returns
+/// The first accepted word modulo `q`.
+pub fn uniform_mod_q(xof: &mut Xof, q: u64) -> u64 {
+    let q = q.max(1);
+    let limit = u64::MAX / q * q;
+    loop {
+        let word = xof.next_word();
+        if word < limit {
+            return word % q;
+        }
+    }
+}
+
+/// Samples `count` elements uniform modulo `q` from the stream.
+///
+/// Returns the same elements as `count` calls of `uniform_mod_q` and leaves the stream at the same
+/// position, but draws the words in batches.
+pub fn uniform_vec_mod_q(xof: &mut Xof, q: u64, count: usize) -> Vec<u64> {
+    let q = q.max(1);
+    let limit = u64::MAX / q * q;
+    let mut elements = Vec::with_capacity(count);
+    while elements.len() < count {
+        // 按缺口一次取一批字，再对整批字做拒绝采样
+        let words = xof.next_words(count - elements.len());
+        elements.extend(words.into_iter().filter(|&word| word < limit).map(|word| word % q));
+    }
+    elements
+}
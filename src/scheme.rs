@@ -0,0 +1,270 @@
+//! This module provides scheme identifiers and the registry resolving them.
+//!
+//! Every byte encoding produced by this crate starts with a header naming the scheme it was
+//! produced under, e.g. `reveler/v1/matvec-q65535-n256/bluehash256`: the format version, the
+//! arithmetic backend with its modulus and dimension, and the hash function. Decoders resolve the
+//! header through a process-wide registry and reject artifacts of unknown schemes with
+//! `CommitError::UnsupportedScheme`, so future backends, hashers and parameter sets can coexist
+//! in one deployment without ambiguity.
+//!
+//! The header is the identifier in ASCII, prefixed by its length as a single byte.
+//!
+//! Peers running different crate versions agree on a scheme before a round: each sends the
+//! `Capabilities` descriptor listing the schemes it supports and both call `negotiate` on the two
+//! lists. The choice depends only on the set of common schemes, not on which side is local, so
+//! both peers settle on the same scheme without a further exchange.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::fft::{LOCAL_N, LOCAL_Q};
+use crate::{grammar, utils};
+
+const SCHEME_FAMILY: &str = "reveler";
+const CAPABILITIES_MAGIC: &[u8; 4] = b"RVCP";
+
+/// Largest number of schemes accepted in a decoded `Capabilities` descriptor.
+pub const MAX_CAPABILITIES: usize = 256;
+
+/// Format version of the artifacts produced by this crate.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Identifier of a commitment scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchemeId {
+    pub version: u32,     // The artifact format version
+    pub backend: String,  // The arithmetic backend, e.g. `matvec`
+    pub modulus: u64,     // The modulus q
+    pub dimension: usize, // The dimension N
+    pub hasher: String,   // The hash function, e.g. `bluehash256`
+}
+
+impl SchemeId {
+    /// Returns the scheme implemented by this build.
+    pub fn current() -> Self {
+        SchemeId {
+            version: FORMAT_VERSION,
+            backend: "matvec".to_string(),
+            modulus: LOCAL_Q,
+            dimension: LOCAL_N,
+            hasher: "bluehash256".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SchemeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{SCHEME_FAMILY}/v{}/{}-q{}-n{}/{}",
+            self.version, self.backend, self.modulus, self.dimension, self.hasher
+        )
+    }
+}
+
+impl FromStr for SchemeId {
+    type Err = CommitError;
+
+    /// Parses `reveler/v<version>/<backend>-q<modulus>-n<dimension>/<hasher>`, the `scheme-id` of
+    /// `grammar`.
+    fn from_str(id: &str) -> Result<Self, CommitError> {
+        let parts: Vec<&str> = id.split('/').collect();
+        let [family, version, parameters, hasher] = parts.as_slice() else {
+            return Err(CommitError::UnsupportedScheme);
+        };
+        let version = version.strip_prefix('v').and_then(decimal);
+        let fields: Vec<&str> = parameters.split('-').collect();
+        let [backend, modulus, dimension] = fields.as_slice() else {
+            return Err(CommitError::UnsupportedScheme);
+        };
+        let modulus = modulus.strip_prefix('q').and_then(decimal);
+        let dimension = dimension.strip_prefix('n').and_then(decimal);
+
+        match (version, modulus, dimension) {
+            (Some(version), Some(modulus), Some(dimension))
+                if *family == SCHEME_FAMILY && grammar::is_name(backend) && grammar::is_name(hasher) =>
+            {
+                Ok(SchemeId { version, backend: backend.to_string(), modulus, dimension, hasher: hasher.to_string() })
+            }
+            _ => Err(CommitError::UnsupportedScheme),
+        }
+    }
+}
+
+// A `decimal` of `grammar` fitting the field it is parsed into.
+fn decimal<T: TryFrom<u64>>(text: &str) -> Option<T> {
+    grammar::parse_decimal(text).ok().and_then(|value| T::try_from(value).ok())
+}
+
+fn registry() -> &'static RwLock<Vec<SchemeId>> {
+    static REGISTRY: OnceLock<RwLock<Vec<SchemeId>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(vec![SchemeId::current()]))
+}
+
+/// Registers a scheme so that its artifacts are accepted at parse time.
+pub fn register(scheme: SchemeId) {
+    let mut schemes = registry().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !schemes.contains(&scheme) {
+        schemes.push(scheme);
+    }
+}
+
+/// Returns every registered scheme, starting with the current one.
+pub fn registered() -> Vec<SchemeId> {
+    registry().read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Resolves an identifier against the registry.
+///
+/// # Returns
+/// The registered `SchemeId`, or `CommitError::UnsupportedScheme` if the identifier is malformed
+/// or not registered.
+pub fn resolve(id: &str) -> Result<SchemeId, CommitError> {
+    let scheme: SchemeId = id.parse()?;
+    let schemes = registry().read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    schemes.iter().find(|&known| *known == scheme).cloned().ok_or(CommitError::UnsupportedScheme)
+}
+
+/// Appends the header of `scheme`, e.g. of an artifact of an older format version.
+pub fn write_scheme_header(scheme: &SchemeId, bytes: &mut Vec<u8>) {
+    let id = scheme.to_string();
+    bytes.push(id.len() as u8);
+    bytes.extend_from_slice(id.as_bytes());
+}
+
+/// Appends the header of the current scheme.
+pub fn write_header(bytes: &mut Vec<u8>) {
+    static HEADER: OnceLock<Vec<u8>> = OnceLock::new();
+    bytes.extend_from_slice(HEADER.get_or_init(|| {
+        let id = SchemeId::current().to_string();
+        let mut header = vec![id.len() as u8];
+        header.extend_from_slice(id.as_bytes());
+        header
+    }));
+}
+
+/// Reads and resolves a scheme header.
+///
+/// # Returns
+/// The resolved `SchemeId` and the remaining input, `CommitError::MalformedEncoding` if the
+/// header is truncated, or `CommitError::UnsupportedScheme` if it names an unknown scheme.
+pub fn read_header(bytes: &[u8]) -> Result<(SchemeId, &[u8]), CommitError> {
+    let (id, rest) = split_header(bytes)?;
+    Ok((resolve(id)?, rest))
+}
+
+/// Reads a scheme header without resolving it, for decoders keeping their own list of schemes
+/// such as `archive::verify_any`.
+///
+/// # Returns
+/// The parsed `SchemeId` and the remaining input, `CommitError::MalformedEncoding` if the header
+/// is truncated, or `CommitError::UnsupportedScheme` if it is not a scheme identifier.
+pub fn parse_header(bytes: &[u8]) -> Result<(SchemeId, &[u8]), CommitError> {
+    let (id, rest) = split_header(bytes)?;
+    Ok((id.parse()?, rest))
+}
+
+fn split_header(bytes: &[u8]) -> Result<(&str, &[u8]), CommitError> {
+    let (&len, rest) = bytes.split_first().ok_or(CommitError::MalformedEncoding)?;
+    if rest.len() < len as usize {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let (id, rest) = rest.split_at(len as usize);
+    let id = std::str::from_utf8(id).map_err(|_| CommitError::UnsupportedScheme)?;
+    Ok((id, rest))
+}
+
+/// Schemes supported by a peer, exchanged before a round to agree on a common one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub schemes: Vec<SchemeId>, // The supported schemes, current one first
+}
+
+impl Capabilities {
+    /// Returns the capabilities of this process: every registered scheme.
+    pub fn local() -> Self {
+        Capabilities { schemes: registered() }
+    }
+
+    /// Negotiates the scheme to use with a peer announcing `remote`.
+    pub fn negotiate(&self, remote: &Capabilities) -> Result<SchemeId, CommitError> {
+        negotiate(&self.schemes, &remote.schemes)
+    }
+
+    /// Encodes the descriptor as the magic bytes, the scheme count and the length-prefixed
+    /// identifier of every scheme.
+    ///
+    /// The encoding carries no scheme header, so that a peer which knows none of the listed
+    /// schemes still decodes it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = CAPABILITIES_MAGIC.to_vec();
+        bytes.extend_from_slice(&(self.schemes.len() as u32).to_be_bytes());
+        for scheme in &self.schemes {
+            bytes.extend(utils::encode_bytes(scheme.to_string().as_bytes()));
+        }
+        bytes
+    }
+
+    /// Decodes a descriptor written by `to_bytes`.
+    ///
+    /// Identifiers are parsed but not resolved against the registry, since a peer usually lists
+    /// schemes this process does not know. Identifiers that do not parse, e.g. of a future
+    /// identifier syntax, are skipped.
+    ///
+    /// # Returns
+    /// The decoded `Capabilities`, or `CommitError::MalformedEncoding` if the input is truncated,
+    /// lists more than `MAX_CAPABILITIES` schemes or carries trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitError> {
+        let Some((magic, rest)) = bytes.split_first_chunk::<4>() else {
+            return Err(CommitError::MalformedEncoding);
+        };
+        let Some((count, rest)) = rest.split_first_chunk::<4>() else {
+            return Err(CommitError::MalformedEncoding);
+        };
+        if magic != CAPABILITIES_MAGIC {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let count = u32::from_be_bytes(*count) as usize;
+        if count > MAX_CAPABILITIES {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let mut schemes = Vec::with_capacity(count);
+        let mut rest = rest;
+        for _ in 0..count {
+            let (id, tail) = utils::decode_bytes(rest)?;
+            rest = tail;
+            if let Some(scheme) = std::str::from_utf8(&id).ok().and_then(|id| id.parse().ok()) {
+                schemes.push(scheme);
+            }
+        }
+        if !rest.is_empty() {
+            return Err(CommitError::MalformedEncoding);
+        }
+        Ok(Capabilities { schemes })
+    }
+}
+
+/// Picks the scheme two peers use from the schemes each supports.
+///
+/// Among the schemes in both lists the one with the highest format version is chosen, ties broken
+/// by the smallest identifier. The result does not depend on the order of the lists nor on which
+/// one is `local`, so both peers reach the same scheme.
+///
+/// # Parameters
+/// - `local`: The schemes supported by this peer, e.g. `registered()`.
+/// - `remote`: The schemes supported by the other peer.
+///
+/// # Returns
+/// The agreed `SchemeId`, or `CommitError::UnsupportedScheme` if the peers have no scheme in
+/// common.
+pub fn negotiate(local: &[SchemeId], remote: &[SchemeId]) -> Result<SchemeId, CommitError> {
+    local
+        .iter()
+        .filter(|&scheme| remote.contains(scheme))
+        .map(|scheme| (scheme, scheme.to_string()))
+        .min_by(|(a, a_id), (b, b_id)| b.version.cmp(&a.version).then_with(|| a_id.cmp(b_id)))
+        .map(|(scheme, _)| scheme.clone())
+        .ok_or(CommitError::UnsupportedScheme)
+}
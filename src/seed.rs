@@ -0,0 +1,125 @@
+//! This module provides seed management: one `MasterSeed` from which every seed of an
+//! application is derived.
+//!
+//! `MasterSeed::derive(label)` returns the subkey `H("reveler/seed/derive" ‖ master ‖ label)`, with
+//! both inputs length-prefixed, so subkeys of distinct labels are independent and none reveals the
+//! master seed or another subkey. A `Seed` is then expanded, again under fixed domains, into
+//! - the `(A, B)` matrices of a parameter set with `Seed::params` or `PublicParams::from_seed`,
+//! - commitment randomness with `Seed::randomness`,
+//! - protocol nonces with `Seed::nonce`.
+//!
+//! Expansion uses `utils::hash_to_range`, so it is reproducible on every platform.
+
+#[cfg(feature = "prover")]
+use rand::Rng;
+use std::fmt;
+use crate::commit_error::CommitError;
+use crate::fft::LOCAL_Q;
+use crate::{params, utils};
+
+pub(crate) const DERIVE_DOMAIN: &[u8] = b"reveler/seed/derive";
+pub(crate) const RANDOMNESS_DOMAIN: &[u8] = b"reveler/seed/randomness";
+pub(crate) const NONCE_DOMAIN: &[u8] = b"reveler/seed/nonce";
+
+/// Length of seeds in bytes.
+pub const SEED_LEN: usize = 32;
+
+/// Root secret every seed of an application is derived from.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MasterSeed {
+    bytes: [u8; SEED_LEN],
+}
+
+/// Seed derived from a `MasterSeed` for one purpose.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Seed {
+    bytes: [u8; SEED_LEN],
+}
+
+impl MasterSeed {
+    /// Creates a master seed from its bytes.
+    pub fn from_bytes(bytes: [u8; SEED_LEN]) -> Self {
+        MasterSeed { bytes }
+    }
+
+    /// Samples a fresh master seed.
+    #[cfg(feature = "prover")]
+    pub fn random() -> Self {
+        MasterSeed { bytes: rand::thread_rng().gen() }
+    }
+
+    /// Returns the bytes of the master seed.
+    pub fn as_bytes(&self) -> &[u8; SEED_LEN] {
+        &self.bytes
+    }
+
+    /// Derives the seed for `label`.
+    ///
+    /// # Parameters
+    /// - `label`: The purpose of the seed, e.g. `b"params/round-7"` or `b"nonces"`.
+    ///
+    /// # Returns
+    /// The `Seed`, the same for the same master seed and label.
+    pub fn derive(&self, label: &[u8]) -> Seed {
+        let mut input = DERIVE_DOMAIN.to_vec();
+        input.extend(utils::encode_bytes(&self.bytes));
+        input.extend(utils::encode_bytes(label));
+        Seed::from_bytes(to_array(&utils::hash_to_commitment(&input)))
+    }
+}
+
+impl Seed {
+    /// Creates a seed from its bytes.
+    pub fn from_bytes(bytes: [u8; SEED_LEN]) -> Self {
+        Seed { bytes }
+    }
+
+    /// Returns the bytes of the seed.
+    pub fn as_bytes(&self) -> &[u8; SEED_LEN] {
+        &self.bytes
+    }
+
+    /// Derives a seed for `label` below this one, for hierarchies of purposes.
+    pub fn derive(&self, label: &[u8]) -> Seed {
+        MasterSeed::from_bytes(self.bytes).derive(label)
+    }
+
+    /// Expands the seed into the `(A, B)` matrices of a parameter set.
+    pub fn params(&self) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+        params::expand_matrices(&self.bytes, LOCAL_Q)
+    }
+
+    /// Expands the seed into `count` coordinates of commitment randomness in `{-1, 0, 1}` modulo
+    /// `q`, e.g. `fft::RANDOMNESS_LEN` for the randomness of a fresh commitment, see
+    /// `utils::hash_to_ternary`.
+    pub fn randomness(&self, count: usize) -> Result<Vec<u64>, CommitError> {
+        utils::hash_to_ternary(RANDOMNESS_DOMAIN, &self.bytes, count)
+    }
+
+    /// Returns the protocol nonce of index `counter`.
+    pub fn nonce(&self, counter: u64) -> [u8; SEED_LEN] {
+        let mut input = NONCE_DOMAIN.to_vec();
+        input.extend(utils::encode_bytes(&self.bytes));
+        input.extend_from_slice(&counter.to_be_bytes());
+        to_array(&utils::hash_to_commitment(&input))
+    }
+}
+
+// 不在调试输出中泄露种子
+impl fmt::Debug for MasterSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MasterSeed(..)")
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Seed(..)")
+    }
+}
+
+fn to_array(digest: &[u8]) -> [u8; SEED_LEN] {
+    let mut bytes = [0u8; SEED_LEN];
+    bytes.iter_mut().zip(digest).for_each(|(byte, &x)| *byte = x);
+    bytes
+}
@@ -0,0 +1,197 @@
+//! This module provides commitments to Shamir shares of a message.
+//!
+//! `split_commit` splits a message into `n` shares such that any `k` of them determine it, and
+//! commits to each share separately. The shares are computed in the prime field of
+//! `SHARE_MODULUS`, the largest prime below `q`, so every share is also a valid message of the
+//! commitment scheme. Each committed vector is `[index, s_1, …, s_L]`: the share index is the
+//! first coordinate, so an opening cannot be replayed as the share of another custodian.
+//!
+//! `reconstruct_and_verify` checks every supplied opening against its commitment, interpolates the
+//! message from `k` of them, and checks that any further openings lie on the same polynomial.
+
+#[cfg(feature = "prover")]
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+#[cfg(feature = "prover")]
+use crate::fft::MESSAGE_LEN;
+use crate::fft::LOCAL_Q;
+use crate::policy::VerifyPolicy;
+use crate::{compute_point, RevelerCommit, RevelerResult};
+#[cfg(feature = "prover")]
+use crate::utils;
+
+/// Prime modulus of the field the shares are computed in.
+pub const SHARE_MODULUS: u64 = 65521;
+
+/// Public commitments to the shares of a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedCommitment {
+    pub threshold: usize,                // The number k of shares needed to reconstruct
+    pub commitments: Vec<RevelerResult>, // The commitment to the share of index i + 1 at position i
+}
+
+/// Opening of one share commitment, handed to its custodian.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareOpening {
+    pub local_m: Vec<u64>, // The committed vector [index, s_1, …, s_L]
+    pub local_r: Vec<u64>, // The randomness of the commitment
+}
+
+/// Splits a message into `shares` Shamir shares with threshold `threshold` and commits to each.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices the shares are committed under.
+/// - `local_m`: The message, of at most `MESSAGE_LEN - 1` elements below `SHARE_MODULUS`.
+/// - `threshold`: The number `k` of shares needed to reconstruct.
+/// - `shares`: The number `n` of shares.
+///
+/// # Returns
+/// The `SharedCommitment` and the `(index, opening)` pair of every custodian,
+/// `CommitError::InvalidThreshold` unless `1 <= threshold <= shares < SHARE_MODULUS`, or
+/// `CommitError::ComputationError` if the message is too long or holds an element outside the
+/// share field.
+#[cfg(feature = "prover")]
+pub fn split_commit(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    local_m: &[u64],
+    threshold: usize,
+    shares: usize,
+) -> Result<(SharedCommitment, Vec<(u64, ShareOpening)>), CommitError> {
+    if threshold == 0 || threshold > shares || shares as u64 >= SHARE_MODULUS {
+        return Err(CommitError::InvalidThreshold);
+    }
+    if local_m.len() >= MESSAGE_LEN || local_m.iter().any(|&x| x >= SHARE_MODULUS) {
+        return Err(CommitError::ComputationError);
+    }
+
+    let mut rng = rand::thread_rng();
+    // 每个消息元素使用独立的 k-1 次多项式，常数项为该元素
+    let polynomials: Vec<Vec<u64>> = local_m.iter()
+        .map(|&secret| {
+            let mut coefficients = vec![secret];
+            coefficients.extend((1..threshold).map(|_| rng.gen_range(0..SHARE_MODULUS)));
+            coefficients
+        })
+        .collect();
+
+    let config = CommitConfig::default();
+    let mut commitments = Vec::with_capacity(shares);
+    let mut openings = Vec::with_capacity(shares);
+    for index in 1..=shares as u64 {
+        let mut share = vec![index];
+        share.extend(polynomials.iter().map(|coefficients| evaluate(coefficients, index)));
+        let local_r = utils::generate_randomness(&mut rng);
+
+        let commitment_point = compute_point(params.0, params.1, &share, &local_r, &config)?;
+        let commitment_hash = utils::hash_to_commitment(&commitment_point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+        commitments.push(RevelerResult::new(commitment_point, commitment_hash));
+        openings.push((index, ShareOpening { local_m: share, local_r }));
+    }
+
+    Ok((SharedCommitment { threshold, commitments }, openings))
+}
+
+/// Verifies share openings against their commitments and reconstructs the message.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices the shares were committed under.
+/// - `shared`: A reference to the `SharedCommitment`.
+/// - `openings`: The `(index, opening)` pairs supplied by the custodians, in any order.
+///
+/// # Returns
+/// The message, `CommitError::InvalidOpening` if an opening does not match the commitment of its
+/// index within the norm bounds of `VerifyPolicy::new` or does not lie on the same polynomial as the others, or `CommitError::InvalidThreshold`
+/// if fewer than `threshold` distinct shares were supplied.
+pub fn reconstruct_and_verify(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    shared: &SharedCommitment,
+    openings: &[(u64, ShareOpening)],
+) -> Result<Vec<u64>, CommitError> {
+    let config = CommitConfig::default();
+    let policy = VerifyPolicy::new();
+    let mut points: Vec<(u64, &[u64])> = Vec::with_capacity(openings.len());
+
+    for (index, opening) in openings {
+        let commitment = index.checked_sub(1)
+            .and_then(|position| usize::try_from(position).ok())
+            .and_then(|position| shared.commitments.get(position))
+            .ok_or(CommitError::InvalidOpening)?;
+        let Some((&embedded, share)) = opening.local_m.split_first() else {
+            return Err(CommitError::InvalidOpening);
+        };
+        if embedded != *index
+            || share.iter().any(|&x| x >= SHARE_MODULUS)
+            || !RevelerCommit::verify(commitment)
+            || policy.check_norms(&opening.local_m, &opening.local_r, config.modulus).is_err()
+            || compute_point(params.0, params.1, &opening.local_m, &opening.local_r, &config).ok().as_ref()
+                != Some(&commitment.commitment_point)
+        {
+            return Err(CommitError::InvalidOpening);
+        }
+        if points.iter().any(|&(other, _)| other == *index) {
+            continue;
+        }
+        if points.first().is_some_and(|(_, first)| first.len() != share.len()) {
+            return Err(CommitError::InvalidOpening);
+        }
+        points.push((*index, share));
+    }
+
+    if shared.threshold == 0 || points.len() < shared.threshold {
+        return Err(CommitError::InvalidThreshold);
+    }
+    let (basis, extra) = points.split_at(shared.threshold);
+    let message = interpolate(basis, 0);
+    for &(index, share) in extra {
+        if interpolate(basis, index) != share {
+            return Err(CommitError::InvalidOpening);
+        }
+    }
+    Ok(message)
+}
+
+// Evaluate the polynomial with the given coefficients at `x` by Horner's rule.
+#[cfg(feature = "prover")]
+fn evaluate(coefficients: &[u64], x: u64) -> u64 {
+    coefficients.iter().rev().fold(0, |acc, &c| (acc * x + c) % SHARE_MODULUS)
+}
+
+// Lagrange-interpolate the shared vectors at `x`, coordinate by coordinate.
+fn interpolate(points: &[(u64, &[u64])], x: u64) -> Vec<u64> {
+    let len = points.first().map_or(0, |(_, share)| share.len());
+    let mut result = vec![0u64; len];
+    for &(i, share) in points {
+        let mut numerator = 1;
+        let mut denominator = 1;
+        for &(j, _) in points {
+            if j != i {
+                numerator = numerator * ((x + SHARE_MODULUS - j) % SHARE_MODULUS) % SHARE_MODULUS;
+                denominator = denominator * ((i + SHARE_MODULUS - j) % SHARE_MODULUS) % SHARE_MODULUS;
+            }
+        }
+        let weight = numerator * inverse(denominator) % SHARE_MODULUS;
+        for (acc, &y) in result.iter_mut().zip(share) {
+            *acc = (*acc + weight * y) % SHARE_MODULUS;
+        }
+    }
+    result
+}
+
+// Invert a nonzero element of the share field by Fermat's little theorem.
+fn inverse(x: u64) -> u64 {
+    let mut result = 1;
+    let mut base = x % SHARE_MODULUS;
+    let mut exponent = SHARE_MODULUS - 2;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % SHARE_MODULUS;
+        }
+        base = base * base % SHARE_MODULUS;
+        exponent >>= 1;
+    }
+    result
+}
+
+const _: () = assert!(SHARE_MODULUS < LOCAL_Q);
@@ -0,0 +1,250 @@
+//! This module verifies proofs read from a byte stream with bounded memory.
+//!
+//! Binary proofs, and therefore the range proofs of `amounts` and linking proofs, run to a few
+//! megabytes. Constrained verifiers such as light clients need
+//! not buffer them: the stream layout puts every Fiat–Shamir input before the first response, so
+//! a verifier hashes the commitments as they arrive, derives the challenges, and then checks the
+//! responses one round at a time, stopping at the first round that fails.
+//!
+//! A streamed proof is the scheme header, the round count, the commitments of every round and
+//! then the response of every round. The verifier keeps the 96 bytes of commitments of each
+//! round and at most one response at a time.
+//!
+//! Every verifier takes the `ProofConfig` the proof was made with.
+
+use std::io::{Read, Write};
+use crate::amounts::AMOUNT_BITS;
+use crate::binary::{self, BinaryProof, BinaryResponse, BinaryRound, Relation};
+use crate::commit_error::CommitError;
+use crate::config::ProofConfig;
+use crate::fft::MESSAGE_LEN;
+use crate::linking::{self, LinkingProof};
+use crate::{scheme, utils, RevelerCommit, RevelerResult};
+
+// Length of the hashes committing to a binary round, and of its salts.
+const HASH_LEN: usize = 32;
+
+/// Writes a binary proof in the stream layout.
+///
+/// # Returns
+/// `Ok(())`, or `CommitError::TransportError` if the write fails.
+pub fn write_binary_proof<W: Write>(proof: &BinaryProof, writer: &mut W) -> Result<(), CommitError> {
+    write_rounds(&proof.rounds, writer)
+}
+
+// Write the rounds of a proof on the binary engine: the header, the round count, the commitments
+// of every round and then the response of every round.
+fn write_rounds<W: Write>(rounds: &[BinaryRound], writer: &mut W) -> Result<(), CommitError> {
+    let mut bytes = Vec::new();
+    scheme::write_header(&mut bytes);
+    bytes.extend_from_slice(&(rounds.len() as u64).to_be_bytes());
+    for round in rounds {
+        bytes.extend_from_slice(&(round.commitments.len() as u64).to_be_bytes());
+        for commitment in &round.commitments {
+            bytes.extend(utils::encode_bytes(commitment));
+        }
+    }
+    write_all(writer, &bytes)?;
+
+    for round in rounds {
+        let mut bytes = Vec::new();
+        match &round.response {
+            BinaryResponse::Permuted { permuted_witness, permuted_mask, mask_salt, shifted_salt } => {
+                bytes.push(0);
+                bytes.extend(utils::encode_vector(permuted_witness));
+                bytes.extend(utils::encode_vector(permuted_mask));
+                bytes.extend(utils::encode_bytes(mask_salt));
+                bytes.extend(utils::encode_bytes(shifted_salt));
+            }
+            BinaryResponse::Shifted { permutation, shifted_witness, shifted_free, point_salt, shifted_salt } => {
+                bytes.push(1);
+                bytes.extend(encode_permutation(permutation));
+                bytes.extend(utils::encode_vector(shifted_witness));
+                bytes.extend(utils::encode_vector(shifted_free));
+                bytes.extend(utils::encode_bytes(point_salt));
+                bytes.extend(utils::encode_bytes(shifted_salt));
+            }
+            BinaryResponse::Masked { permutation, mask, mask_free, point_salt, mask_salt } => {
+                bytes.push(2);
+                bytes.extend(encode_permutation(permutation));
+                bytes.extend(utils::encode_vector(mask));
+                bytes.extend(utils::encode_vector(mask_free));
+                bytes.extend(utils::encode_bytes(point_salt));
+                bytes.extend(utils::encode_bytes(mask_salt));
+            }
+        }
+        write_all(writer, &bytes)?;
+    }
+    Ok(())
+}
+
+/// Verifies a streamed proof that `commitment` hides a binary message of `width` bits.
+///
+/// # Returns
+/// `true` if `config` is valid for binary proofs, the commitment hash is valid, the stream is
+/// well formed, and every round of the proof checks out.
+pub fn verify_binary_from_reader<R: Read>(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    commitment: &RevelerResult,
+    width: usize,
+    reader: &mut R,
+    config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = config.binary_rounds() else {
+        return false;
+    };
+    width <= MESSAGE_LEN
+        && RevelerCommit::verify(commitment)
+        && verify_relation_from_reader(&binary::bits_relation(params, width, commitment), reader, rounds).unwrap_or(false)
+}
+
+/// Verifies a streamed range proof of a confidential amount.
+///
+/// # Returns
+/// `true` if the stream proves that `commitment` hides the bits of a `u64`.
+pub fn verify_range_from_reader<R: Read>(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    commitment: &RevelerResult,
+    reader: &mut R,
+    config: &ProofConfig,
+) -> bool {
+    verify_binary_from_reader(params, commitment, AMOUNT_BITS, reader, config)
+}
+
+/// Writes a linking proof in the stream layout.
+///
+/// # Returns
+/// `Ok(())`, or `CommitError::TransportError` if the write fails.
+pub fn write_linking_proof<W: Write>(proof: &LinkingProof, writer: &mut W) -> Result<(), CommitError> {
+    write_rounds(&proof.rounds, writer)
+}
+
+/// Verifies a streamed proof that `c1` under `params1` and `c2` under `params2` hide the same
+/// message.
+///
+/// # Returns
+/// `true` if `config` is valid for binary proofs, both commitments verify, the stream is well
+/// formed, and every round of the proof checks out.
+pub fn verify_linking_from_reader<R: Read>(
+    params1: (&[Vec<u64>], &[Vec<u64>]),
+    c1: &RevelerResult,
+    params2: (&[Vec<u64>], &[Vec<u64>]),
+    c2: &RevelerResult,
+    reader: &mut R,
+    config: &ProofConfig,
+) -> bool {
+    let Ok(rounds) = config.binary_rounds() else {
+        return false;
+    };
+    RevelerCommit::verify(c1)
+        && RevelerCommit::verify(c2)
+        && verify_relation_from_reader(&linking::linking_relation(params1, c1, params2, c2), reader, rounds).unwrap_or(false)
+}
+
+fn verify_relation_from_reader<R: Read>(relation: &Relation, reader: &mut R, rounds: usize) -> Result<bool, CommitError> {
+    let rounds = read_preamble(reader, rounds)?;
+    let mut transcript = binary::transcript(relation);
+    let mut commitments = Vec::with_capacity(rounds);
+    for _ in 0..rounds {
+        let count = read_u64(reader)?;
+        if count != 3 {
+            return Ok(false);
+        }
+        let round: Vec<Vec<u8>> = (0..3).map(|_| read_bytes(reader, HASH_LEN)).collect::<Result<_, _>>()?;
+        for commitment in &round {
+            transcript.update(&utils::encode_bytes(commitment));
+        }
+        commitments.push(round);
+    }
+
+    let challenges = binary::challenges(&transcript.finalize(), rounds);
+    let (width, free) = (relation.len(), relation.free);
+    for (round, challenge) in commitments.iter().zip(challenges) {
+        let mut tag = [0u8; 1];
+        reader.read_exact(&mut tag).map_err(|_| CommitError::TransportError)?;
+        let response = match tag[0] {
+            0 => BinaryResponse::Permuted {
+                permuted_witness: read_vector(reader, width)?,
+                permuted_mask: read_vector(reader, width)?,
+                mask_salt: read_bytes(reader, HASH_LEN)?,
+                shifted_salt: read_bytes(reader, HASH_LEN)?,
+            },
+            1 => BinaryResponse::Shifted {
+                permutation: read_permutation(reader, width)?,
+                shifted_witness: read_vector(reader, width)?,
+                shifted_free: read_vector(reader, free)?,
+                point_salt: read_bytes(reader, HASH_LEN)?,
+                shifted_salt: read_bytes(reader, HASH_LEN)?,
+            },
+            2 => BinaryResponse::Masked {
+                permutation: read_permutation(reader, width)?,
+                mask: read_vector(reader, width)?,
+                mask_free: read_vector(reader, free)?,
+                point_salt: read_bytes(reader, HASH_LEN)?,
+                mask_salt: read_bytes(reader, HASH_LEN)?,
+            },
+            _ => return Err(CommitError::MalformedEncoding),
+        };
+        if !binary::verify_round(relation, round, &response, challenge) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn write_all<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), CommitError> {
+    writer.write_all(bytes).map_err(|_| CommitError::TransportError)
+}
+
+// Read the scheme header and the round count, which must be `expected`.
+fn read_preamble<R: Read>(reader: &mut R, expected: usize) -> Result<usize, CommitError> {
+    let mut len = [0u8; 1];
+    reader.read_exact(&mut len).map_err(|_| CommitError::TransportError)?;
+    let mut id = vec![0u8; len[0] as usize];
+    reader.read_exact(&mut id).map_err(|_| CommitError::TransportError)?;
+    let header: Vec<u8> = len.into_iter().chain(id).collect();
+    scheme::read_header(&header)?;
+
+    let rounds = read_u64(reader)?;
+    if rounds != expected as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok(expected)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, CommitError> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer).map_err(|_| CommitError::TransportError)?;
+    Ok(u64::from_be_bytes(buffer))
+}
+
+// Read a vector written by `utils::encode_vector` of at most `max_len` elements.
+fn read_vector<R: Read>(reader: &mut R, max_len: usize) -> Result<Vec<u64>, CommitError> {
+    let len = read_u64(reader)?;
+    // 先检查长度，避免恶意长度前缀导致大量内存分配
+    if len > max_len as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    (0..len).map(|_| read_u64(reader)).collect()
+}
+
+// Read a byte string written by `utils::encode_bytes` of at most `max_len` bytes.
+fn read_bytes<R: Read>(reader: &mut R, max_len: usize) -> Result<Vec<u8>, CommitError> {
+    let len = read_u64(reader)?;
+    if len > max_len as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes).map_err(|_| CommitError::TransportError)?;
+    Ok(bytes)
+}
+
+fn read_permutation<R: Read>(reader: &mut R, max_len: usize) -> Result<Vec<u32>, CommitError> {
+    read_vector(reader, max_len)?.into_iter()
+        .map(|target| u32::try_from(target).map_err(|_| CommitError::MalformedEncoding))
+        .collect()
+}
+
+fn encode_permutation(permutation: &[u32]) -> Vec<u8> {
+    utils::encode_vector(&permutation.iter().map(|&target| target as u64).collect::<Vec<u64>>())
+}
@@ -0,0 +1,190 @@
+//! This module provides `Tenant`, which isolates the commitments of the tenants of a multi-tenant
+//! service from each other by construction rather than by convention.
+//!
+//! A tenant is named by an identifier and derived from the master seed of the service, so the
+//! service keeps one secret and every tenant context is recomputed from it:
+//! - Domain tags: `Tenant::domain` prefixes a tag with `TENANT_DOMAIN` and the identifier, so
+//!   hashes and derivations an application makes per tenant never collide across tenants.
+//! - Parameters: the commitment key of a tenant is expanded from its own seed, and
+//!   `Tenant::params` goes through the process-wide `cache::params_cache()` under that seed, so
+//!   tenants never share matrices or cache entries.
+//! - Commitments: `Tenant::commit` and `Tenant::bind` wrap a commitment in a `TenantCommitment`
+//!   carrying the identifier and a tag keyed by a secret of the tenant over the identifier and the
+//!   commitment. `Tenant::verify` refuses a commitment of another tenant with
+//!   `CommitError::TenantMismatch` before any opening is checked, and an opening that passed under
+//!   one tenant's matrices fails under another's even if the wrapper is stripped.
+//! - Stores: `Tenant::round_store` wraps a `RoundStore` so saved states and journal records are
+//!   marked with the tenant, and loading a state saved by another tenant fails with `CommitError::TenantMismatch`.
+
+use std::fmt;
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::key::{CommitmentKey, Opening};
+use crate::params::PublicParams;
+use crate::protocol::store::RoundStore;
+use crate::seed::{MasterSeed, Seed};
+use crate::{utils, RevelerResult};
+
+pub(crate) const TENANT_DOMAIN: &[u8] = b"reveler/tenant";
+
+/// Longest tenant identifier, in bytes.
+pub const MAX_TENANT_ID_LEN: usize = 255;
+
+/// Commitment bound to the tenant it was made for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantCommitment {
+    pub tenant: String,            // The identifier of the tenant
+    pub commitment: RevelerResult, // The commitment under the key of the tenant
+    pub tag: Vec<u8>,              // The keyed tag binding the commitment to the tenant
+}
+
+/// Context of one tenant: its identifier, seed and commitment key.
+pub struct Tenant {
+    id: String,         // The identifier of the tenant
+    seed: Seed,         // The seed every secret and parameter of the tenant is derived from
+    key: CommitmentKey, // The commitment key of the tenant
+}
+
+impl Tenant {
+    /// Derives the context of the tenant `id` from the master seed of the service.
+    ///
+    /// # Returns
+    /// The `Tenant`, the same for the same master seed and identifier, or
+    /// `CommitError::MalformedEncoding` if `id` is empty or longer than `MAX_TENANT_ID_LEN`.
+    pub fn derive(master: &MasterSeed, id: &str) -> Result<Self, CommitError> {
+        if id.is_empty() || id.len() > MAX_TENANT_ID_LEN {
+            return Err(CommitError::MalformedEncoding);
+        }
+        let seed = master.derive(&namespace(id, b"seed"));
+        let key = CommitmentKey::from_seed(&seed.derive(b"params"));
+        Ok(Tenant { id: id.to_string(), seed, key })
+    }
+
+    /// Returns the identifier of the tenant.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the commitment key of the tenant.
+    pub fn key(&self) -> &CommitmentKey {
+        &self.key
+    }
+
+    /// Returns the parameters of the tenant, through the parameters cache.
+    pub fn params(&self) -> Arc<PublicParams> {
+        PublicParams::from_seed(&self.seed.derive(b"params"), &CommitConfig::default())
+    }
+
+    /// Returns the domain tag `tag` namespaced to the tenant.
+    pub fn domain(&self, tag: &[u8]) -> Vec<u8> {
+        namespace(&self.id, tag)
+    }
+
+    /// Derives a seed for `label` in the namespace of the tenant.
+    pub fn seed(&self, label: &[u8]) -> Seed {
+        self.seed.derive(&self.domain(label))
+    }
+
+    /// Commits to `opening` under the key of the tenant.
+    ///
+    /// # Returns
+    /// The `TenantCommitment`, or the `CommitError` of the commitment.
+    #[cfg(feature = "prover")]
+    pub fn commit(&self, opening: &Opening) -> Result<TenantCommitment, CommitError> {
+        Ok(self.bind(self.key.commit_opening(opening)?))
+    }
+
+    /// Binds a commitment computed under the key of the tenant, e.g. by a batch commitment.
+    pub fn bind(&self, commitment: RevelerResult) -> TenantCommitment {
+        let tag = self.tag(&commitment);
+        TenantCommitment { tenant: self.id.clone(), commitment, tag }
+    }
+
+    /// Checks that `commitment` belongs to the tenant and that `opening` opens it.
+    ///
+    /// # Returns
+    /// `true` if `opening` opens the commitment under the key of the tenant, `false` otherwise,
+    /// `CommitError::TenantMismatch` if the commitment names another tenant or its tag does not
+    /// verify, or `CommitError::ParamsRevoked` or `CommitError::ParamsExpired` if
+    /// `revocation::check` refuses the key.
+    pub fn verify(&self, commitment: &TenantCommitment, opening: &Opening) -> Result<bool, CommitError> {
+        self.check(commitment)?;
+        self.key.verify(&commitment.commitment, opening)
+    }
+
+    /// Checks that `commitment` was bound by this tenant.
+    ///
+    /// # Returns
+    /// `Ok(())`, or `CommitError::TenantMismatch` if the commitment names another tenant or its
+    /// tag does not verify.
+    pub fn check(&self, commitment: &TenantCommitment) -> Result<(), CommitError> {
+        if commitment.tenant != self.id || commitment.tag != self.tag(&commitment.commitment) {
+            return Err(CommitError::TenantMismatch);
+        }
+        Ok(())
+    }
+
+    /// Wraps `store` so the states it holds are marked with the tenant.
+    pub fn round_store<S: RoundStore>(&self, store: S) -> TenantRoundStore<S> {
+        TenantRoundStore { marker: utils::hash_to_commitment(&self.domain(b"round-store")), store }
+    }
+
+    // Tag keyed by a secret of the tenant over the identifier and the commitment.
+    fn tag(&self, commitment: &RevelerResult) -> Vec<u8> {
+        let mut input = self.domain(b"commitment");
+        input.extend(utils::encode_bytes(self.seed.derive(b"tag").as_bytes()));
+        input.extend(commitment.to_bytes());
+        utils::hash_to_commitment(&input)
+    }
+}
+
+// 不在调试输出中泄露租户种子
+impl fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tenant").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+/// `RoundStore` holding the states of one tenant.
+#[derive(Debug)]
+pub struct TenantRoundStore<S> {
+    marker: Vec<u8>, // The digest of the tenant namespace, written before every state
+    store: S,        // The underlying store
+}
+
+impl<S: RoundStore> RoundStore for TenantRoundStore<S> {
+    fn save(&self, bytes: &[u8]) -> Result<(), CommitError> {
+        let mut marked = self.marker.clone();
+        marked.extend_from_slice(bytes);
+        self.store.save(&marked)
+    }
+
+    fn load(&self) -> Result<Option<Vec<u8>>, CommitError> {
+        match self.store.load()? {
+            None => Ok(None),
+            Some(bytes) => bytes.strip_prefix(self.marker.as_slice()).map(|state| Some(state.to_vec())).ok_or(CommitError::TenantMismatch),
+        }
+    }
+
+    fn append(&self, record: &[u8]) -> Result<(), CommitError> {
+        let mut marked = self.marker.clone();
+        marked.extend_from_slice(record);
+        self.store.append(&marked)
+    }
+
+    fn journal(&self) -> Result<Vec<Vec<u8>>, CommitError> {
+        self.store.journal()?.iter()
+            .map(|bytes| bytes.strip_prefix(self.marker.as_slice()).map(<[u8]>::to_vec).ok_or(CommitError::TenantMismatch))
+            .collect()
+    }
+}
+
+// TENANT_DOMAIN ‖ len(id) ‖ id ‖ len(tag) ‖ tag
+fn namespace(id: &str, tag: &[u8]) -> Vec<u8> {
+    let mut bytes = TENANT_DOMAIN.to_vec();
+    bytes.extend(utils::encode_bytes(id.as_bytes()));
+    bytes.extend(utils::encode_bytes(tag));
+    bytes
+}
@@ -0,0 +1,216 @@
+//! This module provides time-locked openings based on the RSW time-lock puzzle.
+//!
+//! `seal` encrypts an opening `(m, r)` under the key `H(x^(2^t) mod N)`, where `N = p·q` is an
+//! RSA modulus whose factors are discarded after sealing. Knowing `φ(N)` the sealer reduces the
+//! exponent and derives the key instantly, while everybody else has to perform `t` sequential
+//! modular squarings in `unseal`. Anyone can therefore force the reveal after roughly `t`
+//! squarings of work, so a participant that refuses to reveal cannot block settlement.
+//!
+//! The module is compiled with the `timelock` feature.
+
+use num_bigint::{BigUint, RandBigInt};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::clock::Clock;
+use crate::commit_error::CommitError;
+use crate::utils;
+
+pub(crate) const TIMELOCK_KEY_DOMAIN: &[u8] = b"reveler/timelock/key";
+pub(crate) const TIMELOCK_TAG_DOMAIN: &[u8] = b"reveler/timelock/tag";
+
+/// Default bit length of the RSA modulus.
+pub const DEFAULT_MODULUS_BITS: u64 = 2048;
+
+const MILLER_RABIN_ROUNDS: usize = 40;
+const SMALL_PRIMES: [u32; 15] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+/// An opening encrypted under an RSW time-lock puzzle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeLockedOpening {
+    pub modulus: Vec<u8>,    // The RSA modulus N, big-endian
+    pub base: Vec<u8>,       // The puzzle base x, big-endian
+    pub squarings: u64,      // The number t of sequential squarings
+    pub ciphertext: Vec<u8>, // The encrypted opening
+    pub tag: Vec<u8>,        // The integrity tag over the key and ciphertext
+}
+
+/// Seals an opening with a `DEFAULT_MODULUS_BITS` modulus.
+///
+/// # Parameters
+/// - `local_m`: A reference to the message vector of the opening.
+/// - `local_r`: A reference to the randomness vector of the opening.
+/// - `squarings`: The number `t` of sequential squarings needed to unseal.
+///
+/// # Returns
+/// The `TimeLockedOpening` holding the puzzle and the encrypted opening.
+pub fn seal(local_m: &[u64], local_r: &[u64], squarings: u64) -> Result<TimeLockedOpening, CommitError> {
+    seal_with_modulus_bits(local_m, local_r, squarings, DEFAULT_MODULUS_BITS)
+}
+
+/// Seals an opening so that it unseals at time `unlock_at` on hardware performing
+/// `squarings_per_second` sequential squarings.
+///
+/// # Parameters
+/// - `local_m`: A reference to the message vector of the opening.
+/// - `local_r`: A reference to the randomness vector of the opening.
+/// - `unlock_at`: The time, in seconds since the Unix epoch, at which the opening becomes public.
+/// - `squarings_per_second`: The squaring rate of the fastest expected solver.
+/// - `clock`: The clock providing the current time.
+///
+/// # Returns
+/// The `TimeLockedOpening`. A deadline that already passed yields a puzzle without squarings.
+pub fn seal_until(
+    local_m: &[u64],
+    local_r: &[u64],
+    unlock_at: u64,
+    squarings_per_second: u64,
+    clock: &dyn Clock,
+) -> Result<TimeLockedOpening, CommitError> {
+    let squarings = unlock_at.saturating_sub(clock.now()).saturating_mul(squarings_per_second);
+    seal(local_m, local_r, squarings)
+}
+
+/// Seals an opening with a modulus of `modulus_bits` bits.
+///
+/// # Returns
+/// The `TimeLockedOpening`, or `CommitError::ComputationError` if `modulus_bits` is below 64.
+pub fn seal_with_modulus_bits(
+    local_m: &[u64],
+    local_r: &[u64],
+    squarings: u64,
+    modulus_bits: u64,
+) -> Result<TimeLockedOpening, CommitError> {
+    if modulus_bits < 64 {
+        return Err(CommitError::ComputationError);
+    }
+
+    let mut rng = rand::thread_rng();
+    let p = random_prime(&mut rng, modulus_bits / 2);
+    let q = loop {
+        let candidate = random_prime(&mut rng, modulus_bits - modulus_bits / 2);
+        if candidate != p {
+            break candidate;
+        }
+    };
+
+    let modulus = &p * &q;
+    let phi = (&p - 1u32) * (&q - 1u32);
+    let base = rng.gen_biguint_range(&BigUint::from(2u32), &(&modulus - 1u32));
+
+    // 利用 φ(N) 将指数 2^t 约化，封装者无需执行顺序平方
+    let exponent = BigUint::from(2u32).modpow(&BigUint::from(squarings), &phi);
+    let solution = base.modpow(&exponent, &modulus);
+
+    let key = derive_key(&solution);
+    let mut plaintext = utils::encode_vector(local_m);
+    plaintext.extend(utils::encode_vector(local_r));
+    let ciphertext = utils::xor_keystream(&key, &plaintext);
+
+    Ok(TimeLockedOpening {
+        modulus: modulus.to_bytes_be(),
+        base: base.to_bytes_be(),
+        squarings,
+        tag: compute_tag(&key, &ciphertext),
+        ciphertext,
+    })
+}
+
+impl TimeLockedOpening {
+    /// Estimates the time at which a solver starting now at `squarings_per_second` unseals it.
+    pub fn estimated_unlock_time(&self, squarings_per_second: u64, clock: &dyn Clock) -> u64 {
+        clock.now().saturating_add(self.squarings / squarings_per_second.max(1))
+    }
+}
+
+/// Solves the puzzle by sequential squaring and decrypts the opening.
+///
+/// # Parameters
+/// - `sealed`: A reference to the `TimeLockedOpening` to open.
+///
+/// # Returns
+/// The `(m, r)` opening, or `CommitError::DecryptionFailed` if the tag does not match.
+pub fn unseal(sealed: &TimeLockedOpening) -> Result<(Vec<u64>, Vec<u64>), CommitError> {
+    let modulus = BigUint::from_bytes_be(&sealed.modulus);
+    if modulus < BigUint::from(3u32) {
+        return Err(CommitError::MalformedEncoding);
+    }
+
+    let mut solution = BigUint::from_bytes_be(&sealed.base) % &modulus;
+    for _ in 0..sealed.squarings {
+        solution = &solution * &solution % &modulus;
+    }
+
+    let key = derive_key(&solution);
+    if compute_tag(&key, &sealed.ciphertext) != sealed.tag {
+        return Err(CommitError::DecryptionFailed);
+    }
+
+    let plaintext = utils::xor_keystream(&key, &sealed.ciphertext);
+    let (local_m, rest) = utils::decode_vector(&plaintext)?;
+    let (local_r, rest) = utils::decode_vector(rest)?;
+    if !rest.is_empty() {
+        return Err(CommitError::MalformedEncoding);
+    }
+    Ok((local_m, local_r))
+}
+
+fn derive_key(solution: &BigUint) -> Vec<u8> {
+    let mut input = TIMELOCK_KEY_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(&solution.to_bytes_be()));
+    utils::hash_to_commitment(&input)
+}
+
+fn compute_tag(key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut input = TIMELOCK_TAG_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(key));
+    input.extend(utils::encode_bytes(ciphertext));
+    utils::hash_to_commitment(&input)
+}
+
+// Generate a random probable prime of exactly `bits` bits.
+fn random_prime<R: Rng>(rng: &mut R, bits: u64) -> BigUint {
+    loop {
+        let mut candidate = rng.gen_biguint(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(0, true);
+        if is_probable_prime(rng, &candidate) {
+            return candidate;
+        }
+    }
+}
+
+// Miller-Rabin primality test preceded by trial division.
+fn is_probable_prime<R: Rng>(rng: &mut R, n: &BigUint) -> bool {
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    for &p in SMALL_PRIMES.iter() {
+        if *n == BigUint::from(p) {
+            return true;
+        }
+        if (n % p) == BigUint::from(0u32) {
+            return false;
+        }
+    }
+
+    let n_minus_one = n - 1u32;
+    let shift = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> shift;
+
+    'witness: for _ in 0..MILLER_RABIN_ROUNDS {
+        let a = rng.gen_biguint_range(&two, &n_minus_one);
+        let mut x = a.modpow(&d, n);
+        if x == BigUint::from(1u32) || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..shift {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
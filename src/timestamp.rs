@@ -0,0 +1,204 @@
+//! This module provides committed timestamps, for audit logs that demonstrate the order of their
+//! events without revealing when they happened.
+//!
+//! A timestamp is a count of ticks since an epoch chosen by the application, committed at
+//! coordinate 0 of the message. `prove_monotonic` shows that the timestamp of a new commitment is
+//! at least the one of the previous commitment with the comparison proof of `range::prove_leq`:
+//! a range proof that the previous timestamp lies in `[0, 2^k)` and one that the difference
+//! `next - previous`, committed by `c_next - c_previous`, does. Neither timestamp nor the gap
+//! between them is revealed.
+//!
+//! Values are taken modulo `q`, so timestamps are bounded by `2^k` with `k ≤ range::MAX_LEQ_BITS`,
+//! which makes the comparison hold over the integers; the application picks the tick so that its
+//! log fits, e.g. minutes since the start of a log rotated weekly. `prove_log` chains the proofs
+//! of consecutive entries of a log, and `verify_log` checks that the whole log is non-decreasing.
+
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+#[cfg(feature = "prover")]
+use crate::config::CommitConfig;
+use crate::config::ProofConfig;
+use crate::key::CommitmentKey;
+#[cfg(feature = "prover")]
+use crate::key::Opening;
+use crate::range::{self, LeqProof, MAX_LEQ_BITS};
+#[cfg(feature = "prover")]
+use crate::utils;
+use crate::RevelerResult;
+
+/// Coordinate of the message holding a timestamp.
+pub const TIMESTAMP_INDEX: usize = 0;
+
+/// Width of the timestamps under `prove_monotonic` and `prove_log`.
+pub const DEFAULT_TIMESTAMP_BITS: usize = MAX_LEQ_BITS;
+
+/// Commits to a timestamp of `ticks` with fresh randomness.
+///
+/// # Returns
+/// The commitment and its opening, `CommitError::ComputationError` if `ticks` is not below `q`,
+/// or the `CommitError` of the commitment.
+#[cfg(feature = "prover")]
+pub fn commit_timestamp(key: &CommitmentKey, ticks: u64) -> Result<(RevelerResult, Opening), CommitError> {
+    let q = CommitConfig::default().modulus;
+    if ticks >= q {
+        return Err(CommitError::ComputationError);
+    }
+    let opening = Opening::new(vec![ticks], utils::generate_randomness(&mut rand::thread_rng()));
+    let commitment = key.commit_opening(&opening)?;
+    Ok((commitment, opening))
+}
+
+/// Proves that the timestamp of `next` is at least the timestamp of `previous`, both below
+/// `2^DEFAULT_TIMESTAMP_BITS`.
+///
+/// # Parameters
+/// - `key`: The commitment key of both timestamps.
+/// - `previous`: The earlier timestamp and its opening.
+/// - `next`: The later timestamp and its opening.
+///
+/// # Returns
+/// The `LeqProof` of the timestamps, or the errors of `range::prove_leq`.
+#[cfg(feature = "prover")]
+pub fn prove_monotonic(
+    key: &CommitmentKey,
+    previous: (&RevelerResult, &Opening),
+    next: (&RevelerResult, &Opening),
+) -> Result<LeqProof, CommitError> {
+    prove_monotonic_with_config(key, previous, next, DEFAULT_TIMESTAMP_BITS, &ProofConfig::default())
+}
+
+/// Proves that a timestamp follows another, both below `2^bits`, with the soundness of
+/// `proof_config`.
+///
+/// # Returns
+/// The `LeqProof` of the timestamps, or the errors of `range::prove_leq_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_monotonic_with_config(
+    key: &CommitmentKey,
+    previous: (&RevelerResult, &Opening),
+    next: (&RevelerResult, &Opening),
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> Result<LeqProof, CommitError> {
+    range::prove_leq_with_config(key, previous, next, TIMESTAMP_INDEX, bits, proof_config)
+}
+
+/// Verifies that the timestamp of `next` is at least the timestamp of `previous`.
+///
+/// # Returns
+/// The outcome of `range::verify_leq` at the timestamp coordinate with width
+/// `DEFAULT_TIMESTAMP_BITS`.
+pub fn verify_monotonic(key: &CommitmentKey, previous: &RevelerResult, next: &RevelerResult, proof: &LeqProof) -> bool {
+    verify_monotonic_with_config(key, previous, next, proof, DEFAULT_TIMESTAMP_BITS, &ProofConfig::default())
+}
+
+/// Verifies a monotonicity proof made for timestamps below `2^bits` with the soundness of
+/// `proof_config`.
+///
+/// # Returns
+/// The outcome of `range::verify_leq_with_config` at the timestamp coordinate.
+pub fn verify_monotonic_with_config(
+    key: &CommitmentKey,
+    previous: &RevelerResult,
+    next: &RevelerResult,
+    proof: &LeqProof,
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> bool {
+    range::verify_leq_with_config(key, previous, next, TIMESTAMP_INDEX, bits, proof, proof_config)
+}
+
+/// Proves that the timestamps of a log are non-decreasing.
+///
+/// # Returns
+/// The `LeqProof` of every pair of consecutive entries, none for a log of fewer than two entries,
+/// or the errors of `prove_monotonic`.
+#[cfg(feature = "prover")]
+pub fn prove_log(key: &CommitmentKey, entries: &[(RevelerResult, Opening)]) -> Result<Vec<LeqProof>, CommitError> {
+    prove_log_with_config(key, entries, DEFAULT_TIMESTAMP_BITS, &ProofConfig::default())
+}
+
+/// Proves that the timestamps of a log, below `2^bits`, are non-decreasing with the soundness of
+/// `proof_config`.
+///
+/// # Returns
+/// The `LeqProof` of every pair of consecutive entries, or the errors of
+/// `prove_monotonic_with_config`.
+#[cfg(feature = "prover")]
+pub fn prove_log_with_config(
+    key: &CommitmentKey,
+    entries: &[(RevelerResult, Opening)],
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> Result<Vec<LeqProof>, CommitError> {
+    entries.windows(2)
+        .map(|pair| match pair {
+            [(previous, previous_opening), (next, next_opening)] => {
+                prove_monotonic_with_config(key, (previous, previous_opening), (next, next_opening), bits, proof_config)
+            }
+            _ => Err(CommitError::ComputationError),
+        })
+        .collect()
+}
+
+/// Verifies that the timestamps of a log are non-decreasing.
+///
+/// # Returns
+/// `true` if there is one proof per pair of consecutive timestamps and every proof checks out.
+pub fn verify_log(key: &CommitmentKey, timestamps: &[RevelerResult], proofs: &[LeqProof]) -> bool {
+    verify_log_with_config(key, timestamps, proofs, DEFAULT_TIMESTAMP_BITS, &ProofConfig::default())
+}
+
+/// Verifies the proofs of a log made for timestamps below `2^bits` with the soundness of
+/// `proof_config`.
+///
+/// # Returns
+/// The outcome of `verify_log` under these parameters.
+pub fn verify_log_with_config(
+    key: &CommitmentKey,
+    timestamps: &[RevelerResult],
+    proofs: &[LeqProof],
+    bits: usize,
+    proof_config: &ProofConfig,
+) -> bool {
+    proofs.len() == timestamps.len().saturating_sub(1)
+        && timestamps.windows(2).zip(proofs).all(|(pair, proof)| match pair {
+            [previous, next] => verify_monotonic_with_config(key, previous, next, proof, bits, proof_config),
+            _ => false,
+        })
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn ordered_logs_verify() {
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(277));
+        let entries: Vec<(RevelerResult, Opening)> = [3, 3, 10, 40].iter().map(|&ticks| commit_timestamp(&key, ticks).unwrap()).collect();
+        let timestamps: Vec<RevelerResult> = entries.iter().map(|(commitment, _)| commitment.clone()).collect();
+        let proofs = prove_log_with_config(&key, &entries, 6, &ProofConfig::default()).unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert!(verify_log_with_config(&key, &timestamps, &proofs, 6, &ProofConfig::default()));
+        assert!(!verify_log_with_config(&key, &timestamps, &proofs[..2], 6, &ProofConfig::default()));
+
+        // 交换两条日志或两份证明都不能通过
+        let swapped = [timestamps[0].clone(), timestamps[2].clone(), timestamps[1].clone(), timestamps[3].clone()];
+        assert!(!verify_log_with_config(&key, &swapped, &proofs, 6, &ProofConfig::default()));
+        let reordered = [proofs[1].clone(), proofs[0].clone(), proofs[2].clone()];
+        assert!(!verify_log_with_config(&key, &timestamps, &reordered, 6, &ProofConfig::default()));
+    }
+
+    #[test]
+    fn earlier_timestamps_are_refused() {
+        let key = CommitmentKey::setup(&mut StdRng::seed_from_u64(277));
+        let previous = commit_timestamp(&key, 12).unwrap();
+        let next = commit_timestamp(&key, 11).unwrap();
+        let result = prove_monotonic_with_config(&key, (&previous.0, &previous.1), (&next.0, &next.1), 6, &ProofConfig::default());
+        assert_eq!(result, Err(CommitError::InvalidOpening));
+        assert_eq!(commit_timestamp(&key, CommitConfig::default().modulus).map(|_| ()), Err(CommitError::ComputationError));
+    }
+}
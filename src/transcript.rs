@@ -0,0 +1,135 @@
+//! This module provides `Transcript`, the Fiat–Shamir transcript that makes the proofs of `proofs`
+//! non-interactive: the prover and the verifier append the same labeled messages and derive the
+//! same challenges from them, so the challenges are fixed only once everything before them is.
+//!
+//! A transcript starts from a protocol label, the commitment key and the commitment, so every
+//! challenge binds the protocol, the parameters and the commitment point with its hash: a proof
+//! made for one protocol, key or commitment yields other challenges under any other and fails.
+//! Further keys and commitments, e.g. the second side of a relation, are appended with
+//! `append_key` and `append_commitment`.
+//!
+//! Every message is absorbed into BlueHash as its length-prefixed label followed by the
+//! length-prefixed message, so no two sequences of appends share an encoding. `challenges`
+//! absorbs its label, finalizes the hash to a digest, expands the digest with `utils::hash_to_range`
+//! under `TRANSCRIPT_DOMAIN` and restarts the hash from the digest, so later challenges depend on
+//! the earlier ones and multi-stage protocols keep one transcript throughout.
+//!
+//! The proofs take any `ProofTranscript`, so they also run inside a larger protocol on the
+//! transcript of that protocol. With the `merlin` feature `merlin::Transcript` implements the
+//! trait: the statement is appended to the Merlin transcript and every challenge is expanded from
+//! 32 bytes of `challenge_bytes`, so reveler proofs compose with Rust tooling built on Merlin.
+//!
+//! The `binary`, `linking` and `disclosure` proofs keep their own challenge derivations, so proofs
+//! recorded by earlier versions still verify.
+
+use crate::key::CommitmentKey;
+use crate::{utils, RevelerResult};
+
+pub(crate) const TRANSCRIPT_DOMAIN: &[u8] = b"reveler/transcript";
+
+/// Transcript the non-interactive proofs derive their challenges from.
+pub trait ProofTranscript {
+    /// Appends `message` under the label `label`.
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+
+    /// Derives `count` challenges uniform in `[0, bound)` from everything appended so far.
+    fn challenge_values(&mut self, label: &'static [u8], count: usize, bound: u64) -> Vec<u64>;
+
+    /// Appends the statement of a proof: the protocol label, the digest of the commitment key and
+    /// the commitment.
+    fn bind_statement(&mut self, protocol: &[u8], key: &CommitmentKey, commitment: &RevelerResult) {
+        self.append_message(b"protocol", protocol);
+        self.append_message(b"key", &key.digest());
+        self.append_message(b"commitment", &commitment.to_bytes());
+    }
+}
+
+/// Fiat–Shamir transcript of a proof.
+pub struct Transcript {
+    hasher: utils::CommitmentHasher,
+}
+
+impl Transcript {
+    /// Starts a transcript.
+    ///
+    /// # Parameters
+    /// - `protocol`: The label of the protocol, e.g. its domain separation tag.
+    /// - `key`: The commitment key the statement is about.
+    /// - `commitment`: The commitment the statement is about.
+    pub fn new(protocol: &[u8], key: &CommitmentKey, commitment: &RevelerResult) -> Self {
+        let mut transcript = Transcript { hasher: utils::CommitmentHasher::new() };
+        transcript.append(b"domain", TRANSCRIPT_DOMAIN);
+        transcript.bind_statement(protocol, key, commitment);
+        transcript
+    }
+
+    /// Appends the message `message` under the label `label`.
+    pub fn append(&mut self, label: &[u8], message: &[u8]) {
+        self.hasher.update(&utils::encode_bytes(label));
+        self.hasher.update(&utils::encode_bytes(message));
+    }
+
+    /// Appends a vector of field elements under the label `label`.
+    pub fn append_vector(&mut self, label: &[u8], values: &[u64]) {
+        self.append(label, &utils::encode_vector(values));
+    }
+
+    /// Appends a `u64` under the label `label`.
+    pub fn append_u64(&mut self, label: &[u8], value: u64) {
+        self.append(label, &value.to_be_bytes());
+    }
+
+    /// Appends the digest of a commitment key.
+    pub fn append_key(&mut self, key: &CommitmentKey) {
+        self.append(b"key", &key.digest());
+    }
+
+    /// Appends a commitment: its point and its hash.
+    pub fn append_commitment(&mut self, commitment: &RevelerResult) {
+        self.append(b"commitment", &commitment.to_bytes());
+    }
+
+    /// Derives challenges from everything appended so far.
+    ///
+    /// # Parameters
+    /// - `label`: The label of the challenges.
+    /// - `count`: The number of challenges.
+    /// - `bound`: The exclusive upper bound of every challenge.
+    ///
+    /// # Returns
+    /// `count` challenges uniform in `[0, bound)`.
+    pub fn challenges(&mut self, label: &[u8], count: usize, bound: u64) -> Vec<u64> {
+        self.append_u64(b"challenge-count", count as u64);
+        self.append_u64(b"challenge-bound", bound);
+        self.append(b"challenge", label);
+        // 派生挑战后以摘要重新开始，使后续挑战依赖于此前的全部挑战
+        let digest = std::mem::take(&mut self.hasher).finalize();
+        self.hasher.update(&utils::encode_bytes(&digest));
+        utils::hash_to_range(TRANSCRIPT_DOMAIN, &digest, count, bound)
+    }
+}
+
+impl ProofTranscript for Transcript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.append(label, message);
+    }
+
+    fn challenge_values(&mut self, label: &'static [u8], count: usize, bound: u64) -> Vec<u64> {
+        self.challenges(label, count, bound)
+    }
+}
+
+#[cfg(feature = "merlin")]
+impl ProofTranscript for merlin::Transcript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        merlin::Transcript::append_message(self, label, message);
+    }
+
+    fn challenge_values(&mut self, label: &'static [u8], count: usize, bound: u64) -> Vec<u64> {
+        self.append_u64(b"challenge-count", count as u64);
+        self.append_u64(b"challenge-bound", bound);
+        let mut seed = [0u8; 32];
+        self.challenge_bytes(label, &mut seed);
+        utils::hash_to_range(TRANSCRIPT_DOMAIN, &seed, count, bound)
+    }
+}
@@ -0,0 +1,137 @@
+//! This module provides commitments to state transitions, for audit logs of replicated state
+//! machines.
+//!
+//! Every transition commits to the new state together with the digest of the previous transition:
+//! the committed message is the bytes of that digest, one per coordinate, followed by the state.
+//! The transition also carries the digest of its witness, e.g. the command that produced the
+//! state, so the chain records why the state changed without revealing it. The first transition
+//! links to `genesis_digest()`.
+//!
+//! `verify_chain` checks that a sequence of transitions is well-linked, and `verify_opening`
+//! checks a revealed state and witness against their transition.
+
+use serde::{Serialize, Deserialize};
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::fft::MESSAGE_LEN;
+use crate::policy::VerifyPolicy;
+use crate::{compute_point, utils, RevelerCommit, RevelerResult};
+
+pub(crate) const GENESIS_DOMAIN: &[u8] = b"reveler/transition/genesis";
+pub(crate) const LINK_DOMAIN: &[u8] = b"reveler/transition/link";
+pub(crate) const WITNESS_DOMAIN: &[u8] = b"reveler/transition/witness";
+
+/// Commitment to one state transition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionCommitment {
+    pub sequence: u64,              // The position of the transition in the chain
+    pub previous: Vec<u8>,          // The digest of the previous transition
+    pub commitment: RevelerResult,  // The commitment to the previous digest and the new state
+    pub witness_digest: Vec<u8>,    // The digest of the witness of the transition
+}
+
+/// Secret opening of a transition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionOpening {
+    pub state: Vec<u64>,
+    pub local_r: Vec<u64>, // The randomness of the commitment
+    pub witness: Vec<u8>,
+}
+
+impl TransitionCommitment {
+    /// Computes the digest the next transition links to.
+    pub fn digest(&self) -> Vec<u8> {
+        let mut input = LINK_DOMAIN.to_vec();
+        input.extend_from_slice(&self.sequence.to_be_bytes());
+        input.extend(utils::encode_bytes(&self.previous));
+        input.extend(self.commitment.to_bytes());
+        input.extend(utils::encode_bytes(&self.witness_digest));
+        utils::hash_to_commitment(&input)
+    }
+}
+
+/// Returns the digest the first transition of a chain links to.
+pub fn genesis_digest() -> Vec<u8> {
+    utils::hash_to_commitment(GENESIS_DOMAIN)
+}
+
+/// Commits to the state reached after `previous`.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the chain.
+/// - `previous`: The previous transition, or `None` for the first one.
+/// - `new_state`: The new state.
+/// - `witness`: The witness of the transition, e.g. the command applied.
+///
+/// # Returns
+/// The `TransitionCommitment` and its `TransitionOpening`, or `CommitError::ComputationError` if
+/// the state does not fit next to the previous digest in `MESSAGE_LEN` coordinates.
+#[cfg(feature = "prover")]
+pub fn commit_transition(
+    params: (&[Vec<u64>], &[Vec<u64>]),
+    previous: Option<&TransitionCommitment>,
+    new_state: &[u64],
+    witness: &[u8],
+) -> Result<(TransitionCommitment, TransitionOpening), CommitError> {
+    let (sequence, previous) = match previous {
+        Some(previous) => (previous.sequence + 1, previous.digest()),
+        None => (0, genesis_digest()),
+    };
+    let mut rng = rand::thread_rng();
+    let opening = TransitionOpening {
+        state: new_state.to_vec(),
+        local_r: utils::generate_randomness(&mut rng),
+        witness: witness.to_vec(),
+    };
+    let commitment = commit_linked(params, &previous, &opening)?;
+
+    let transition = TransitionCommitment { sequence, previous, commitment, witness_digest: witness_digest(witness) };
+    Ok((transition, opening))
+}
+
+/// Checks that transitions form a chain starting at the genesis digest.
+///
+/// # Returns
+/// `true` if the sequence numbers count up from 0, every transition links to the digest of its
+/// predecessor, and every commitment hash is valid.
+pub fn verify_chain(chain: &[TransitionCommitment]) -> bool {
+    let mut expected = genesis_digest();
+    for (sequence, transition) in chain.iter().enumerate() {
+        if transition.sequence != sequence as u64
+            || transition.previous != expected
+            || !RevelerCommit::verify(&transition.commitment)
+        {
+            return false;
+        }
+        expected = transition.digest();
+    }
+    true
+}
+
+/// Checks a revealed state and witness against their transition, with the norm bounds of
+/// `VerifyPolicy::new` on the randomness.
+pub fn verify_opening(params: (&[Vec<u64>], &[Vec<u64>]), transition: &TransitionCommitment, opening: &TransitionOpening) -> bool {
+    transition.witness_digest == witness_digest(&opening.witness)
+        && RevelerCommit::verify(&transition.commitment)
+        && VerifyPolicy::new().check_norms(&opening.state, &opening.local_r, CommitConfig::default().modulus).is_ok()
+        && commit_linked(params, &transition.previous, opening)
+            .map(|commitment| commitment.commitment_point == transition.commitment.commitment_point)
+            .unwrap_or(false)
+}
+
+fn witness_digest(witness: &[u8]) -> Vec<u8> {
+    let mut input = WITNESS_DOMAIN.to_vec();
+    input.extend_from_slice(witness);
+    utils::hash_to_commitment(&input)
+}
+
+// Commit to the message [previous digest bytes, state].
+fn commit_linked(params: (&[Vec<u64>], &[Vec<u64>]), previous: &[u8], opening: &TransitionOpening) -> Result<RevelerResult, CommitError> {
+    if previous.len() + opening.state.len() > MESSAGE_LEN {
+        return Err(CommitError::ComputationError);
+    }
+    let message: Vec<u64> = previous.iter().map(|&byte| byte as u64).chain(opening.state.iter().copied()).collect();
+    let point = compute_point(params.0, params.1, &message, &opening.local_r, &CommitConfig::default())?;
+    let hash = utils::hash_to_commitment(&point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+    Ok(RevelerResult::new(point, hash))
+}
@@ -7,13 +7,23 @@
 //! The `hash_to_commitment` function applies multiple rounds of hashing to increase randomness and
 //! strength of the commitment.
 
+#[cfg(feature = "prover")]
 use num_cpus;
+#[cfg(feature = "prover")]
 use std::cmp;
 use BlueHash::DigestSize::{Bit256};
+#[cfg(feature = "prover")]
 use rand::Rng;
-use crate::fft::{LOCAL_N, LOCAL_Q};
+use crate::arith::ArithmeticPolicy;
+use crate::commit_error::CommitError;
+use crate::limits;
+use crate::sampling;
+use crate::fft::LOCAL_Q;
+#[cfg(feature = "prover")]
+use crate::fft::{LOCAL_N, RANDOMNESS_LEN};
 
 // Get the optimal number of threads to use for parallel computations.
+#[cfg(feature = "prover")]
 pub fn get_optimal_thread_count() -> usize {
     let cpu_cores = num_cpus::get();
     if LOCAL_N > 1000 {
@@ -23,27 +33,302 @@ pub fn get_optimal_thread_count() -> usize {
     }
 }
 
+// Worker threads running independent tasks, such as the rounds of a proof.
+#[cfg(all(feature = "prover", feature = "parallel"))]
+#[derive(Clone, Copy)]
+pub(crate) enum Workers<'a> {
+    // Scoped threads split like the blocks of the worker-thread path of `commit`.
+    Threads,
+    // A caller-provided rayon pool.
+    Pool(&'a rayon::ThreadPool),
+}
+
+#[cfg(all(feature = "prover", not(feature = "parallel")))]
+#[derive(Clone, Copy)]
+pub(crate) enum Workers {
+    Threads,
+}
+
+// Run `task` for every index in 0..count on the workers and collect the results in index order.
+#[cfg(feature = "prover")]
+pub(crate) fn run_indexed<T, F>(workers: Workers, count: usize, task: F) -> Result<Vec<T>, CommitError>
+where
+    T: Send,
+    F: Fn(usize) -> Result<T, CommitError> + Sync,
+{
+    match workers {
+        #[cfg(feature = "parallel")]
+        Workers::Pool(pool) => {
+            use rayon::prelude::*;
+            return pool.install(|| (0..count).into_par_iter().map(&task).collect());
+        }
+        Workers::Threads => {}
+    }
+
+    let thread_count = cmp::min(get_optimal_thread_count(), count).max(1);
+    let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count).map(|thread_id| {
+            let task = &task;
+            scope.spawn(move || {
+                (thread_id..count).step_by(thread_count)
+                    .map(|index| (index, task(index)))
+                    .collect::<Vec<_>>()
+            })
+        }).collect();
+
+        // 合并线程结果
+        for handle in handles {
+            for (index, result) in handle.join().map_err(|_| CommitError::ComputationError)? {
+                *results.get_mut(index).ok_or(CommitError::ComputationError)? = Some(result?);
+            }
+        }
+        Ok(())
+    })?;
+    results.into_iter().map(|result| result.ok_or(CommitError::ComputationError)).collect()
+}
+
 
 // Hash input data and apply multiple rounds for better randomness.
 pub fn hash_to_commitment(input: &[u8]) -> Vec<u8> {
-    let mut hasher = BlueHash::BlueHash::new(Bit256);
+    let mut hasher = CommitmentHasher::new();
     hasher.update(input);
-    let mut result = hasher.finalize().to_vec();
+    hasher.finalize()
+}
+
+/// Hashes `input` under the domain `label` to `count` elements uniform modulo `q`.
+///
+/// This is `hash_to_range(label, input, count, LOCAL_Q)`.
+pub fn hash_to_field_elements(label: &[u8], input: &[u8], count: usize) -> Vec<u64> {
+    hash_to_range(label, input, count, LOCAL_Q)
+}
+
+/// Hashes `input` under the domain `label` to `count` elements uniform in `{-1, 0, 1}`, i.e.
+/// `{q - 1, 0, 1}` modulo `LOCAL_Q`, as the randomness of a fresh commitment.
+///
+/// # Returns
+/// The elements, reduced under `ArithmeticPolicy::for_build()`, or the error of the policy.
+pub fn hash_to_ternary(label: &[u8], input: &[u8], count: usize) -> Result<Vec<u64>, CommitError> {
+    let policy = ArithmeticPolicy::for_build();
+    hash_to_range(label, input, count, 3).into_iter().map(|x| policy.reduce_signed_mod(x as i64 - 1, LOCAL_Q)).collect()
+}
 
-    for _ in 0..3 {
-        hasher.update(&result);
-        result = hasher.finalize().to_vec();
+/// Hashes `input` under the domain `label` to `count` elements uniform in `[0, bound)`.
+///
+/// This is `sampling::uniform_vec_mod_q(&mut Xof::new(label, input), bound, count)`; see the
+/// `sampling` module for the expansion and the rejection sampling. Every Fiat–Shamir challenge of
+/// the crate is derived this way. A `bound` of 0 is treated as 1.
+pub fn hash_to_range(label: &[u8], input: &[u8], count: usize, bound: u64) -> Vec<u64> {
+    sampling::uniform_vec_mod_q(&mut sampling::Xof::new(label, input), bound, count)
+}
+
+// Number of times `hash_to_commitment` feeds its output back into the hasher after the first
+// finalization.
+pub(crate) const HASH_EXTRA_ROUNDS: usize = 3;
+
+// Incremental form of `hash_to_commitment` for inputs that arrive in pieces.
+pub struct CommitmentHasher {
+    hasher: BlueHash::BlueHash,
+}
+
+impl CommitmentHasher {
+    pub fn new() -> Self {
+        CommitmentHasher { hasher: BlueHash::BlueHash::new(Bit256) }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input);
+    }
+
+    // Finish the hash exactly as `hash_to_commitment` of the concatenated input.
+    pub fn finalize(mut self) -> Vec<u8> {
+        let mut result = self.hasher.finalize().to_vec();
+        for _ in 0..HASH_EXTRA_ROUNDS {
+            self.hasher.update(&result);
+            result = self.hasher.finalize().to_vec();
+        }
+        result
     }
-    result
 }
 
-fn generate_matrix() -> Vec<Vec<u64>> {
-    let mut rng = rand::thread_rng();
+impl Default for CommitmentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "prover")]
+fn generate_matrix<R: Rng>(rng: &mut R) -> Vec<Vec<u64>> {
     (0..LOCAL_N)
         .map(|_| (0..LOCAL_N).map(|_| rng.gen_range(0..LOCAL_Q)).collect())
         .collect()
 }
 
+#[cfg(feature = "prover")]
 pub fn generate_params() -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
-    (generate_matrix(), generate_matrix())
+    generate_params_with_rng(&mut rand::thread_rng())
+}
+
+// Generate the (A, B) matrices from the given random number generator, e.g. a seeded one for replay.
+#[cfg(feature = "prover")]
+pub fn generate_params_with_rng<R: Rng>(rng: &mut R) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    (generate_matrix(rng), generate_matrix(rng))
+}
+
+/// Generates the randomness of a fresh commitment: `RANDOMNESS_LEN` coordinates drawn uniformly
+/// from `{-1, 0, 1}`, i.e. `{q - 1, 0, 1}` modulo `LOCAL_Q`.
+#[cfg(feature = "prover")]
+pub fn generate_randomness<R: Rng>(rng: &mut R) -> Vec<u64> {
+    (0..RANDOMNESS_LEN)
+        .map(|_| match rng.gen_range(0..3u64) {
+            0 => LOCAL_Q - 1,
+            x => x - 1,
+        })
+        .collect()
+}
+
+// Encode a vector canonically as its big-endian length followed by its big-endian elements.
+pub fn encode_vector(values: &[u64]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 * (values.len() + 1));
+    bytes.extend_from_slice(&(values.len() as u64).to_be_bytes());
+    bytes.extend(values.iter().flat_map(|&x| x.to_be_bytes()));
+    bytes
+}
+
+// Encode a byte string canonically as its big-endian length followed by the bytes.
+pub fn encode_bytes(values: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + values.len());
+    bytes.extend_from_slice(&(values.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(values);
+    bytes
+}
+
+// Encode a matrix canonically as its big-endian row count followed by each encoded row.
+pub fn encode_matrix(rows: &[Vec<u64>]) -> Vec<u8> {
+    let mut bytes = (rows.len() as u64).to_be_bytes().to_vec();
+    for row in rows {
+        bytes.extend(encode_vector(row));
+    }
+    bytes
+}
+
+// Split a big-endian u64 off the front of the input.
+pub fn decode_u64(bytes: &[u8]) -> Result<(u64, &[u8]), CommitError> {
+    if bytes.len() < 8 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let (head, rest) = bytes.split_at(8);
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(head);
+    Ok((u64::from_be_bytes(buffer), rest))
+}
+
+// Decode a vector written by `encode_vector`, returning it with the remaining input; vectors
+// longer than `max_point_len` of the installed limits fail with `CommitError::LimitExceeded`.
+pub fn decode_vector(bytes: &[u8]) -> Result<(Vec<u64>, &[u8]), CommitError> {
+    decode_vector_within(bytes, limits::current().max_point_len)
+}
+
+fn decode_vector_within(bytes: &[u8], max_len: usize) -> Result<(Vec<u64>, &[u8]), CommitError> {
+    let (len, mut rest) = decode_u64(bytes)?;
+    // 先检查长度，避免恶意长度前缀导致大量内存分配
+    if len > (rest.len() / 8) as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    limits::check(usize::try_from(len).unwrap_or(usize::MAX), max_len)?;
+    let mut values = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        let (value, tail) = decode_u64(rest)?;
+        values.push(value);
+        rest = tail;
+    }
+    Ok((values, rest))
+}
+
+// Decode a byte string written by `encode_bytes`, returning it with the remaining input.
+pub fn decode_bytes(bytes: &[u8]) -> Result<(Vec<u8>, &[u8]), CommitError> {
+    let (len, rest) = decode_u64(bytes)?;
+    if len > rest.len() as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    let (values, rest) = rest.split_at(len as usize);
+    Ok((values.to_vec(), rest))
+}
+
+// Decode a matrix written by `encode_matrix`, returning it with the remaining input; matrices
+// with more rows or columns than `max_dimension` fail with `CommitError::LimitExceeded`.
+pub fn decode_matrix(bytes: &[u8]) -> Result<(Vec<Vec<u64>>, &[u8]), CommitError> {
+    let max_dimension = limits::current().max_dimension;
+    decode_rows(bytes, max_dimension, max_dimension)
+}
+
+// Decode a list of vectors in the format of `encode_matrix`, e.g. the points of a batch, with
+// any number of vectors of at most `max_point_len` coordinates.
+pub fn decode_vectors(bytes: &[u8]) -> Result<(Vec<Vec<u64>>, &[u8]), CommitError> {
+    decode_rows(bytes, usize::MAX, limits::current().max_point_len)
+}
+
+fn decode_rows(bytes: &[u8], max_rows: usize, max_len: usize) -> Result<(Vec<Vec<u64>>, &[u8]), CommitError> {
+    let (rows, mut rest) = decode_u64(bytes)?;
+    if rows > (rest.len() / 8) as u64 {
+        return Err(CommitError::MalformedEncoding);
+    }
+    limits::check(usize::try_from(rows).unwrap_or(usize::MAX), max_rows)?;
+    let mut matrix = Vec::with_capacity(rows as usize);
+    for _ in 0..rows {
+        let (row, tail) = decode_vector_within(rest, max_len)?;
+        matrix.push(row);
+        rest = tail;
+    }
+    Ok((matrix, rest))
+}
+
+// Encode a u64 as a LEB128 varint: seven bits per byte, least significant first.
+pub fn encode_varint(value: u64, bytes: &mut Vec<u8>) {
+    let mut value = value;
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// Decode a varint written by `encode_varint`, rejecting overlong and overflowing encodings so
+// every value has exactly one encoding.
+pub fn decode_varint(bytes: &[u8]) -> Result<(u64, &[u8]), CommitError> {
+    let mut value = 0u64;
+    for (index, &byte) in bytes.iter().enumerate().take(10) {
+        let bits = u64::from(byte & 0x7f);
+        if index == 9 && byte > 1 {
+            return Err(CommitError::MalformedEncoding);
+        }
+        value |= bits << (7 * index);
+        if byte & 0x80 == 0 {
+            if byte == 0 && index > 0 {
+                return Err(CommitError::MalformedEncoding);
+            }
+            return Ok((value, bytes.get(index + 1..).unwrap_or_default()));
+        }
+    }
+    Err(CommitError::MalformedEncoding)
+}
+
+// Encrypt or decrypt data by XOR with a keystream of hash_to_commitment(key ‖ counter) blocks.
+pub fn xor_keystream(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut counter = 0u64;
+    while output.len() < data.len() {
+        let mut block_input = key.to_vec();
+        block_input.extend_from_slice(&counter.to_be_bytes());
+        let block = hash_to_commitment(&block_input);
+        let offset = output.len();
+        output.extend(block.iter().zip(data.iter().skip(offset)).map(|(k, d)| k ^ d));
+        counter += 1;
+    }
+    output
+}
+
+// Number of leading coordinates needed to hold every non-zero entry of the vector.
+pub fn support_len(values: &[u64]) -> usize {
+    values.iter().rposition(|&x| x != 0).map_or(0, |last| last + 1)
 }
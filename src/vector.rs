@@ -0,0 +1,155 @@
+//! This module provides vector commitments: a commitment to an array of values in which single
+//! entries are later revealed with a proof of their position.
+//!
+//! `commit_vector` hides every entry behind a salt and commits to the salted entries in the hash
+//! tree of `hierarchy`, whose leaves are bound to their index. `CommittedVector::open_at` returns
+//! the `PositionProof` of one entry: its salt and the sibling hashes on its path, so a proof holds
+//! one salt and a hash per level, logarithmic in the length of the array, where an opening of the
+//! lattice commitment holds the whole message and its randomness, and a partial opening of
+//! `disclosure` a proof over every hidden coordinate. `verify_at` checks an entry against the
+//! `VectorCommitment` alone.
+//!
+//! `CommittedVector::open_subvector` opens many entries with one `SubvectorProof`: their salts and
+//! the `hierarchy::MultiInclusionProof` of their leaves, which leaves out the siblings the verifier
+//! computes from the opened entries. The paths of `k` entries out of `n` share their upper levels,
+//! so the proof holds about `k·log2(n/k)` hashes instead of the `k·log2(n)` of `k` position
+//! proofs, and fewer when the entries are clustered. `verify_subvector` checks the entries against
+//! the commitment at once.
+//!
+//! The salts are derived from a secret seed drawn by `commit_vector`, `H(SALT_DOMAIN ‖ seed ‖ i)`
+//! for entry `i`, so the producer keeps one seed instead of a salt per entry. The salt of an entry
+//! is revealed with it and tells nothing about the salts of the others, so the entries that are
+//! not opened stay hidden even when they are drawn from a small set. The commitment binds the
+//! length of the array, so a proof never verifies at an index past its end.
+
+#[cfg(feature = "prover")]
+use rand::RngCore;
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+use crate::hierarchy::{self, AggregateCommitment, InclusionProof, MultiInclusionProof};
+#[cfg(feature = "prover")]
+use crate::hierarchy::AggregateTree;
+use crate::utils;
+
+pub(crate) const ENTRY_DOMAIN: &[u8] = b"reveler/vector/entry";
+pub(crate) const SALT_DOMAIN: &[u8] = b"reveler/vector/salt";
+
+/// Commitment to an array of values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorCommitment {
+    pub count: u64,    // The number of entries
+    pub root: Vec<u8>, // The root of the hash tree over the salted entries
+}
+
+/// Proof that a value sits at a given index of a committed array.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionProof {
+    pub salt: Vec<u8>,        // The salt hiding the entry
+    pub path: InclusionProof, // The index of the entry and the sibling hashes on its path
+}
+
+/// Proof that values sit at given indices of a committed array.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubvectorProof {
+    pub salts: Vec<Vec<u8>>,        // The salts hiding the entries, in the order of the indices
+    pub paths: MultiInclusionProof, // The indices of the entries and the siblings of their paths
+}
+
+/// Committed array, kept by its producer to open entries.
+#[cfg(feature = "prover")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommittedVector {
+    values: Vec<u64>,
+    seed: Vec<u8>, // The secret the salts are derived from
+    tree: AggregateTree,
+    commitment: VectorCommitment,
+}
+
+/// Commits to an array of values with a fresh seed.
+///
+/// # Returns
+/// The `CommittedVector`, or `CommitError::ComputationError` if `values` is empty.
+#[cfg(feature = "prover")]
+pub fn commit_vector(values: &[u64]) -> Result<CommittedVector, CommitError> {
+    let mut seed = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut seed);
+    let children: Vec<Vec<u8>> = values.iter().enumerate().map(|(index, &value)| encode_entry(&salt(&seed, index as u64), value)).collect();
+    let tree = hierarchy::build(0, &children)?;
+    let aggregate = tree.aggregate();
+    let commitment = VectorCommitment { count: aggregate.count, root: aggregate.root.clone() };
+    Ok(CommittedVector { values: values.to_vec(), seed, tree, commitment })
+}
+
+#[cfg(feature = "prover")]
+impl CommittedVector {
+    /// Returns the commitment to the array.
+    pub fn commitment(&self) -> &VectorCommitment {
+        &self.commitment
+    }
+
+    /// Returns the committed values.
+    pub fn values(&self) -> &[u64] {
+        &self.values
+    }
+
+    /// Returns the value at `index`, or `None` if the array has no such entry.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        self.values.get(index).copied()
+    }
+
+    /// Returns the proof of the entry at `index`, or `None` if the array has no such entry.
+    pub fn open_at(&self, index: usize) -> Option<PositionProof> {
+        self.values.get(index)?;
+        let path = self.tree.prove(index as u64)?;
+        Some(PositionProof { salt: salt(&self.seed, index as u64), path })
+    }
+
+    /// Returns the proof of the entries at `indices`, or `None` if the indices are empty, not
+    /// strictly increasing, or past the last entry.
+    pub fn open_subvector(&self, indices: &[usize]) -> Option<SubvectorProof> {
+        let indices: Vec<u64> = indices.iter().map(|&index| index as u64).collect();
+        let paths = self.tree.prove_many(&indices)?;
+        let salts = indices.iter().map(|&index| salt(&self.seed, index)).collect();
+        Some(SubvectorProof { salts, paths })
+    }
+}
+
+/// Checks that `value` sits at `index` of the committed array.
+pub fn verify_at(commitment: &VectorCommitment, index: usize, value: u64, proof: &PositionProof) -> bool {
+    proof.path.index == index as u64 && hierarchy::verify_path(&as_aggregate(commitment), &encode_entry(&proof.salt, value), &proof.path)
+}
+
+/// Checks that `values` sit at `indices` of the committed array, one value per index.
+pub fn verify_subvector(commitment: &VectorCommitment, indices: &[usize], values: &[u64], proof: &SubvectorProof) -> bool {
+    let matches = indices.len() == values.len()
+        && proof.salts.len() == values.len()
+        && proof.paths.indices.iter().copied().eq(indices.iter().map(|&index| index as u64));
+    if !matches {
+        return false;
+    }
+    let children: Vec<Vec<u8>> = proof.salts.iter().zip(values).map(|(salt, &value)| encode_entry(salt, value)).collect();
+    hierarchy::verify_paths(&as_aggregate(commitment), &children, &proof.paths)
+}
+
+// H(SALT_DOMAIN ‖ seed ‖ i)
+#[cfg(feature = "prover")]
+fn salt(seed: &[u8], index: u64) -> Vec<u8> {
+    let mut input = SALT_DOMAIN.to_vec();
+    input.extend(utils::encode_bytes(seed));
+    input.extend_from_slice(&index.to_be_bytes());
+    utils::hash_to_commitment(&input)
+}
+
+// Starts with the zero high byte of the domain length, as the entries of `map`, so entries never
+// share a leaf of level 0 with commitments; the domain tells them apart from map entries.
+fn encode_entry(salt: &[u8], value: u64) -> Vec<u8> {
+    let mut bytes = utils::encode_bytes(ENTRY_DOMAIN);
+    bytes.extend(utils::encode_bytes(salt));
+    bytes.extend_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+fn as_aggregate(commitment: &VectorCommitment) -> AggregateCommitment {
+    AggregateCommitment { level: 0, count: commitment.count, root: commitment.root.clone() }
+}
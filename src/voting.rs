@@ -0,0 +1,184 @@
+//! This module provides vote tallying over homomorphic commitments.
+//!
+//! A ballot commits to one bit per option, 1 for the options the voter approves, and carries a
+//! `binary` proof that every committed coordinate is 0 or 1. Because the commitment is linear,
+//! the sum of all ballot commitments commits to the per-option counts under the sum of the ballot
+//! randomness. The tallier, who receives the ballot openings over a private channel, publishes
+//! the counts with the summed randomness, and anyone checks that opening against the ballots
+//! with `verify_tally` without learning any single vote.
+//!
+//! The summed randomness of `k` ballots stays within `k·policy::RANDOMNESS_BOUND` per coordinate, and
+//! `verify_tally` checks the tally under `VerifyPolicy::for_sum(k)`: the commitment binds every
+//! option separately, so a tally that moves approvals from one option to another does not open
+//! the aggregate. Binding is statistical up to `policy::MAX_BINDING_SUMMANDS` ballots and rests on
+//! the hardness of SIS past it.
+//!
+//! Counts are computed modulo `q`, so an election holds fewer than `q` ballots. The ballot proof
+//! shows bits opened with ternary randomness, so a voter who commits to a count above 1 for an
+//! option cannot pass `verify_ballot`, not even by absorbing the excess into the identity block of
+//! `[B | I]`.
+
+use serde::{Serialize, Deserialize};
+use crate::amounts;
+use crate::binary::{self, BinaryProof};
+#[cfg(feature = "prover")]
+use crate::commit_error::CommitError;
+use crate::config::CommitConfig;
+use crate::fft::RANDOMNESS_LEN;
+use crate::policy::VerifyPolicy;
+#[cfg(feature = "prover")]
+use crate::utils;
+#[cfg(feature = "prover")]
+use crate::compute_point;
+use crate::RevelerResult;
+
+/// Committed ballot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ballot {
+    pub commitment: RevelerResult, // The commitment to one bit per option
+    pub proof: BinaryProof,        // Proof that every committed bit is 0 or 1
+}
+
+/// Secret opening of a ballot, sent to the tallier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BallotOpening {
+    pub choices: Vec<u64>, // 1 for every approved option, 0 otherwise
+    pub local_r: Vec<u64>, // The randomness of the commitment
+}
+
+/// Opening of the aggregate of all ballots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TallyOpening {
+    pub counts: Vec<u64>,  // The number of approvals of every option
+    pub local_r: Vec<u64>, // The sum of the ballot randomness
+}
+
+/// Casts a ballot approving the given options.
+///
+/// # Parameters
+/// - `params`: The `(A, B)` matrices of the election.
+/// - `choices`: One entry per option, `true` if the voter approves it.
+///
+/// # Returns
+/// The `Ballot` to publish and the `BallotOpening` for the tallier, or
+/// `CommitError::InvalidOpening` if there are more than `MESSAGE_LEN` options.
+#[cfg(feature = "prover")]
+pub fn cast(params: (&[Vec<u64>], &[Vec<u64>]), choices: &[bool]) -> Result<(Ballot, BallotOpening), CommitError> {
+    let mut rng = rand::thread_rng();
+    let opening = BallotOpening {
+        choices: choices.iter().map(|&choice| choice as u64).collect(),
+        local_r: utils::generate_randomness(&mut rng),
+    };
+    let point = compute_point(params.0, params.1, &opening.choices, &opening.local_r, &CommitConfig::default())?;
+    let hash = utils::hash_to_commitment(&point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+    let commitment = RevelerResult::new(point, hash);
+    let proof = binary::prove_binary(params, &opening.choices, &opening.local_r, &commitment)?;
+    Ok((Ballot { commitment, proof }, opening))
+}
+
+/// Verifies the bit proof of a ballot for an election with `options` options.
+pub fn verify_ballot(params: (&[Vec<u64>], &[Vec<u64>]), options: usize, ballot: &Ballot) -> bool {
+    binary::verify_binary(params, &ballot.commitment, options, &ballot.proof)
+}
+
+/// Computes the commitment to the counts from the ballots.
+pub fn aggregate(ballots: &[Ballot]) -> RevelerResult {
+    let commitments: Vec<RevelerResult> = ballots.iter().map(|ballot| ballot.commitment.clone()).collect();
+    amounts::sum(&commitments)
+}
+
+/// Sums the ballot openings into the opening of the aggregate.
+pub fn open_tally(options: usize, openings: &[BallotOpening]) -> TallyOpening {
+    let q = CommitConfig::default().modulus;
+    let mut tally = TallyOpening { counts: vec![0; options], local_r: vec![0; RANDOMNESS_LEN] };
+    for opening in openings {
+        for (count, &choice) in tally.counts.iter_mut().zip(&opening.choices) {
+            *count = (*count + choice) % q;
+        }
+        for (acc, &x) in tally.local_r.iter_mut().zip(&opening.local_r) {
+            *acc = (*acc + x % q) % q;
+        }
+    }
+    tally
+}
+
+/// Verifies every ballot and the published tally against their aggregate.
+///
+/// # Returns
+/// `true` if there are fewer than `q` ballots, every ballot proof checks out, and the tally
+/// opens the sum of the ballot commitments under `VerifyPolicy::for_sum` of the number of ballots.
+pub fn verify_tally(params: (&[Vec<u64>], &[Vec<u64>]), options: usize, ballots: &[Ballot], tally: &TallyOpening) -> bool {
+    let voters = ballots.len() as u64;
+    if voters >= CommitConfig::default().modulus
+        || tally.counts.len() != options
+        || tally.counts.iter().any(|&count| count > voters)
+        || !ballots.iter().all(|ballot| verify_ballot(params, options, ballot))
+    {
+        return false;
+    }
+    VerifyPolicy::for_sum(voters)
+        .verify_opening(params, &aggregate(ballots), &tally.counts, &tally.local_r)
+        .unwrap_or(false)
+}
+
+#[cfg(all(test, feature = "prover"))]
+#[cfg_attr(feature = "strict-no-panic", allow(clippy::unwrap_used, clippy::indexing_slicing))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    type Params = (Vec<Vec<u64>>, Vec<Vec<u64>>);
+
+    fn election() -> (Params, Vec<Ballot>, TallyOpening) {
+        let (a, b) = utils::generate_params_with_rng(&mut StdRng::seed_from_u64(231));
+        let votes = [[true, false, false], [true, true, false], [false, true, true], [true, false, false]];
+        let (ballots, openings): (Vec<Ballot>, Vec<BallotOpening>) = votes.iter().map(|choices| cast((&a, &b), choices).unwrap()).unzip();
+        let tally = open_tally(3, &openings);
+        ((a, b), ballots, tally)
+    }
+
+    #[test]
+    fn correct_tally_verifies() {
+        let ((a, b), ballots, tally) = election();
+        assert_eq!(tally.counts, vec![3, 2, 1]);
+        assert!(verify_tally((&a, &b), 3, &ballots, &tally));
+    }
+
+    #[test]
+    fn swapped_counts_are_rejected() {
+        // 计数总和不变，只是在选项之间挪动
+        let ((a, b), ballots, tally) = election();
+        for counts in [vec![2, 3, 1], vec![1, 2, 3], vec![3, 1, 2]] {
+            let swapped = TallyOpening { counts, local_r: tally.local_r.clone() };
+            assert!(!verify_tally((&a, &b), 3, &ballots, &swapped));
+        }
+    }
+
+    #[test]
+    fn over_counted_ballots_are_rejected() {
+        // 给第一个选项投 2 票，再声称是 1 票，把多出的 A·e_0 塞进 [B | I] 的单位块
+        let (a, b) = utils::generate_params_with_rng(&mut StdRng::seed_from_u64(231));
+        let q = CommitConfig::default().modulus;
+        let counts = vec![2, 0, 1];
+        let local_r = utils::generate_randomness(&mut StdRng::seed_from_u64(231));
+        let point = compute_point(&a, &b, &counts, &local_r, &CommitConfig::default()).unwrap();
+        let hash = utils::hash_to_commitment(&point.iter().flat_map(|&x| x.to_be_bytes()).collect::<Vec<u8>>());
+        let commitment = RevelerResult::new(point, hash);
+        assert!(binary::prove_binary((&a, &b), &counts, &local_r, &commitment).is_err());
+
+        let claimed = vec![1, 0, 1];
+        let mut forged = local_r.clone();
+        for (slot, row) in forged.iter_mut().skip(RANDOMNESS_LEN - a.len()).zip(&a) {
+            *slot = (*slot + row[0]) % q;
+        }
+        assert_eq!(compute_point(&a, &b, &claimed, &forged, &CommitConfig::default()).unwrap(), commitment.commitment_point);
+
+        let relation = binary::bits_relation((&a, &b), 3, &commitment);
+        for (bits, randomness) in [(claimed, forged), (counts, local_r)] {
+            let witness = binary::Witness { free: Vec::new(), blocks: vec![bits, randomness] };
+            let proof = binary::prove_relation(&relation, &witness, binary::round_count(), utils::Workers::Threads).unwrap();
+            assert!(!verify_ballot((&a, &b), 3, &Ballot { commitment: commitment.clone(), proof }));
+        }
+    }
+}